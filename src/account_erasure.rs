@@ -0,0 +1,289 @@
+//! Account erasure (`DELETE /users/me`).
+//!
+//! Runs as a background job after the request is accepted. The user's posts, comments and
+//! post revisions are kept but anonymized (author `ERASED_AUTHOR_NAME`, no `user_id`), in
+//! every copy and the search index, and leave the author's listings. Votes and reactions are
+//! deleted, while scores and reaction counts keep them. The profile is deleted last, after
+//! sign-in is locked out. Every step is idempotent, so a failed job can be run again for the
+//! user id in the audit log.
+
+use actix_web::{delete, web, HttpResponse, Responder, ResponseError};
+use chrono::Utc;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::audit;
+use crate::auth;
+use crate::config;
+use crate::db;
+use crate::errors::ApiError;
+use crate::jwt_middleware::AuthenticatedUser;
+use crate::models::{AccountDeletionResponse, User};
+use crate::post_revisions;
+use crate::reactions;
+use crate::routes::{self, execute_cached, record_db_operation, DbCounter};
+use crate::search;
+use crate::sessions;
+use crate::votes;
+
+/// Users whose erasure job is running on this instance
+static RUNNING: OnceLock<Mutex<HashSet<Uuid>>> = OnceLock::new();
+
+fn running() -> MutexGuard<'static, HashSet<Uuid>> {
+    RUNNING
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Columns of the `posts` rows read below, in `SELECT` order
+type PostRow = (Uuid, Option<Uuid>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>);
+
+/// Anonymize the user's posts and their copies, returning how many there were
+async fn anonymize_posts(session: &Session, user_id: Uuid, author: &str) -> Result<usize, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT id, board_id, author, created_at, updated_at, tags FROM posts WHERE user_id = ?",
+        (user_id,),
+    ).await?;
+    let posts: Vec<PostRow> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    for (post_id, board_id, previous_author, created_at, updated_at, tags) in &posts {
+        // The copies are keyed by timestamps; IF EXISTS keeps a mismatch from creating stray rows
+        if let Some(updated_at) = updated_at {
+            execute_cached(
+                session,
+                "UPDATE posts_by_updated SET author = ? WHERE day = ? AND updated_at = ? AND id = ? IF EXISTS",
+                (author, db::updated_day(*updated_at), *updated_at, *post_id),
+            ).await?;
+        }
+        if let (Some(board_id), Some(created_at)) = (board_id, created_at) {
+            execute_cached(
+                session,
+                "UPDATE posts_by_board SET author = ?, user_id = null WHERE board_id = ? AND created_at = ? AND id = ? IF EXISTS",
+                (author, *board_id, *created_at, *post_id),
+            ).await?;
+        }
+        if let Some(created_at) = created_at {
+            // The post no longer belongs to the author, so it leaves their listing
+            if let Some(previous_author) = previous_author {
+                execute_cached(
+                    session,
+                    "DELETE FROM posts_by_author WHERE author = ? AND created_at = ? AND id = ?",
+                    (previous_author, *created_at, *post_id),
+                ).await?;
+            }
+            for tag in tags.iter().flatten() {
+                execute_cached(
+                    session,
+                    "UPDATE posts_by_tag SET author = ? WHERE tag = ? AND created_at = ? AND id = ? IF EXISTS",
+                    (author, tag, *created_at, *post_id),
+                ).await?;
+            }
+        }
+        // The post itself goes last: it is what the next run finds the post by
+        execute_cached(
+            session,
+            "UPDATE posts SET author = ?, user_id = null WHERE id = ?",
+            (author, *post_id),
+        ).await?;
+        routes::invalidate_post_cache(*post_id).await;
+        search::set_author(*post_id, author);
+    }
+    Ok(posts.len())
+}
+
+/// Columns of the `comments` rows read below, in `SELECT` order
+type CommentRow = (Uuid, Option<Uuid>, Option<String>, Option<i64>);
+
+/// Anonymize the user's comments and their per-post and per-board copies, returning how many
+/// there were
+async fn anonymize_comments(session: &Session, user_id: Uuid, author: &str) -> Result<usize, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT id, post_id, author, created_at FROM comments WHERE user_id = ?",
+        (user_id,),
+    ).await?;
+    let comments: Vec<CommentRow> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    let mut boards: HashMap<Uuid, Option<Uuid>> = HashMap::new();
+    for (comment_id, post_id, previous_author, created_at) in &comments {
+        if let (Some(previous_author), Some(created_at)) = (previous_author, created_at) {
+            execute_cached(
+                session,
+                "DELETE FROM comments_by_author WHERE author = ? AND created_at = ? AND id = ?",
+                (previous_author, *created_at, *comment_id),
+            ).await?;
+        }
+        if let (Some(post_id), Some(created_at)) = (post_id, created_at) {
+            execute_cached(
+                session,
+                "UPDATE comments_by_post SET author = ?, user_id = null WHERE post_id = ? AND created_at = ? AND id = ? IF EXISTS",
+                (author, *post_id, *created_at, *comment_id),
+            ).await?;
+            let board_id = match boards.get(post_id) {
+                Some(board_id) => *board_id,
+                None => {
+                    let rows = execute_cached(session, "SELECT board_id FROM posts WHERE id = ?", (*post_id,)).await?;
+                    let board_id = rows
+                        .maybe_first_row_typed::<(Option<Uuid>,)>()
+                        .ok()
+                        .flatten()
+                        .and_then(|(board_id,)| board_id);
+                    boards.insert(*post_id, board_id);
+                    board_id
+                }
+            };
+            if let Some(board_id) = board_id {
+                execute_cached(
+                    session,
+                    "UPDATE comments_by_board SET author = ? WHERE board_id = ? AND created_at = ? AND id = ? IF EXISTS",
+                    (author, board_id, *created_at, *comment_id),
+                ).await?;
+            }
+        }
+        execute_cached(
+            session,
+            "UPDATE comments SET author = ?, user_id = null WHERE id = ?",
+            (author, *comment_id),
+        ).await?;
+        search::set_author(*comment_id, author);
+    }
+    Ok(comments.len())
+}
+
+/// Stop the user from signing in: password, OAuth links and every session
+async fn lock_out(session: &Session, user_id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM credentials WHERE user_id = ?", (user_id,)).await?;
+
+    let rows = execute_cached(
+        session,
+        "SELECT provider, subject FROM oauth_identities WHERE user_id = ?",
+        (user_id,),
+    ).await?;
+    let identities: Vec<(String, String)> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    for (provider, subject) in &identities {
+        execute_cached(
+            session,
+            "DELETE FROM oauth_identities WHERE provider = ? AND subject = ?",
+            (provider, subject),
+        ).await?;
+    }
+
+    sessions::revoke_all(session, user_id, None).await?;
+    Ok(())
+}
+
+/// Delete what is left of the profile: subscriptions, the username and the account itself
+async fn delete_profile(session: &Session, user: &User) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM subscriptions WHERE author = ?", (&user.username,)).await?;
+    execute_cached(
+        session,
+        "DELETE FROM users_by_username WHERE username = ? IF user_id = ?",
+        (auth::username_key(&user.username), user.id),
+    ).await?;
+    execute_cached(session, "DELETE FROM users WHERE id = ?", (user.id,)).await?;
+    Ok(())
+}
+
+async fn erase(session: &Session, user: &User) -> Result<(usize, usize), QueryError> {
+    let author = &config::get().erased_author_name;
+    lock_out(session, user.id).await?;
+    let posts = anonymize_posts(session, user.id, author).await?;
+    let comments = anonymize_comments(session, user.id, author).await?;
+    post_revisions::anonymize(session, user.id, author).await?;
+    votes::forget_user(session, user.id).await?;
+    reactions::forget_user(session, user.id).await?;
+    delete_profile(session, user).await?;
+    Ok((posts, comments))
+}
+
+async fn run_job(session: Arc<Session>, user: User, db_counter: web::Data<DbCounter>) {
+    let outcome = erase(&session, &user).await;
+    running().remove(&user.id);
+
+    let (action, details) = match &outcome {
+        Ok((posts, comments)) => {
+            record_db_operation(&db_counter, "update", "users", true);
+            info!("Erased account {} ({}): {} posts and {} comments anonymized", user.id, user.username, posts, comments);
+            ("account_erasure_completed", format!("{} posts and {} comments anonymized", posts, comments))
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "update", "users", false);
+            error!("Erasure of account {} failed: {}", user.id, e);
+            ("account_erasure_failed", e.to_string())
+        }
+    };
+    if let Err(e) = audit::record(&session, action, None, user.id, &details).await {
+        error!("Error recording audit event {} for user {}: {}", action, user.id, e);
+    }
+}
+
+/// Delete my account
+///
+/// Schedules erasure of the token owner's account and answers right away. Posts and comments
+/// are kept but anonymized (author `ERASED_AUTHOR_NAME`); votes, reactions, the account,
+/// password, sessions, OAuth links and subscriptions are deleted, which also logs the user
+/// out everywhere. Scores and reaction counts are left as they are.
+/// Requesting it again while a job runs is harmless.
+#[utoipa::path(
+    delete,
+    path = "/users/me",
+    responses(
+        (status = 202, description = "Erasure scheduled", body = AccountDeletionResponse),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Account already deleted"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/users/me")]
+pub async fn delete_account(
+    session: web::Data<Arc<Session>>,
+    caller: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let user = match auth::fetch_user(&session, caller.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return ApiError::not_found("Account already deleted").error_response(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "users", false);
+            error!("Error fetching user {}: {}", caller.user_id, e);
+            return ApiError::internal(format!("Error fetching user: {}", e)).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "users", true);
+
+    let requested_at = Utc::now();
+    let audit_id = match audit::record(&session, "account_erasure_requested", Some(user.id), user.id, &user.username).await {
+        Ok(audit_id) => audit_id,
+        Err(e) => {
+            error!("Error recording erasure request of user {}: {}", user.id, e);
+            return ApiError::internal(format!("Error scheduling erasure: {}", e)).error_response();
+        }
+    };
+
+    if running().insert(user.id) {
+        warn!("Scheduling erasure of account {} ({})", user.id, user.username);
+        tokio::spawn(run_job(session.get_ref().clone(), user.clone(), db_counter.clone()));
+    } else {
+        info!("Erasure of account {} is already running", user.id);
+    }
+
+    HttpResponse::Accepted().json(AccountDeletionResponse {
+        user_id: user.id,
+        audit_id,
+        requested_at,
+    })
+}
@@ -0,0 +1,686 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use prometheus::proto::{Histogram, MetricType};
+use prometheus::Registry;
+use scylla::Session;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use uuid::Uuid;
+use tracing::{info, warn, error};
+use crate::api_keys;
+use crate::auth;
+use crate::config;
+use crate::errors::ApiError;
+use crate::in_flight_middleware;
+use crate::jwt_middleware::Admin;
+use crate::maintenance_middleware;
+use crate::pool_health;
+use crate::models::{
+    CacheEntryType, CacheRefreshRequest, CacheStats, CacheStatsResponse,
+    LatencyPercentiles, LatencySnapshot,
+    MaintenanceRequest, MaintenanceStatus,
+    PoolNode, PoolStats, InFlightRequestInfo,
+    RoleUpdateRequest,
+    ApiKeyScope, CreateApiKeyRequest, CreatedApiKey, UpdateApiKeyRequest,
+};
+use crate::routes::{self, CacheCounter, DbCounter, IntegrityCounter};
+
+/// Check the `X-Admin-Token` header against the configured `ADMIN_TOKEN`
+///
+/// Admin endpoints are disabled entirely while no token is configured.
+pub fn require_admin(req: &HttpRequest) -> Result<(), ApiError> {
+    let expected = match &config::get().admin_token {
+        Some(token) => token,
+        None => {
+            warn!("Admin endpoint {} called but ADMIN_TOKEN is not set", req.path());
+            return Err(ApiError::forbidden("Admin endpoints are disabled"));
+        }
+    };
+
+    let provided = req.headers()
+        .get("X-Admin-Token")
+        .and_then(|v| v.to_str().ok());
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => {
+            warn!("Rejected admin request to {} with missing or invalid token", req.path());
+            Err(ApiError::unauthorized("Missing or invalid admin token"))
+        }
+    }
+}
+
+/// Compare secrets without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Refresh a single cache entry
+///
+/// Drops the cached board or post and reloads it from the database, returning the fresh value
+#[utoipa::path(
+    post,
+    path = "/admin/cache/refresh",
+    request_body = CacheRefreshRequest,
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "Entry refreshed; body is the fresh board or post"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled"),
+        (status = 404, description = "Entity no longer exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/cache/refresh")]
+pub async fn refresh_cache_entry(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    refresh: web::Json<CacheRefreshRequest>,
+    db_counter: web::Data<DbCounter>,
+    cache_counter: web::Data<CacheCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let id = refresh.id;
+    info!("Refreshing {:?} cache entry {}", refresh.entry_type, id);
+
+    match refresh.entry_type {
+        CacheEntryType::Board => {
+            let was_cached = routes::invalidate_board_cache(id).await;
+            match routes::fetch_board_from_db(&session, id).await {
+                Ok(Some(board)) => {
+                    routes::record_db_operation(&db_counter, "select", "boards", true);
+                    routes::cache_board(&board).await;
+                    routes::record_cache_metric(&cache_counter, "boards", "refreshed");
+                    HttpResponse::Ok()
+                        .append_header(("X-Cache-Was-Present", was_cached.to_string()))
+                        .json(board)
+                }
+                Ok(None) => {
+                    routes::record_db_operation(&db_counter, "select", "boards", true);
+                    ApiError::not_found(format!("Board with id {} not found", id)).error_response()
+                }
+                Err(e) => {
+                    routes::record_db_operation(&db_counter, "select", "boards", false);
+                    error!("Error refreshing board {}: {}", id, e);
+                    ApiError::internal(format!("Error fetching board: {}", e)).error_response()
+                }
+            }
+        }
+        CacheEntryType::Post => {
+            let was_cached = routes::invalidate_post_cache(id).await;
+            match routes::fetch_post_from_db(&session, id, &integrity_counter).await {
+                Ok(Some(post)) => {
+                    routes::record_db_operation(&db_counter, "select", "posts", true);
+                    routes::cache_post(&post).await;
+                    routes::record_cache_metric(&cache_counter, "posts", "refreshed");
+                    HttpResponse::Ok()
+                        .append_header(("X-Cache-Was-Present", was_cached.to_string()))
+                        .json(post)
+                }
+                Ok(None) => {
+                    routes::record_db_operation(&db_counter, "select", "posts", true);
+                    ApiError::not_found(format!("Post with id {} not found", id)).error_response()
+                }
+                Err(e) => {
+                    routes::record_db_operation(&db_counter, "select", "posts", false);
+                    error!("Error refreshing post {}: {}", id, e);
+                    ApiError::internal(format!("Error fetching post: {}", e)).error_response()
+                }
+            }
+        }
+    }
+}
+
+/// Toggle maintenance mode
+///
+/// While enabled, mutating endpoints return 503 with `Retry-After`; reads keep working unless `block_reads` is set
+#[utoipa::path(
+    post,
+    path = "/admin/maintenance",
+    request_body = MaintenanceRequest,
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "Maintenance mode updated", body = MaintenanceStatus),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled")
+    )
+)]
+#[post("/admin/maintenance")]
+pub async fn set_maintenance(
+    req: HttpRequest,
+    maintenance: web::Json<MaintenanceRequest>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    maintenance_middleware::set(maintenance.enabled, maintenance.block_reads);
+    warn!("Maintenance mode {} (block_reads: {})", if maintenance.enabled { "enabled" } else { "disabled" }, maintenance.block_reads);
+
+    HttpResponse::Ok().json(MaintenanceStatus {
+        enabled: maintenance_middleware::is_enabled(),
+        block_reads: maintenance_middleware::blocks_reads(),
+    })
+}
+
+/// Get cache statistics
+///
+/// Returns the current number of entries and since-startup hit/miss counts per cache
+#[utoipa::path(
+    get,
+    path = "/admin/cache/stats",
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "Cache statistics", body = CacheStatsResponse),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled")
+    )
+)]
+#[get("/admin/cache/stats")]
+pub async fn get_cache_stats(
+    req: HttpRequest,
+    cache_counter: web::Data<CacheCounter>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let (boards, posts, board_stats) = routes::cache_entry_counts().await;
+    let stats_for = |cache_type: &str, entries: usize| CacheStats {
+        entries,
+        hits: cache_counter.0.with_label_values(&[cache_type, "hit"]).get(),
+        misses: cache_counter.0.with_label_values(&[cache_type, "miss"]).get(),
+        expired: cache_counter.0.with_label_values(&[cache_type, "expired"]).get(),
+    };
+
+    HttpResponse::Ok().json(CacheStatsResponse {
+        boards: stats_for("boards", boards),
+        posts: stats_for("posts", posts),
+        board_stats: stats_for("board_stats", board_stats),
+    })
+}
+
+/// Get connection pool state
+///
+/// Lists the ScyllaDB nodes known to the driver with their status and pool size, and the
+/// outcome of the background pool probes (see `POOL_HEALTH_INTERVAL_SECS`).
+#[utoipa::path(
+    get,
+    path = "/admin/pool",
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "Connection pool state", body = PoolStats),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled")
+    )
+)]
+#[get("/admin/pool")]
+pub async fn get_pool_stats(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let cluster = session.get_cluster_data();
+    let nodes = cluster
+        .get_nodes_info()
+        .iter()
+        .map(|node| PoolNode {
+            address: node.address.to_string(),
+            datacenter: node.datacenter.clone(),
+            rack: node.rack.clone(),
+            host_id: node.host_id,
+            is_down: node.is_down(),
+            connections: config::get().scylla_pool_size,
+        })
+        .collect();
+
+    let health = pool_health::snapshot();
+    HttpResponse::Ok().json(PoolStats {
+        nodes,
+        last_check_at: health.last_check_at,
+        consecutive_failures: health.consecutive_failures,
+        refreshes: health.refreshes,
+        last_error: health.last_error,
+    })
+}
+
+/// Lists the requests currently being handled, longest running first, to spot a hung one.
+/// Listing doesn't cancel anything: handlers are only cut off by `HANDLER_TIMEOUT_MS`.
+#[utoipa::path(
+    get,
+    path = "/admin/inflight",
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "Requests in flight", body = Vec<InFlightRequestInfo>),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled")
+    )
+)]
+#[get("/admin/inflight")]
+pub async fn get_in_flight_requests(req: HttpRequest) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let requests: Vec<InFlightRequestInfo> = in_flight_middleware::snapshot()
+        .into_iter()
+        .map(|request| InFlightRequestInfo {
+            elapsed_ms: request.started.elapsed().as_millis() as u64,
+            method: request.method,
+            path: request.path,
+            trace_id: request.trace_id,
+            started_at: request.started_at,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(requests)
+}
+
+/// Set a user's role
+///
+/// Grants or revokes `moderator`/`admin`. Tokens carry the role they were issued with, so the
+/// change applies once the user gets a new token. Takes an admin's bearer token or the admin
+/// token.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{user_id}/role",
+    request_body = RoleUpdateRequest,
+    params(
+        ("user_id" = uuid::Uuid, Path, description = "User ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token")
+    ),
+    responses(
+        (status = 200, description = "Role updated", body = User),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below admin, or admin endpoints are disabled"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/admin/users/{user_id}/role")]
+pub async fn set_user_role(
+    admin: Admin,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    update: web::Json<RoleUpdateRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let mut user = match auth::fetch_user(&session, user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return ApiError::not_found(format!("User with id {} not found", user_id)).error_response(),
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "select", "users", false);
+            error!("Error fetching user {}: {}", user_id, e);
+            return ApiError::internal(format!("Error fetching user: {}", e)).error_response();
+        }
+    };
+    routes::record_db_operation(&db_counter, "select", "users", true);
+
+    let role = update.role;
+    if let Err(e) = routes::execute_cached(&session, "UPDATE users SET role = ? WHERE id = ?", (role.as_str(), user_id)).await {
+        routes::record_db_operation(&db_counter, "update", "users", false);
+        error!("Error setting role of user {}: {}", user_id, e);
+        return ApiError::internal(format!("Error updating user: {}", e)).error_response();
+    }
+    routes::record_db_operation(&db_counter, "update", "users", true);
+
+    let changed_by = admin.0.map_or_else(|| "the admin token".to_string(), |admin| admin.username);
+    warn!("User {} ({}) role changed from {} to {} by {}", user.id, user.username, user.role.as_str(), role.as_str(), changed_by);
+    user.role = role;
+    HttpResponse::Ok().json(user)
+}
+
+const MAX_API_KEY_NAME_LENGTH: usize = 100;
+
+fn validate_api_key(name: &str, scopes: &[ApiKeyScope], rate_limit_per_minute: u32) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_API_KEY_NAME_LENGTH {
+        return Err(format!("name must be at most {} characters", MAX_API_KEY_NAME_LENGTH));
+    }
+    if scopes.is_empty() {
+        return Err("scopes must not be empty".to_string());
+    }
+    if rate_limit_per_minute == 0 {
+        return Err("rate_limit_per_minute must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+/// Create an API key
+///
+/// Issues a key for an automated client, sent as `X-Api-Key`. The secret is only returned
+/// here; store it, since it can't be retrieved later. A key can create boards, posts and
+/// comments but isn't a user, so it can't edit, delete or moderate them.
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    request_body = CreateApiKeyRequest,
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 201, description = "API key created", body = CreatedApiKey),
+        (status = 400, description = "Empty name or scopes, or a zero rate limit"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/api-keys")]
+pub async fn create_api_key(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    request: web::Json<CreateApiKeyRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let request = request.into_inner();
+    let name = request.name.trim().to_string();
+    let rate_limit = request.rate_limit_per_minute.unwrap_or(config::get().api_key_default_rate_limit);
+    if let Err(message) = validate_api_key(&name, &request.scopes, rate_limit) {
+        return ApiError::bad_request(message).error_response();
+    }
+    let mut scopes = request.scopes;
+    scopes.sort();
+    scopes.dedup();
+
+    match api_keys::create(&session, name, scopes, rate_limit).await {
+        Ok((api_key, secret)) => {
+            routes::record_db_operation(&db_counter, "insert", "api_keys", true);
+            warn!("API key {} ({}) created", api_key.id, api_key.name);
+            HttpResponse::Created().json(CreatedApiKey { api_key, secret })
+        }
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "insert", "api_keys", false);
+            error!("Error creating API key: {}", e);
+            ApiError::internal(format!("Error creating API key: {}", e)).error_response()
+        }
+    }
+}
+
+/// List API keys
+///
+/// Returns every key, oldest first, without secrets.
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys",
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "API keys", body = Vec<ApiKey>),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/api-keys")]
+pub async fn list_api_keys(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    match api_keys::list(&session).await {
+        Ok(keys) => {
+            routes::record_db_operation(&db_counter, "select", "api_keys", true);
+            HttpResponse::Ok().json(keys)
+        }
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "select", "api_keys", false);
+            error!("Error listing API keys: {}", e);
+            ApiError::internal(format!("Error listing API keys: {}", e)).error_response()
+        }
+    }
+}
+
+/// Update an API key
+///
+/// Changes a key's name, scopes or rate limit; the secret stays the same. Other instances
+/// pick up the change within 30 seconds.
+#[utoipa::path(
+    put,
+    path = "/admin/api-keys/{key_id}",
+    request_body = UpdateApiKeyRequest,
+    params(
+        ("key_id" = uuid::Uuid, Path, description = "API key ID"),
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "API key updated", body = ApiKey),
+        (status = 400, description = "Empty name or scopes, or a zero rate limit"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled"),
+        (status = 404, description = "API key not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/admin/api-keys/{key_id}")]
+pub async fn update_api_key(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    update: web::Json<UpdateApiKeyRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let key_id = path.into_inner();
+    let mut key = match api_keys::fetch(&session, key_id).await {
+        Ok(Some(key)) => key,
+        Ok(None) => return ApiError::not_found(format!("API key with id {} not found", key_id)).error_response(),
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "select", "api_keys", false);
+            error!("Error fetching API key {}: {}", key_id, e);
+            return ApiError::internal(format!("Error fetching API key: {}", e)).error_response();
+        }
+    };
+    routes::record_db_operation(&db_counter, "select", "api_keys", true);
+
+    let update = update.into_inner();
+    if let Some(name) = update.name {
+        key.name = name.trim().to_string();
+    }
+    if let Some(mut scopes) = update.scopes {
+        scopes.sort();
+        scopes.dedup();
+        key.scopes = scopes;
+    }
+    if let Some(rate_limit) = update.rate_limit_per_minute {
+        key.rate_limit_per_minute = rate_limit;
+    }
+    if let Err(message) = validate_api_key(&key.name, &key.scopes, key.rate_limit_per_minute) {
+        return ApiError::bad_request(message).error_response();
+    }
+
+    match api_keys::update(&session, &key).await {
+        Ok(true) => {
+            routes::record_db_operation(&db_counter, "update", "api_keys", true);
+            warn!("API key {} ({}) updated", key.id, key.name);
+            HttpResponse::Ok().json(key)
+        }
+        Ok(false) => {
+            routes::record_db_operation(&db_counter, "update", "api_keys", true);
+            ApiError::not_found(format!("API key with id {} not found", key_id)).error_response()
+        }
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "update", "api_keys", false);
+            error!("Error updating API key {}: {}", key_id, e);
+            ApiError::internal(format!("Error updating API key: {}", e)).error_response()
+        }
+    }
+}
+
+/// Delete an API key
+///
+/// Revokes the key; other instances stop accepting it within 30 seconds.
+#[utoipa::path(
+    delete,
+    path = "/admin/api-keys/{key_id}",
+    params(
+        ("key_id" = uuid::Uuid, Path, description = "API key ID"),
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 204, description = "API key deleted"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled"),
+        (status = 404, description = "API key not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/api-keys/{key_id}")]
+pub async fn delete_api_key(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let key_id = path.into_inner();
+    let key = match api_keys::fetch(&session, key_id).await {
+        Ok(Some(key)) => key,
+        Ok(None) => return ApiError::not_found(format!("API key with id {} not found", key_id)).error_response(),
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "select", "api_keys", false);
+            error!("Error fetching API key {}: {}", key_id, e);
+            return ApiError::internal(format!("Error fetching API key: {}", e)).error_response();
+        }
+    };
+    routes::record_db_operation(&db_counter, "select", "api_keys", true);
+
+    match api_keys::delete(&session, key_id).await {
+        Ok(()) => {
+            routes::record_db_operation(&db_counter, "delete", "api_keys", true);
+            warn!("API key {} ({}) deleted", key.id, key.name);
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => {
+            routes::record_db_operation(&db_counter, "delete", "api_keys", false);
+            error!("Error deleting API key {}: {}", key_id, e);
+            ApiError::internal(format!("Error deleting API key: {}", e)).error_response()
+        }
+    }
+}
+
+/// Wrapper for the Prometheus registry all service metrics are registered in
+#[derive(Clone)]
+pub struct MetricsRegistry(pub Registry);
+
+/// Estimate a quantile from cumulative histogram buckets, interpolating linearly inside
+/// the bucket it falls in (the same approach as PromQL's `histogram_quantile`)
+fn histogram_quantile(histogram: &Histogram, quantile: f64) -> Option<f64> {
+    let count = histogram.get_sample_count();
+    if count == 0 {
+        return None;
+    }
+    let rank = quantile * count as f64;
+
+    let mut lower_bound = 0.0;
+    let mut lower_count = 0u64;
+    for bucket in histogram.get_bucket() {
+        let upper_bound = bucket.get_upper_bound();
+        let cumulative = bucket.get_cumulative_count();
+        if cumulative as f64 >= rank {
+            if upper_bound.is_infinite() {
+                return Some(lower_bound);
+            }
+            let in_bucket = (cumulative - lower_count) as f64;
+            if in_bucket == 0.0 {
+                return Some(upper_bound);
+            }
+            return Some(lower_bound + (upper_bound - lower_bound) * (rank - lower_count as f64) / in_bucket);
+        }
+        lower_bound = upper_bound;
+        lower_count = cumulative;
+    }
+    // Rank lies in the implicit +Inf bucket
+    Some(lower_bound)
+}
+
+/// Get latency percentiles
+///
+/// Returns p50/p90/p99 (in seconds) for every latency histogram the service records, per label
+/// set (e.g. per endpoint). Values are cumulative since startup, not a recent window, and are
+/// bucket estimates rather than exact figures.
+#[utoipa::path(
+    get,
+    path = "/admin/latency",
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token (ADMIN_TOKEN)")
+    ),
+    responses(
+        (status = 200, description = "Latency percentiles since startup", body = LatencySnapshot),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 403, description = "Admin endpoints are disabled")
+    )
+)]
+#[get("/admin/latency")]
+pub async fn get_latency_percentiles(
+    req: HttpRequest,
+    registry: web::Data<MetricsRegistry>,
+) -> impl Responder {
+    if let Err(e) = require_admin(&req) {
+        return e.error_response();
+    }
+
+    let mut histograms = Vec::new();
+    for family in registry.0.gather() {
+        if family.get_field_type() != MetricType::HISTOGRAM {
+            continue;
+        }
+        for metric in family.get_metric() {
+            let histogram = metric.get_histogram();
+            let labels: BTreeMap<String, String> = metric.get_label()
+                .iter()
+                .map(|label| (label.get_name().to_string(), label.get_value().to_string()))
+                .collect();
+            histograms.push(LatencyPercentiles {
+                metric: family.get_name().to_string(),
+                labels,
+                count: histogram.get_sample_count(),
+                p50: histogram_quantile(histogram, 0.50),
+                p90: histogram_quantile(histogram, 0.90),
+                p99: histogram_quantile(histogram, 0.99),
+            });
+        }
+    }
+
+    HttpResponse::Ok().json(LatencySnapshot {
+        since_startup: true,
+        histograms,
+    })
+}
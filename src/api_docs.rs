@@ -1,9 +1,23 @@
-use utoipa::OpenApi;
+use utoipa::openapi::{self, Content, Ref};
+use utoipa::{Modify, OpenApi};
+use crate::problem_json_middleware::PROBLEM_JSON;
 use crate::models::{
-    Board, CreateBoardRequest,
-    Post, CreatePostRequest,
-    Comment, CreateCommentRequest,
-    HealthResponse,
+    Board, CreateBoardRequest, UpdateBoardRequest, BoardStatsRequest, BoardStats,
+    Category, CreateCategoryRequest, BoardGroup, GroupedBoardsResponse,
+    Post, PostWithBoard, PostRevision, CreatePostRequest, UpdatePostRequest, LockPostRequest, PostChangesResponse, PostTitleMatch, TagUsage,
+    Comment, CreateCommentRequest, UpdateCommentRequest, CommentCount,
+    VoteRequest, VoteResponse, ReactionCount, ReactionsResponse,
+    SearchKind, SearchHighlights, SearchHit,
+    HealthResponse, ErrorResponse, FieldError, ProblemDetails,
+    CacheEntryType, CacheRefreshRequest, CacheStats, CacheStatsResponse,
+    MaintenanceRequest, MaintenanceStatus,
+    LatencyPercentiles, LatencySnapshot,
+    PoolNode, PoolStats, InFlightRequestInfo,
+    Subscription, SubscriptionRequest,
+    FullThread,
+    User, Role, RoleUpdateRequest, RegisterRequest, LoginRequest, PasswordChangeRequest, TokenResponse, RefreshRequest,
+    ApiKey, ApiKeyScope, CreateApiKeyRequest, CreatedApiKey, UpdateApiKeyRequest,
+    AccountDeletionResponse, DeletionJob, DeletionJobStatus,
 };
 
 /// Generate OpenAPI documentation for our REST API
@@ -14,24 +28,137 @@ use crate::models::{
         crate::routes::create_board,
         crate::routes::get_boards,
         crate::routes::get_board,
+        crate::routes::get_board_by_slug,
+        crate::routes::update_board,
+        crate::board_deletion::delete_board,
+        crate::board_deletion::restore_board,
+        crate::board_deletion::get_deletion_job,
+        crate::categories::create_category,
+        crate::categories::get_categories,
+        crate::routes::get_boards_stats,
         crate::routes::create_post,
         crate::routes::get_posts_by_board,
+        crate::title_search::search_posts_by_title,
+        crate::subscriptions::subscribe_to_board,
+        crate::subscriptions::unsubscribe_from_board,
+        crate::subscriptions::get_author_subscriptions,
         crate::routes::get_post,
+        crate::routes::get_full_post,
+        crate::routes::update_post,
+        crate::post_revisions::get_post_revisions,
+        crate::post_revisions::get_post_revision,
+        crate::routes::delete_post,
+        crate::routes::restore_post,
+        crate::routes::lock_post,
+        crate::votes::vote_on_post,
+        crate::reactions::add_post_reaction,
+        crate::reactions::remove_post_reaction,
+        crate::routes::get_post_changes,
+        crate::routes::get_posts_by_tag,
+        crate::routes::get_popular_tags,
         crate::routes::create_comment,
+        crate::routes::update_comment,
+        crate::routes::delete_comment,
+        crate::routes::restore_comment,
+        crate::votes::vote_on_comment,
+        crate::reactions::add_comment_reaction,
+        crate::reactions::remove_comment_reaction,
         crate::routes::get_comments_by_post,
+        crate::routes::count_comments_by_post,
+        crate::routes::get_recent_board_comments,
+        crate::search::search,
+        crate::auth::register,
+        crate::auth::login,
+        crate::auth::issue_token,
+        crate::auth::change_password,
+        crate::auth::refresh_token,
+        crate::auth::logout,
+        crate::oauth::start,
+        crate::oauth::callback,
+        crate::account_erasure::delete_account,
+        crate::author_content::get_author_posts,
+        crate::author_content::get_author_comments,
         crate::routes::slow_endpoint,
+        crate::admin::refresh_cache_entry,
+        crate::admin::set_maintenance,
+        crate::admin::get_cache_stats,
+        crate::admin::get_latency_percentiles,
+        crate::admin::get_pool_stats,
+        crate::admin::get_in_flight_requests,
+        crate::admin::set_user_role,
+        crate::admin::create_api_key,
+        crate::admin::list_api_keys,
+        crate::admin::update_api_key,
+        crate::admin::delete_api_key,
     ),
     components(
         schemas(
             Board, 
             CreateBoardRequest, 
+            UpdateBoardRequest,
+            BoardStatsRequest,
+            BoardStats,
+            Category,
+            CreateCategoryRequest,
+            BoardGroup,
+            GroupedBoardsResponse,
             Post, 
+            PostWithBoard,
+            PostRevision,
             CreatePostRequest, 
+            UpdatePostRequest,
+            LockPostRequest,
+            PostChangesResponse,
+            PostTitleMatch,
+            TagUsage,
             Comment, 
             CreateCommentRequest, 
-            HealthResponse
+            UpdateCommentRequest,
+            CommentCount,
+            VoteRequest,
+            VoteResponse,
+            ReactionCount,
+            ReactionsResponse,
+            SearchKind,
+            SearchHighlights,
+            SearchHit,
+            HealthResponse,
+            ErrorResponse,
+            FieldError,
+            ProblemDetails,
+            CacheEntryType,
+            CacheRefreshRequest,
+            MaintenanceRequest,
+            MaintenanceStatus,
+            CacheStats,
+            CacheStatsResponse,
+            LatencyPercentiles,
+            LatencySnapshot,
+            PoolNode,
+            PoolStats,
+            InFlightRequestInfo,
+            Subscription,
+            SubscriptionRequest,
+            FullThread,
+            User,
+            Role,
+            RoleUpdateRequest,
+            RegisterRequest,
+            LoginRequest,
+            PasswordChangeRequest,
+            TokenResponse,
+            RefreshRequest,
+            ApiKey,
+            ApiKeyScope,
+            CreateApiKeyRequest,
+            CreatedApiKey,
+            UpdateApiKeyRequest,
+            AccountDeletionResponse,
+            DeletionJob,
+            DeletionJobStatus
         )
     ),
+    modifiers(&ErrorBodies),
     info(
         title = "Forum API",
         version = "1.0.0",
@@ -46,4 +173,30 @@ use crate::models::{
         )
     )
 )]
-pub struct ApiDoc; 
\ No newline at end of file
+pub struct ApiDoc; 
+
+/// Documents the error bodies (see `errors::ApiError`) on every 4xx and 5xx response the paths
+/// declare without a body of their own: `ErrorResponse`, or `ProblemDetails` for clients
+/// accepting `application/problem+json`
+struct ErrorBodies;
+
+impl Modify for ErrorBodies {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let operations = openapi.paths.paths.values_mut().flat_map(|item| item.operations.values_mut());
+        for operation in operations {
+            for (status, response) in operation.responses.responses.iter_mut() {
+                let openapi::RefOr::T(response) = response else { continue };
+                if (status.starts_with('4') || status.starts_with('5')) && response.content.is_empty() {
+                    response.content.insert(
+                        "application/json".to_string(),
+                        Content::new(Ref::from_schema_name("ErrorResponse")),
+                    );
+                    response.content.insert(
+                        PROBLEM_JSON.to_string(),
+                        Content::new(Ref::from_schema_name("ProblemDetails")),
+                    );
+                }
+            }
+        }
+    }
+}
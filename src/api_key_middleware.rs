@@ -0,0 +1,133 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{web, Error, HttpMessage, ResponseError};
+use futures_util::future::LocalBoxFuture;
+use scylla::Session;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+use crate::api_keys;
+use crate::errors::ApiError;
+use crate::models::ApiKeyScope;
+
+/// Header automated clients send their API key in
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// The API key a request was authenticated with, stored in the request extensions
+#[derive(Clone, Debug)]
+pub struct ApiKeyClient {
+    pub key_id: Uuid,
+    pub name: String,
+}
+
+/// Scope a request needs: reads for safe methods, writes for everything else
+fn required_scope(method: &Method) -> ApiKeyScope {
+    match *method {
+        Method::GET | Method::HEAD => ApiKeyScope::Read,
+        _ => ApiKeyScope::Write,
+    }
+}
+
+/// Check the request's API key, if any: `Err` is the rejection to send
+async fn check_api_key(req: &ServiceRequest, value: &str) -> Result<ApiKeyClient, ApiError> {
+    let session = req
+        .app_data::<web::Data<Arc<Session>>>()
+        .ok_or_else(|| ApiError::unavailable("Unable to verify the API key, please retry"))?;
+
+    let key = match api_keys::authenticate(session, value).await {
+        Ok(Some(key)) => key,
+        Ok(None) => {
+            warn!("Rejecting {} {}: invalid API key", req.method(), req.path());
+            return Err(ApiError::unauthorized("Invalid API key"));
+        }
+        Err(e) => {
+            error!("Error checking API key: {}", e);
+            return Err(ApiError::unavailable("Unable to verify the API key, please retry"));
+        }
+    };
+
+    let scope = required_scope(req.method());
+    if !key.scopes.contains(&scope) {
+        debug!("API key {} ({}) lacks scope {} for {} {}", key.id, key.name, scope.as_str(), req.method(), req.path());
+        return Err(ApiError::forbidden(format!("This API key lacks the {} scope", scope.as_str())));
+    }
+
+    if let Err(retry_after) = api_keys::check_rate_limit(&key) {
+        debug!("API key {} ({}) is over its limit of {} requests per minute", key.id, key.name, key.rate_limit_per_minute);
+        return Err(ApiError::TooManyRequests {
+            message: format!("Rate limit of {} requests per minute exceeded", key.rate_limit_per_minute),
+            retry_after_secs: retry_after,
+        });
+    }
+
+    Ok(ApiKeyClient { key_id: key.id, name: key.name })
+}
+
+// Middleware factory authenticating automated clients by `X-Api-Key`, enforcing the key's
+// scopes and rate limit. Requests without the header pass through untouched.
+pub struct ApiKeyAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for ApiKeyAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ApiKeyAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ApiKeyAuthMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct ApiKeyAuthMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ApiKeyAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let value = req
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string());
+
+            if let Some(value) = value {
+                match check_api_key(&req, &value).await {
+                    Ok(client) => {
+                        req.extensions_mut().insert(client);
+                    }
+                    Err(e) => return Ok(req.into_response(e.error_response()).map_into_right_body()),
+                }
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
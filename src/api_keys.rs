@@ -0,0 +1,192 @@
+//! API keys of automated clients (scrapers, bridge bots).
+//!
+//! A key is `<key id>.<secret>`; like refresh tokens, only a SHA-256 of the secret is stored.
+//! Keys are not users: a `write` key stands in for a bearer token only when creating boards,
+//! posts and comments, while edits, deletes and moderation still need a bearer token of the
+//! owner or a moderator, or the admin token.
+//! Keys looked up by the middleware are cached for 30 seconds, so a revoked or changed key
+//! takes at most that long to stop working on other instances. Rate limits are counted per
+//! instance in fixed one-minute windows.
+
+use chrono::{TimeZone, Utc};
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use crate::models::{ApiKey, ApiKeyScope};
+use crate::routes::execute_cached;
+use crate::sessions::{hash_secret, new_secret};
+
+const CACHE_TTL: Duration = Duration::from_secs(30);
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// A cached lookup: the key with the digest of its secret (`None` for unknown ids), and when it
+/// was read
+type CachedKey = (Option<(ApiKey, String)>, Instant);
+
+/// Keys by id
+static CACHE: OnceLock<Mutex<HashMap<Uuid, CachedKey>>> = OnceLock::new();
+/// Start of each key's current rate-limit window and the requests counted in it
+static WINDOWS: OnceLock<Mutex<HashMap<Uuid, (Instant, u32)>>> = OnceLock::new();
+
+fn lock<T>(cell: &'static OnceLock<Mutex<HashMap<Uuid, T>>>) -> MutexGuard<'static, HashMap<Uuid, T>> {
+    cell.get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+type ApiKeyRow = (Uuid, Option<String>, Option<String>, Option<Vec<String>>, Option<i32>, Option<i64>);
+
+fn from_row((id, name, key_hash, scopes, rate_limit, created_at): ApiKeyRow) -> (ApiKey, String) {
+    let key = ApiKey {
+        id,
+        name: name.unwrap_or_default(),
+        scopes: scopes
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|scope| ApiKeyScope::from_column(scope))
+            .collect(),
+        rate_limit_per_minute: rate_limit.unwrap_or(0).max(0) as u32,
+        created_at: created_at
+            .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+            .unwrap_or_default(),
+    };
+    (key, key_hash.unwrap_or_default())
+}
+
+fn scope_names(scopes: &[ApiKeyScope]) -> Vec<&'static str> {
+    scopes.iter().map(|scope| scope.as_str()).collect()
+}
+
+/// Store a new key, returning it with the value clients send in `X-Api-Key`
+pub async fn create(
+    session: &Session,
+    name: String,
+    scopes: Vec<ApiKeyScope>,
+    rate_limit_per_minute: u32,
+) -> Result<(ApiKey, String), QueryError> {
+    let key = ApiKey {
+        id: Uuid::new_v4(),
+        name,
+        scopes,
+        rate_limit_per_minute,
+        created_at: Utc::now(),
+    };
+    let secret = new_secret();
+    execute_cached(
+        session,
+        "INSERT INTO api_keys (id, name, key_hash, scopes, rate_limit_per_minute, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        (
+            key.id,
+            &key.name,
+            hash_secret(&secret),
+            scope_names(&key.scopes),
+            key.rate_limit_per_minute as i32,
+            key.created_at.timestamp_millis(),
+        ),
+    ).await?;
+    let value = format!("{}.{}", key.id, secret);
+    Ok((key, value))
+}
+
+/// All keys, oldest first
+pub async fn list(session: &Session) -> Result<Vec<ApiKey>, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT id, name, key_hash, scopes, rate_limit_per_minute, created_at FROM api_keys",
+        (),
+    ).await?;
+    let mut keys: Vec<ApiKey> = rows
+        .rows_typed::<ApiKeyRow>()
+        .map(|rows| rows.filter_map(Result::ok).map(|row| from_row(row).0).collect())
+        .unwrap_or_default();
+    keys.sort_by_key(|key| key.created_at);
+    Ok(keys)
+}
+
+async fn fetch_with_hash(session: &Session, id: Uuid) -> Result<Option<(ApiKey, String)>, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT id, name, key_hash, scopes, rate_limit_per_minute, created_at FROM api_keys WHERE id = ?",
+        (id,),
+    ).await?;
+    Ok(rows.maybe_first_row_typed::<ApiKeyRow>().ok().flatten().map(from_row))
+}
+
+pub async fn fetch(session: &Session, id: Uuid) -> Result<Option<ApiKey>, QueryError> {
+    Ok(fetch_with_hash(session, id).await?.map(|(key, _)| key))
+}
+
+/// Save a key's name, scopes and rate limit; `false` when it was deleted meanwhile
+pub async fn update(session: &Session, key: &ApiKey) -> Result<bool, QueryError> {
+    let result = execute_cached(
+        session,
+        "UPDATE api_keys SET name = ?, scopes = ?, rate_limit_per_minute = ? WHERE id = ? IF EXISTS",
+        (&key.name, scope_names(&key.scopes), key.rate_limit_per_minute as i32, key.id),
+    ).await?;
+    lock(&CACHE).remove(&key.id);
+    Ok(result
+        .rows
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true))
+}
+
+pub async fn delete(session: &Session, id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM api_keys WHERE id = ?", (id,)).await?;
+    lock(&CACHE).remove(&id);
+    lock(&WINDOWS).remove(&id);
+    Ok(())
+}
+
+/// The key an `X-Api-Key` value belongs to, if it is valid
+pub async fn authenticate(session: &Session, value: &str) -> Result<Option<ApiKey>, QueryError> {
+    let (id, secret) = match value.split_once('.').and_then(|(id, secret)| Some((Uuid::parse_str(id).ok()?, secret))) {
+        Some(parsed) => parsed,
+        None => return Ok(None),
+    };
+
+    let cached = lock(&CACHE)
+        .get(&id)
+        .filter(|(_, read_at)| read_at.elapsed() < CACHE_TTL)
+        .map(|(stored, _)| stored.clone());
+    let stored = match cached {
+        Some(stored) => stored,
+        None => {
+            let stored = fetch_with_hash(session, id).await?;
+            let mut cache = lock(&CACHE);
+            // Drop stale entries now and then, since unknown ids are cached too
+            if cache.len() > 10_000 {
+                cache.retain(|_, (_, read_at)| read_at.elapsed() < CACHE_TTL);
+            }
+            cache.insert(id, (stored.clone(), Instant::now()));
+            stored
+        }
+    };
+
+    Ok(stored
+        .filter(|(_, key_hash)| *key_hash == hash_secret(secret))
+        .map(|(key, _)| key))
+}
+
+/// Count a request against the key's limit; `Err` carries the seconds until the next window
+pub fn check_rate_limit(key: &ApiKey) -> Result<(), u64> {
+    let mut windows = lock(&WINDOWS);
+    let now = Instant::now();
+    let (started, count) = windows.entry(key.id).or_insert((now, 0));
+    if now.duration_since(*started) >= RATE_WINDOW {
+        *started = now;
+        *count = 0;
+    }
+    if *count >= key.rate_limit_per_minute {
+        let remaining = RATE_WINDOW.saturating_sub(now.duration_since(*started));
+        return Err(remaining.as_secs().max(1));
+    }
+    *count += 1;
+    Ok(())
+}
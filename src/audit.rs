@@ -0,0 +1,34 @@
+//! Audit log of security-relevant events (account erasure, ...).
+//!
+//! Events go to the `audit_log` table, partitioned by UTC day, and to the `audit` tracing
+//! target, so they are kept even when the database write fails.
+
+use chrono::Utc;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use tracing::info;
+use uuid::Uuid;
+use crate::db;
+use crate::routes::execute_cached;
+
+/// Record an event, returning its id
+///
+/// `actor_id` is who performed the action (`None` for the system), `subject_id` what it was
+/// performed on.
+pub async fn record(
+    session: &Session,
+    action: &str,
+    actor_id: Option<Uuid>,
+    subject_id: Uuid,
+    details: &str,
+) -> Result<Uuid, QueryError> {
+    let id = Uuid::new_v4();
+    let created_at = Utc::now().timestamp_millis();
+    info!(target: "audit", %id, action, ?actor_id, %subject_id, details, "audit event");
+    execute_cached(
+        session,
+        "INSERT INTO audit_log (day, created_at, id, action, actor_id, subject_id, details) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        (db::updated_day(created_at), created_at, id, action, actor_id, subject_id, details),
+    ).await?;
+    Ok(id)
+}
@@ -0,0 +1,699 @@
+//! User accounts: registration, password login and change, access and refresh tokens and
+//! resolving the account behind a post or comment.
+//!
+//! Passwords live in the `credentials` table as argon2id PHC strings (algorithm, parameters
+//! and salt included) tagged with the hashing scheme version. A successful login with an
+//! older scheme rehashes the password with the current one, so raising the cost only needs a
+//! new entry in `hasher`.
+
+use actix_web::{post, web, HttpResponse, Responder, ResponseError};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use scylla::batch::BatchType;
+use chrono::{TimeZone, Utc};
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::sync::Arc;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+use crate::config;
+use crate::db;
+use crate::db_client;
+use crate::errors::ApiError;
+use crate::jwt_middleware::{self, AuthenticatedUser};
+use crate::models::{LoginRequest, PasswordChangeRequest, RefreshRequest, RegisterRequest, Role, TokenResponse, User};
+use crate::routes::{self, execute_cached, get_or_prepare, record_db_operation, DbCounter};
+use crate::sessions::{self, RefreshError};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+/// Hashing cost grows with the input, so overly long passwords are refused up front
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+fn validate_password(password: &str) -> Result<(), String> {
+    let length = password.chars().count();
+    if length < MIN_PASSWORD_LENGTH {
+        return Err(format!("password must be at least {} characters", MIN_PASSWORD_LENGTH));
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        return Err(format!("password must be at most {} characters", MAX_PASSWORD_LENGTH));
+    }
+    Ok(())
+}
+
+/// Key of `users_by_username`: usernames are unique regardless of case
+pub(crate) fn username_key(username: &str) -> String {
+    username.to_lowercase()
+}
+
+/// Hashing scheme new passwords are stored with
+const CURRENT_SCHEME_VERSION: i32 = 1;
+
+/// Hasher of a scheme version
+///
+/// Verification reads the parameters from the stored PHC string, so old versions only need
+/// to stay listed here while they are still the current one.
+fn hasher(version: i32) -> Result<Argon2<'static>, String> {
+    let params = match version {
+        // argon2id, 19 MiB, 2 passes, 1 lane (OWASP minimum)
+        1 => Params::new(19 * 1024, 2, 1, None),
+        other => return Err(format!("unknown password scheme version {}", other)),
+    };
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params.map_err(|e| e.to_string())?))
+}
+
+/// Hash a password with the current scheme into a PHC string
+fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    hasher(CURRENT_SCHEME_VERSION)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Check a password against a stored PHC string, whatever parameters it was hashed with
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+/// Hash off the async workers: argon2 takes tens of milliseconds of CPU by design
+async fn hash_password_blocking(password: String) -> Result<String, String> {
+    web::block(move || hash_password(&password))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+async fn verify_password_blocking(password: String, hash: String) -> Result<bool, String> {
+    web::block(move || verify_password(&password, &hash))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) async fn fetch_user(session: &Session, user_id: Uuid) -> Result<Option<User>, QueryError> {
+    let rows = execute_cached(session, "SELECT id, username, created_at, role FROM users WHERE id = ?", (user_id,)).await?;
+    let row = rows
+        .maybe_first_row_typed::<(Uuid, Option<String>, Option<i64>, Option<String>)>()
+        .ok()
+        .flatten();
+    Ok(row.map(|(id, username, created_at, role)| User {
+        id,
+        username: username.unwrap_or_default(),
+        role: Role::from_column(role.as_deref()),
+        created_at: created_at
+            .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+            .unwrap_or_else(Utc::now),
+    }))
+}
+
+/// Stored password hash of a user and the scheme version it was hashed with
+async fn fetch_credentials(session: &Session, user_id: Uuid) -> Result<Option<(String, i32)>, QueryError> {
+    let rows = execute_cached(session, "SELECT password_hash, scheme_version FROM credentials WHERE user_id = ?", (user_id,)).await?;
+    Ok(rows
+        .maybe_first_row_typed::<(Option<String>, Option<i32>)>()
+        .ok()
+        .flatten()
+        .and_then(|(hash, version)| hash.map(|hash| (hash, version.unwrap_or(1)))))
+}
+
+async fn store_credentials(session: &Session, user_id: Uuid, password_hash: &str) -> Result<(), QueryError> {
+    execute_cached(
+        session,
+        "INSERT INTO credentials (user_id, password_hash, scheme_version, updated_at) VALUES (?, ?, ?, ?)",
+        (user_id, password_hash, CURRENT_SCHEME_VERSION, Utc::now().timestamp_millis()),
+    ).await?;
+    Ok(())
+}
+
+/// Id of the user holding this username, if any
+async fn find_user_id(session: &Session, username: &str) -> Result<Option<Uuid>, QueryError> {
+    let rows = execute_cached(session, "SELECT user_id FROM users_by_username WHERE username = ?", (username_key(username),)).await?;
+    Ok(rows
+        .maybe_first_row_typed::<(Option<Uuid>,)>()
+        .ok()
+        .flatten()
+        .and_then(|(user_id,)| user_id))
+}
+
+/// Account a new post or comment is written as: the bearer token's user
+///
+/// A `user_id` in the body must name that same user; without a bearer token it is refused,
+/// since API keys and unauthenticated writes could otherwise post as anyone.
+fn writing_account(authenticated: Option<&AuthenticatedUser>, user_id: Option<Uuid>) -> Result<Option<Uuid>, ApiError> {
+    match (authenticated, user_id) {
+        (Some(authenticated), Some(user_id)) if user_id != authenticated.user_id => {
+            warn!("Rejecting user_id {} from user {} ({})", user_id, authenticated.user_id, authenticated.username);
+            Err(ApiError::forbidden("user_id must match the authenticated user"))
+        }
+        (Some(authenticated), _) => Ok(Some(authenticated.user_id)),
+        (None, Some(user_id)) => {
+            warn!("Rejecting user_id {} without a bearer token", user_id);
+            Err(ApiError::forbidden("user_id requires a bearer token of that user"))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Author name and account of a new post or comment
+///
+/// With a bearer token the author is the user's username and a differing `author` is
+/// rejected; without one the free-form `author` is validated as before.
+pub(crate) async fn resolve_author(
+    session: &Session,
+    authenticated: Option<&AuthenticatedUser>,
+    user_id: Option<Uuid>,
+    author: &str,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<(String, Option<Uuid>), ApiError> {
+    let user_id = match writing_account(authenticated, user_id) {
+        Ok(user_id) => user_id,
+        Err(e) => return Err(e),
+    };
+    let user_id = match user_id {
+        Some(user_id) => user_id,
+        None => {
+            if let Err(message) = routes::validate_author(author) {
+                warn!("Rejecting invalid author {:?}: {}", author, message);
+                return Err(ApiError::bad_request(message));
+            }
+            return Ok((author.to_string(), None));
+        }
+    };
+
+    let user = match fetch_user(session, user_id).await {
+        Ok(Some(user)) => {
+            record_db_operation(db_counter, "select", "users", true);
+            user
+        }
+        Ok(None) => {
+            record_db_operation(db_counter, "select", "users", true);
+            return Err(ApiError::bad_request(format!("User with id {} not found", user_id)));
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "users", false);
+            error!("Error fetching user {}: {}", user_id, e);
+            return Err(ApiError::internal(format!("Error fetching user: {}", e)));
+        }
+    };
+
+    if !author.trim().is_empty() && author != user.username {
+        warn!("Rejecting author {:?} for user {} ({})", author, user.id, user.username);
+        return Err(ApiError::bad_request("author must match the username of user_id"));
+    }
+    Ok((user.username, Some(user.id)))
+}
+
+/// Register a user
+///
+/// Creates an account with a unique username; the password is stored as an argon2id hash.
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "User created", body = User),
+        (status = 400, description = "Invalid username or password"),
+        (status = 409, description = "Username is taken"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/register")]
+pub async fn register(
+    session: web::Data<Arc<Session>>,
+    request: web::Json<RegisterRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let request = request.into_inner();
+    let username = request.username.trim().to_string();
+    if let Err(message) = routes::validate_author(&username) {
+        warn!("Rejecting registration with invalid username {:?}: {}", username, message);
+        return ApiError::bad_request(message.replacen("author", "username", 1)).error_response();
+    }
+    if let Err(message) = validate_password(&request.password) {
+        return ApiError::bad_request(message).error_response();
+    }
+
+    let password_hash = match hash_password_blocking(request.password).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            error!("Error hashing password: {}", e);
+            return ApiError::internal("Error hashing password").error_response();
+        }
+    };
+
+    match create_user(&session, username.clone(), Some(&password_hash), &db_counter).await {
+        Ok(Some(user)) => {
+            info!("Registered user {} ({})", user.username, user.id);
+            HttpResponse::Created().json(user)
+        }
+        Ok(None) => {
+            warn!("Rejecting registration: username {} is taken", username);
+            ApiError::conflict(format!("Username '{}' is taken", username)).error_response()
+        }
+        Err(e) => {
+            error!("Error creating user {}: {}", username, e);
+            ApiError::internal(format!("Error creating user: {}", e)).error_response()
+        }
+    }
+}
+
+/// Create an account, returning `None` when the username is taken
+///
+/// Accounts without a password hash (signed up through an OAuth provider) have no
+/// credentials row and can't log in with a password.
+pub(crate) async fn create_user(
+    session: &Session,
+    username: String,
+    password_hash: Option<&str>,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<Option<User>, QueryError> {
+    let user = User {
+        id: config::get().id_scheme.new_id(),
+        username,
+        role: Role::User,
+        created_at: Utc::now(),
+    };
+
+    // Claim the username first; the lightweight transaction makes concurrent claims exclusive
+    let claim = execute_cached(
+        session,
+        "INSERT INTO users_by_username (username, user_id) VALUES (?, ?) IF NOT EXISTS",
+        (username_key(&user.username), user.id),
+    ).await;
+    let claimed = match claim {
+        Ok(result) => result
+            .rows
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.columns.first())
+            .and_then(|c| c.as_ref())
+            .and_then(|c| c.as_boolean())
+            .unwrap_or(true),
+        Err(e) => {
+            record_db_operation(db_counter, "insert", "users_by_username", false);
+            return Err(e);
+        }
+    };
+    record_db_operation(db_counter, "insert", "users_by_username", true);
+    if !claimed {
+        return Ok(None);
+    }
+
+    // The account and its credentials are written together in a logged batch
+    let created_at_millis = user.created_at.timestamp_millis();
+    let result = match password_hash {
+        Some(password_hash) => async {
+            let mut batch = db::new_batch(BatchType::Logged);
+            for cql in [
+                "INSERT INTO users (id, username, created_at, role) VALUES (?, ?, ?, ?)",
+                "INSERT INTO credentials (user_id, password_hash, scheme_version, updated_at) VALUES (?, ?, ?, ?)",
+            ] {
+                batch.append_statement(get_or_prepare(session, cql).await?);
+            }
+            db_client::call(session.batch(
+                &batch,
+                (
+                    (user.id, &user.username, created_at_millis, user.role.as_str()),
+                    (user.id, password_hash, CURRENT_SCHEME_VERSION, created_at_millis),
+                ),
+            )).await?;
+            Ok::<(), QueryError>(())
+        }.await,
+        None => execute_cached(
+            session,
+            "INSERT INTO users (id, username, created_at, role) VALUES (?, ?, ?, ?)",
+            (user.id, &user.username, created_at_millis, user.role.as_str()),
+        ).await.map(|_| ()),
+    };
+
+    match result {
+        Ok(()) => {
+            record_db_operation(db_counter, "insert", "users", true);
+            Ok(Some(user))
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "insert", "users", false);
+            // Release the name so the registration can be retried
+            if let Err(e) = execute_cached(
+                session,
+                "DELETE FROM users_by_username WHERE username = ? IF user_id = ?",
+                (username_key(&user.username), user.id),
+            ).await {
+                error!("Error releasing username {}: {}", user.username, e);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Check a username and password, answering 401 when either is wrong
+///
+/// Passwords hashed with an older scheme are rehashed with the current one on success.
+async fn authenticate(
+    session: &Session,
+    username: &str,
+    password: String,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<User, ApiError> {
+    let username = username.trim();
+
+    let found = match find_user_id(session, username).await {
+        Ok(Some(user_id)) => match (fetch_user(session, user_id).await, fetch_credentials(session, user_id).await) {
+            (Ok(Some(user)), Ok(Some(credentials))) => Ok(Some((user, credentials))),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+            _ => Ok(None),
+        },
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    let (user, (password_hash, scheme_version)) = match found {
+        Ok(Some(found)) => {
+            record_db_operation(db_counter, "select", "credentials", true);
+            found
+        }
+        Ok(None) => {
+            record_db_operation(db_counter, "select", "credentials", true);
+            warn!("Failed login for unknown username {:?}", username);
+            return Err(ApiError::unauthorized("Invalid username or password"));
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "credentials", false);
+            error!("Error fetching user {}: {}", username, e);
+            return Err(ApiError::internal(format!("Error fetching user: {}", e)));
+        }
+    };
+
+    match verify_password_blocking(password.clone(), password_hash).await {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("Failed login for user {}", user.id);
+            return Err(ApiError::unauthorized("Invalid username or password"));
+        }
+        Err(e) => {
+            error!("Password verification task failed: {}", e);
+            return Err(ApiError::internal("Error verifying password"));
+        }
+    }
+
+    if scheme_version < CURRENT_SCHEME_VERSION {
+        // The plaintext is only available now, so the upgrade happens here; failing it is harmless
+        let upgraded = match hash_password_blocking(password).await {
+            Ok(hash) => store_credentials(session, user.id, &hash).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match upgraded {
+            Ok(()) => info!("Upgraded password hash of user {} from scheme {} to {}", user.id, scheme_version, CURRENT_SCHEME_VERSION),
+            Err(e) => warn!("Failed to upgrade password hash of user {}: {}", user.id, e),
+        }
+    }
+
+    Ok(user)
+}
+
+/// Log in
+///
+/// Checks a username and password and returns the account.
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Credentials are valid", body = User),
+        (status = 401, description = "Unknown username or wrong password"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/login")]
+pub async fn login(
+    session: web::Data<Arc<Session>>,
+    request: web::Json<LoginRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let request = request.into_inner();
+    match authenticate(&session, &request.username, request.password, &db_counter).await {
+        Ok(user) => {
+            info!("User {} logged in", user.id);
+            HttpResponse::Ok().json(user)
+        }
+        Err(e) => e.error_response(),
+    }
+}
+
+/// Access token for a login session together with the session's refresh token
+pub(crate) fn token_response(user: &User, session_id: Uuid, refresh_secret: String) -> HttpResponse {
+    match jwt_middleware::issue_token(user, session_id) {
+        Ok((access_token, expires_in)) => HttpResponse::Ok().json(TokenResponse {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in,
+            refresh_token: refresh_secret,
+            refresh_expires_in: config::get().refresh_token_ttl_secs,
+        }),
+        Err(e) => {
+            error!("Error signing token for user {}: {}", user.id, e);
+            ApiError::internal("Error issuing token").error_response()
+        }
+    }
+}
+
+pub(crate) fn tokens_disabled() -> Option<HttpResponse> {
+    config::get()
+        .jwt_secret
+        .is_none()
+        .then(|| ApiError::unavailable("Token authentication is disabled (JWT_SECRET is not set)").error_response())
+}
+
+/// Issue an access token
+///
+/// Exchanges a username and password for a short-lived bearer token (`JWT_TTL_SECS`),
+/// required for creating boards, posts and comments while `JWT_SECRET` is set, and a refresh
+/// token (`REFRESH_TOKEN_TTL_SECS`) for getting new ones.
+#[utoipa::path(
+    post,
+    path = "/auth/token",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Token issued", body = TokenResponse),
+        (status = 401, description = "Unknown username or wrong password"),
+        (status = 503, description = "Token authentication is disabled (JWT_SECRET is not set)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/token")]
+pub async fn issue_token(
+    session: web::Data<Arc<Session>>,
+    request: web::Json<LoginRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Some(response) = tokens_disabled() {
+        return response;
+    }
+
+    let request = request.into_inner();
+    let user = match authenticate(&session, &request.username, request.password, &db_counter).await {
+        Ok(user) => user,
+        Err(e) => return e.error_response(),
+    };
+
+    let (session_id, refresh_secret) = match sessions::create(&session, user.id).await {
+        Ok(created) => {
+            record_db_operation(&db_counter, "insert", "sessions", true);
+            created
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "insert", "sessions", false);
+            error!("Error creating session for user {}: {}", user.id, e);
+            return ApiError::internal(format!("Error creating session: {}", e)).error_response();
+        }
+    };
+
+    info!("Issued tokens to user {} (session {})", user.id, session_id);
+    token_response(&user, session_id, refresh_secret)
+}
+
+/// Refresh an access token
+///
+/// Exchanges a refresh token for a new access token and a new refresh token; the old refresh
+/// token stops working. Presenting it again revokes the whole session.
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Tokens issued", body = TokenResponse),
+        (status = 401, description = "Invalid, expired, revoked or reused refresh token"),
+        (status = 503, description = "Token authentication is disabled (JWT_SECRET is not set)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/refresh")]
+pub async fn refresh_token(
+    session: web::Data<Arc<Session>>,
+    request: web::Json<RefreshRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Some(response) = tokens_disabled() {
+        return response;
+    }
+
+    let (session_id, user_id, refresh_token) = match sessions::rotate(&session, &request.refresh_token).await {
+        Ok(rotated) => {
+            record_db_operation(&db_counter, "update", "sessions", true);
+            rotated
+        }
+        Err(RefreshError::Invalid) => {
+            record_db_operation(&db_counter, "update", "sessions", true);
+            return ApiError::unauthorized("Invalid or expired refresh token").error_response();
+        }
+        Err(RefreshError::Reused) => {
+            record_db_operation(&db_counter, "update", "sessions", true);
+            return ApiError::unauthorized("Refresh token was already used; the session has been revoked").error_response();
+        }
+        Err(RefreshError::Query(e)) => {
+            record_db_operation(&db_counter, "update", "sessions", false);
+            error!("Error refreshing session: {}", e);
+            return ApiError::internal(format!("Error refreshing session: {}", e)).error_response();
+        }
+    };
+
+    // Reload the user, so a changed role or username reaches the new access token
+    let user = match fetch_user(&session, user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return ApiError::unauthorized("Invalid or expired refresh token").error_response(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "users", false);
+            error!("Error fetching user {}: {}", user_id, e);
+            return ApiError::internal(format!("Error fetching user: {}", e)).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "users", true);
+
+    token_response(&user, session_id, refresh_token)
+}
+
+/// Log out
+///
+/// Revokes the refresh token's session, which also invalidates its access tokens (on other
+/// instances within 30 seconds). Only the session's current token revokes it; unknown or
+/// already-rotated tokens are accepted and ignored, so logging out twice is harmless.
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    request_body = RefreshRequest,
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/logout")]
+pub async fn logout(
+    session: web::Data<Arc<Session>>,
+    request: web::Json<RefreshRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    match sessions::revoke_token(&session, &request.refresh_token).await {
+        Ok(()) => {
+            record_db_operation(&db_counter, "delete", "sessions", true);
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "sessions", false);
+            error!("Error revoking session: {}", e);
+            ApiError::internal(format!("Error revoking session: {}", e)).error_response()
+        }
+    }
+}
+
+/// Change a password
+///
+/// Replaces the password after checking the current one and revokes the user's login
+/// sessions, so refresh tokens issued under the old password stop working (and so do their
+/// access tokens). With a bearer token of the same user, its own session is kept.
+#[utoipa::path(
+    post,
+    path = "/auth/password",
+    request_body = PasswordChangeRequest,
+    responses(
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "New password too short or too long"),
+        (status = 401, description = "Unknown username or wrong current password"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/password")]
+pub async fn change_password(
+    session: web::Data<Arc<Session>>,
+    request: web::Json<PasswordChangeRequest>,
+    authenticated: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let request = request.into_inner();
+    if let Err(message) = validate_password(&request.new_password) {
+        return ApiError::bad_request(message).error_response();
+    }
+
+    let user = match authenticate(&session, &request.username, request.current_password, &db_counter).await {
+        Ok(user) => user,
+        Err(e) => return e.error_response(),
+    };
+
+    let password_hash = match hash_password_blocking(request.new_password).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            error!("Error hashing password: {}", e);
+            return ApiError::internal("Error hashing password").error_response();
+        }
+    };
+
+    if let Err(e) = store_credentials(&session, user.id, &password_hash).await {
+        record_db_operation(&db_counter, "insert", "credentials", false);
+        error!("Error storing password of user {}: {}", user.id, e);
+        return ApiError::internal(format!("Error changing password: {}", e)).error_response();
+    }
+    record_db_operation(&db_counter, "insert", "credentials", true);
+
+    let keep = authenticated
+        .filter(|authenticated| authenticated.user_id == user.id)
+        .and_then(|authenticated| authenticated.session_id);
+    match sessions::revoke_all(&session, user.id, keep).await {
+        Ok(revoked) => {
+            record_db_operation(&db_counter, "delete", "sessions", true);
+            info!("User {} changed their password, {} sessions revoked", user.id, revoked);
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "sessions", false);
+            error!("Error revoking sessions of user {} after a password change: {}", user.id, e);
+            ApiError::database("Password changed, but revoking the other sessions failed", &e).error_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    fn user(user_id: Uuid) -> AuthenticatedUser {
+        AuthenticatedUser { user_id, username: "alice".to_string(), role: Role::User, session_id: None }
+    }
+
+    #[test]
+    fn writing_account_is_the_token_holder() {
+        let alice = Uuid::new_v4();
+        assert_eq!(writing_account(Some(&user(alice)), None).ok(), Some(Some(alice)));
+        assert_eq!(writing_account(Some(&user(alice)), Some(alice)).ok(), Some(Some(alice)));
+        assert_eq!(writing_account(None, None).ok(), Some(None));
+    }
+
+    #[test]
+    fn writing_account_refuses_a_foreign_user_id() {
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let status = |result: Result<Option<Uuid>, ApiError>| result.map_err(|e| e.status_code());
+        assert_eq!(status(writing_account(Some(&user(alice)), Some(bob))), Err(StatusCode::FORBIDDEN));
+        // No bearer token (API key, or JWT_SECRET unset): a user_id can't be claimed at all
+        assert_eq!(status(writing_account(None, Some(bob))), Err(StatusCode::FORBIDDEN));
+    }
+}
@@ -0,0 +1,323 @@
+//! Posts and comments of one author (`GET /users/{author}/posts`, `GET /users/{author}/comments`).
+//!
+//! Served from `posts_by_author` and `comments_by_author`, copies of each post and comment
+//! partitioned by author and clustered newest first, so a page is a read of one partition
+//! instead of a secondary-index lookup. The copies are written with the post or comment and
+//! follow it through edits, soft deletion, restores and purges; account erasure removes them,
+//! since the content no longer belongs to the author.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use actix_web::web::Query;
+use chrono::{TimeZone, Utc};
+use scylla::Session;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::db_client;
+use crate::errors::ApiError;
+use crate::jwt_middleware::AuthenticatedUser;
+use crate::models::{
+    Comment, DeletedFilterParams, PaginatedResponse, PaginationMeta, PaginationParams, Post, TimestampFormatParams,
+};
+use crate::reactions;
+use crate::routes::{
+    deletion_state, empty_list_no_content, fill_post_locks, get_or_prepare, include_deleted, record_db_operation,
+    respond_json, DbCounter,
+};
+use crate::votes;
+
+/// Page metadata of a listing read in clustering order, where only a short page proves the end
+fn page_meta(page: u32, limit: u32, fetched: usize) -> (PaginationMeta, bool) {
+    let has_more = fetched as u32 == limit;
+    let meta = PaginationMeta {
+        page,
+        limit,
+        total: None,
+        total_pages: if has_more { None } else { Some(page) },
+        total_is_estimate: false,
+        sort: None,
+        order: None,
+    };
+    (meta, has_more)
+}
+
+/// Get an author's posts
+///
+/// Returns the posts written under this author name, newest first.
+#[utoipa::path(
+    get,
+    path = "/users/{author}/posts",
+    params(
+        ("author" = String, Path, description = "Author name"),
+        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
+        ("limit" = Option<u32>, Query, description = "Number of posts per page (1-100)", example = 10),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "The author's posts", body = PaginatedResponse<Post>),
+        (status = 204, description = "No posts on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/users/{author}/posts")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_author_posts(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<String>,
+    pagination: Query<PaginationParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let start = Instant::now();
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+
+    let author = path.into_inner();
+    let page = pagination.page.max(1);
+    let limit = pagination.limit().clamp(1, 100);
+
+    let mut prepared = match get_or_prepare(&session, "SELECT id, board_id, title, content, created_at, updated_at, tags, is_deleted, deleted_at FROM posts_by_author WHERE author = ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_author", false);
+            return ApiError::internal(format!("Error preparing query: {}", e)).error_response();
+        }
+    };
+    prepared.set_page_size(limit as i32);
+
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, (&author,))).await {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_author", false);
+            return ApiError::database("Error executing query", &e).error_response();
+        }
+    };
+
+    let mut posts = Vec::new();
+    let skip_count = (page - 1) * limit;
+    let mut skipped = 0u32;
+
+    let mut rows_stream = row_iterator.into_typed::<(Uuid, Uuid, String, String, i64, i64, Option<Vec<String>>, Option<bool>, Option<i64>)>();
+    while let Some(next_row_res) = read.next(&mut rows_stream).await {
+        match next_row_res {
+            Ok((id, board_id, title, content, created_at_millis, updated_at_millis, tags, is_deleted, deleted_at)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                if is_deleted && !include_deleted {
+                    continue;
+                }
+                if skipped < skip_count {
+                    skipped += 1;
+                    continue;
+                }
+                if posts.len() as u32 >= limit {
+                    break;
+                }
+
+                let (Some(created_at), Some(updated_at)) = (
+                    Utc.timestamp_millis_opt(created_at_millis).single(),
+                    Utc.timestamp_millis_opt(updated_at_millis).single(),
+                ) else {
+                    warn!("Invalid timestamps for post {}: {}, {}", id, created_at_millis, updated_at_millis);
+                    continue;
+                };
+
+                posts.push(Post {
+                    id,
+                    board_id,
+                    title,
+                    content,
+                    created_at,
+                    updated_at,
+                    author: author.clone(),
+                    tags: tags.unwrap_or_default(),
+                    is_deleted,
+                    deleted_at,
+                    is_locked: false,
+                    score: 0,
+                    reactions: Vec::new(),
+                });
+            }
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(&db_counter, "select", "posts_by_author", false);
+                return ApiError::database("Error reading row", &e).error_response();
+            }
+        }
+    }
+
+    fill_post_locks(&session, &mut posts).await;
+    votes::fill_post_scores(&session, &mut posts).await;
+    reactions::fill_post_reactions(&session, &mut posts).await;
+
+    let duration = start.elapsed();
+    record_db_operation(&db_counter, "select", "posts_by_author", true);
+
+    let (meta, has_more) = page_meta(page, limit, posts.len());
+    let response = PaginatedResponse { meta, data: posts };
+
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        info!("No posts by {} (page: {}, limit: {}), returning 204", author, page, limit);
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
+    }
+
+    info!("Fetched {} posts by {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), author, page, limit, duration.as_millis());
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
+}
+
+/// Get an author's comments
+///
+/// Returns the comments written under this author name on any post, newest first.
+#[utoipa::path(
+    get,
+    path = "/users/{author}/comments",
+    params(
+        ("author" = String, Path, description = "Author name"),
+        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
+        ("limit" = Option<u32>, Query, description = "Number of comments per page (1-100)", example = 10),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "The author's comments", body = PaginatedResponse<Comment>),
+        (status = 204, description = "No comments on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/users/{author}/comments")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_author_comments(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<String>,
+    pagination: Query<PaginationParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let start = Instant::now();
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+
+    let author = path.into_inner();
+    let page = pagination.page.max(1);
+    let limit = pagination.limit().clamp(1, 100);
+
+    let mut prepared = match get_or_prepare(&session, "SELECT id, post_id, content, created_at, edited_at, is_deleted, deleted_at FROM comments_by_author WHERE author = ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "comments_by_author", false);
+            return ApiError::internal(format!("Error preparing query: {}", e)).error_response();
+        }
+    };
+    prepared.set_page_size(limit as i32);
+
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, (&author,))).await {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "comments_by_author", false);
+            return ApiError::database("Error executing query", &e).error_response();
+        }
+    };
+
+    let mut comments = Vec::new();
+    let skip_count = (page - 1) * limit;
+    let mut skipped = 0u32;
+
+    let mut rows_stream = row_iterator.into_typed::<(Uuid, Uuid, String, i64, Option<i64>, Option<bool>, Option<i64>)>();
+    while let Some(next_row_res) = read.next(&mut rows_stream).await {
+        match next_row_res {
+            Ok((id, post_id, content, created_at_millis, edited_at_millis, is_deleted, deleted_at)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                if is_deleted && !include_deleted {
+                    continue;
+                }
+                if skipped < skip_count {
+                    skipped += 1;
+                    continue;
+                }
+                if comments.len() as u32 >= limit {
+                    break;
+                }
+
+                let created_at = match Utc.timestamp_millis_opt(created_at_millis).single() {
+                    Some(dt) => dt,
+                    None => {
+                        warn!("Invalid timestamp for comment {}: {}", id, created_at_millis);
+                        continue;
+                    }
+                };
+
+                comments.push(Comment {
+                    id,
+                    post_id,
+                    content,
+                    author: author.clone(),
+                    created_at,
+                    edited_at: edited_at_millis.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                    is_deleted,
+                    deleted_at,
+                    score: 0,
+                    reactions: Vec::new(),
+                });
+            }
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(&db_counter, "select", "comments_by_author", false);
+                return ApiError::database("Error reading row", &e).error_response();
+            }
+        }
+    }
+
+    votes::fill_comment_scores(&session, &mut comments).await;
+    reactions::fill_comment_reactions(&session, &mut comments).await;
+
+    let duration = start.elapsed();
+    record_db_operation(&db_counter, "select", "comments_by_author", true);
+
+    let (meta, has_more) = page_meta(page, limit, comments.len());
+    let response = PaginatedResponse { meta, data: comments };
+
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        info!("No comments by {} (page: {}, limit: {}), returning 204", author, page, limit);
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
+    }
+
+    info!("Fetched {} comments by {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), author, page, limit, duration.as_millis());
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
+}
@@ -0,0 +1,484 @@
+//! Board deletion (`DELETE /boards/{board_id}`).
+//!
+//! By default a board is only soft-deleted: it is flagged on `boards` and `boards_by_created`,
+//! hidden from reads, and `POST /boards/{board_id}/restore` brings it back. Purging it for good
+//! (`?purge=true`) is the cascade below.
+//!
+//! A board can hold any number of posts, each with comments and tag/change-feed copies, so the
+//! cascade runs as a background job whose progress is kept in `deletion_jobs`. Posts are
+//! removed a page at a time until none are left, then the board's subscriptions, and the board
+//! itself last: a failed or interrupted job leaves the board in place, and deleting it again
+//! picks up what is left. Posts created while the job runs are caught by the next page.
+
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use chrono::{TimeZone, Utc};
+use scylla::batch::BatchType;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::audit;
+use crate::counts;
+use crate::db;
+use crate::db_client;
+use crate::errors::ApiError;
+use crate::jwt_middleware::{AuthenticatedUser, Moderator};
+use crate::models::{Board, DeletionJob, DeletionJobStatus, PurgeParams, TimestampFormatParams};
+use crate::routes::{self, execute_cached, get_or_prepare, record_db_operation, respond_json, DbCounter, PostDeletionRow};
+
+/// Posts read per page while emptying a board
+const PAGE_SIZE: i32 = 500;
+
+/// Boards being deleted on this instance, with their job
+static RUNNING: OnceLock<Mutex<HashMap<Uuid, Uuid>>> = OnceLock::new();
+
+fn running() -> MutexGuard<'static, HashMap<Uuid, Uuid>> {
+    RUNNING
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+type DeletionJobRow = (
+    Uuid,
+    Option<Uuid>,
+    Option<String>,
+    Option<String>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<String>,
+);
+
+async fn fetch_job(session: &Session, job_id: Uuid) -> Result<Option<DeletionJob>, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT id, board_id, board_name, status, posts_total, posts_deleted, comments_deleted, started_at, finished_at, error FROM deletion_jobs WHERE id = ?",
+        (job_id,),
+    ).await?;
+    let row = rows.maybe_first_row_typed::<DeletionJobRow>().ok().flatten();
+    Ok(row.map(|(id, board_id, board_name, status, posts_total, posts_deleted, comments_deleted, started_at, finished_at, error)| DeletionJob {
+        id,
+        board_id: board_id.unwrap_or_default(),
+        board_name: board_name.unwrap_or_default(),
+        status: status
+            .as_deref()
+            .and_then(DeletionJobStatus::from_column)
+            .unwrap_or(DeletionJobStatus::Failed),
+        posts_total: posts_total.unwrap_or(0),
+        posts_deleted: posts_deleted.unwrap_or(0),
+        comments_deleted: comments_deleted.unwrap_or(0),
+        started_at: started_at
+            .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+            .unwrap_or_default(),
+        finished_at: finished_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+        error,
+    }))
+}
+
+async fn save_progress(session: &Session, job: &DeletionJob) -> Result<(), QueryError> {
+    execute_cached(
+        session,
+        "UPDATE deletion_jobs SET status = ?, posts_deleted = ?, comments_deleted = ?, finished_at = ?, error = ? WHERE id = ?",
+        (
+            job.status.as_str(),
+            job.posts_deleted,
+            job.comments_deleted,
+            job.finished_at.map(|finished_at| finished_at.timestamp_millis()),
+            &job.error,
+            job.id,
+        ),
+    ).await?;
+    Ok(())
+}
+
+/// Columns of the `posts` rows read below, in `SELECT` order
+type PostRow = (Uuid, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>, Option<Uuid>, Option<bool>);
+
+/// Delete every post on the board with its comments, saving progress after each page
+async fn remove_posts(session: &Session, job: &mut DeletionJob, db_counter: &web::Data<DbCounter>) -> Result<(), QueryError> {
+    loop {
+        let rows = execute_cached(
+            session,
+            "SELECT id, title, author, created_at, updated_at, tags, user_id, is_deleted FROM posts_by_board WHERE board_id = ? LIMIT ?",
+            (job.board_id, PAGE_SIZE),
+        ).await?;
+        let posts: Vec<PostRow> = rows
+            .rows_typed()
+            .map(|rows| rows.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+        if posts.is_empty() {
+            return Ok(());
+        }
+
+        for (post_id, title, author, created_at, updated_at, tags, owner, is_deleted) in posts {
+            let post = PostDeletionRow {
+                board_id: Some(job.board_id),
+                title: title.unwrap_or_default(),
+                author,
+                created_at,
+                updated_at,
+                tags: tags.unwrap_or_default(),
+                owner,
+                is_deleted: is_deleted.unwrap_or(false),
+            };
+            let comments = routes::remove_post(session, post_id, &post, db_counter).await?;
+            job.posts_deleted += 1;
+            job.comments_deleted += comments as i64;
+        }
+        save_progress(session, job).await?;
+    }
+}
+
+/// Drop the board's subscriptions and post count, then the board and its ordered-listing row,
+/// and free its slug
+async fn remove_board(session: &Session, board_id: Uuid, created_at: i64, slug: &str) -> Result<(), QueryError> {
+    let rows = execute_cached(session, "SELECT author FROM subscriptions WHERE board_id = ?", (board_id,)).await?;
+    let authors: Vec<(String,)> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    for (author,) in &authors {
+        execute_cached(
+            session,
+            "DELETE FROM subscriptions WHERE author = ? AND board_id = ?",
+            (author, board_id),
+        ).await?;
+    }
+
+    counts::remove_board(session, board_id).await?;
+
+    let mut batch = db::new_batch(BatchType::Logged);
+    batch.append_statement(get_or_prepare(session, "DELETE FROM boards_by_created WHERE bucket = ? AND created_at = ? AND id = ?").await?);
+    batch.append_statement(get_or_prepare(session, "DELETE FROM boards WHERE id = ?").await?);
+    db_client::call(session.batch(&batch, ((db::BOARDS_BUCKET, created_at, board_id), (board_id,)))).await?;
+    routes::release_board_slug(session, slug, board_id).await;
+    routes::invalidate_board_cache(board_id).await;
+    Ok(())
+}
+
+async fn run_job(session: Arc<Session>, mut job: DeletionJob, board: Board, db_counter: web::Data<DbCounter>) {
+    let outcome = match remove_posts(&session, &mut job, &db_counter).await {
+        Ok(()) => remove_board(&session, board.id, board.created_at.timestamp_millis(), &board.slug).await,
+        Err(e) => Err(e),
+    };
+    running().remove(&board.id);
+
+    job.finished_at = Some(Utc::now());
+    let action = match &outcome {
+        Ok(()) => {
+            record_db_operation(&db_counter, "delete", "boards", true);
+            info!("Deleted board {} ({}): {} posts and {} comments", board.id, board.name, job.posts_deleted, job.comments_deleted);
+            job.status = DeletionJobStatus::Completed;
+            "board_deletion_completed"
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "boards", false);
+            error!("Deletion of board {} failed after {} posts: {}", board.id, job.posts_deleted, e);
+            job.status = DeletionJobStatus::Failed;
+            job.error = Some(e.to_string());
+            "board_deletion_failed"
+        }
+    };
+    if let Err(e) = save_progress(&session, &job).await {
+        error!("Error saving deletion job {}: {}", job.id, e);
+    }
+    let details = format!("job {}: {} posts and {} comments deleted", job.id, job.posts_deleted, job.comments_deleted);
+    if let Err(e) = audit::record(&session, action, None, board.id, &details).await {
+        error!("Error recording audit event {} for board {}: {}", action, board.id, e);
+    }
+}
+
+/// Mark a board deleted at `deleted_at`, or restore it with `None`; the ordered-listing row is
+/// updated with IF EXISTS so a mismatched timestamp can't create a stray row
+async fn set_board_deleted(session: &Session, board: &Board, deleted_at: Option<i64>) -> Result<(), QueryError> {
+    let is_deleted = deleted_at.is_some();
+    execute_cached(
+        session,
+        "UPDATE boards_by_created SET is_deleted = ?, deleted_at = ? WHERE bucket = ? AND created_at = ? AND id = ? IF EXISTS",
+        (is_deleted, deleted_at, db::BOARDS_BUCKET, board.created_at.timestamp_millis(), board.id),
+    ).await?;
+    execute_cached(
+        session,
+        "UPDATE boards SET is_deleted = ?, deleted_at = ? WHERE id = ?",
+        (is_deleted, deleted_at, board.id),
+    ).await?;
+    routes::invalidate_board_cache(board.id).await;
+    Ok(())
+}
+
+/// The board with `board_id`; `Err` is the error to answer with
+async fn fetch_board(session: &Session, board_id: Uuid, db_counter: &web::Data<DbCounter>) -> Result<Board, ApiError> {
+    match routes::fetch_board_from_db(session, board_id).await {
+        Ok(Some(board)) => {
+            record_db_operation(db_counter, "select", "boards", true);
+            Ok(board)
+        }
+        Ok(None) => {
+            record_db_operation(db_counter, "select", "boards", true);
+            Err(ApiError::not_found(format!("Board with id {} not found", board_id)))
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "boards", false);
+            error!("Error fetching board {}: {}", board_id, e);
+            Err(ApiError::internal(format!("Error fetching board: {}", e)))
+        }
+    }
+}
+
+/// Soft-delete the board; `DELETE /boards/{board_id}` without `purge`
+async fn soft_delete_board(
+    session: &Session,
+    board_id: Uuid,
+    actor_id: Option<Uuid>,
+    db_counter: &web::Data<DbCounter>,
+) -> HttpResponse {
+    let board = match fetch_board(session, board_id, db_counter).await {
+        Ok(board) if !board.is_deleted => board,
+        Ok(_) => return ApiError::not_found(format!("Board with id {} not found", board_id)).error_response(),
+        Err(e) => return e.error_response(),
+    };
+    if let Err(e) = set_board_deleted(session, &board, Some(Utc::now().timestamp_millis())).await {
+        record_db_operation(db_counter, "update", "boards", false);
+        error!("Error deleting board {}: {}", board_id, e);
+        return ApiError::internal(format!("Error deleting board: {}", e)).error_response();
+    }
+    record_db_operation(db_counter, "update", "boards", true);
+
+    if let Err(e) = audit::record(session, "board_deleted", actor_id, board_id, &board.name).await {
+        error!("Error recording deletion of board {}: {}", board_id, e);
+    }
+    info!("Board {} ({}) deleted", board_id, board.name);
+    HttpResponse::NoContent().finish()
+}
+
+/// The board to delete and how many posts it has; `Err` is the error to answer with
+async fn prepare_job(
+    session: &Session,
+    board_id: Uuid,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<(Board, i64), ApiError> {
+    let board = fetch_board(session, board_id, db_counter).await?;
+
+    match routes::count_board_posts(session, board_id).await {
+        Ok(posts_total) => {
+            record_db_operation(db_counter, "count", "posts", true);
+            Ok((board, posts_total))
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "count", "posts", false);
+            error!("Error counting posts of board {}: {}", board_id, e);
+            Err(ApiError::internal(format!("Error counting posts: {}", e)))
+        }
+    }
+}
+
+/// Delete a board
+///
+/// Soft-deletes the board: it and its post listing are hidden until
+/// `POST /boards/{board_id}/restore`. With `purge=true` it schedules deletion of the board with
+/// all its posts and comments instead, and answers right away with the job, whose progress
+/// `GET /deletion-jobs/{job_id}` reports; purging a board whose purge is already running returns
+/// that job. Takes a moderator's bearer token or the admin token.
+#[utoipa::path(
+    delete,
+    path = "/boards/{board_id}",
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("purge" = Option<bool>, Query, description = "Delete permanently, with all posts and comments, as a background job"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token")
+    ),
+    responses(
+        (status = 202, description = "Purge scheduled", body = DeletionJob),
+        (status = 204, description = "Board deleted (soft)"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Board not found (or already deleted, without purge)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/boards/{board_id}")]
+pub async fn delete_board(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    purge: web::Query<PurgeParams>,
+    moderator: Moderator,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let board_id = path.into_inner();
+    if !purge.purge {
+        return soft_delete_board(&session, board_id, moderator.0.as_ref().map(|user| user.user_id), &db_counter).await;
+    }
+
+    // Claim the board first, so concurrent requests can't start a second job
+    let job_id = Uuid::new_v4();
+    let running_job = {
+        let mut running = running();
+        match running.get(&board_id) {
+            Some(running_job) => Some(*running_job),
+            None => {
+                running.insert(board_id, job_id);
+                None
+            }
+        }
+    };
+    if let Some(running_job) = running_job {
+        info!("Deletion of board {} is already running as job {}", board_id, running_job);
+        return match fetch_job(&session, running_job).await {
+            Ok(Some(job)) => HttpResponse::Accepted().json(job),
+            Ok(None) => ApiError::internal(format!("Deletion job {} not found", running_job)).error_response(),
+            Err(e) => ApiError::internal(format!("Error fetching deletion job: {}", e)).error_response(),
+        };
+    }
+
+    let (board, posts_total) = match prepare_job(&session, board_id, &db_counter).await {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            running().remove(&board_id);
+            return e.error_response();
+        }
+    };
+
+    let job = DeletionJob {
+        id: job_id,
+        board_id,
+        board_name: board.name.clone(),
+        status: DeletionJobStatus::Running,
+        posts_total,
+        posts_deleted: 0,
+        comments_deleted: 0,
+        started_at: Utc::now(),
+        finished_at: None,
+        error: None,
+    };
+    if let Err(e) = execute_cached(
+        &session,
+        "INSERT INTO deletion_jobs (id, board_id, board_name, status, posts_total, posts_deleted, comments_deleted, started_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        (job.id, board_id, &job.board_name, job.status.as_str(), posts_total, 0i64, 0i64, job.started_at.timestamp_millis()),
+    ).await {
+        record_db_operation(&db_counter, "insert", "deletion_jobs", false);
+        running().remove(&board_id);
+        error!("Error creating deletion job for board {}: {}", board_id, e);
+        return ApiError::internal(format!("Error scheduling deletion: {}", e)).error_response();
+    }
+    record_db_operation(&db_counter, "insert", "deletion_jobs", true);
+
+    let actor_id = moderator.0.as_ref().map(|user| user.user_id);
+    if let Err(e) = audit::record(&session, "board_deletion_requested", actor_id, board_id, &format!("job {}: {}", job.id, board.name)).await {
+        error!("Error recording deletion request of board {}: {}", board_id, e);
+    }
+
+    warn!("Scheduling deletion of board {} ({}) with {} posts as job {}", board_id, board.name, posts_total, job.id);
+    tokio::spawn(run_job(session.get_ref().clone(), job.clone(), board, db_counter.clone()));
+    HttpResponse::Accepted().json(job)
+}
+
+/// Restore a deleted board
+///
+/// Undoes `DELETE /boards/{board_id}` (not a purge). Takes a moderator's bearer token or the
+/// admin token.
+#[utoipa::path(
+    post,
+    path = "/boards/{board_id}/restore",
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Board restored (or was not deleted)", body = Board),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Board not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/boards/{board_id}/restore")]
+pub async fn restore_board(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: web::Query<TimestampFormatParams>,
+) -> impl Responder {
+    let board_id = path.into_inner();
+    if let Err(e) = routes::authorize_moderation(&req, user.as_ref(), "board", board_id, "restore") {
+        return e.error_response();
+    }
+    let mut board = match fetch_board(&session, board_id, &db_counter).await {
+        Ok(board) => board,
+        Err(e) => return e.error_response(),
+    };
+
+    if board.is_deleted {
+        if let Err(e) = set_board_deleted(&session, &board, None).await {
+            record_db_operation(&db_counter, "update", "boards", false);
+            error!("Error restoring board {}: {}", board_id, e);
+            return ApiError::internal(format!("Error restoring board: {}", e)).error_response();
+        }
+        record_db_operation(&db_counter, "update", "boards", true);
+        board.is_deleted = false;
+        board.deleted_at = None;
+
+        let actor_id = user.as_ref().map(|user| user.user_id);
+        if let Err(e) = audit::record(&session, "board_restored", actor_id, board_id, &board.name).await {
+            error!("Error recording restore of board {}: {}", board_id, e);
+        }
+        info!("Board {} ({}) restored", board_id, board.name);
+    }
+
+    respond_json(&mut HttpResponse::Ok(), &board, &ts)
+}
+
+/// Get a board deletion job
+///
+/// Progress of a deletion started by `DELETE /boards/{board_id}`. Takes a moderator's bearer
+/// token or the admin token.
+#[utoipa::path(
+    get,
+    path = "/deletion-jobs/{job_id}",
+    params(
+        ("job_id" = uuid::Uuid, Path, description = "Deletion job ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token")
+    ),
+    responses(
+        (status = 200, description = "Job progress", body = DeletionJob),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Job not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/deletion-jobs/{job_id}")]
+pub async fn get_deletion_job(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let job_id = path.into_inner();
+    match fetch_job(&session, job_id).await {
+        Ok(Some(job)) => {
+            record_db_operation(&db_counter, "select", "deletion_jobs", true);
+            if let Err(e) = routes::authorize_moderation(&req, user.as_ref(), "board", job.board_id, "delete") {
+                return e.error_response();
+            }
+            HttpResponse::Ok().json(job)
+        }
+        Ok(None) => {
+            record_db_operation(&db_counter, "select", "deletion_jobs", true);
+            ApiError::not_found(format!("Deletion job {} not found", job_id)).error_response()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "deletion_jobs", false);
+            error!("Error fetching deletion job {}: {}", job_id, e);
+            ApiError::internal(format!("Error fetching deletion job: {}", e)).error_response()
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use actix_web::body::{to_bytes, BodySize, BoxBody, MessageBody};
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE};
+use actix_web::{web, Error};
+use futures_util::future::LocalBoxFuture;
+use serde_json::Value;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tracing::debug;
+use crate::config;
+use crate::errors::ApiError;
+
+/// Bodies larger than this are never buffered for logging, whatever `BODY_LOG_MAX_LENGTH` is
+const MAX_BUFFERED_BODY_BYTES: usize = 64 * 1024;
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// Replace the values of `BODY_LOG_REDACT_FIELDS` keys (at any depth) with a placeholder
+fn redact(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.iter().any(|field| field.eq_ignore_ascii_case(key)) {
+                    *value = Value::String("[REDACTED]".to_string());
+                } else {
+                    redact(value, fields);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, fields)),
+        _ => {}
+    }
+}
+
+/// Render a body for the log: redacted if it parses as JSON, raw otherwise (malformed payloads
+/// are what this log is for), truncated to `BODY_LOG_MAX_LENGTH` characters
+fn render_body(bytes: &[u8]) -> String {
+    let config = config::get();
+    let mut text = match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut value) => {
+            redact(&mut value, &config.body_log_redact_fields);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    };
+    if let Some((cut, _)) = text.char_indices().nth(config.body_log_max_length) {
+        let total = text.chars().count();
+        text.truncate(cut);
+        text.push_str(&format!("... [truncated, {} chars]", total));
+    }
+    text
+}
+
+// Middleware factory logging JSON request and response bodies at debug level (`BODY_LOG_ENABLED`).
+// Must be wrapped outside `TracingLogger` so responses already carry `X-Trace-Id`.
+pub struct BodyLogger;
+
+impl<S, B> Transform<S, ServiceRequest> for BodyLogger
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = BodyLoggerMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BodyLoggerMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct BodyLoggerMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyLoggerMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let method = req.method().to_string();
+            let path = req.path().to_owned();
+
+            // Only JSON bodies with a declared, small length are read; chunked uploads and
+            // large bodies stream through untouched
+            let request_length = req
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<usize>().ok());
+            let request_body = match request_length {
+                Some(length) if length > 0 && length <= MAX_BUFFERED_BODY_BYTES && is_json(req.headers()) => {
+                    let bytes = req.extract::<web::Bytes>().await?;
+                    req.set_payload(Payload::from(bytes.clone()));
+                    Some(bytes)
+                }
+                _ => None,
+            };
+
+            let res = service.call(req).await?;
+            let trace_id = res
+                .headers()
+                .get("x-trace-id")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("-")
+                .to_owned();
+
+            if let Some(bytes) = &request_body {
+                debug!("Request body {} {} (trace_id: {}): {}", method, path, trace_id, render_body(bytes));
+            }
+
+            let loggable = is_json(res.headers())
+                && matches!(res.response().body().size(), BodySize::Sized(size) if size > 0 && size as usize <= MAX_BUFFERED_BODY_BYTES);
+            if !loggable {
+                return Ok(res.map_into_boxed_body());
+            }
+
+            let status = res.status().as_u16();
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let bytes = match to_bytes(body).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    let e: Box<dyn std::error::Error> = e.into();
+                    return Err(ApiError::internal(e.to_string()).into());
+                }
+            };
+            debug!("Response body {} {} - {} (trace_id: {}): {}", method, path, status, trace_id, render_body(&bytes));
+            Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(bytes))))
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use prometheus::{Gauge, IntCounterVec};
+use std::time::Duration;
+use tracing::{info, warn};
+use crate::config;
+use crate::process_metrics::update_memory_usage;
+use crate::routes;
+
+/// Most eviction rounds per check, so a process whose RSS doesn't shrink (freed memory kept
+/// by the allocator) can't spin emptying caches that are already empty
+const MAX_EVICTION_ROUNDS: usize = 8;
+
+/// Watch process memory and shed cache entries when RSS crosses `CACHE_MEMORY_HIGH_WATERMARK`
+///
+/// Each round evicts the oldest `CACHE_PRESSURE_EVICT_FRACTION` of every cache and re-reads
+/// RSS, until it drops below the low watermark or the caches are empty. Does nothing when no
+/// high watermark is configured.
+pub fn spawn_monitor(memory_gauge: Gauge, cache_counter: IntCounterVec, interval: Duration) {
+    let config = config::get();
+    let Some(high) = config.cache_memory_high_watermark_bytes else {
+        return;
+    };
+    let low = config.cache_memory_low_watermark_bytes.unwrap_or(high / 10 * 8).min(high);
+    let fraction = config.cache_pressure_evict_fraction;
+    println!("🧹 Cache memory pressure eviction above {} bytes (down to {} bytes)", high, low);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            update_memory_usage(&memory_gauge);
+            let rss = memory_gauge.get() as u64;
+            if rss <= high {
+                continue;
+            }
+
+            warn!("Memory usage {} bytes is above the cache high watermark {} bytes, evicting cache entries", rss, high);
+            let mut total_evicted = 0usize;
+            for _ in 0..MAX_EVICTION_ROUNDS {
+                let evicted = routes::evict_oldest_cache_entries(fraction).await;
+                let round_total: usize = evicted.iter().map(|(_, count)| count).sum();
+                for (cache_type, count) in evicted {
+                    if count > 0 {
+                        cache_counter.with_label_values(&[cache_type, "pressure_evicted"]).inc_by(count as u64);
+                    }
+                }
+                total_evicted += round_total;
+
+                update_memory_usage(&memory_gauge);
+                if round_total == 0 || memory_gauge.get() as u64 <= low {
+                    break;
+                }
+            }
+            info!(
+                "Evicted {} cache entries under memory pressure (memory now {} bytes)",
+                total_evicted, memory_gauge.get() as u64
+            );
+        }
+    });
+}
@@ -0,0 +1,165 @@
+//! Board categories (`POST /categories`, `GET /categories`), e.g. "General" or "Tech".
+//!
+//! A board belongs to at most one category through its `category_id`; `GET /boards?group_by=category`
+//! groups a page of boards by it. Categories are few, so they are read with a full scan of
+//! `categories` and ordered in memory.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use actix_web::web::Query;
+use chrono::{TimeZone, Utc};
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::config;
+use crate::errors::ApiError;
+use crate::jwt_middleware::AuthenticatedUser;
+use crate::models::{Board, BoardGroup, Category, CreateCategoryRequest, TimestampFormatParams};
+use crate::routes::{authorize_moderation, execute_cached, record_db_operation, respond_json, DbCounter};
+
+/// Longest accepted category name, in characters
+const MAX_NAME_CHARS: usize = 100;
+
+/// All categories, by `position` and then name
+pub(crate) async fn fetch_categories(session: &Session) -> Result<Vec<Category>, QueryError> {
+    let rows = execute_cached(session, "SELECT id, name, position, created_at FROM categories", &[]).await?;
+    let mut categories: Vec<Category> = rows
+        .rows_typed_or_empty::<(Uuid, Option<String>, Option<i32>, Option<i64>)>()
+        .filter_map(Result::ok)
+        .map(|(id, name, position, created_at)| Category {
+            id,
+            name: name.unwrap_or_default(),
+            position: position.unwrap_or(0),
+            created_at: created_at
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+                .unwrap_or_default(),
+        })
+        .collect();
+    categories.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.name.cmp(&b.name)));
+    Ok(categories)
+}
+
+/// Whether a category with this id exists
+pub(crate) async fn category_exists(session: &Session, category_id: Uuid) -> Result<bool, QueryError> {
+    let rows = execute_cached(session, "SELECT id FROM categories WHERE id = ?", (category_id,)).await?;
+    Ok(rows.rows.is_some_and(|rows| !rows.is_empty()))
+}
+
+/// Group boards by category in category order, keeping the boards' order within each group;
+/// boards without a (known) category come last, in a group with a null category
+pub(crate) fn group_boards(boards: Vec<Board>, categories: Vec<Category>) -> Vec<BoardGroup> {
+    let mut by_category: HashMap<Uuid, Vec<Board>> = HashMap::new();
+    let mut uncategorized = Vec::new();
+    for board in boards {
+        match board.category_id.filter(|id| categories.iter().any(|category| category.id == *id)) {
+            Some(category_id) => by_category.entry(category_id).or_default().push(board),
+            None => uncategorized.push(board),
+        }
+    }
+
+    let mut groups: Vec<BoardGroup> = categories
+        .into_iter()
+        .filter_map(|category| {
+            by_category
+                .remove(&category.id)
+                .map(|boards| BoardGroup { category: Some(category), boards })
+        })
+        .collect();
+    if !uncategorized.is_empty() {
+        groups.push(BoardGroup { category: None, boards: uncategorized });
+    }
+    groups
+}
+
+/// Create a category
+///
+/// Adds a category boards can be assigned to with `category_id`. Takes a moderator's bearer
+/// token or the admin token.
+#[utoipa::path(
+    post,
+    path = "/categories",
+    request_body = CreateCategoryRequest,
+    params(
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 201, description = "Category created", body = Category),
+        (status = 400, description = "Empty or too long name, or unknown field in the body"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/categories")]
+pub async fn create_category(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    request: web::Json<CreateCategoryRequest>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let request = request.into_inner();
+    let category = Category {
+        id: config::get().id_scheme.new_id(),
+        name: request.name.trim().to_string(),
+        position: request.position.unwrap_or(0),
+        created_at: Utc::now(),
+    };
+    if let Err(e) = authorize_moderation(&req, user.as_ref(), "category", category.id, "create") {
+        return e.error_response();
+    }
+    if category.name.is_empty() || category.name.chars().count() > MAX_NAME_CHARS {
+        warn!("Rejecting category name: {:?}", category.name);
+        return ApiError::bad_request(format!("name must be between 1 and {} characters", MAX_NAME_CHARS)).error_response();
+    }
+
+    if let Err(e) = execute_cached(
+        &session,
+        "INSERT INTO categories (id, name, position, created_at) VALUES (?, ?, ?, ?)",
+        (category.id, &category.name, category.position, category.created_at.timestamp_millis()),
+    ).await {
+        record_db_operation(&db_counter, "insert", "categories", false);
+        error!("Error creating category {}: {}", category.name, e);
+        return ApiError::internal(format!("Error creating category: {}", e)).error_response();
+    }
+    record_db_operation(&db_counter, "insert", "categories", true);
+    info!("Category {} created: {}", category.id, category.name);
+    respond_json(&mut HttpResponse::Created(), &category, &ts)
+}
+
+/// List categories
+///
+/// Returns every category, ordered by `position` and then name.
+#[utoipa::path(
+    get,
+    path = "/categories",
+    params(
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "All categories", body = Vec<Category>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/categories")]
+pub async fn get_categories(
+    session: web::Data<Arc<Session>>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    match fetch_categories(&session).await {
+        Ok(categories) => {
+            record_db_operation(&db_counter, "select", "categories", true);
+            respond_json(&mut HttpResponse::Ok(), &categories, &ts)
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "categories", false);
+            error!("Error fetching categories: {}", e);
+            ApiError::internal(format!("Error fetching categories: {}", e)).error_response()
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use actix_web::Error;
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use crate::config;
+
+/// Whether a response body is already compressed and shouldn't go through `Compress` again
+fn is_incompressible(res: &ServiceResponse<impl MessageBody>) -> bool {
+    let headers = res.headers();
+    if headers.contains_key(CONTENT_ENCODING) {
+        return true;
+    }
+    let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    // Compare the bare media type, ignoring parameters like `; charset=utf-8`
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    config::path_matches(&config::get().incompressible_content_types, &media_type)
+}
+
+// Middleware factory marking pre-compressed responses so the outer `Compress` leaves them alone.
+// Must be wrapped inside (before) `Compress`.
+pub struct CompressionExemption;
+
+impl<S, B> Transform<S, ServiceRequest> for CompressionExemption
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CompressionExemptionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CompressionExemptionMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct CompressionExemptionMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for CompressionExemptionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let mut res = fut.await?;
+            // `Compress` skips responses that already declare an encoding; `identity` opts
+            // incompressible content types out without changing how the body is sent
+            if is_incompressible(&res) && !res.headers().contains_key(CONTENT_ENCODING) {
+                res.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static("identity"));
+            }
+            Ok(res)
+        })
+    }
+}
@@ -0,0 +1,485 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+/// How `create_board` handles a name another board already has (`DUPLICATE_NAME_STRATEGY`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateNameStrategy {
+    /// Skip the name lookup; a name whose slug another board holds still gets 409
+    Allow,
+    /// Answer 409 Conflict
+    Reject,
+    /// Create the board as "Name (2)", "Name (3)", ... using the first free suffix
+    Suffix,
+    /// Answer 200 with the existing board instead of creating one
+    ReturnExisting,
+}
+
+impl FromStr for DuplicateNameStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "reject" => Ok(Self::Reject),
+            "suffix" => Ok(Self::Suffix),
+            "return_existing" => Ok(Self::ReturnExisting),
+            other => Err(format!("unknown duplicate name strategy '{}'", other)),
+        }
+    }
+}
+
+/// How ids of new boards, posts and comments are generated (`ID_SCHEME`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdScheme {
+    /// Random UUIDv4
+    V4,
+    /// Time-ordered UUIDv7, so ids sort by creation time
+    V7,
+}
+
+impl IdScheme {
+    pub fn new_id(self) -> Uuid {
+        match self {
+            Self::V4 => Uuid::new_v4(),
+            Self::V7 => Uuid::now_v7(),
+        }
+    }
+}
+
+impl FromStr for IdScheme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "v4" => Ok(Self::V4),
+            "v7" => Ok(Self::V7),
+            other => Err(format!("unknown id scheme '{}'", other)),
+        }
+    }
+}
+
+/// Search engine that posts and comments are mirrored into (`SEARCH_BACKEND`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchBackend {
+    /// No search; `GET /search` answers 503
+    None,
+    /// Meilisearch at `SEARCH_URL`
+    Meilisearch,
+}
+
+impl FromStr for SearchBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "meilisearch" => Ok(Self::Meilisearch),
+            other => Err(format!("unknown search backend '{}'", other)),
+        }
+    }
+}
+
+/// Client registration with an OAuth provider
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Runtime settings read from environment variables once at startup
+pub struct AppConfig {
+    /// Request paths that never get a tracing span (exact match, or prefix match with a trailing `*`)
+    pub trace_exclude_paths: Vec<String>,
+    /// Status returned for listings with no rows (200 with `data: []`, or 204)
+    pub empty_list_status: u16,
+    /// Author substituted for stored posts whose `author` column is null
+    pub missing_author_placeholder: String,
+    /// Client addresses rejected with 403 before any handler runs
+    pub banned_ips: Vec<IpAddr>,
+    /// Load balancers/proxies whose `X-Forwarded-For` header is trusted
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Shared secret for `/admin/*` endpoints (sent as `X-Admin-Token`); admin endpoints are disabled when unset
+    pub admin_token: Option<String>,
+    /// Maximum author name length in characters
+    pub author_max_length: usize,
+    /// Maximum board name length in characters
+    pub board_name_max_length: usize,
+    /// Maximum board description length in characters
+    pub board_description_max_length: usize,
+    /// Maximum post title length in characters
+    pub post_title_max_length: usize,
+    /// Maximum post content length in characters
+    pub post_content_max_length: usize,
+    /// Maximum comment content length in characters
+    pub comment_content_max_length: usize,
+    /// When set, author names may only contain alphanumerics and these punctuation characters
+    pub author_allowed_punctuation: Option<String>,
+    /// Whether the service starts in maintenance mode
+    pub maintenance_mode: bool,
+    /// `Retry-After` value sent with maintenance-mode 503 responses
+    pub maintenance_retry_after_secs: u64,
+    /// Trim post titles and collapse runs of whitespace before storing them
+    pub normalize_whitespace: bool,
+    /// Also tidy post content (trailing spaces, runs of blank lines)
+    pub normalize_content_whitespace: bool,
+    /// Board names users may not take, compared case-insensitively
+    pub reserved_board_names: Vec<String>,
+    /// How often the background task refreshes process metrics
+    pub process_metrics_interval_secs: u64,
+    /// Fraction of successful requests that get access log lines (failures are always logged)
+    pub access_log_sample_ratio: f64,
+    /// Directory holding `docs.html` and the assets it references
+    pub static_dir: String,
+    /// Upper bound on lazily prepared statements kept in the statement cache
+    pub prepared_statement_cache_max: usize,
+    /// ScyllaDB contact points (`host:port`); the driver discovers the rest of the cluster
+    pub scylla_nodes: Vec<String>,
+    /// Keyspace holding the forum's tables, created on startup if missing
+    pub scylla_keyspace: String,
+    /// Replication strategy of a newly created keyspace: `SimpleStrategy` or `NetworkTopologyStrategy`
+    pub scylla_replication_class: String,
+    /// Replication factor of a newly created keyspace (per datacenter with `NetworkTopologyStrategy`)
+    pub scylla_replication_factor: u32,
+    /// Connections the driver keeps open to each node
+    pub scylla_pool_size: usize,
+    /// Username for ScyllaDB password authentication (with `scylla_password`)
+    pub scylla_username: Option<String>,
+    /// Password for ScyllaDB password authentication (with `scylla_username`)
+    pub scylla_password: Option<String>,
+    /// Connect to ScyllaDB over TLS (also on when a CA file is set)
+    pub scylla_tls: bool,
+    /// PEM file with the CA certificates trusted for ScyllaDB (unset uses the system store)
+    pub scylla_tls_ca_path: Option<String>,
+    /// Load balancing policy for the ScyllaDB session: `round-robin`, `token-aware` or `latency-aware`
+    pub lb_policy: String,
+    /// Datacenter whose nodes are preferred by the load balancing policy
+    pub lb_local_dc: Option<String>,
+    /// How long startup keeps retrying to connect to and set up the database before giving up
+    pub db_startup_max_wait_secs: u64,
+    /// First delay between startup attempts; it doubles after each failure (up to 30 seconds)
+    pub db_startup_initial_backoff_ms: u64,
+    /// How long startup waits for cluster-wide schema agreement after DDL
+    pub schema_agreement_timeout_secs: u64,
+    /// Seconds in-flight requests get to finish after a shutdown signal before workers are stopped
+    pub shutdown_timeout_secs: u64,
+    /// Maximum number of tags on one post
+    pub tag_max_per_post: usize,
+    /// Maximum tag length in characters (after normalization)
+    pub tag_max_length: usize,
+    /// Response media types never run through `Compress` (exact, or prefix with a trailing `*`)
+    pub incompressible_content_types: Vec<String>,
+    /// Deadline for a handler to produce a response before a 504 is returned (0 disables it)
+    pub handler_timeout_ms: u64,
+    /// Deadline for each database call made by the handlers, answered with a 504 (0 disables it)
+    pub db_query_timeout_ms: u64,
+    /// Cluster failures in a row that open the database circuit breaker (0 disables it)
+    pub db_breaker_failure_threshold: u32,
+    /// Seconds between recovery probes while the breaker is open, also sent as `Retry-After`
+    pub db_breaker_probe_interval_secs: u64,
+    /// Paths exempt from the handler deadline, e.g. streaming endpoints (`/exact` or `/prefix*`)
+    pub handler_timeout_exempt_paths: Vec<String>,
+    /// Comments included in a full-thread response when the request doesn't ask for a number
+    pub full_thread_max_comments: u32,
+    /// Upper bound for the per-request `max_comments` of a full-thread response
+    pub full_thread_max_comments_limit: u32,
+    /// Origins allowed to call the API from browsers (`*` for any)
+    pub cors_allowed_origins: Vec<String>,
+    /// What creating a board with an existing name does
+    pub duplicate_name_strategy: DuplicateNameStrategy,
+    /// Process RSS in bytes above which cache entries are evicted (unset disables eviction)
+    pub cache_memory_high_watermark_bytes: Option<u64>,
+    /// RSS in bytes eviction tries to get back under (defaults to 80% of the high watermark)
+    pub cache_memory_low_watermark_bytes: Option<u64>,
+    /// Fraction of each cache dropped per eviction round
+    pub cache_pressure_evict_fraction: f64,
+    /// Endpoints left unregistered (canonical names from `endpoints::ENDPOINT_NAMES`)
+    pub disabled_endpoints: Vec<String>,
+    /// Consistency level for batch writes (`BATCH_CONSISTENCY`, unset falls back to `write_consistency`)
+    pub batch_consistency: Option<String>,
+    /// Consistency level for prepared reads (`READ_CONSISTENCY`, unset keeps the session default)
+    pub read_consistency: Option<String>,
+    /// Consistency level for prepared writes (`WRITE_CONSISTENCY`, unset keeps the session default)
+    pub write_consistency: Option<String>,
+    /// Per-statement levels for the prepared-statement registry, as (statement name, level)
+    pub consistency_overrides: Vec<(String, String)>,
+    /// Estimated serialized size above which variable-size batches are split
+    pub batch_max_bytes: usize,
+    /// UUID version used for new board, post and comment ids
+    pub id_scheme: IdScheme,
+    /// How far in the future a client-supplied `created_at` may be, in seconds
+    pub created_at_max_future_secs: i64,
+    /// Seconds between connection pool probes (0 disables them)
+    pub pool_health_interval_secs: u64,
+    /// Failed probe rounds in a row before the cluster metadata is refreshed
+    pub pool_health_failure_threshold: u32,
+    /// Log JSON request and response bodies at debug level
+    pub body_log_enabled: bool,
+    /// Characters of each logged body kept before truncating
+    pub body_log_max_length: usize,
+    /// JSON keys whose values are replaced with `[REDACTED]` in logged bodies (case-insensitive)
+    pub body_log_redact_fields: Vec<String>,
+    /// Seconds an idle keep-alive connection is kept open (0 disables keep-alive)
+    pub keep_alive_secs: u64,
+    /// Set TCP_NODELAY on accepted connections, so small responses aren't held back by Nagle
+    pub tcp_nodelay: bool,
+    /// Secret signing access tokens; when unset, tokens aren't issued or required
+    pub jwt_secret: Option<String>,
+    /// Lifetime of issued access tokens, in seconds
+    pub jwt_ttl_secs: u64,
+    /// Lifetime of refresh tokens (and their login sessions), in seconds; renewed on each refresh
+    pub refresh_token_ttl_secs: u64,
+    /// GitHub sign-in; off unless both the client id and secret are set
+    pub oauth_github: Option<OAuthClient>,
+    /// Google sign-in; off unless both the client id and secret are set
+    pub oauth_google: Option<OAuthClient>,
+    /// Public base URL of the forum, used to build the OAuth callback URLs registered with providers
+    pub oauth_redirect_base_url: String,
+    /// Requests per minute allowed for API keys created without an explicit limit
+    pub api_key_default_rate_limit: u32,
+    /// Author shown on posts and comments of deleted accounts
+    pub erased_author_name: String,
+    /// Search engine for `GET /search`; search is off with `none`
+    pub search_backend: SearchBackend,
+    /// Base URL of the search engine
+    pub search_url: String,
+    /// Key sent to the search engine as a bearer token
+    pub search_api_key: Option<String>,
+    /// Index holding the mirrored posts and comments
+    pub search_index: String,
+    /// Timeout of each request to the search engine
+    pub search_timeout_secs: u64,
+    /// How often the background task re-ranks hot posts (0 turns it off)
+    pub hot_rank_interval_secs: u64,
+    /// Posts kept in each board's hot ranking
+    pub hot_posts_per_board: usize,
+}
+
+static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        Self {
+            trace_exclude_paths: env_list("TRACE_EXCLUDE_PATHS", &["/health", "/ready", "/metrics"]),
+            empty_list_status: env_parse("EMPTY_LIST_STATUS", 200),
+            missing_author_placeholder: env_parse("MISSING_AUTHOR_PLACEHOLDER", "[unknown]".to_string()),
+            banned_ips: env_ip_list("BANNED_IPS"),
+            trusted_proxies: env_ip_list("TRUSTED_PROXIES"),
+            admin_token: env_opt("ADMIN_TOKEN"),
+            author_max_length: env_parse("AUTHOR_MAX_LENGTH", 64),
+            board_name_max_length: env_parse("BOARD_NAME_MAX_LENGTH", 100),
+            board_description_max_length: env_parse("BOARD_DESCRIPTION_MAX_LENGTH", 2_000),
+            post_title_max_length: env_parse("POST_TITLE_MAX_LENGTH", 300),
+            post_content_max_length: env_parse("POST_CONTENT_MAX_LENGTH", 40_000),
+            comment_content_max_length: env_parse("COMMENT_CONTENT_MAX_LENGTH", 10_000),
+            author_allowed_punctuation: std::env::var("AUTHOR_ALLOWED_PUNCTUATION").ok(),
+            maintenance_mode: env_bool("MAINTENANCE_MODE", false),
+            maintenance_retry_after_secs: env_parse("MAINTENANCE_RETRY_AFTER_SECS", 120),
+            normalize_whitespace: env_bool("NORMALIZE_WHITESPACE", true),
+            normalize_content_whitespace: env_bool("NORMALIZE_CONTENT_WHITESPACE", false),
+            process_metrics_interval_secs: env_parse("PROCESS_METRICS_INTERVAL_SECS", 15),
+            access_log_sample_ratio: env_parse("ACCESS_LOG_SAMPLE_RATIO", 1.0),
+            static_dir: env_parse("STATIC_DIR", "/app/static".to_string()),
+            prepared_statement_cache_max: env_parse("PREPARED_STATEMENT_CACHE_MAX", 128),
+            scylla_nodes: env_list("SCYLLA_NODES", &["scylladb:9042"]),
+            scylla_keyspace: env_parse("SCYLLA_KEYSPACE", "posts".to_string()),
+            scylla_replication_class: env_parse("SCYLLA_REPLICATION_CLASS", "SimpleStrategy".to_string()),
+            scylla_replication_factor: env_parse("SCYLLA_REPLICATION_FACTOR", 1),
+            scylla_pool_size: env_parse("SCYLLA_POOL_SIZE", 8),
+            scylla_username: env_opt("SCYLLA_USERNAME"),
+            scylla_password: env_opt("SCYLLA_PASSWORD"),
+            scylla_tls: env_bool("SCYLLA_TLS", false) || env_opt("SCYLLA_TLS_CA_PATH").is_some(),
+            scylla_tls_ca_path: env_opt("SCYLLA_TLS_CA_PATH"),
+            lb_policy: env_parse("LB_POLICY", "token-aware".to_string()).to_lowercase(),
+            lb_local_dc: env_opt("LB_LOCAL_DC"),
+            db_startup_max_wait_secs: env_parse("DB_STARTUP_MAX_WAIT_SECS", 120),
+            db_startup_initial_backoff_ms: env_parse("DB_STARTUP_INITIAL_BACKOFF_MS", 500),
+            schema_agreement_timeout_secs: env_parse("SCHEMA_AGREEMENT_TIMEOUT_SECS", 30),
+            shutdown_timeout_secs: env_parse("SHUTDOWN_TIMEOUT_SECS", 30),
+            tag_max_per_post: env_parse("TAG_MAX_PER_POST", 10),
+            tag_max_length: env_parse("TAG_MAX_LENGTH", 32),
+            incompressible_content_types: env_list(
+                "INCOMPRESSIBLE_CONTENT_TYPES",
+                &["image/*", "video/*", "audio/*", "application/zip", "application/gzip", "application/x-gzip", "application/zstd"],
+            ),
+            handler_timeout_ms: env_parse("HANDLER_TIMEOUT_MS", 10_000),
+            db_query_timeout_ms: env_parse("DB_QUERY_TIMEOUT_MS", 5_000),
+            db_breaker_failure_threshold: env_parse("DB_BREAKER_FAILURE_THRESHOLD", 5),
+            db_breaker_probe_interval_secs: env_parse("DB_BREAKER_PROBE_INTERVAL_SECS", 5),
+            handler_timeout_exempt_paths: env_list("HANDLER_TIMEOUT_EXEMPT_PATHS", &[]),
+            full_thread_max_comments: env_parse("FULL_THREAD_MAX_COMMENTS", 100),
+            full_thread_max_comments_limit: env_parse("FULL_THREAD_MAX_COMMENTS_LIMIT", 500),
+            cors_allowed_origins: env_list("CORS_ALLOWED_ORIGINS", &["*"]),
+            duplicate_name_strategy: env_parse("DUPLICATE_NAME_STRATEGY", DuplicateNameStrategy::Reject),
+            cache_memory_high_watermark_bytes: env_opt("CACHE_MEMORY_HIGH_WATERMARK").and_then(|v| v.parse().ok()),
+            cache_memory_low_watermark_bytes: env_opt("CACHE_MEMORY_LOW_WATERMARK").and_then(|v| v.parse().ok()),
+            cache_pressure_evict_fraction: env_parse("CACHE_PRESSURE_EVICT_FRACTION", 0.25),
+            disabled_endpoints: env_list("DISABLED_ENDPOINTS", &[]),
+            batch_consistency: env_opt("BATCH_CONSISTENCY"),
+            read_consistency: env_opt("READ_CONSISTENCY"),
+            write_consistency: env_opt("WRITE_CONSISTENCY"),
+            consistency_overrides: env_list("CONSISTENCY_OVERRIDES", &[])
+                .into_iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((name, level)) => (name.trim().to_string(), level.trim().to_string()),
+                    None => (entry, String::new()),
+                })
+                .collect(),
+            batch_max_bytes: env_parse("BATCH_MAX_BYTES", 5120),
+            id_scheme: env_parse("ID_SCHEME", IdScheme::V4),
+            created_at_max_future_secs: env_parse("CREATED_AT_MAX_FUTURE_SECS", 300),
+            pool_health_interval_secs: env_parse("POOL_HEALTH_INTERVAL_SECS", 30),
+            pool_health_failure_threshold: env_parse("POOL_HEALTH_FAILURE_THRESHOLD", 3),
+            body_log_enabled: env_bool("BODY_LOG_ENABLED", false),
+            body_log_max_length: env_parse("BODY_LOG_MAX_LENGTH", 2048),
+            body_log_redact_fields: env_list("BODY_LOG_REDACT_FIELDS", &["password", "current_password", "new_password", "token", "access_token", "refresh_token", "secret", "authorization", "admin_token"]),
+            keep_alive_secs: env_parse("KEEP_ALIVE_SECS", 5),
+            tcp_nodelay: env_bool("TCP_NODELAY", true),
+            jwt_secret: env_opt("JWT_SECRET"),
+            jwt_ttl_secs: env_parse("JWT_TTL_SECS", 900),
+            refresh_token_ttl_secs: env_parse("REFRESH_TOKEN_TTL_SECS", 30 * 24 * 3600),
+            oauth_github: env_oauth_client("OAUTH_GITHUB"),
+            oauth_google: env_oauth_client("OAUTH_GOOGLE"),
+            oauth_redirect_base_url: env_opt("OAUTH_REDIRECT_BASE_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| "http://localhost:8080".to_string()),
+            api_key_default_rate_limit: env_parse("API_KEY_DEFAULT_RATE_LIMIT", 60),
+            erased_author_name: env_parse("ERASED_AUTHOR_NAME", "[deleted]".to_string()),
+            reserved_board_names: env_list("RESERVED_BOARD_NAMES", &["admin", "api", "metrics", "health", "docs", "swagger"]),
+            search_backend: env_parse("SEARCH_BACKEND", SearchBackend::None),
+            search_url: env_parse("SEARCH_URL", "http://meilisearch:7700".to_string()),
+            search_api_key: env_opt("SEARCH_API_KEY"),
+            search_index: env_parse("SEARCH_INDEX", "forum".to_string()),
+            search_timeout_secs: env_parse("SEARCH_TIMEOUT_SECS", 5),
+            hot_rank_interval_secs: env_parse("HOT_RANK_INTERVAL_SECS", 300),
+            hot_posts_per_board: env_parse("HOT_POSTS_PER_BOARD", 500),
+        }
+    }
+}
+
+/// Get the global configuration, loading it from the environment on first access
+pub fn get() -> &'static AppConfig {
+    CONFIG.get_or_init(AppConfig::from_env)
+}
+
+impl AppConfig {
+    /// Reject ScyllaDB settings that can't work, before connecting
+    ///
+    /// The keyspace name and replication class end up in the `CREATE KEYSPACE` statement
+    /// (DDL can't take bind markers), so they are checked against what CQL allows there.
+    pub fn validate_scylla(&self) -> Result<(), String> {
+        if self.scylla_nodes.is_empty() {
+            return Err("SCYLLA_NODES must name at least one node".to_string());
+        }
+        let keyspace = &self.scylla_keyspace;
+        let valid_keyspace = keyspace.len() <= 48
+            && keyspace.starts_with(|c: char| c.is_ascii_alphabetic())
+            && keyspace.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_keyspace {
+            return Err(format!(
+                "Invalid SCYLLA_KEYSPACE '{}', expected a letter followed by up to 47 letters, digits or underscores",
+                keyspace
+            ));
+        }
+        match self.scylla_replication_class.as_str() {
+            "SimpleStrategy" | "NetworkTopologyStrategy" => {}
+            other => {
+                return Err(format!(
+                    "Invalid SCYLLA_REPLICATION_CLASS '{}', expected SimpleStrategy or NetworkTopologyStrategy",
+                    other
+                ))
+            }
+        }
+        if self.scylla_replication_factor == 0 {
+            return Err("SCYLLA_REPLICATION_FACTOR must be at least 1".to_string());
+        }
+        if self.scylla_pool_size == 0 {
+            return Err("SCYLLA_POOL_SIZE must be at least 1".to_string());
+        }
+        if self.scylla_username.is_some() != self.scylla_password.is_some() {
+            return Err("SCYLLA_USERNAME and SCYLLA_PASSWORD must be set together".to_string());
+        }
+        if self.scylla_tls && !cfg!(feature = "scylla-tls") {
+            return Err("SCYLLA_TLS is set, but this build has no TLS support; build with the scylla-tls feature".to_string());
+        }
+        if let Some(path) = &self.scylla_tls_ca_path {
+            if !std::path::Path::new(path).is_file() {
+                return Err(format!("SCYLLA_TLS_CA_PATH '{}' is not a readable file", path));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check a path (or any other string, such as a media type) against a list of patterns (`/exact` or `/prefix*`)
+pub fn path_matches(patterns: &[String], path: &str) -> bool {
+    patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == pattern,
+    })
+}
+
+/// Read a comma-separated list, falling back to `default` when the variable is unset
+fn env_list(key: &str, default: &[&str]) -> Vec<String> {
+    match std::env::var(key) {
+        Ok(value) => value
+            .split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| item.to_string())
+            .collect(),
+        Err(_) => default.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+/// Read an optional variable, treating an empty value as unset
+fn env_opt(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Read `<PREFIX>_CLIENT_ID` and `<PREFIX>_CLIENT_SECRET`, both required
+fn env_oauth_client(prefix: &str) -> Option<OAuthClient> {
+    let client_id = env_opt(&format!("{}_CLIENT_ID", prefix));
+    let client_secret = env_opt(&format!("{}_CLIENT_SECRET", prefix));
+    match (client_id, client_secret) {
+        (Some(client_id), Some(client_secret)) => Some(OAuthClient { client_id, client_secret }),
+        (None, None) => None,
+        _ => {
+            eprintln!("Ignoring {}: both {}_CLIENT_ID and {}_CLIENT_SECRET must be set", prefix, prefix, prefix);
+            None
+        }
+    }
+}
+
+/// Parse a variable into `T`, falling back to `default` when unset or invalid
+fn env_parse<T: FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Read a comma-separated list of IP addresses, skipping (and reporting) invalid entries
+fn env_ip_list(key: &str) -> Vec<IpAddr> {
+    env_list(key, &[])
+        .into_iter()
+        .filter_map(|item| match item.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                eprintln!("Ignoring invalid IP address '{}' in {}", item, key);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Read a boolean flag (`1`/`true`/`yes`/`on`), falling back to `default` when unset
+fn env_bool(key: &str, default: bool) -> bool {
+    match std::env::var(key) {
+        Ok(value) => matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"),
+        Err(_) => default,
+    }
+}
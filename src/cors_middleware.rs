@@ -0,0 +1,165 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{
+    HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+};
+use actix_web::http::Method;
+use actix_web::{Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use crate::config;
+
+/// Request headers the API reads, advertised in `Access-Control-Allow-Headers`
+///
+/// Add a header here whenever a handler or middleware starts reading a new one, or browsers
+/// will refuse to send it cross-origin.
+pub const ACCEPTED_REQUEST_HEADERS: &[&str] = &[
+    "content-type",
+    "accept",
+    "cache-control",       // get_board / get_post cache bypass
+    "authorization",       // bearer tokens
+    "x-admin-token",       // admin endpoints
+    "x-api-key",           // automated clients
+    "x-empty-list-status", // listing endpoints
+    "x-load-test",         // tracing span attribute
+    "traceparent",         // trace context propagation
+    "tracestate",
+    "baggage",
+];
+
+/// Response headers set by the API that browser scripts may read
+pub const EXPOSED_RESPONSE_HEADERS: &[&str] = &[
+    "x-request-id",
+    "x-trace-id",
+    "x-trace-sampled",
+    "x-response-time-ms",
+    "x-processing-time-ms",
+    "x-has-more",
+    "x-cache-was-present",
+    "retry-after",
+];
+
+const ALLOWED_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
+
+/// The `Access-Control-Allow-Origin` value for a request's `Origin`, if it is allowed
+fn allowed_origin(origin: &str) -> Option<HeaderValue> {
+    let allowed = &config::get().cors_allowed_origins;
+    if allowed.iter().any(|o| o == "*") {
+        Some(HeaderValue::from_static("*"))
+    } else if allowed.iter().any(|o| o == origin) {
+        HeaderValue::from_str(origin).ok()
+    } else {
+        None
+    }
+}
+
+// Middleware factory answering CORS preflight requests and tagging cross-origin responses
+pub struct Cors;
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CorsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CorsMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let origin = req.headers()
+            .get(ORIGIN)
+            .and_then(|v| v.to_str().ok())
+            .and_then(allowed_origin);
+
+        let is_preflight = req.method() == Method::OPTIONS && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+        if is_preflight {
+            let mut response = HttpResponse::NoContent();
+            if let Some(origin) = &origin {
+                response
+                    .insert_header((ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone()))
+                    .insert_header((ACCESS_CONTROL_ALLOW_METHODS, ALLOWED_METHODS))
+                    .insert_header((ACCESS_CONTROL_ALLOW_HEADERS, ACCEPTED_REQUEST_HEADERS.join(", ")))
+                    .insert_header((ACCESS_CONTROL_MAX_AGE, "600"));
+            }
+            let response = response.insert_header((VARY, "Origin")).finish();
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if let Some(origin) = origin {
+                let headers = res.headers_mut();
+                headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+                if let Ok(exposed) = HeaderValue::from_str(&EXPOSED_RESPONSE_HEADERS.join(", ")) {
+                    headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, exposed);
+                }
+                headers.append(VARY, HeaderValue::from_static("Origin"));
+            }
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+
+    #[actix_web::test]
+    async fn preflight_advertises_accepted_request_headers() {
+        // Default CORS_ALLOWED_ORIGINS is `*`
+        let app = test::init_service(App::new().wrap(Cors).route("/boards", web::post().to(HttpResponse::Ok))).await;
+        let req = test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/boards")
+            .insert_header((ORIGIN, "https://forum.example"))
+            .insert_header((ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), actix_web::http::StatusCode::NO_CONTENT);
+        let allowed: Vec<&str> = res.headers()
+            .get(ACCESS_CONTROL_ALLOW_HEADERS)
+            .and_then(|v| v.to_str().ok())
+            .expect("Access-Control-Allow-Headers on preflight")
+            .split(", ")
+            .collect();
+        assert_eq!(allowed, ACCEPTED_REQUEST_HEADERS);
+        for header in ["content-type", "authorization", "x-admin-token", "x-api-key", "traceparent"] {
+            assert!(allowed.contains(&header), "{} not allowed", header);
+        }
+        assert_eq!(res.headers().get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(), ALLOWED_METHODS);
+    }
+}
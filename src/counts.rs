@@ -0,0 +1,84 @@
+//! Exact totals for the post and comment listings (`meta.total` of `GET /boards/{board_id}/posts`
+//! and `GET /posts/{post_id}/comments`).
+//!
+//! `board_post_counts` and `post_comment_counts` are counter tables holding the number of
+//! listed (not deleted) posts per board and comments per post. Creating, soft-deleting,
+//! restoring and purging adjust them after the item itself is written; counters can't take
+//! part in a batch with regular tables, so a failed adjustment is logged and leaves the total
+//! off until the item changes again. Purged posts and boards drop their counter rows.
+
+use actix_web::web;
+use scylla::frame::value::Counter;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use tracing::error;
+use uuid::Uuid;
+use crate::routes::{execute_cached, record_db_operation, DbCounter};
+
+/// Add `delta` to the number of listed posts on a board, logging failures
+pub(crate) async fn add_posts(session: &Session, board_id: Uuid, delta: i64, db_counter: &web::Data<DbCounter>) {
+    match execute_cached(
+        session,
+        "UPDATE board_post_counts SET posts = posts + ? WHERE board_id = ?",
+        (Counter(delta), board_id),
+    ).await {
+        Ok(_) => record_db_operation(db_counter, "update", "board_post_counts", true),
+        Err(e) => {
+            record_db_operation(db_counter, "update", "board_post_counts", false);
+            error!("Post count of board {} was not adjusted by {}: {}", board_id, delta, e);
+        }
+    }
+}
+
+/// Add `delta` to the number of listed comments on a post, logging failures
+pub(crate) async fn add_comments(session: &Session, post_id: Uuid, delta: i64, db_counter: &web::Data<DbCounter>) {
+    match execute_cached(
+        session,
+        "UPDATE post_comment_counts SET comments = comments + ? WHERE post_id = ?",
+        (Counter(delta), post_id),
+    ).await {
+        Ok(_) => record_db_operation(db_counter, "update", "post_comment_counts", true),
+        Err(e) => {
+            record_db_operation(db_counter, "update", "post_comment_counts", false);
+            error!("Comment count of post {} was not adjusted by {}: {}", post_id, delta, e);
+        }
+    }
+}
+
+/// Number of listed posts on a board
+pub(crate) async fn board_posts(session: &Session, board_id: Uuid) -> Result<u64, QueryError> {
+    let rows = execute_cached(session, "SELECT posts FROM board_post_counts WHERE board_id = ?", (board_id,)).await?;
+    Ok(read_count(rows))
+}
+
+/// Number of listed comments on a post
+pub(crate) async fn post_comments(session: &Session, post_id: Uuid) -> Result<u64, QueryError> {
+    let rows = execute_cached(session, "SELECT comments FROM post_comment_counts WHERE post_id = ?", (post_id,)).await?;
+    Ok(read_count(rows))
+}
+
+/// A missing row is no items; a count driven below zero by lost adjustments reads as zero
+fn read_count(rows: scylla::QueryResult) -> u64 {
+    rows.maybe_first_row_typed::<(Option<Counter>,)>()
+        .ok()
+        .flatten()
+        .and_then(|(count,)| count)
+        .map_or(0, |Counter(count)| count.max(0) as u64)
+}
+
+/// Drop the comment count of a purged post
+pub(crate) async fn remove_post(session: &Session, post_id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM post_comment_counts WHERE post_id = ?", (post_id,)).await?;
+    Ok(())
+}
+
+/// Drop the post count of a purged board
+pub(crate) async fn remove_board(session: &Session, board_id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM board_post_counts WHERE board_id = ?", (board_id,)).await?;
+    Ok(())
+}
+
+/// `meta.total_pages` for `total` items at `limit` per page (an empty listing has one page)
+pub(crate) fn total_pages(total: u64, limit: u32) -> u32 {
+    (total.div_ceil(limit.max(1) as u64).max(1)).min(u32::MAX as u64) as u32
+}
@@ -1,35 +1,382 @@
+use futures::stream::StreamExt;
+use std::collections::{HashMap, HashSet};
+use scylla::batch::{Batch, BatchType};
+use scylla::execution_profile::{ExecutionProfile, ExecutionProfileHandle};
+use scylla::load_balancing::{DefaultPolicy, LatencyAwarenessBuilder};
+use scylla::prepared_statement::PreparedStatement;
+use scylla::statement::Consistency;
 use scylla::Session;
+use uuid::Uuid;
+use crate::config;
+
+/// Single partition of `boards_by_created`; the board count is small enough to keep in one
+pub const BOARDS_BUCKET: i32 = 0;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// Partition of `posts_by_updated` (and `audit_log`) holding a given timestamp (one partition per UTC day)
+pub fn updated_day(updated_at_millis: i64) -> i64 {
+    updated_at_millis.div_euclid(MILLIS_PER_DAY)
+}
+
+/// Build the session's default execution profile from `LB_POLICY` / `LB_LOCAL_DC`
+///
+/// - `round-robin`: spread queries over all nodes, ignoring token ownership
+/// - `token-aware` (default): send each query straight to a replica owning its partition
+/// - `latency-aware`: token-aware, but skip replicas that are noticeably slower than the fastest one
+pub fn load_balancing_profile(policy: &str, local_dc: Option<&str>) -> ExecutionProfileHandle {
+    let mut builder = DefaultPolicy::builder();
+    if let Some(dc) = local_dc {
+        builder = builder.prefer_datacenter(dc.to_string());
+    }
+    builder = match policy {
+        "round-robin" => builder.token_aware(false),
+        "latency-aware" => builder
+            .token_aware(true)
+            .latency_awareness(LatencyAwarenessBuilder::new()),
+        "token-aware" => builder.token_aware(true),
+        other => {
+            eprintln!("Unknown LB_POLICY '{}', falling back to token-aware", other);
+            builder.token_aware(true)
+        }
+    };
+
+    ExecutionProfile::builder()
+        .load_balancing_policy(builder.build())
+        .build()
+        .into_handle()
+}
+
+/// TLS context for the driver's connections, or `None` when `SCYLLA_TLS` is off
+///
+/// Server certificates are verified against `SCYLLA_TLS_CA_PATH`, or the system's trusted
+/// certificates when it is unset.
+#[cfg(feature = "scylla-tls")]
+pub fn tls_context(config: &config::AppConfig) -> Result<Option<openssl::ssl::SslContext>, openssl::error::ErrorStack> {
+    use openssl::ssl::{SslContextBuilder, SslMethod, SslVerifyMode};
+
+    if !config.scylla_tls {
+        return Ok(None);
+    }
+    let mut builder = SslContextBuilder::new(SslMethod::tls())?;
+    match &config.scylla_tls_ca_path {
+        Some(path) => builder.set_ca_file(path)?,
+        None => builder.set_default_verify_paths()?,
+    }
+    builder.set_verify(SslVerifyMode::PEER);
+    Ok(Some(builder.build()))
+}
+
+/// Parse a consistency level name as written in CQL (`ONE`, `QUORUM`, `LOCAL_QUORUM`, ...)
+pub fn parse_consistency(value: &str) -> Option<Consistency> {
+    match value.trim().to_ascii_uppercase().replace('-', "_").as_str() {
+        "ANY" => Some(Consistency::Any),
+        "ONE" => Some(Consistency::One),
+        "TWO" => Some(Consistency::Two),
+        "THREE" => Some(Consistency::Three),
+        "QUORUM" => Some(Consistency::Quorum),
+        "ALL" => Some(Consistency::All),
+        "LOCAL_QUORUM" => Some(Consistency::LocalQuorum),
+        "EACH_QUORUM" => Some(Consistency::EachQuorum),
+        "LOCAL_ONE" => Some(Consistency::LocalOne),
+        _ => None,
+    }
+}
+
+/// Set a prepared statement's consistency from `READ_CONSISTENCY` / `WRITE_CONSISTENCY`
+///
+/// `SELECT`s are reads and everything else is a write. Statements of the prepared-statement
+/// registry are looked up by `name` in `CONSISTENCY_OVERRIDES` first. Without a configured
+/// level the statement keeps the session default.
+pub fn apply_consistency(statement: &mut PreparedStatement, name: Option<&str>) {
+    let config = config::get();
+    let overridden = name.and_then(|name| {
+        config.consistency_overrides
+            .iter()
+            .find(|(statement_name, _)| statement_name == name)
+            .map(|(_, level)| level.as_str())
+    });
+    let is_read = statement.get_statement().trim_start().get(..6).is_some_and(|verb| verb.eq_ignore_ascii_case("SELECT"));
+    let level = overridden.or(if is_read {
+        config.read_consistency.as_deref()
+    } else {
+        config.write_consistency.as_deref()
+    });
+    if let Some(consistency) = level.and_then(parse_consistency) {
+        statement.set_consistency(consistency);
+    }
+}
+
+/// Start a batch, applying `BATCH_CONSISTENCY` (or `WRITE_CONSISTENCY`) when it is set
+///
+/// Batches get their own setting because every statement in them adds load on the
+/// coordinator; the consistency of the statements inside is ignored.
+pub fn new_batch(batch_type: BatchType) -> Batch {
+    let mut batch = Batch::new(batch_type);
+    let config = config::get();
+    let level = config.batch_consistency.as_deref().or(config.write_consistency.as_deref());
+    if let Some(consistency) = level.and_then(parse_consistency) {
+        batch.set_consistency(consistency);
+    }
+    batch
+}
+
+/// Group rows so the estimated serialized size of each group stays under `BATCH_MAX_BYTES`
+///
+/// Scylla logs a warning for batches above `batch_size_warn_threshold_in_kb` (5 KiB by default)
+/// and rejects them above `batch_size_fail_threshold_in_kb` (50 KiB). A row that is over the
+/// limit by itself still goes out, alone in its group.
+pub fn chunk_by_bytes<T>(rows: Vec<T>, row_bytes: impl Fn(&T) -> usize) -> Vec<Vec<T>> {
+    let max_bytes = config::get().batch_max_bytes;
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    let mut current_bytes = 0;
+    for row in rows {
+        let bytes = row_bytes(&row);
+        if !current.is_empty() && current_bytes + bytes > max_bytes {
+            chunks.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += bytes;
+        current.push(row);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Run a startup step until it succeeds, waiting between attempts
+///
+/// The delay starts at `DB_STARTUP_INITIAL_BACKOFF_MS` and doubles up to 30 seconds; once
+/// `DB_STARTUP_MAX_WAIT_SECS` has passed, the last error is returned. This covers Scylla
+/// still booting when the service starts (docker-compose, rolling restarts).
+pub async fn retry_startup<T, E, F, Fut>(what: &str, mut attempt: F) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+    let config = config::get();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(config.db_startup_max_wait_secs);
+    let mut backoff = std::time::Duration::from_millis(config.db_startup_initial_backoff_ms.max(1));
+    let mut attempts = 1u32;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let now = std::time::Instant::now();
+                if now >= deadline {
+                    eprintln!("{} failed after {} attempts: {}", what, attempts, e);
+                    return Err(e);
+                }
+                let delay = backoff.min(deadline - now);
+                eprintln!("{} failed (attempt {}), retrying in {}ms: {}", what, attempts, delay.as_millis(), e);
+                tokio::time::sleep(delay).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                attempts += 1;
+            }
+        }
+    }
+}
 
 pub async fn init_db(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
-    // Create keyspace with optimized settings
+    // Create the keyspace (SCYLLA_KEYSPACE, SCYLLA_REPLICATION_*); an existing one keeps its
+    // replication. The name and class were validated at startup, so they can go into the statement.
+    let config = config::get();
     session
         .query(
-            "CREATE KEYSPACE IF NOT EXISTS posts WITH REPLICATION = {
-                'class': 'SimpleStrategy',
-                'replication_factor': 1
-            }",
+            format!(
+                "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{
+                    'class': '{}',
+                    'replication_factor': {}
+                }}",
+                config.scylla_keyspace, config.scylla_replication_class, config.scylla_replication_factor,
+            ),
             &[],
         ).await?;
 
+    // Other instances starting at the same time may still be propagating the keyspace
+    wait_for_schema_agreement(session).await;
+
     // Set keyspace
-    session.use_keyspace("posts", false).await?;
+    session.use_keyspace(config.scylla_keyspace.as_str(), false).await?;
+
+    // Applied migration ids, so each schema change runs once per cluster
+    session.query("
+        CREATE TABLE IF NOT EXISTS migrations (
+            id INT PRIMARY KEY,
+            name TEXT,
+            applied_at BIGINT
+        )
+    ", &[]).await?;
+
+    run_migrations(session).await?;
 
+    println!("Database initialized successfully with optimized indexes");
+    Ok(())
+}
+
+/// Schema migrations in the order they are applied
+///
+/// Ids are never renumbered or reused. Each migration must be idempotent (`IF NOT EXISTS`,
+/// `add_column_if_missing`, backfills that skip existing data): one interrupted before it was
+/// recorded runs again from the start on the next startup, and instances starting at the
+/// same time may both apply it.
+const MIGRATIONS: &[(i32, &str)] = &[
+    (1, "initial_schema"),
+    (2, "comments_by_board"),
+    (3, "board_display_settings"),
+    (4, "users"),
+    (5, "user_roles"),
+    (6, "credentials"),
+    (7, "sessions"),
+    (8, "oauth"),
+    (9, "api_keys"),
+    (10, "account_erasure"),
+    (11, "comment_edits"),
+    (12, "deletion_jobs"),
+    (13, "soft_delete"),
+    (14, "post_revisions"),
+    (15, "votes"),
+    (16, "reactions"),
+    (17, "post_locks"),
+    (18, "board_categories"),
+    (19, "board_slugs"),
+    (20, "posts_by_title_prefix"),
+    (21, "content_by_author"),
+    (22, "hot_posts"),
+    (23, "list_order"),
+    (24, "listing_counts"),
+    (25, "posts_by_board"),
+    (26, "comments_by_post"),
+    (27, "session_rotated_hashes"),
+    (28, "vote_reaction_users"),
+    (29, "vote_pending_delta"),
+];
+
+async fn apply_migration(session: &Session, id: i32) -> Result<(), Box<dyn std::error::Error>> {
+    match id {
+        1 => migration_0001_initial_schema(session).await,
+        2 => migration_0002_comments_by_board(session).await,
+        3 => migration_0003_board_display_settings(session).await,
+        4 => migration_0004_users(session).await,
+        5 => migration_0005_user_roles(session).await,
+        6 => migration_0006_credentials(session).await,
+        7 => migration_0007_sessions(session).await,
+        8 => migration_0008_oauth(session).await,
+        9 => migration_0009_api_keys(session).await,
+        10 => migration_0010_account_erasure(session).await,
+        11 => migration_0011_comment_edits(session).await,
+        12 => migration_0012_deletion_jobs(session).await,
+        13 => migration_0013_soft_delete(session).await,
+        14 => migration_0014_post_revisions(session).await,
+        15 => migration_0015_votes(session).await,
+        16 => migration_0016_reactions(session).await,
+        17 => migration_0017_post_locks(session).await,
+        18 => migration_0018_board_categories(session).await,
+        19 => migration_0019_board_slugs(session).await,
+        20 => migration_0020_posts_by_title_prefix(session).await,
+        21 => migration_0021_content_by_author(session).await,
+        22 => migration_0022_hot_posts(session).await,
+        23 => migration_0023_list_order(session).await,
+        24 => migration_0024_listing_counts(session).await,
+        25 => migration_0025_posts_by_board(session).await,
+        26 => migration_0026_comments_by_post(session).await,
+        27 => migration_0027_session_rotated_hashes(session).await,
+        28 => migration_0028_vote_reaction_users(session).await,
+        29 => migration_0029_vote_pending_delta(session).await,
+        other => Err(format!("No migration with id {}", other).into()),
+    }
+}
+
+/// Apply every migration not yet recorded in the `migrations` table, in id order
+///
+/// Each one is followed by a schema agreement wait and then recorded, so a failure stops the
+/// run with earlier migrations kept and the failed one retried on the next startup.
+pub async fn run_migrations(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let applied: Vec<i32> = session
+        .query("SELECT id FROM migrations", &[])
+        .await?
+        .rows_typed::<(i32,)>()?
+        .filter_map(|row| row.ok().map(|(id,)| id))
+        .collect();
+
+    // Migrations recorded by a newer release: an older binary can run against that schema as
+    // long as the newer changes only added to it, but it won't write the newer tables
+    let unknown: Vec<i32> = applied
+        .iter()
+        .copied()
+        .filter(|id| !MIGRATIONS.iter().any(|(known, _)| known == id))
+        .collect();
+    if !unknown.is_empty() {
+        eprintln!(
+            "Schema has migrations this build doesn't know ({:?}); it was migrated by a newer release",
+            unknown,
+        );
+    }
+
+    for &(id, name) in MIGRATIONS {
+        if applied.contains(&id) {
+            continue;
+        }
+
+        println!("Applying migration {:04}_{}", id, name);
+        let start = std::time::Instant::now();
+        if let Err(e) = apply_migration(session, id).await {
+            eprintln!("Migration {:04}_{} failed: {}", id, name, e);
+            return Err(e);
+        }
+        wait_for_schema_agreement(session).await;
+
+        session.query(
+            "INSERT INTO migrations (id, name, applied_at) VALUES (?, ?, ?)",
+            (id, name, chrono::Utc::now().timestamp_millis()),
+        ).await?;
+        println!("Applied migration {:04}_{} in {}ms", id, name, start.elapsed().as_millis());
+    }
+    Ok(())
+}
+
+/// Tables and indexes of the forum, including columns added before migrations existed
+async fn migration_0001_initial_schema(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
     // Create boards table with optimizations
     session.query("
         CREATE TABLE IF NOT EXISTS boards (
             id UUID PRIMARY KEY,
             name TEXT,
             description TEXT,
-            created_at BIGINT
+            created_at BIGINT,
+            max_posts INT
         ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
         AND compression = {'sstable_compression': 'LZ4Compressor'}
         AND gc_grace_seconds = 86400
     ", &[]).await?;
 
+    // Columns added after the initial schema, for clusters created by older versions
+    add_column_if_missing(session, "boards", "max_posts", "INT").await?;
+
     // Add index on name for faster searches
-    session.query(
-        "CREATE INDEX IF NOT EXISTS boards_name_idx ON boards (name)", &[]
-    ).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS boards_name_idx ON boards (name)").await?;
+
+    // Boards ordered by creation time, so paging through the list is deterministic
+    session.query("
+        CREATE TABLE IF NOT EXISTS boards_by_created (
+            bucket INT,
+            created_at BIGINT,
+            id UUID,
+            name TEXT,
+            description TEXT,
+            max_posts INT,
+            PRIMARY KEY (bucket, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at ASC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    backfill_boards_by_created(session).await?;
 
     // Create posts table with optimizations
     session.query("
@@ -46,10 +393,71 @@ pub async fn init_db(session: &Session) -> Result<(), Box<dyn std::error::Error>
         AND gc_grace_seconds = 86400
     ", &[]).await?;
 
+    add_column_if_missing(session, "posts", "tags", "SET<TEXT>").await?;
+
     // Add index on board_id for faster board-specific queries
-    session.query(
-        "CREATE INDEX IF NOT EXISTS posts_board_idx ON posts (board_id)", &[]
-    ).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS posts_board_idx ON posts (board_id)").await?;
+
+    // Posts per tag, newest first; collection indexes are too limited to query tags on `posts`
+    session.query("
+        CREATE TABLE IF NOT EXISTS posts_by_tag (
+            tag TEXT,
+            created_at BIGINT,
+            id UUID,
+            board_id UUID,
+            title TEXT,
+            content TEXT,
+            author TEXT,
+            updated_at BIGINT,
+            tags SET<TEXT>,
+            PRIMARY KEY (tag, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    // Posts ordered by last update within per-day partitions, for delta sync clients
+    session.query("
+        CREATE TABLE IF NOT EXISTS posts_by_updated (
+            day BIGINT,
+            updated_at BIGINT,
+            id UUID,
+            board_id UUID,
+            title TEXT,
+            content TEXT,
+            author TEXT,
+            created_at BIGINT,
+            PRIMARY KEY (day, updated_at, id)
+        ) WITH CLUSTERING ORDER BY (updated_at ASC, id ASC)
+        AND compaction = {'class': 'TimeWindowCompactionStrategy', 'compaction_window_unit': 'DAYS', 'compaction_window_size': 1}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    // Boards each author follows, for new-post notifications
+    session.query("
+        CREATE TABLE IF NOT EXISTS subscriptions (
+            author TEXT,
+            board_id UUID,
+            created_at BIGINT,
+            PRIMARY KEY (author, board_id)
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    // Usage count per tag, for popular-tag listings
+    session.query("
+        CREATE TABLE IF NOT EXISTS tags (
+            tag TEXT PRIMARY KEY,
+            uses COUNTER
+        )
+    ", &[]).await?;
+
+    add_column_if_missing(session, "posts_by_updated", "tags", "SET<TEXT>").await?;
+
+    backfill_posts_by_updated(session).await?;
 
     // Create comments table with optimizations
     session.query("
@@ -65,28 +473,1067 @@ pub async fn init_db(session: &Session) -> Result<(), Box<dyn std::error::Error>
     ", &[]).await?;
 
     // Add index on post_id for faster post-specific queries
-    session.query(
-        "CREATE INDEX IF NOT EXISTS comments_post_idx ON comments (post_id)", &[]
-    ).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS comments_post_idx ON comments (post_id)").await?;
 
     // Add index on author for faster author-specific queries
-    session.query(
-        "CREATE INDEX IF NOT EXISTS posts_author_idx ON posts (author)", &[]
-    ).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS posts_author_idx ON posts (author)").await?;
 
-    session.query(
-        "CREATE INDEX IF NOT EXISTS comments_author_idx ON comments (author)", &[]
-    ).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS comments_author_idx ON comments (author)").await?;
 
     // Add index on created_at for better time-based queries
-    session.query(
-        "CREATE INDEX IF NOT EXISTS posts_created_at_idx ON posts (created_at)", &[]
-    ).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS posts_created_at_idx ON posts (created_at)").await?;
+
+    create_index(session, "CREATE INDEX IF NOT EXISTS comments_created_at_idx ON comments (created_at)").await?;
+
+    Ok(())
+}
+
+/// Comments per board, newest first, for the board's recent activity
+///
+/// `comments` only stores `post_id`, so listing a board's comments from it would mean
+/// scanning every post of the board. Each comment is written here as well, together with
+/// the board of its post.
+async fn migration_0002_comments_by_board(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS comments_by_board (
+            board_id UUID,
+            created_at BIGINT,
+            id UUID,
+            post_id UUID,
+            content TEXT,
+            author TEXT,
+            PRIMARY KEY (board_id, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    backfill_comments_by_board(session).await
+}
+
+/// Create a secondary index, tolerating races with other instances starting concurrently
+///
+/// `IF NOT EXISTS` isn't enough when several replicas run the DDL at once: some Scylla
+/// versions still answer "already exists" or time out waiting for schema agreement. Both
+/// leave the index in place, so they are logged and treated as success.
+async fn create_index(session: &Session, cql: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match session.query(cql, &[]).await {
+        Ok(_) => Ok(()),
+        Err(e) if is_ddl_race_error(&e.to_string()) => {
+            eprintln!("Warning: ignoring concurrent schema change error for `{}`: {}", cql, e);
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn is_ddl_race_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("already exists") || message.contains("schema agreement") || message.contains("schema version")
+}
+
+/// Wait (up to `SCHEMA_AGREEMENT_TIMEOUT_SECS`) for all nodes to report the same schema version
+///
+/// A timeout is only logged: the schema usually converges shortly afterwards and failing
+/// startup here would turn a slow rollout into a crash loop.
+async fn wait_for_schema_agreement(session: &Session) {
+    let timeout = std::time::Duration::from_secs(config::get().schema_agreement_timeout_secs);
+    match tokio::time::timeout(timeout, session.await_schema_agreement()).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => eprintln!("Warning: schema agreement check failed: {}", e),
+        Err(_) => eprintln!("Warning: schema agreement not reached within {}s", timeout.as_secs()),
+    }
+}
+
+/// Per-board defaults for the post listing, set by the board creator
+async fn migration_0003_board_display_settings(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    for table in ["boards", "boards_by_created"] {
+        add_column_if_missing(session, table, "default_page_size", "INT").await?;
+        add_column_if_missing(session, table, "default_sort", "TEXT").await?;
+    }
+    Ok(())
+}
+
+/// User accounts, plus the account that wrote each post and comment
+///
+/// `users_by_username` is keyed by the lowercased username and claimed with a lightweight
+/// transaction, so two registrations can't end up with the same name.
+async fn migration_0004_users(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS users (
+            id UUID PRIMARY KEY,
+            username TEXT,
+            password_hash TEXT,
+            created_at BIGINT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    session.query("
+        CREATE TABLE IF NOT EXISTS users_by_username (
+            username TEXT PRIMARY KEY,
+            user_id UUID
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    // Null for anonymous posts and comments, which keep only the free-form author
+    add_column_if_missing(session, "posts", "user_id", "UUID").await?;
+    add_column_if_missing(session, "comments", "user_id", "UUID").await?;
+    Ok(())
+}
+
+/// Role of each user (`user`, `moderator` or `admin`; null reads as `user`)
+async fn migration_0005_user_roles(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    add_column_if_missing(session, "users", "role", "TEXT").await
+}
+
+/// Password hashes moved out of `users`, tagged with the hashing scheme version
+///
+/// Hashes written by migration 0004's `users.password_hash` are copied as scheme 1; the old
+/// column is left in place but no longer read.
+async fn migration_0006_credentials(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS credentials (
+            user_id UUID PRIMARY KEY,
+            password_hash TEXT,
+            scheme_version INT,
+            updated_at BIGINT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    let mut rows = session
+        .query_iter("SELECT id, password_hash, created_at FROM users", &[])
+        .await?
+        .into_typed::<(Uuid, Option<String>, Option<i64>)>();
+
+    let mut copied = 0u64;
+    while let Some(row) = rows.next().await {
+        let (user_id, password_hash, created_at) = row?;
+        let Some(password_hash) = password_hash else { continue };
+        // IF NOT EXISTS keeps a password changed by an instance already on the new schema
+        session.query(
+            "INSERT INTO credentials (user_id, password_hash, scheme_version, updated_at) VALUES (?, ?, ?, ?) IF NOT EXISTS",
+            (user_id, password_hash, 1i32, created_at.unwrap_or(0)),
+        ).await?;
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("Copied {} password hashes into credentials", copied);
+    }
+    Ok(())
+}
+
+/// Login sessions behind refresh tokens; rows are written with the refresh token TTL
+async fn migration_0007_sessions(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS sessions (
+            id UUID PRIMARY KEY,
+            user_id UUID,
+            token_hash TEXT,
+            created_at BIGINT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    Ok(())
+}
+
+/// Provider accounts linked to local users, and the pending sign-in states (short-lived, by TTL)
+async fn migration_0008_oauth(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS oauth_identities (
+            provider TEXT,
+            subject TEXT,
+            user_id UUID,
+            created_at BIGINT,
+            PRIMARY KEY ((provider, subject))
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+
+    session.query("
+        CREATE TABLE IF NOT EXISTS oauth_states (
+            state TEXT PRIMARY KEY,
+            provider TEXT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    Ok(())
+}
+
+/// Keys of automated clients; only a digest of each key's secret is stored
+async fn migration_0009_api_keys(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id UUID PRIMARY KEY,
+            name TEXT,
+            key_hash TEXT,
+            scopes SET<TEXT>,
+            rate_limit_per_minute INT,
+            created_at BIGINT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    Ok(())
+}
+
+/// Lookups of everything a user owns, for erasing an account, and the audit log
+async fn migration_0010_account_erasure(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    create_index(session, "CREATE INDEX IF NOT EXISTS posts_user_idx ON posts (user_id)").await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS comments_user_idx ON comments (user_id)").await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS sessions_user_idx ON sessions (user_id)").await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS oauth_identities_user_idx ON oauth_identities (user_id)").await?;
+
+    // Security-relevant events, one partition per UTC day
+    session.query("
+        CREATE TABLE IF NOT EXISTS audit_log (
+            day BIGINT,
+            created_at BIGINT,
+            id UUID,
+            action TEXT,
+            actor_id UUID,
+            subject_id UUID,
+            details TEXT,
+            PRIMARY KEY (day, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'TimeWindowCompactionStrategy', 'compaction_window_unit': 'DAYS', 'compaction_window_size': 1}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    Ok(())
+}
+
+/// When a comment was last edited, on the comment and its per-board copy
+async fn migration_0011_comment_edits(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    for table in ["comments", "comments_by_board"] {
+        add_column_if_missing(session, table, "edited_at", "BIGINT").await?;
+    }
+    Ok(())
+}
+
+/// Progress of background board deletions, and the subscription lookup they need
+async fn migration_0012_deletion_jobs(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS deletion_jobs (
+            id UUID PRIMARY KEY,
+            board_id UUID,
+            board_name TEXT,
+            status TEXT,
+            posts_total BIGINT,
+            posts_deleted BIGINT,
+            comments_deleted BIGINT,
+            started_at BIGINT,
+            finished_at BIGINT,
+            error TEXT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS subscriptions_board_idx ON subscriptions (board_id)").await?;
+    Ok(())
+}
 
-    session.query(
-        "CREATE INDEX IF NOT EXISTS comments_created_at_idx ON comments (created_at)", &[]
+/// Soft-delete markers on every table holding boards, posts or comments
+async fn migration_0013_soft_delete(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    for table in ["boards", "boards_by_created", "posts", "posts_by_tag", "posts_by_updated", "comments", "comments_by_board"] {
+        add_column_if_missing(session, table, "is_deleted", "BOOLEAN").await?;
+        add_column_if_missing(session, table, "deleted_at", "BIGINT").await?;
+    }
+    Ok(())
+}
+
+/// Edit history of posts, and the number of the current version on the post itself
+async fn migration_0014_post_revisions(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS post_revisions (
+            post_id UUID,
+            revision INT,
+            title TEXT,
+            content TEXT,
+            editor TEXT,
+            editor_id UUID,
+            edited_at BIGINT,
+            PRIMARY KEY (post_id, revision)
+        ) WITH CLUSTERING ORDER BY (revision ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS post_revisions_editor_idx ON post_revisions (editor_id)").await?;
+    add_column_if_missing(session, "posts", "revision", "INT").await?;
+    Ok(())
+}
+
+/// One vote per user on each post or comment, and the resulting scores
+async fn migration_0015_votes(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS votes (
+            target_id UUID,
+            user_id UUID,
+            target_type TEXT,
+            value INT,
+            voted_at BIGINT,
+            PRIMARY KEY (target_id, user_id)
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    session.query("
+        CREATE TABLE IF NOT EXISTS post_score (
+            post_id UUID PRIMARY KEY,
+            score COUNTER
+        )
+    ", &[]).await?;
+    session.query("
+        CREATE TABLE IF NOT EXISTS comment_score (
+            comment_id UUID PRIMARY KEY,
+            score COUNTER
+        )
+    ", &[]).await?;
+    Ok(())
+}
+
+/// Emoji reactions of users on posts and comments, and per-emoji counts
+async fn migration_0016_reactions(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS reactions (
+            content_id UUID,
+            emoji TEXT,
+            user_id UUID,
+            content_type TEXT,
+            reacted_at BIGINT,
+            PRIMARY KEY (content_id, emoji, user_id)
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    session.query("
+        CREATE TABLE IF NOT EXISTS reaction_counts (
+            content_id UUID,
+            emoji TEXT,
+            count COUNTER,
+            PRIMARY KEY (content_id, emoji)
+        )
+    ", &[]).await?;
+    Ok(())
+}
+
+/// Lock flag of posts; only the posts table has it, listings of the copies look it up there
+async fn migration_0017_post_locks(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    add_column_if_missing(session, "posts", "is_locked", "BOOLEAN").await?;
+    Ok(())
+}
+
+/// Categories boards can be grouped by, and each board's category
+async fn migration_0018_board_categories(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS categories (
+            id UUID PRIMARY KEY,
+            name TEXT,
+            position INT,
+            created_at BIGINT
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    for table in ["boards", "boards_by_created"] {
+        add_column_if_missing(session, table, "category_id", "UUID").await?;
+    }
+    Ok(())
+}
+
+/// Unique URL slugs of boards, claimed in `boards_by_slug` with lightweight transactions
+async fn migration_0019_board_slugs(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS boards_by_slug (
+            slug TEXT PRIMARY KEY,
+            board_id UUID
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    for table in ["boards", "boards_by_created"] {
+        add_column_if_missing(session, table, "slug", "TEXT").await?;
+    }
+    wait_for_schema_agreement(session).await;
+    backfill_board_slugs(session).await
+}
+
+/// Title prefixes of posts, for `GET /boards/{board_id}/posts/search` without a search engine
+async fn migration_0020_posts_by_title_prefix(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS posts_by_title_prefix (
+            board_id UUID,
+            prefix TEXT,
+            created_at BIGINT,
+            id UUID,
+            title TEXT,
+            PRIMARY KEY ((board_id, prefix), created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    wait_for_schema_agreement(session).await;
+    backfill_posts_by_title_prefix(session).await
+}
+
+/// Index the titles of posts written before `posts_by_title_prefix` existed
+///
+/// Rewriting rows that are already there is harmless, so an interrupted backfill can simply
+/// run again.
+async fn backfill_posts_by_title_prefix(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter("SELECT id, board_id, title, created_at, is_deleted FROM posts", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<i64>, Option<bool>)>();
+
+    let mut indexed = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, board_id, title, created_at, is_deleted) = row?;
+        let (Some(board_id), Some(title), Some(created_at)) = (board_id, title, created_at) else {
+            continue;
+        };
+        if is_deleted == Some(true) {
+            continue;
+        }
+        crate::title_search::index(session, board_id, id, &title, created_at).await?;
+        indexed += 1;
+    }
+    if indexed > 0 {
+        println!("Indexed the titles of {} posts", indexed);
+    }
+    Ok(())
+}
+
+/// Posts and comments partitioned by author, replacing the secondary indexes on `author`
+async fn migration_0021_content_by_author(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS posts_by_author (
+            author TEXT,
+            created_at BIGINT,
+            id UUID,
+            board_id UUID,
+            title TEXT,
+            content TEXT,
+            updated_at BIGINT,
+            tags SET<TEXT>,
+            is_deleted BOOLEAN,
+            deleted_at BIGINT,
+            PRIMARY KEY (author, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    session.query("
+        CREATE TABLE IF NOT EXISTS comments_by_author (
+            author TEXT,
+            created_at BIGINT,
+            id UUID,
+            post_id UUID,
+            content TEXT,
+            edited_at BIGINT,
+            is_deleted BOOLEAN,
+            deleted_at BIGINT,
+            PRIMARY KEY (author, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    wait_for_schema_agreement(session).await;
+    backfill_content_by_author(session).await?;
+
+    // Nothing reads the posts and comments tables by author any more
+    session.query("DROP INDEX IF EXISTS posts_author_idx", &[]).await?;
+    session.query("DROP INDEX IF EXISTS comments_author_idx", &[]).await?;
+    Ok(())
+}
+
+/// Hot ranking of each board's posts, refreshed by a background task
+async fn migration_0022_hot_posts(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS hot_posts (
+            board_id UUID,
+            rank INT,
+            post_id UUID,
+            hot DOUBLE,
+            computed_at BIGINT,
+            PRIMARY KEY (board_id, rank)
+        ) WITH compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    Ok(())
+}
+
+/// Posts per board and comments per post in `created_at`, `updated_at` and `score` order
+/// (see the list_order module)
+async fn migration_0023_list_order(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS post_order (
+            board_id UUID,
+            sort TEXT,
+            value BIGINT,
+            id UUID,
+            PRIMARY KEY ((board_id, sort), value, id)
+        ) WITH CLUSTERING ORDER BY (value DESC, id DESC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    session.query("
+        CREATE TABLE IF NOT EXISTS comment_order (
+            post_id UUID,
+            sort TEXT,
+            value BIGINT,
+            id UUID,
+            PRIMARY KEY ((post_id, sort), value, id)
+        ) WITH CLUSTERING ORDER BY (value DESC, id DESC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    wait_for_schema_agreement(session).await;
+    backfill_list_order(session).await
+}
+
+/// Listed (not deleted) posts per board and comments per post, for `meta.total`
+async fn migration_0024_listing_counts(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS board_post_counts (
+            board_id UUID PRIMARY KEY,
+            posts COUNTER
+        )
+    ", &[]).await?;
+    session.query("
+        CREATE TABLE IF NOT EXISTS post_comment_counts (
+            post_id UUID PRIMARY KEY,
+            comments COUNTER
+        )
+    ", &[]).await?;
+    wait_for_schema_agreement(session).await;
+    backfill_listing_counts(session).await
+}
+
+/// Posts partitioned by board, newest first, so board listings read one partition instead of
+/// filtering `posts`
+///
+/// The board's `created_at` order now comes from here, so its rows in `post_order` go.
+async fn migration_0025_posts_by_board(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS posts_by_board (
+            board_id UUID,
+            created_at BIGINT,
+            id UUID,
+            title TEXT,
+            content TEXT,
+            author TEXT,
+            updated_at BIGINT,
+            tags SET<TEXT>,
+            user_id UUID,
+            is_deleted BOOLEAN,
+            deleted_at BIGINT,
+            is_locked BOOLEAN,
+            PRIMARY KEY (board_id, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at DESC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    wait_for_schema_agreement(session).await;
+    let boards = backfill_posts_by_board(session).await?;
+
+    for board_id in boards {
+        session.query("DELETE FROM post_order WHERE board_id = ? AND sort = 'created_at'", (board_id,)).await?;
+    }
+    // Nothing reads posts by board from `posts` any more
+    session.query("DROP INDEX IF EXISTS posts_board_idx", &[]).await?;
+    Ok(())
+}
+
+/// Comments partitioned by post, oldest first, so a post's comments are read from one partition
+/// instead of filtering `comments`
+///
+/// The post's `created_at` order now comes from here, so its rows in `comment_order` go.
+async fn migration_0026_comments_by_post(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    session.query("
+        CREATE TABLE IF NOT EXISTS comments_by_post (
+            post_id UUID,
+            created_at BIGINT,
+            id UUID,
+            content TEXT,
+            author TEXT,
+            edited_at BIGINT,
+            user_id UUID,
+            is_deleted BOOLEAN,
+            deleted_at BIGINT,
+            PRIMARY KEY (post_id, created_at, id)
+        ) WITH CLUSTERING ORDER BY (created_at ASC, id ASC)
+        AND compaction = {'class': 'LeveledCompactionStrategy'}
+        AND compression = {'sstable_compression': 'LZ4Compressor'}
+        AND gc_grace_seconds = 86400
+    ", &[]).await?;
+    wait_for_schema_agreement(session).await;
+    let posts = backfill_comments_by_post(session).await?;
+
+    for post_id in posts {
+        session.query("DELETE FROM comment_order WHERE post_id = ? AND sort = 'created_at'", (post_id,)).await?;
+    }
+    // Nothing reads comments by post from `comments` any more
+    session.query("DROP INDEX IF EXISTS comments_post_idx", &[]).await?;
+    Ok(())
+}
+
+/// Hashes of the refresh token secrets a session has rotated away from, so presenting one
+/// can be told apart from a token that never belonged to the session
+async fn migration_0027_session_rotated_hashes(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    add_column_if_missing(session, "sessions", "rotated_hashes", "SET<TEXT>").await
+}
+
+/// Votes and reactions by user, so account erasure can find and remove them
+async fn migration_0028_vote_reaction_users(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    create_index(session, "CREATE INDEX IF NOT EXISTS votes_user_idx ON votes (user_id)").await?;
+    create_index(session, "CREATE INDEX IF NOT EXISTS reactions_user_idx ON reactions (user_id)").await?;
+    Ok(())
+}
+
+/// Score change of a vote not yet added to the score counter, so a failed update can be redone
+async fn migration_0029_vote_pending_delta(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    add_column_if_missing(session, "votes", "pending_delta", "INT").await
+}
+
+/// Copy comments into `comments_by_post`, returning the posts they are on
+///
+/// Rewriting rows that are already there is harmless, so an interrupted backfill can simply
+/// run again.
+async fn backfill_comments_by_post(session: &Session) -> Result<HashSet<Uuid>, Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter("SELECT id, post_id, content, author, created_at, edited_at, user_id, is_deleted, deleted_at FROM comments", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Uuid>, Option<bool>, Option<i64>)>();
+    let mut posts = HashSet::new();
+    let mut copied = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, post_id, content, author, created_at, edited_at, user_id, is_deleted, deleted_at) = row?;
+        let (Some(post_id), Some(created_at)) = (post_id, created_at) else {
+            continue;
+        };
+        session.query(
+            "INSERT INTO comments_by_post (post_id, created_at, id, content, author, edited_at, user_id, is_deleted, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (post_id, created_at, id, content, author, edited_at, user_id, is_deleted, deleted_at),
+        ).await?;
+        posts.insert(post_id);
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("Backfilled {} comments into comments_by_post", copied);
+    }
+    Ok(posts)
+}
+
+/// Copy posts into `posts_by_board`, returning the boards they are on
+///
+/// Rewriting rows that are already there is harmless, so an interrupted backfill can simply
+/// run again.
+async fn backfill_posts_by_board(session: &Session) -> Result<HashSet<Uuid>, Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter("SELECT id, board_id, title, content, author, created_at, updated_at, tags, user_id, is_deleted, deleted_at, is_locked FROM posts", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>, Option<Uuid>, Option<bool>, Option<i64>, Option<bool>)>();
+    let mut boards = HashSet::new();
+    let mut copied = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, board_id, title, content, author, created_at, updated_at, tags, user_id, is_deleted, deleted_at, is_locked) = row?;
+        let (Some(board_id), Some(created_at)) = (board_id, created_at) else {
+            continue;
+        };
+        session.query(
+            "INSERT INTO posts_by_board (board_id, created_at, id, title, content, author, updated_at, tags, user_id, is_deleted, deleted_at, is_locked) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (board_id, created_at, id, title, content, author, updated_at.unwrap_or(created_at), tags, user_id, is_deleted, deleted_at, is_locked),
+        ).await?;
+        boards.insert(board_id);
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("Backfilled {} posts into posts_by_board", copied);
+    }
+    Ok(boards)
+}
+
+/// Copy posts and comments written before the author tables existed into them
+///
+/// Rewriting rows that are already there is harmless, so an interrupted backfill can simply
+/// run again.
+async fn backfill_content_by_author(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter("SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at FROM posts", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>, Option<bool>, Option<i64>)>();
+    let mut posts = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at) = row?;
+        let (Some(author), Some(created_at)) = (author, created_at) else {
+            continue;
+        };
+        session.query(
+            "INSERT INTO posts_by_author (author, created_at, id, board_id, title, content, updated_at, tags, is_deleted, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (author, created_at, id, board_id, title, content, updated_at.unwrap_or(created_at), tags, is_deleted, deleted_at),
+        ).await?;
+        posts += 1;
+    }
+
+    let mut rows = session
+        .query_iter("SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<bool>, Option<i64>)>();
+    let mut comments = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at) = row?;
+        let (Some(author), Some(created_at)) = (author, created_at) else {
+            continue;
+        };
+        session.query(
+            "INSERT INTO comments_by_author (author, created_at, id, post_id, content, edited_at, is_deleted, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            (author, created_at, id, post_id, content, edited_at, is_deleted, deleted_at),
+        ).await?;
+        comments += 1;
+    }
+
+    if posts > 0 || comments > 0 {
+        println!("Backfilled {} posts and {} comments into the author tables", posts, comments);
+    }
+    Ok(())
+}
+
+/// Write the ordering rows of posts and comments created before the ordering tables existed
+///
+/// Rows are keyed by their values, so rewriting them is harmless and an interrupted backfill
+/// can simply run again.
+async fn backfill_list_order(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let post_scores = read_scores(session, "SELECT post_id, score FROM post_score").await?;
+    let mut rows = session
+        .query_iter("SELECT id, board_id, created_at, updated_at FROM posts", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<i64>, Option<i64>)>();
+    let mut posts = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, board_id, created_at, updated_at) = row?;
+        let (Some(board_id), Some(created_at)) = (board_id, created_at) else {
+            continue;
+        };
+        let score = post_scores.get(&id).copied().unwrap_or(0);
+        for (sort, value) in [("created_at", created_at), ("updated_at", updated_at.unwrap_or(created_at)), ("score", score)] {
+            session.query(
+                "INSERT INTO post_order (board_id, sort, value, id) VALUES (?, ?, ?, ?)",
+                (board_id, sort, value, id),
+            ).await?;
+        }
+        posts += 1;
+    }
+
+    let comment_scores = read_scores(session, "SELECT comment_id, score FROM comment_score").await?;
+    let mut rows = session
+        .query_iter("SELECT id, post_id, created_at, edited_at FROM comments", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<i64>, Option<i64>)>();
+    let mut comments = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, post_id, created_at, edited_at) = row?;
+        let (Some(post_id), Some(created_at)) = (post_id, created_at) else {
+            continue;
+        };
+        let score = comment_scores.get(&id).copied().unwrap_or(0);
+        for (sort, value) in [("created_at", created_at), ("updated_at", edited_at.unwrap_or(created_at)), ("score", score)] {
+            session.query(
+                "INSERT INTO comment_order (post_id, sort, value, id) VALUES (?, ?, ?, ?)",
+                (post_id, sort, value, id),
+            ).await?;
+        }
+        comments += 1;
+    }
+
+    if posts > 0 || comments > 0 {
+        println!("Backfilled {} posts and {} comments into the ordering tables", posts, comments);
+    }
+    Ok(())
+}
+
+/// Every score in a `post_score`/`comment_score` counter table
+async fn read_scores(session: &Session, cql: &str) -> Result<HashMap<Uuid, i64>, Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter(cql, &[])
+        .await?
+        .into_typed::<(Uuid, Option<scylla::frame::value::Counter>)>();
+    let mut scores = HashMap::new();
+    while let Some(row) = rows.next().await {
+        let (id, score) = row?;
+        scores.insert(id, score.map_or(0, |score| score.0));
+    }
+    Ok(scores)
+}
+
+/// Count the posts and comments written before the counter tables existed
+///
+/// Counters can't be overwritten, only incremented, so this only runs while the tables are
+/// still empty.
+async fn backfill_listing_counts(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = session.query("SELECT board_id FROM board_post_counts LIMIT 1", &[]).await?;
+    if !existing.rows.unwrap_or_default().is_empty() {
+        return Ok(());
+    }
+
+    let mut board_posts: HashMap<Uuid, i64> = HashMap::new();
+    let mut rows = session
+        .query_iter("SELECT board_id, is_deleted FROM posts", &[])
+        .await?
+        .into_typed::<(Option<Uuid>, Option<bool>)>();
+    while let Some(row) = rows.next().await {
+        if let (Some(board_id), is_deleted) = row? {
+            if is_deleted != Some(true) {
+                *board_posts.entry(board_id).or_default() += 1;
+            }
+        }
+    }
+    let mut post_comments: HashMap<Uuid, i64> = HashMap::new();
+    let mut rows = session
+        .query_iter("SELECT post_id, is_deleted FROM comments", &[])
+        .await?
+        .into_typed::<(Option<Uuid>, Option<bool>)>();
+    while let Some(row) = rows.next().await {
+        if let (Some(post_id), is_deleted) = row? {
+            if is_deleted != Some(true) {
+                *post_comments.entry(post_id).or_default() += 1;
+            }
+        }
+    }
+
+    for (board_id, posts) in &board_posts {
+        session.query(
+            "UPDATE board_post_counts SET posts = posts + ? WHERE board_id = ?",
+            (scylla::frame::value::Counter(*posts), *board_id),
+        ).await?;
+    }
+    for (post_id, comments) in &post_comments {
+        session.query(
+            "UPDATE post_comment_counts SET comments = comments + ? WHERE post_id = ?",
+            (scylla::frame::value::Counter(*comments), *post_id),
+        ).await?;
+    }
+    if !board_posts.is_empty() || !post_comments.is_empty() {
+        println!("Counted the posts of {} boards and the comments of {} posts", board_posts.len(), post_comments.len());
+    }
+    Ok(())
+}
+
+/// Give boards created before slugs existed one, suffixing `-2`, `-3`, ... on collisions
+async fn backfill_board_slugs(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter("SELECT id, name, created_at, slug FROM boards", &[])
+        .await?
+        .into_typed::<(Uuid, Option<String>, Option<i64>, Option<String>)>();
+
+    let mut assigned = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, name, created_at, slug) = row?;
+        if slug.is_some_and(|slug| !slug.is_empty()) {
+            continue;
+        }
+        let base = crate::routes::board_slug(name.as_deref().unwrap_or_default(), id);
+        let mut claimed = None;
+        for suffix in 1..=1000u32 {
+            let candidate = if suffix == 1 { base.clone() } else { format!("{}-{}", base, suffix) };
+            let result = session.query(
+                "INSERT INTO boards_by_slug (slug, board_id) VALUES (?, ?) IF NOT EXISTS",
+                (&candidate, id),
+            ).await?;
+            // A rerun after an interruption finds its own earlier claim
+            let row = result.rows.as_ref().and_then(|rows| rows.first());
+            let applied = row.and_then(|row| row.columns.first()).and_then(|c| c.as_ref()).and_then(|c| c.as_boolean()).unwrap_or(true);
+            let holder = row.and_then(|row| row.columns.get(2)).and_then(|c| c.as_ref()).and_then(|c| c.as_uuid());
+            if applied || holder == Some(id) {
+                claimed = Some(candidate);
+                break;
+            }
+        }
+        let Some(slug) = claimed else {
+            return Err(format!("No free slug for board {}", id).into());
+        };
+        session.query("UPDATE boards SET slug = ? WHERE id = ?", (&slug, id)).await?;
+        if let Some(created_at) = created_at {
+            session.query(
+                "UPDATE boards_by_created SET slug = ? WHERE bucket = ? AND created_at = ? AND id = ? IF EXISTS",
+                (&slug, BOARDS_BUCKET, created_at, id),
+            ).await?;
+        }
+        assigned += 1;
+    }
+    if assigned > 0 {
+        println!("Assigned slugs to {} boards", assigned);
+    }
+    Ok(())
+}
+
+/// Copy boards missing from `boards_by_created` (created before it existed, or left out by an
+/// interrupted run) into it
+async fn backfill_boards_by_created(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let mut existing = HashSet::new();
+    let mut indexed = session
+        .query_iter("SELECT id FROM boards_by_created WHERE bucket = ?", (BOARDS_BUCKET,))
+        .await?
+        .into_typed::<(Uuid,)>();
+    while let Some(row) = indexed.next().await {
+        existing.insert(row?.0);
+    }
+
+    let mut rows = session
+        .query_iter("SELECT id, name, description, created_at, max_posts FROM boards", &[])
+        .await?
+        .into_typed::<(Uuid, Option<String>, Option<String>, Option<i64>, Option<i32>)>();
+
+    let mut copied = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, name, description, created_at, max_posts) = row?;
+        if existing.contains(&id) {
+            continue;
+        }
+        session.query(
+            "INSERT INTO boards_by_created (bucket, created_at, id, name, description, max_posts) VALUES (?, ?, ?, ?, ?, ?)",
+            (BOARDS_BUCKET, created_at.unwrap_or(0), id, name.unwrap_or_default(), description.unwrap_or_default(), max_posts),
+        ).await?;
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("Backfilled {} boards into boards_by_created", copied);
+    }
+    Ok(())
+}
+
+/// Copy posts written before `posts_by_updated` existed into it
+async fn backfill_posts_by_updated(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = session.query("SELECT id FROM posts_by_updated LIMIT 1", &[]).await?;
+    if !existing.rows.unwrap_or_default().is_empty() {
+        return Ok(());
+    }
+
+    let mut rows = session
+        .query_iter("SELECT id, board_id, title, content, author, created_at, updated_at, tags FROM posts", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>)>();
+
+    let mut copied = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, board_id, title, content, author, created_at, updated_at, tags) = row?;
+        let updated_at = updated_at.or(created_at).unwrap_or(0);
+        session.query(
+            "INSERT INTO posts_by_updated (day, updated_at, id, board_id, title, content, author, created_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (updated_day(updated_at), updated_at, id, board_id, title, content, author, created_at.unwrap_or(updated_at), tags),
+        ).await?;
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("Backfilled {} posts into posts_by_updated", copied);
+    }
+    Ok(())
+}
+
+/// Copy comments written before `comments_by_board` existed into it
+///
+/// Rewriting a comment that is already there is harmless, so an interrupted backfill can
+/// simply run again.
+async fn backfill_comments_by_board(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rows = session
+        .query_iter("SELECT id, post_id, content, author, created_at FROM comments", &[])
+        .await?
+        .into_typed::<(Uuid, Option<Uuid>, Option<String>, Option<String>, Option<i64>)>();
+
+    // Boards of the posts seen so far, to look each post up once
+    let mut post_boards: HashMap<Uuid, Option<Uuid>> = HashMap::new();
+    let mut copied = 0u64;
+    while let Some(row) = rows.next().await {
+        let (id, post_id, content, author, created_at) = row?;
+        let Some(post_id) = post_id else {
+            continue;
+        };
+        let board_id = match post_boards.get(&post_id) {
+            Some(board_id) => *board_id,
+            None => {
+                let board_id = session
+                    .query("SELECT board_id FROM posts WHERE id = ?", (post_id,))
+                    .await?
+                    .maybe_first_row_typed::<(Option<Uuid>,)>()?
+                    .and_then(|(board_id,)| board_id);
+                post_boards.insert(post_id, board_id);
+                board_id
+            }
+        };
+        let Some(board_id) = board_id else {
+            continue;
+        };
+        session.query(
+            "INSERT INTO comments_by_board (board_id, created_at, id, post_id, content, author) VALUES (?, ?, ?, ?, ?, ?)",
+            (board_id, created_at.unwrap_or(0), id, post_id, content, author),
+        ).await?;
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("Backfilled {} comments into comments_by_board", copied);
+    }
+    Ok(())
+}
+
+/// Add a column to an existing table unless it is already present
+///
+/// `ALTER TABLE ... ADD` has no `IF NOT EXISTS` form, so the schema tables are checked first.
+pub async fn add_column_if_missing(
+    session: &Session,
+    table: &str,
+    column: &str,
+    cql_type: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = session.query(
+        "SELECT column_name FROM system_schema.columns WHERE keyspace_name = ? AND table_name = ? AND column_name = ?",
+        (config::get().scylla_keyspace.as_str(), table, column),
     ).await?;
 
-    println!("Database initialized successfully with optimized indexes");
+    if existing.rows.unwrap_or_default().is_empty() {
+        session.query(format!("ALTER TABLE {} ADD {} {}", table, column, cql_type), &[]).await?;
+        println!("Added column {}.{}", table, column);
+    }
     Ok(())
 }
+
+/// Estimate the number of rows in a table from Scylla's `system.size_estimates`
+///
+/// Every table here has one row per partition, so the partition count doubles as a row count.
+/// Scylla refreshes these figures periodically from flushed SSTables on the local token ranges,
+/// so recent writes may be missing and the value is only a ballpark.
+pub async fn estimate_table_rows(session: &Session, table: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let result = session.query(
+        "SELECT partitions_count FROM system.size_estimates WHERE keyspace_name = ? AND table_name = ?",
+        (config::get().scylla_keyspace.as_str(), table),
+    ).await?;
+
+    let mut total = 0u64;
+    for row in result.rows_typed::<(Option<i64>,)>()? {
+        let (partitions_count,) = row?;
+        total = total.saturating_add(partitions_count.unwrap_or(0).max(0) as u64);
+    }
+    Ok(total)
+}
@@ -0,0 +1,215 @@
+//! Guarded access to ScyllaDB for the request handlers: a per-call timeout and a circuit
+//! breaker.
+//!
+//! Every call gets `DB_QUERY_TIMEOUT_MS`, and so does every paged read as a whole (see
+//! `PagedRead`). After `DB_BREAKER_FAILURE_THRESHOLD` failures in a
+//! row that point at the cluster rather than the query (timeouts, lost connections,
+//! unavailable or overloaded replicas) the breaker opens: calls fail at once instead of piling
+//! up on a dead cluster, and handlers answer 503 with `Retry-After`. A background task probes
+//! the cluster every `DB_BREAKER_PROBE_INTERVAL_SECS` while the breaker is open and closes it
+//! after the first successful probe. `forum_api_db_circuit_breaker_open` is 1 while it is open.
+
+use futures::stream::{Stream, StreamExt};
+use prometheus::IntGauge;
+use scylla::transport::errors::{DbError, QueryError};
+use scylla::transport::iterator::NextRowError;
+use scylla::Session;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use crate::config;
+
+static OPEN: AtomicBool = AtomicBool::new(false);
+static CONSECUTIVE_FAILURES: AtomicU32 = AtomicU32::new(0);
+static OPEN_GAUGE: OnceLock<IntGauge> = OnceLock::new();
+
+/// Error carried (as an I/O error) by calls rejected while the breaker is open
+#[derive(Debug)]
+struct BreakerOpen;
+
+impl std::fmt::Display for BreakerOpen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Database circuit breaker is open")
+    }
+}
+
+impl std::error::Error for BreakerOpen {}
+
+/// Register the gauge reporting the breaker state, set by main once metrics are registered
+pub fn set_open_gauge(gauge: IntGauge) {
+    gauge.set(OPEN.load(Ordering::Relaxed) as i64);
+    let _ = OPEN_GAUGE.set(gauge);
+}
+
+/// Run a database call under the timeout and the breaker
+///
+/// A call that runs past `DB_QUERY_TIMEOUT_MS` is dropped, which cancels the request on the
+/// driver side (a write may still have been applied), and fails with
+/// `QueryError::RequestTimeout`. While the breaker is open the call isn't made at all.
+pub(crate) async fn call<T>(call: impl Future<Output = Result<T, QueryError>>) -> Result<T, QueryError> {
+    PagedRead::start().call(call).await
+}
+
+/// A paged read (`execute_iter`, `query_iter`) under a single `DB_QUERY_TIMEOUT_MS`
+///
+/// The query that opens the iterator and every later page fetched while iterating share one
+/// deadline, so a read can't run past the timeout one page at a time.
+pub(crate) struct PagedRead {
+    deadline: Option<Instant>,
+}
+
+impl PagedRead {
+    pub(crate) fn start() -> Self {
+        let timeout_ms = config::get().db_query_timeout_ms;
+        Self { deadline: (timeout_ms > 0).then(|| Instant::now() + Duration::from_millis(timeout_ms)) }
+    }
+
+    /// Run a call (usually the one opening the iterator) under what is left of the deadline
+    pub(crate) async fn call<T>(&self, call: impl Future<Output = Result<T, QueryError>>) -> Result<T, QueryError> {
+        if OPEN.load(Ordering::Relaxed) {
+            return Err(breaker_open());
+        }
+        let result = match self.deadline {
+            None => call.await,
+            Some(deadline) => match tokio::time::timeout_at(deadline.into(), call).await {
+                Ok(result) => result,
+                Err(_) => Err(self.timed_out()),
+            },
+        };
+        record(&result);
+        result
+    }
+
+    /// The next row of `rows`, or `RequestTimeout` once the deadline has passed
+    pub(crate) async fn next<S, T, E>(&self, rows: &mut S) -> Option<Result<T, QueryError>>
+    where
+        S: Stream<Item = Result<T, E>> + Unpin,
+        E: Into<RowError>,
+    {
+        if OPEN.load(Ordering::Relaxed) {
+            return Some(Err(breaker_open()));
+        }
+        let row = match self.deadline {
+            None => rows.next().await?.map_err(|e| e.into().0),
+            Some(deadline) => match tokio::time::timeout_at(deadline.into(), rows.next()).await {
+                Ok(row) => row?.map_err(|e| e.into().0),
+                Err(_) => Err(self.timed_out()),
+            },
+        };
+        record(&row);
+        Some(row)
+    }
+
+    fn timed_out(&self) -> QueryError {
+        QueryError::RequestTimeout(format!("No response from the database within {}ms", config::get().db_query_timeout_ms))
+    }
+}
+
+/// Error of reading a row from an untyped or typed row iterator, as a `QueryError`
+pub(crate) struct RowError(QueryError);
+
+impl From<QueryError> for RowError {
+    fn from(e: QueryError) -> Self {
+        RowError(e)
+    }
+}
+
+impl From<NextRowError> for RowError {
+    fn from(e: NextRowError) -> Self {
+        match e {
+            NextRowError::QueryError(e) => RowError(e),
+            // A row that doesn't match the expected columns
+            NextRowError::FromRowError(e) => RowError(QueryError::InvalidMessage(e.to_string())),
+        }
+    }
+}
+
+fn breaker_open() -> QueryError {
+    QueryError::IoError(Arc::new(std::io::Error::other(BreakerOpen)))
+}
+
+/// Count a call's outcome towards opening the breaker
+fn record<T>(result: &Result<T, QueryError>) {
+    match result {
+        Err(e) if is_cluster_failure(e) => record_failure(e),
+        // Query errors (bad statements, missing rows) say nothing about the cluster's health
+        _ => CONSECUTIVE_FAILURES.store(0, Ordering::Relaxed),
+    }
+}
+
+/// Whether `e` is a call rejected by the open breaker
+pub(crate) fn is_breaker_open(e: &QueryError) -> bool {
+    matches!(e, QueryError::IoError(io) if io.get_ref().is_some_and(|inner| inner.is::<BreakerOpen>()))
+}
+
+/// Seconds clients are told to wait before retrying a rejected call
+pub(crate) fn retry_after_secs() -> u64 {
+    config::get().db_breaker_probe_interval_secs.max(1)
+}
+
+/// Failures caused by the cluster being unreachable or overwhelmed
+fn is_cluster_failure(e: &QueryError) -> bool {
+    match e {
+        QueryError::RequestTimeout(_) | QueryError::TimeoutError | QueryError::IoError(_) => true,
+        QueryError::DbError(error, _) => matches!(
+            error,
+            DbError::Unavailable { .. }
+                | DbError::ReadTimeout { .. }
+                | DbError::WriteTimeout { .. }
+                | DbError::Overloaded
+                | DbError::IsBootstrapping
+        ),
+        _ => false,
+    }
+}
+
+fn record_failure(e: &QueryError) {
+    let threshold = config::get().db_breaker_failure_threshold;
+    let failures = CONSECUTIVE_FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+    if threshold == 0 || failures < threshold {
+        return;
+    }
+    if !OPEN.swap(true, Ordering::Relaxed) {
+        warn!("Database circuit breaker opened after {} failures in a row, last: {}", failures, e);
+        if let Some(gauge) = OPEN_GAUGE.get() {
+            gauge.set(1);
+        }
+    }
+}
+
+fn close() {
+    CONSECUTIVE_FAILURES.store(0, Ordering::Relaxed);
+    if OPEN.swap(false, Ordering::Relaxed) {
+        info!("Database circuit breaker closed, the cluster answers again");
+        if let Some(gauge) = OPEN_GAUGE.get() {
+            gauge.set(0);
+        }
+    }
+}
+
+/// Start the task probing the cluster while the breaker is open (does nothing when
+/// `DB_BREAKER_FAILURE_THRESHOLD` is 0)
+pub fn spawn_prober(session: Arc<Session>) {
+    if config::get().db_breaker_failure_threshold == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(retry_after_secs());
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if !OPEN.load(Ordering::Relaxed) {
+                continue;
+            }
+            let probe = tokio::time::timeout(interval, session.query("SELECT now() FROM system.local", &[])).await;
+            match probe {
+                Ok(Ok(_)) => close(),
+                Ok(Err(e)) => warn!("Database circuit breaker probe failed: {}", e),
+                Err(_) => warn!("Database circuit breaker probe timed out after {}s", interval.as_secs()),
+            }
+        }
+    });
+}
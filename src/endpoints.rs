@@ -0,0 +1,127 @@
+//! Registry of the API endpoints, so a deployment can switch individual ones off with
+//! `DISABLED_ENDPOINTS` (a disabled endpoint is not registered and answers 404, or 405 when
+//! its path still has other methods).
+
+use actix_web::web;
+use crate::{account_erasure, admin, auth, author_content, board_deletion, categories, config, oauth, post_revisions, reactions, routes, search, subscriptions, title_search, votes};
+
+macro_rules! endpoints {
+    ($($name:literal => $service:path),* $(,)?) => {
+        /// Canonical endpoint names accepted by `DISABLED_ENDPOINTS`, in registration order
+        pub const ENDPOINT_NAMES: &[&str] = &[$($name),*];
+
+        /// Register every endpoint not listed in `DISABLED_ENDPOINTS`
+        pub fn configure(cfg: &mut web::ServiceConfig) {
+            let disabled = &config::get().disabled_endpoints;
+            $(
+                if !disabled.iter().any(|name| name == $name) {
+                    cfg.service($service);
+                }
+            )*
+        }
+
+        /// Whether the endpoint served by the handler function `handler` is disabled
+        pub fn is_handler_disabled(handler: &str) -> bool {
+            let disabled = &config::get().disabled_endpoints;
+            $(
+                if stringify!($service).rsplit("::").next().map(str::trim) == Some(handler) {
+                    return disabled.iter().any(|name| name == $name);
+                }
+            )*
+            false
+        }
+    };
+}
+
+// Order matters where paths overlap: actix tries services in registration order
+endpoints! {
+    // Health endpoint (metrics endpoint is auto-registered by actix-web-prom at /metrics)
+    "health_check" => routes::health_check,
+    // Board related endpoints
+    "create_board" => routes::create_board,
+    "get_boards_stats" => routes::get_boards_stats,
+    "get_boards" => routes::get_boards,
+    "get_board_by_slug" => routes::get_board_by_slug,
+    "get_board" => routes::get_board,
+    "update_board" => routes::update_board,
+    "delete_board" => board_deletion::delete_board,
+    "restore_board" => board_deletion::restore_board,
+    "get_deletion_job" => board_deletion::get_deletion_job,
+    // Board category endpoints
+    "create_category" => categories::create_category,
+    "get_categories" => categories::get_categories,
+    // Board subscription endpoints
+    "subscribe_to_board" => subscriptions::subscribe_to_board,
+    "unsubscribe_from_board" => subscriptions::unsubscribe_from_board,
+    "get_author_subscriptions" => subscriptions::get_author_subscriptions,
+    // Post related endpoints
+    "create_post" => routes::create_post,
+    "get_posts_by_board" => routes::get_posts_by_board,
+    "search_posts_by_title" => title_search::search_posts_by_title,
+    "get_popular_tags" => routes::get_popular_tags,
+    "get_posts_by_tag" => routes::get_posts_by_tag,
+    "get_post_changes" => routes::get_post_changes, // Before /posts/{post_id} so "changes" isn't taken for an ID
+    "get_post" => routes::get_post,
+    "get_full_post" => routes::get_full_post,
+    "update_post" => routes::update_post,
+    "get_post_revisions" => post_revisions::get_post_revisions,
+    "get_post_revision" => post_revisions::get_post_revision,
+    "delete_post" => routes::delete_post,
+    "restore_post" => routes::restore_post,
+    "lock_post" => routes::lock_post,
+    "vote_on_post" => votes::vote_on_post,
+    "add_post_reaction" => reactions::add_post_reaction,
+    "remove_post_reaction" => reactions::remove_post_reaction,
+    // Comment related endpoints
+    "create_comment" => routes::create_comment,
+    "update_comment" => routes::update_comment,
+    "delete_comment" => routes::delete_comment,
+    "restore_comment" => routes::restore_comment,
+    "vote_on_comment" => votes::vote_on_comment,
+    "add_comment_reaction" => reactions::add_comment_reaction,
+    "remove_comment_reaction" => reactions::remove_comment_reaction,
+    "get_comments_by_post" => routes::get_comments_by_post,
+    "count_comments_by_post" => routes::count_comments_by_post,
+    "get_recent_board_comments" => routes::get_recent_board_comments,
+    // Full-text search
+    "search" => search::search,
+    // User account endpoints
+    "register" => auth::register,
+    "login" => auth::login,
+    "issue_token" => auth::issue_token,
+    "change_password" => auth::change_password,
+    "refresh_token" => auth::refresh_token,
+    "logout" => auth::logout,
+    "oauth_start" => oauth::start,
+    "oauth_callback" => oauth::callback,
+    "delete_account" => account_erasure::delete_account,
+    "get_author_posts" => author_content::get_author_posts,
+    "get_author_comments" => author_content::get_author_comments,
+    // Artificial slow endpoint for testing alerts and profiling
+    "slow" => routes::slow_endpoint,
+    // Admin endpoints (require X-Admin-Token)
+    "refresh_cache_entry" => admin::refresh_cache_entry,
+    "set_maintenance" => admin::set_maintenance,
+    "get_cache_stats" => admin::get_cache_stats,
+    "get_latency_percentiles" => admin::get_latency_percentiles,
+    "get_pool_stats" => admin::get_pool_stats,
+    "get_in_flight_requests" => admin::get_in_flight_requests,
+    "set_user_role" => admin::set_user_role,
+    "create_api_key" => admin::create_api_key,
+    "list_api_keys" => admin::list_api_keys,
+    "update_api_key" => admin::update_api_key,
+    "delete_api_key" => admin::delete_api_key,
+}
+
+/// Reject `DISABLED_ENDPOINTS` entries that don't name an endpoint, so a typo can't leave
+/// an endpoint enabled unnoticed
+pub fn validate_disabled(disabled: &[String]) -> Result<(), String> {
+    match disabled.iter().find(|name| !ENDPOINT_NAMES.contains(&name.as_str())) {
+        Some(unknown) => Err(format!(
+            "Unknown endpoint '{}' in DISABLED_ENDPOINTS, expected one of: {}",
+            unknown,
+            ENDPOINT_NAMES.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
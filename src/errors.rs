@@ -0,0 +1,235 @@
+//! `ApiError`: the failures handlers and middleware answer with, rendered as a JSON
+//! `ErrorResponse` (`{code, message, trace_id}`), or as an RFC 7807 `ProblemDetails`
+//! (`application/problem+json`) when the client's `Accept` prefers it.
+//!
+//! `code` is a stable machine-readable identifier per kind of failure, `message` the human
+//! readable detail, and `trace_id` the OpenTelemetry trace of the request (the same value as the
+//! `X-Trace-Id` header) so a failure reported by a client can be found in Jaeger. Failures
+//! raised outside the tracing middleware (IP bans, CORS, maintenance) carry `trace_id: null`.
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, ResponseError};
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::Context;
+use scylla::transport::errors::QueryError;
+use crate::db_client;
+use crate::models::{ErrorResponse, FieldError, ProblemDetails};
+use crate::problem_json_middleware::{self, PROBLEM_JSON};
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// Malformed or out-of-range input (400 `bad_request`)
+    BadRequest(String),
+    /// Field of a create request body the model doesn't have (400 `unknown_field`)
+    UnknownField(String),
+    /// Missing or invalid credentials (401 `unauthorized`), with the `WWW-Authenticate` challenge
+    Unauthorized { message: String, challenge: Option<String> },
+    /// Authenticated but not allowed (403 `forbidden`)
+    Forbidden(String),
+    /// Unknown resource (404 `not_found`)
+    NotFound(String),
+    /// Path exists but not for this method (405 `method_not_allowed`), with the `Allow` list
+    MethodNotAllowed { message: String, allowed: Vec<String> },
+    /// Conflicts with the current state of the resource (409 `conflict`)
+    Conflict(String),
+    /// The resource existed but was deleted (410 `gone`)
+    Gone(String),
+    /// Request body fields failing `validation` checks (422 `validation_failed`), listed in `errors`
+    Validation(Vec<FieldError>),
+    /// Over a rate limit (429 `rate_limited`), with `Retry-After`
+    TooManyRequests { message: String, retry_after_secs: u64 },
+    /// Unexpected failure (500 `internal_error`)
+    Internal(String),
+    /// A failed database call: 503 `db_unavailable` while the circuit breaker is open, 504
+    /// `db_timeout` past `DB_QUERY_TIMEOUT_MS`, 500 `db_error` otherwise
+    Database { context: String, source: QueryError },
+    /// An upstream service (search engine, OAuth provider) failed (502 `bad_gateway`)
+    BadGateway(String),
+    /// Temporarily unable to serve (503 `service_unavailable`), with `Retry-After` when known
+    ServiceUnavailable { message: String, retry_after_secs: Option<u64> },
+    /// The request didn't complete within `HANDLER_TIMEOUT_MS` (504 `timeout`)
+    GatewayTimeout(String),
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::BadRequest(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::Unauthorized { message: message.into(), challenge: None }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict(message.into())
+    }
+
+    pub fn gone(message: impl Into<String>) -> Self {
+        ApiError::Gone(message.into())
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::Internal(message.into())
+    }
+
+    pub fn bad_gateway(message: impl Into<String>) -> Self {
+        ApiError::BadGateway(message.into())
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        ApiError::ServiceUnavailable { message: message.into(), retry_after_secs: None }
+    }
+
+    /// A failed database call, described as `"{context}: {error}"`
+    pub fn database(context: &str, source: &QueryError) -> Self {
+        ApiError::Database { context: context.to_string(), source: source.clone() }
+    }
+
+    /// Machine-readable `code` of the response body
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::UnknownField(_) => "unknown_field",
+            ApiError::Unauthorized { .. } => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::MethodNotAllowed { .. } => "method_not_allowed",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Gone(_) => "gone",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::TooManyRequests { .. } => "rate_limited",
+            ApiError::Internal(_) => "internal_error",
+            ApiError::Database { source, .. } if db_client::is_breaker_open(source) => "db_unavailable",
+            ApiError::Database { source: QueryError::RequestTimeout(_), .. } => "db_timeout",
+            ApiError::Database { .. } => "db_error",
+            ApiError::BadGateway(_) => "bad_gateway",
+            ApiError::ServiceUnavailable { .. } => "service_unavailable",
+            ApiError::GatewayTimeout(_) => "timeout",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::UnknownField(field) => write!(f, "Unknown field '{}' in request body", field),
+            ApiError::Validation(errors) => {
+                let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+                write!(f, "Invalid fields in request body: {}", fields.join(", "))
+            }
+            ApiError::Database { source, .. } if db_client::is_breaker_open(source) => write!(f, "{}", source),
+            ApiError::Database { source: QueryError::RequestTimeout(message), .. } => write!(f, "{}", message),
+            ApiError::Database { context, source } => write!(f, "{}: {}", context, source),
+            ApiError::BadRequest(message)
+            | ApiError::Unauthorized { message, .. }
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::MethodNotAllowed { message, .. }
+            | ApiError::Conflict(message)
+            | ApiError::Gone(message)
+            | ApiError::TooManyRequests { message, .. }
+            | ApiError::Internal(message)
+            | ApiError::BadGateway(message)
+            | ApiError::ServiceUnavailable { message, .. }
+            | ApiError::GatewayTimeout(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) | ApiError::UnknownField(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::MethodNotAllowed { .. } => StatusCode::METHOD_NOT_ALLOWED,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Gone(_) => StatusCode::GONE,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Database { source, .. } if db_client::is_breaker_open(source) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Database { source: QueryError::RequestTimeout(_), .. } => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::Database { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ServiceUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::GatewayTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut builder = HttpResponse::build(self.status_code());
+        match self {
+            ApiError::Unauthorized { challenge: Some(challenge), .. } => {
+                builder.insert_header((header::WWW_AUTHENTICATE, challenge.clone()));
+            }
+            ApiError::MethodNotAllowed { allowed, .. } => {
+                builder.insert_header((header::ALLOW, allowed.join(", ")));
+            }
+            ApiError::TooManyRequests { retry_after_secs, .. }
+            | ApiError::ServiceUnavailable { retry_after_secs: Some(retry_after_secs), .. } => {
+                builder.insert_header((header::RETRY_AFTER, retry_after_secs.to_string()));
+            }
+            ApiError::Database { source, .. } if db_client::is_breaker_open(source) => {
+                builder.insert_header((header::RETRY_AFTER, db_client::retry_after_secs().to_string()));
+            }
+            _ => {}
+        }
+
+        let allowed_methods = match self {
+            ApiError::MethodNotAllowed { allowed, .. } => allowed.clone(),
+            _ => Vec::new(),
+        };
+        let field = match self {
+            ApiError::UnknownField(field) => Some(field.clone()),
+            _ => None,
+        };
+        let errors = match self {
+            ApiError::Validation(errors) => errors.clone(),
+            _ => Vec::new(),
+        };
+
+        match problem_json_middleware::problem_instance() {
+            Some(instance) => {
+                let status = self.status_code();
+                builder.content_type(PROBLEM_JSON).json(ProblemDetails {
+                    problem_type: format!("urn:forum-api:problem:{}", self.code()),
+                    title: status.canonical_reason().unwrap_or("Error").to_string(),
+                    status: status.as_u16(),
+                    detail: self.to_string(),
+                    instance,
+                    code: self.code().to_string(),
+                    trace_id: current_trace_id(),
+                    allowed_methods,
+                    field,
+                    errors,
+                })
+            }
+            None => builder.json(ErrorResponse {
+                code: self.code().to_string(),
+                message: self.to_string(),
+                trace_id: current_trace_id(),
+                allowed_methods,
+                field,
+                errors,
+            }),
+        }
+    }
+}
+
+/// Trace id of the request being handled, attached as the current context by the tracing
+/// middleware
+fn current_trace_id() -> Option<String> {
+    let cx = Context::current();
+    let span_context = cx.span().span_context().clone();
+    span_context.is_valid().then(|| span_context.trace_id().to_string())
+}
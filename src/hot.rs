@@ -0,0 +1,152 @@
+//! "Hot" ranking of a board's posts (`GET /boards/{board_id}/posts?sort=hot`).
+//!
+//! A post's hot score is Reddit's: the order of magnitude of its vote score plus its age
+//! bonus, where every 12.5 hours of recency weigh as much as ten times the votes. Scoring a
+//! board needs all of its posts and their scores, which is too much per request, so a
+//! background task ranks every board each `HOT_RANK_INTERVAL_SECS` and stores the top
+//! `HOT_POSTS_PER_BOARD` posts in `hot_posts`, keyed by board and rank. A page of the hot
+//! listing is then a rank range of one partition, plus the posts themselves by id.
+//!
+//! Each instance runs the ranking; they compute the same ranks, so concurrent runs just write
+//! the same rows. Votes, new posts and deletions show up in the ranking on the next run.
+
+use chrono::Utc;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+use crate::config;
+use crate::list_order;
+use crate::models::Post;
+use crate::routes::execute_cached;
+use crate::votes;
+
+/// Start of Reddit's time scale, in seconds since the Unix epoch
+const EPOCH_OFFSET_SECS: f64 = 1_134_028_003.0;
+/// Seconds of recency worth a tenfold score
+const DECAY_SECS: f64 = 45_000.0;
+/// Ids per `IN` query when reading scores and posts
+const IN_CHUNK: usize = 100;
+
+/// Hot score of a post with this vote score, created at `created_at` (epoch milliseconds)
+fn hot_score(score: i64, created_at: i64) -> f64 {
+    let order = (score.unsigned_abs().max(1) as f64).log10();
+    let sign = score.signum() as f64;
+    let seconds = created_at as f64 / 1000.0 - EPOCH_OFFSET_SECS;
+    sign * order + seconds / DECAY_SECS
+}
+
+/// Start the ranking loop (does nothing when `HOT_RANK_INTERVAL_SECS` is 0)
+pub fn spawn_ranker(session: Arc<Session>) {
+    let config = config::get();
+    if config.hot_rank_interval_secs == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(config.hot_rank_interval_secs);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let start = Instant::now();
+            match rank_all(&session).await {
+                Ok(boards) => info!("Ranked hot posts of {} boards in {}ms", boards, start.elapsed().as_millis()),
+                Err(e) => warn!("Hot post ranking failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Rank every board that is not deleted, returning how many were ranked
+async fn rank_all(session: &Session) -> Result<usize, QueryError> {
+    let rows = execute_cached(session, "SELECT id, is_deleted FROM boards", &[]).await?;
+    let boards: Vec<Uuid> = rows
+        .rows_typed_or_empty::<(Uuid, Option<bool>)>()
+        .filter_map(Result::ok)
+        .filter(|(_, is_deleted)| *is_deleted != Some(true))
+        .map(|(id, _)| id)
+        .collect();
+    for board_id in &boards {
+        // One board failing doesn't hold up the others
+        if let Err(e) = rank_board(session, *board_id).await {
+            warn!("Error ranking hot posts of board {}: {}", board_id, e);
+        }
+    }
+    Ok(boards.len())
+}
+
+/// Replace the stored ranking of one board
+async fn rank_board(session: &Session, board_id: Uuid) -> Result<(), QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT id, created_at, is_deleted FROM posts_by_board WHERE board_id = ?",
+        (board_id,),
+    ).await?;
+    let posts: Vec<(Uuid, i64)> = rows
+        .rows_typed_or_empty::<(Uuid, Option<i64>, Option<bool>)>()
+        .filter_map(Result::ok)
+        .filter(|(_, _, is_deleted)| *is_deleted != Some(true))
+        .filter_map(|(id, created_at, _)| created_at.map(|created_at| (id, created_at)))
+        .collect();
+
+    let mut scores = HashMap::new();
+    for chunk in posts.chunks(IN_CHUNK) {
+        scores.extend(votes::post_scores(session, chunk.iter().map(|(id, _)| *id).collect()).await?);
+    }
+    let mut ranked: Vec<(Uuid, f64)> = posts
+        .iter()
+        .map(|(id, created_at)| (*id, hot_score(scores.get(id).copied().unwrap_or(0), *created_at)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.truncate(config::get().hot_posts_per_board);
+
+    let computed_at = Utc::now().timestamp_millis();
+    for (rank, (post_id, hot)) in ranked.iter().enumerate() {
+        execute_cached(
+            session,
+            "INSERT INTO hot_posts (board_id, rank, post_id, hot, computed_at) VALUES (?, ?, ?, ?, ?)",
+            (board_id, rank as i32, *post_id, *hot, computed_at),
+        ).await?;
+    }
+    // Ranks past the end of this run are left over from a board that had more posts
+    execute_cached(
+        session,
+        "DELETE FROM hot_posts WHERE board_id = ? AND rank >= ?",
+        (board_id, ranked.len() as i32),
+    ).await?;
+    Ok(())
+}
+
+/// Posts of a page of the board's hot listing, best first, and the number of ranks read
+///
+/// Posts deleted since the last ranking are dropped (unless `include_deleted`), so a page can
+/// hold fewer posts than ranks.
+pub(crate) async fn fetch_page(
+    session: &Session,
+    board_id: Uuid,
+    page: u32,
+    limit: u32,
+    include_deleted: bool,
+) -> Result<(Vec<Post>, u32), QueryError> {
+    let offset = (page - 1).saturating_mul(limit).min(i32::MAX as u32) as i32;
+    let rows = execute_cached(
+        session,
+        "SELECT post_id FROM hot_posts WHERE board_id = ? AND rank >= ? LIMIT ?",
+        (board_id, offset, limit as i32),
+    ).await?;
+    let ids: Vec<Uuid> = rows
+        .rows_typed_or_empty::<(Uuid,)>()
+        .filter_map(Result::ok)
+        .map(|(id,)| id)
+        .collect();
+    if ids.is_empty() {
+        return Ok((Vec::new(), 0));
+    }
+
+    let mut posts = list_order::posts_by_ids(session, &ids).await?;
+    posts.retain(|_, post| include_deleted || !post.is_deleted);
+    Ok((ids.iter().filter_map(|id| posts.remove(id)).collect(), ids.len() as u32))
+}
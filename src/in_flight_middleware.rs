@@ -0,0 +1,140 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpMessage};
+use chrono::{DateTime, Utc};
+use futures_util::future::LocalBoxFuture;
+use prometheus::IntGauge;
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+/// A request currently being handled, as listed by `GET /admin/inflight`
+#[derive(Clone, Debug)]
+pub struct InFlightRequest {
+    pub method: String,
+    pub path: String,
+    /// Set by `TracingLogger` once the request's span exists (absent for untraced paths)
+    pub trace_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub started: Instant,
+}
+
+/// Key of a request in the in-flight registry, stored in the request extensions
+#[derive(Clone, Copy, Debug)]
+pub struct InFlightId(u64);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static REQUESTS: OnceLock<Mutex<HashMap<u64, InFlightRequest>>> = OnceLock::new();
+
+fn requests() -> MutexGuard<'static, HashMap<u64, InFlightRequest>> {
+    REQUESTS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Attach the trace id to an in-flight request
+pub fn set_trace_id(id: InFlightId, trace_id: &str) {
+    if let Some(request) = requests().get_mut(&id.0) {
+        request.trace_id = Some(trace_id.to_string());
+    }
+}
+
+/// Requests currently in flight, longest running first
+pub fn snapshot() -> Vec<InFlightRequest> {
+    let mut snapshot: Vec<InFlightRequest> = requests().values().cloned().collect();
+    snapshot.sort_by_key(|request| request.started);
+    snapshot
+}
+
+/// Decrements the gauge and drops the registry entry when dropped, so cancelled and failed
+/// requests are released too
+struct InFlightGuard {
+    gauge: IntGauge,
+    id: u64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.gauge.dec();
+        requests().remove(&self.id);
+    }
+}
+
+// Middleware factory keeping a gauge and a registry of requests currently being handled
+pub struct InFlightTracker {
+    gauge: IntGauge,
+}
+
+impl InFlightTracker {
+    pub fn new(gauge: IntGauge) -> Self {
+        Self { gauge }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for InFlightTracker
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = InFlightTrackerMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(InFlightTrackerMiddleware {
+            service: Rc::new(service),
+            gauge: self.gauge.clone(),
+        }))
+    }
+}
+
+pub struct InFlightTrackerMiddleware<S> {
+    service: Rc<S>,
+    gauge: IntGauge,
+}
+
+impl<S, B> Service<ServiceRequest> for InFlightTrackerMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        self.gauge.inc();
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        requests().insert(id, InFlightRequest {
+            method: req.method().to_string(),
+            path: req.path().to_owned(),
+            trace_id: None,
+            started_at: Utc::now(),
+            started: Instant::now(),
+        });
+        req.extensions_mut().insert(InFlightId(id));
+        let guard = InFlightGuard {
+            gauge: self.gauge.clone(),
+            id,
+        };
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            drop(guard);
+            res
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpRequest, ResponseError};
+use futures_util::future::LocalBoxFuture;
+use prometheus::IntCounter;
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tracing::warn;
+use crate::config;
+use crate::errors::ApiError;
+
+/// Resolve the real client address of a request
+///
+/// `X-Forwarded-For` is only honoured when the direct peer is one of `TRUSTED_PROXIES`;
+/// the rightmost untrusted hop is taken so clients can't spoof their address by
+/// prepending entries to the header.
+pub fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let trusted = &config::get().trusted_proxies;
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = req.headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+
+    forwarded.iter()
+        .rev()
+        .find(|ip| !trusted.contains(ip))
+        .or(forwarded.first())
+        .copied()
+        .or(Some(peer))
+}
+
+// Middleware factory rejecting requests from banned client addresses
+pub struct IpBanFilter {
+    banned_counter: IntCounter,
+}
+
+impl IpBanFilter {
+    pub fn new(banned_counter: IntCounter) -> Self {
+        Self { banned_counter }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for IpBanFilter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IpBanFilterMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IpBanFilterMiddleware {
+            service: Rc::new(service),
+            banned_counter: self.banned_counter.clone(),
+        }))
+    }
+}
+
+pub struct IpBanFilterMiddleware<S> {
+    service: Rc<S>,
+    banned_counter: IntCounter,
+}
+
+impl<S, B> Service<ServiceRequest> for IpBanFilterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let banned_ips = &config::get().banned_ips;
+        if !banned_ips.is_empty() {
+            if let Some(ip) = client_ip(req.request()) {
+                if banned_ips.contains(&ip) {
+                    warn!("Rejecting request from banned IP {}: {} {}", ip, req.method(), req.path());
+                    self.banned_counter.inc();
+                    let response = ApiError::forbidden("Access denied").error_response();
+                    return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+                }
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
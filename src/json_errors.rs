@@ -0,0 +1,53 @@
+//! Extractor configuration: unknown fields in create requests are answered with a JSON
+//! `ErrorResponse` naming the field, other malformed bodies and query strings with a JSON 400
+//! and unparsable path segments (e.g. a malformed UUID) with a JSON 404. Oversized (413) and
+//! non-JSON (415) bodies keep actix's default response.
+
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::http::StatusCode;
+use actix_web::{web, Error, HttpRequest, ResponseError};
+use crate::errors::ApiError;
+
+/// Field name from a serde "unknown field `name`, expected ..." message
+fn unknown_field(message: &str) -> Option<&str> {
+    let rest = message.strip_prefix("unknown field `")?;
+    rest.split('`').next()
+}
+
+fn handle_error(err: JsonPayloadError, _req: &HttpRequest) -> Error {
+    let field = match &err {
+        JsonPayloadError::Deserialize(inner) => {
+            let message = inner.to_string();
+            unknown_field(&message).map(str::to_string)
+        }
+        _ => None,
+    };
+
+    let response = match field {
+        Some(field) => ApiError::UnknownField(field).error_response(),
+        None if err.status_code() == StatusCode::BAD_REQUEST => ApiError::bad_request(err.to_string()).error_response(),
+        None => return err.into(),
+    };
+    InternalError::from_response(err, response).into()
+}
+
+/// `JsonConfig` for the app, registered with `app_data`
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(handle_error)
+}
+
+/// `QueryConfig` for the app, registered with `app_data`
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|err, _req| {
+        let response = ApiError::bad_request(err.to_string()).error_response();
+        InternalError::from_response(err, response).into()
+    })
+}
+
+/// `PathConfig` for the app, registered with `app_data`
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default().error_handler(|err, _req| {
+        let response = ApiError::not_found(err.to_string()).error_response();
+        InternalError::from_response(err, response).into()
+    })
+}
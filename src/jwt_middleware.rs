@@ -0,0 +1,297 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, ResponseError};
+use chrono::Utc;
+use futures_util::future::LocalBoxFuture;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use scylla::Session;
+use std::sync::Arc;
+use tracing::{debug, error};
+use uuid::Uuid;
+use crate::admin;
+use crate::api_key_middleware::ApiKeyClient;
+use crate::config;
+use crate::errors::ApiError;
+use crate::sessions;
+use crate::models::{Role, User};
+
+/// Writes that need a valid bearer token once `JWT_SECRET` is set
+const PROTECTED_WRITES: &[(Method, &str)] = &[
+    (Method::POST, "/boards"),
+    (Method::POST, "/posts"),
+    (Method::POST, "/comments"),
+];
+
+/// Claims of the access tokens issued by `POST /auth/token` (HS256)
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// User id
+    sub: Uuid,
+    /// Username at the time the token was issued
+    name: String,
+    /// Role at the time the token was issued (role changes apply to the next token)
+    #[serde(default)]
+    role: Role,
+    /// Login session the token belongs to; the token stops working once it is revoked
+    #[serde(default)]
+    sid: Option<Uuid>,
+    iat: i64,
+    exp: i64,
+}
+
+/// The user a request's bearer token was issued to
+///
+/// Use it as a handler argument to require authentication (401 without a valid token), or as
+/// `Option<AuthenticatedUser>` to accept anonymous requests too.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: Role,
+    /// Login session the token was issued for
+    pub session_id: Option<Uuid>,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<AuthenticatedUser>()
+                .cloned()
+                .ok_or_else(|| ApiError::unauthorized("Authentication required").into()),
+        )
+    }
+}
+
+/// The request's user if their role is at least `required` (403 below it); without a bearer
+/// token the `X-Admin-Token` header stands in for an admin, and with neither the answer is 401
+fn require_role(req: &HttpRequest, required: Role) -> Result<Option<AuthenticatedUser>, ApiError> {
+    let user = req.extensions().get::<AuthenticatedUser>().cloned();
+    match user {
+        Some(user) if user.role >= required => Ok(Some(user)),
+        Some(user) => {
+            debug!("User {} ({}) lacks role {} for {}", user.user_id, user.role.as_str(), required.as_str(), req.path());
+            Err(ApiError::forbidden(format!("This operation requires the {} role", required.as_str())))
+        }
+        None if req.headers().contains_key("X-Admin-Token") => admin::require_admin(req).map(|()| None),
+        None => Err(ApiError::unauthorized("Authentication required")),
+    }
+}
+
+/// Handler argument admitting only moderators and admins; holds the user, or `None` when the
+/// admin token was used
+#[derive(Clone, Debug)]
+pub(crate) struct Moderator(pub Option<AuthenticatedUser>);
+
+impl FromRequest for Moderator {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(require_role(req, Role::Moderator).map(Moderator))
+    }
+}
+
+/// Handler argument admitting only admins; holds the user, or `None` when the admin token was
+/// used
+#[derive(Clone, Debug)]
+pub(crate) struct Admin(pub Option<AuthenticatedUser>);
+
+impl FromRequest for Admin {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(require_role(req, Role::Admin).map(Admin))
+    }
+}
+
+/// Sign an access token for the user's login session, returning it with its lifetime in seconds
+pub fn issue_token(user: &User, session_id: Uuid) -> Result<(String, u64), String> {
+    let config = config::get();
+    let secret = config.jwt_secret.as_ref().ok_or("JWT_SECRET is not set")?;
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        sub: user.id,
+        name: user.username.clone(),
+        role: user.role,
+        sid: Some(session_id),
+        iat: now,
+        exp: now + config.jwt_ttl_secs as i64,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
+        .map(|token| (token, config.jwt_ttl_secs))
+        .map_err(|e| e.to_string())
+}
+
+fn verify_token(secret: &str, token: &str) -> Result<AuthenticatedUser, String> {
+    let data = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &Validation::default())
+        .map_err(|e| e.to_string())?;
+    Ok(AuthenticatedUser {
+        user_id: data.claims.sub,
+        username: data.claims.name,
+        role: data.claims.role,
+        session_id: data.claims.sid,
+    })
+}
+
+fn unauthorized(challenge: &str, message: &str) -> ApiError {
+    ApiError::Unauthorized { message: message.to_string(), challenge: Some(challenge.to_string()) }
+}
+
+/// Reject tokens whose login session was revoked (logout, refresh token reuse) or expired
+async fn check_session(req: &ServiceRequest, user: &AuthenticatedUser) -> Result<(), ApiError> {
+    let session_id = match user.session_id {
+        Some(session_id) => session_id,
+        None => return Ok(()),
+    };
+    let session = match req.app_data::<web::Data<Arc<Session>>>() {
+        Some(session) => session,
+        None => return Ok(()),
+    };
+    match sessions::is_active(session, session_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            debug!("Rejecting {} {}: session {} is revoked", req.method(), req.path(), session_id);
+            Err(unauthorized(r#"Bearer error="invalid_token""#, "Session has been revoked"))
+        }
+        Err(e) => {
+            error!("Error checking session {}: {}", session_id, e);
+            Err(ApiError::unavailable("Unable to verify the session, please retry"))
+        }
+    }
+}
+
+// Middleware factory validating bearer tokens (including their session not being revoked) and
+// rejecting anonymous writes (off without JWT_SECRET)
+pub struct JwtAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for JwtAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = JwtAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JwtAuthMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct JwtAuthMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for JwtAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if let Some(secret) = &config::get().jwt_secret {
+                let bearer = req
+                    .headers()
+                    .get(header::AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .map(str::trim);
+
+                let rejection = match bearer.map(|token| verify_token(secret, token)) {
+                    Some(Ok(user)) => match check_session(&req, &user).await {
+                        Ok(()) => {
+                            req.extensions_mut().insert(user);
+                            None
+                        }
+                        Err(e) => Some(e),
+                    },
+                    Some(Err(e)) => {
+                        debug!("Rejecting {} {}: invalid bearer token: {}", req.method(), req.path(), e);
+                        Some(unauthorized(r#"Bearer error="invalid_token""#, "Invalid or expired access token"))
+                    }
+                    None if PROTECTED_WRITES.iter().any(|(method, path)| req.method() == method && req.path() == *path) => {
+                        // Automated clients authenticate with an API key instead (checked by ApiKeyAuth)
+                        let client = req.extensions().get::<ApiKeyClient>().cloned();
+                        match client {
+                            Some(client) => {
+                                debug!("Accepting {} {} with API key {} ({})", req.method(), req.path(), client.key_id, client.name);
+                                None
+                            }
+                            None => {
+                                debug!("Rejecting anonymous {} {}", req.method(), req.path());
+                                Some(unauthorized("Bearer", "Authentication required"))
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                if let Some(e) = rejection {
+                    return Ok(req.into_response(e.error_response()).map_into_right_body());
+                }
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    fn request_as(role: Option<Role>) -> HttpRequest {
+        let req = TestRequest::default().to_http_request();
+        if let Some(role) = role {
+            req.extensions_mut().insert(AuthenticatedUser { user_id: Uuid::new_v4(), username: "alice".to_string(), role, session_id: None });
+        }
+        req
+    }
+
+    async fn status<T: FromRequest<Error = ApiError>>(req: &HttpRequest) -> Result<(), StatusCode> {
+        T::from_request(req, &mut Payload::None).await.map(|_| ()).map_err(|e| e.status_code())
+    }
+
+    #[actix_web::test]
+    async fn moderator_extractor_admits_moderators_and_admins() {
+        assert_eq!(status::<Moderator>(&request_as(Some(Role::User))).await, Err(StatusCode::FORBIDDEN));
+        assert_eq!(status::<Moderator>(&request_as(Some(Role::Moderator))).await, Ok(()));
+        assert_eq!(status::<Moderator>(&request_as(Some(Role::Admin))).await, Ok(()));
+        assert_eq!(status::<Moderator>(&request_as(None)).await, Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[actix_web::test]
+    async fn admin_extractor_refuses_moderators() {
+        assert_eq!(status::<Admin>(&request_as(Some(Role::Moderator))).await, Err(StatusCode::FORBIDDEN));
+        assert_eq!(status::<Admin>(&request_as(Some(Role::Admin))).await, Ok(()));
+        assert_eq!(status::<Admin>(&request_as(None)).await, Err(StatusCode::UNAUTHORIZED));
+    }
+}
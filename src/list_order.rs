@@ -0,0 +1,335 @@
+//! Database-side ordering of a board's posts and a post's comments
+//! (`?sort=created_at|updated_at|score` on `GET /boards/{board_id}/posts` and
+//! `GET /posts/{post_id}/comments`).
+//!
+//! `posts` and `comments` are keyed by id, so a board's posts come back in token order and
+//! could only be sorted a page at a time. `post_order` and `comment_order` hold one row per
+//! post (comment) and sort key, in a partition per board (post) and key clustered by the sort
+//! value, so a listing reads ids in order (either direction) and then the items by id. Rows
+//! are moved when their value changes: `updated_at` on edits, `score` on votes. A row whose
+//! value no longer matches the item, left behind when a move raced another one, is skipped.
+//!
+//! The `created_at` order needs no rows here: `posts_by_board` and `comments_by_post` are
+//! partitioned by board (post) and clustered by creation time, so `post_order` and
+//! `comment_order` only hold `updated_at` and `score`.
+//!
+//! Reads are a clustering range of the partition, which also serves `since`/`until` on the
+//! `created_at` order. Soft-deleted items keep their rows and are filtered out when read;
+//! purges drop them.
+
+use chrono::{TimeZone, Utc};
+use scylla::batch::BatchType;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Range;
+use uuid::Uuid;
+use crate::config;
+use crate::db;
+use crate::db_client;
+use crate::models::{Comment, Post};
+use crate::query_fields::SortOrder;
+use crate::routes::{execute_cached, get_or_prepare};
+use crate::votes;
+
+/// Orders kept in the database; other sort fields are applied within the page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SortKey {
+    CreatedAt,
+    UpdatedAt,
+    Score,
+}
+
+impl SortKey {
+    pub(crate) const ALL: [SortKey; 3] = [SortKey::CreatedAt, SortKey::UpdatedAt, SortKey::Score];
+
+    /// The key for a resolved sort column, if that column is ordered in the database
+    pub(crate) fn from_column(column: &str) -> Option<SortKey> {
+        match column {
+            "created_at" => Some(SortKey::CreatedAt),
+            "updated_at" => Some(SortKey::UpdatedAt),
+            "score" => Some(SortKey::Score),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            SortKey::CreatedAt => "created_at",
+            SortKey::UpdatedAt => "updated_at",
+            SortKey::Score => "score",
+        }
+    }
+
+    fn post_value(self, post: &Post) -> i64 {
+        match self {
+            SortKey::CreatedAt => post.created_at.timestamp_millis(),
+            SortKey::UpdatedAt => post.updated_at.timestamp_millis(),
+            SortKey::Score => post.score,
+        }
+    }
+
+    /// A comment that was never edited sorts by its creation time under `updated_at`
+    fn comment_value(self, comment: &Comment) -> i64 {
+        match self {
+            SortKey::CreatedAt => comment.created_at.timestamp_millis(),
+            SortKey::UpdatedAt => comment.edited_at.unwrap_or(comment.created_at).timestamp_millis(),
+            SortKey::Score => comment.score,
+        }
+    }
+}
+
+/// Whose ordering rows: a board's posts or a post's comments
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Listing {
+    Posts(Uuid),
+    Comments(Uuid),
+}
+
+impl Listing {
+    pub(crate) fn table(self) -> &'static str {
+        match self {
+            Listing::Posts(_) => "post_order",
+            Listing::Comments(_) => "comment_order",
+        }
+    }
+
+    fn parent(self) -> Uuid {
+        match self {
+            Listing::Posts(board_id) | Listing::Comments(board_id) => board_id,
+        }
+    }
+
+    fn insert(self) -> &'static str {
+        match self {
+            Listing::Posts(_) => "INSERT INTO post_order (board_id, sort, value, id) VALUES (?, ?, ?, ?)",
+            Listing::Comments(_) => "INSERT INTO comment_order (post_id, sort, value, id) VALUES (?, ?, ?, ?)",
+        }
+    }
+
+    fn delete(self) -> &'static str {
+        match self {
+            Listing::Posts(_) => "DELETE FROM post_order WHERE board_id = ? AND sort = ? AND value = ? AND id = ?",
+            Listing::Comments(_) => "DELETE FROM comment_order WHERE post_id = ? AND sort = ? AND value = ? AND id = ?",
+        }
+    }
+
+    fn select(self, order: SortOrder) -> &'static str {
+        match (self, order) {
+            (Listing::Posts(_), SortOrder::Desc) => "SELECT value, id FROM post_order WHERE board_id = ? AND sort = ? AND value >= ? AND value < ?",
+            (Listing::Posts(_), SortOrder::Asc) => "SELECT value, id FROM post_order WHERE board_id = ? AND sort = ? AND value >= ? AND value < ? ORDER BY value ASC, id ASC",
+            (Listing::Comments(_), SortOrder::Desc) => "SELECT value, id FROM comment_order WHERE post_id = ? AND sort = ? AND value >= ? AND value < ?",
+            (Listing::Comments(_), SortOrder::Asc) => "SELECT value, id FROM comment_order WHERE post_id = ? AND sort = ? AND value >= ? AND value < ? ORDER BY value ASC, id ASC",
+        }
+    }
+}
+
+/// Move an item's row for `key` from `previous` to `value`
+pub(crate) async fn move_row(
+    session: &Session,
+    listing: Listing,
+    key: SortKey,
+    id: Uuid,
+    previous: i64,
+    value: i64,
+) -> Result<(), QueryError> {
+    if previous == value {
+        return Ok(());
+    }
+    let mut batch = db::new_batch(BatchType::Logged);
+    batch.append_statement(get_or_prepare(session, listing.delete()).await?);
+    batch.append_statement(get_or_prepare(session, listing.insert()).await?);
+    let parent = listing.parent();
+    db_client::call(session.batch(&batch, ((parent, key.as_str(), previous, id), (parent, key.as_str(), value, id)))).await?;
+    Ok(())
+}
+
+/// Drop the ordering rows of all comments on a post, ahead of deleting the post
+pub(crate) async fn remove_comments(session: &Session, post_id: Uuid) -> Result<(), QueryError> {
+    for key in SortKey::ALL {
+        execute_cached(session, "DELETE FROM comment_order WHERE post_id = ? AND sort = ?", (post_id, key.as_str())).await?;
+    }
+    Ok(())
+}
+
+/// Posts with the given ids that exist; the caller decides about deleted ones
+pub(crate) async fn posts_by_ids(session: &Session, ids: &[Uuid]) -> Result<HashMap<Uuid, Post>, QueryError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = execute_cached(
+        session,
+        "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked FROM posts WHERE id IN ?",
+        (ids,),
+    ).await?;
+    type Row = (Uuid, Uuid, String, String, Option<String>, i64, i64, Option<Vec<String>>, Option<bool>, Option<i64>, Option<bool>);
+    Ok(rows
+        .rows_typed_or_empty::<Row>()
+        .filter_map(Result::ok)
+        .map(|(id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked)| {
+            let post = Post {
+                id,
+                board_id,
+                title,
+                content,
+                created_at: Utc.timestamp_millis_opt(created_at).single().unwrap_or_default(),
+                updated_at: Utc.timestamp_millis_opt(updated_at).single().unwrap_or_default(),
+                author: author.unwrap_or_else(|| config::get().missing_author_placeholder.clone()),
+                tags: tags.unwrap_or_default(),
+                is_deleted: is_deleted.unwrap_or(false),
+                deleted_at: deleted_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                is_locked: is_locked.unwrap_or(false),
+                score: 0,
+                reactions: Vec::new(),
+            };
+            (id, post)
+        })
+        .collect())
+}
+
+/// Comments with the given ids that exist; the caller decides about deleted ones
+async fn comments_by_ids(session: &Session, ids: &[Uuid]) -> Result<HashMap<Uuid, Comment>, QueryError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = execute_cached(
+        session,
+        "SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments WHERE id IN ?",
+        (ids,),
+    ).await?;
+    type Row = (Uuid, Uuid, String, Option<String>, i64, Option<i64>, Option<bool>, Option<i64>);
+    Ok(rows
+        .rows_typed_or_empty::<Row>()
+        .filter_map(Result::ok)
+        .map(|(id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at)| {
+            let comment = Comment {
+                id,
+                post_id,
+                content,
+                created_at: Utc.timestamp_millis_opt(created_at).single().unwrap_or_default(),
+                author: author.unwrap_or_else(|| config::get().missing_author_placeholder.clone()),
+                edited_at: edited_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                is_deleted: is_deleted.unwrap_or(false),
+                deleted_at: deleted_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                score: 0,
+                reactions: Vec::new(),
+            };
+            (id, comment)
+        })
+        .collect())
+}
+
+/// Read a listing's rows with values in `range`, in order, and resolve them a chunk at a time
+/// with `resolve`, which returns the items to list (in row order); returns page `page` of those
+#[allow(clippy::too_many_arguments)]
+async fn read_page<T, F, Fut>(
+    session: &Session,
+    listing: Listing,
+    key: SortKey,
+    order: SortOrder,
+    range: Range<i64>,
+    page: u32,
+    limit: u32,
+    mut resolve: F,
+) -> Result<Vec<T>, QueryError>
+where
+    F: FnMut(Vec<(i64, Uuid)>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, QueryError>>,
+{
+    let mut prepared = get_or_prepare(session, listing.select(order)).await?;
+    prepared.set_page_size(limit as i32);
+    let read = db_client::PagedRead::start();
+    let mut rows = read.call(session.execute_iter(prepared, (listing.parent(), key.as_str(), range.start, range.end))).await?;
+
+    // Items left out by `resolve` don't count towards pages
+    let mut skip = (page - 1).saturating_mul(limit);
+    let mut items = Vec::new();
+    let mut chunk = Vec::with_capacity(limit as usize);
+    loop {
+        let row = read.next(&mut rows).await;
+        let done = row.is_none();
+        match row {
+            // Rows are written whole, so one that doesn't parse would only be written by hand
+            Some(Ok(row)) => chunk.extend(row.into_typed::<(i64, Uuid)>().ok()),
+            Some(Err(e)) => return Err(e),
+            None => {}
+        }
+        if chunk.len() == limit as usize || (done && !chunk.is_empty()) {
+            for item in resolve(std::mem::take(&mut chunk)).await? {
+                if skip > 0 {
+                    skip -= 1;
+                } else {
+                    items.push(item);
+                    if items.len() == limit as usize {
+                        return Ok(items);
+                    }
+                }
+            }
+        }
+        if done {
+            return Ok(items);
+        }
+    }
+}
+
+/// A page of a board's posts in `key` order, with values in `range`
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn post_page(
+    session: &Session,
+    board_id: Uuid,
+    key: SortKey,
+    order: SortOrder,
+    range: Range<i64>,
+    page: u32,
+    limit: u32,
+    include_deleted: bool,
+) -> Result<Vec<Post>, QueryError> {
+    read_page(session, Listing::Posts(board_id), key, order, range, page, limit, |rows| async move {
+        let ids: Vec<Uuid> = rows.iter().map(|(_, id)| *id).collect();
+        let mut posts = posts_by_ids(session, &ids).await?;
+        if key == SortKey::Score {
+            let scores = votes::post_scores(session, ids).await?;
+            for post in posts.values_mut() {
+                post.score = scores.get(&post.id).copied().unwrap_or(0);
+            }
+        }
+        Ok(rows
+            .into_iter()
+            .filter_map(|(value, id)| posts.remove(&id).filter(|post| key.post_value(post) == value))
+            .filter(|post| include_deleted || !post.is_deleted)
+            .collect())
+    }).await
+}
+
+/// A page of a post's comments in `key` order, with values in `range`, only those by `author`
+/// when given
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn comment_page(
+    session: &Session,
+    post_id: Uuid,
+    key: SortKey,
+    order: SortOrder,
+    range: Range<i64>,
+    page: u32,
+    limit: u32,
+    include_deleted: bool,
+    author: Option<&str>,
+) -> Result<Vec<Comment>, QueryError> {
+    read_page(session, Listing::Comments(post_id), key, order, range, page, limit, |rows| async move {
+        let ids: Vec<Uuid> = rows.iter().map(|(_, id)| *id).collect();
+        let mut comments = comments_by_ids(session, &ids).await?;
+        if key == SortKey::Score {
+            let scores = votes::comment_scores(session, ids).await?;
+            for comment in comments.values_mut() {
+                comment.score = scores.get(&comment.id).copied().unwrap_or(0);
+            }
+        }
+        Ok(rows
+            .into_iter()
+            .filter_map(|(value, id)| comments.remove(&id).filter(|comment| key.comment_value(comment) == value))
+            .filter(|comment| include_deleted || !comment.is_deleted)
+            .filter(|comment| author.is_none_or(|author| comment.author == author))
+            .collect())
+    }).await
+}
@@ -1,7 +1,9 @@
-use actix_web::{web, App, HttpServer, middleware::Logger};
-use actix_web::middleware::Compress;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, ResponseError};
+use actix_web::http::KeepAlive;
+use actix_web::dev::{fn_service, ServiceRequest, ServiceResponse};
+use actix_web::middleware::{Compress, Condition};
 use actix_web::get;
-use actix_files::NamedFile;
+use actix_files::{Files, NamedFile};
 use scylla::{SessionBuilder, transport::session::PoolSize};
 use std::num::NonZeroUsize;
 use std::sync::Arc;
@@ -10,23 +12,83 @@ use std::collections::HashMap;
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa::OpenApi;
 use actix_web_prom::{PrometheusMetricsBuilder};
-use prometheus::{opts, IntCounterVec, Histogram, Counter, Gauge};
+use prometheus::{opts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Histogram, Counter, Gauge};
+use crate::errors::ApiError;
 
+mod account_erasure;
+mod admin;
 mod api_docs;
+mod api_key_middleware;
+mod api_keys;
+mod audit;
+mod author_content;
+mod auth;
+mod board_deletion;
+mod body_log_middleware;
+mod cache_pressure;
+mod categories;
+mod compression_exemption_middleware;
+mod config;
+mod cors_middleware;
+mod counts;
 mod db;
+mod db_client;
+mod endpoints;
+mod errors;
+mod hot;
+mod in_flight_middleware;
+mod ip_filter_middleware;
+mod jwt_middleware;
+mod json_errors;
+mod list_order;
+mod maintenance_middleware;
+mod method_not_allowed;
 mod models;
+mod normalize;
+mod oauth;
+mod pool_health;
+mod problem_json_middleware;
+mod post_revisions;
+mod process_metrics;
+mod query_fields;
+mod reactions;
 mod routes;
+mod search;
+mod selftest;
+mod sessions;
+mod subscriptions;
 mod telemetry;
+mod timeout_middleware;
+mod title_search;
 mod tracing_middleware;
+mod validation;
+mod votes;
+
+/// JSON 404 for docs files missing from `STATIC_DIR`
+fn static_not_found(path: &str) -> HttpResponse {
+    ApiError::not_found(format!("Static file not found: {}", path)).error_response()
+}
+
+/// Serve `docs.html` from `STATIC_DIR`, answering 404 instead of an IO error when it is missing
+fn serve_docs_page(req: &HttpRequest) -> HttpResponse {
+    let path = std::path::Path::new(&config::get().static_dir).join("docs.html");
+    match NamedFile::open(&path) {
+        Ok(file) => file.into_response(req),
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", path.display(), e);
+            static_not_found("docs.html")
+        }
+    }
+}
 
 #[get("/docs")]
-async fn html_docs() -> io::Result<NamedFile> {
-    NamedFile::open("/app/static/docs.html")
+async fn html_docs(req: HttpRequest) -> HttpResponse {
+    serve_docs_page(&req)
 }
 
 #[get("/docs/")]
-async fn html_docs_slash() -> io::Result<NamedFile> {
-    NamedFile::open("app/static/docs.html")
+async fn html_docs_slash(req: HttpRequest) -> HttpResponse {
+    serve_docs_page(&req)
 }
 
 #[actix_web::main]
@@ -37,22 +99,110 @@ async fn main() -> io::Result<()> {
     // Enable logging
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
+    let app_config = config::get();
+
+    // Refuse to start when DISABLED_ENDPOINTS names an endpoint that doesn't exist
+    if let Err(message) = endpoints::validate_disabled(&app_config.disabled_endpoints) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+    if !app_config.disabled_endpoints.is_empty() {
+        println!("🚫 Disabled endpoints: {}", app_config.disabled_endpoints.join(", "));
+    }
+
+    // Refuse to start with ScyllaDB settings that can't work (SCYLLA_*)
+    if let Err(message) = app_config.validate_scylla() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+
+    // Refuse to start with consistency levels that don't exist (BATCH_/READ_/WRITE_CONSISTENCY)
+    for (variable, consistency) in [
+        ("BATCH_CONSISTENCY", &app_config.batch_consistency),
+        ("READ_CONSISTENCY", &app_config.read_consistency),
+        ("WRITE_CONSISTENCY", &app_config.write_consistency),
+    ] {
+        if let Some(consistency) = consistency {
+            if db::parse_consistency(consistency).is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid {} '{}', expected a level such as ONE, QUORUM or LOCAL_QUORUM", variable, consistency),
+                ));
+            }
+        }
+    }
+    for (name, consistency) in &app_config.consistency_overrides {
+        if !routes::PREPARED_STATEMENT_NAMES.contains(&name.as_str()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown statement '{}' in CONSISTENCY_OVERRIDES, expected one of: {}", name, routes::PREPARED_STATEMENT_NAMES.join(", ")),
+            ));
+        }
+        if db::parse_consistency(consistency).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid consistency '{}' for {} in CONSISTENCY_OVERRIDES, expected name=LEVEL", consistency, name),
+            ));
+        }
+    }
+
+    // Route queries to the replicas that own the data (see LB_POLICY)
+    let lb_profile = db::load_balancing_profile(&app_config.lb_policy, app_config.lb_local_dc.as_deref());
+    println!("⚖️  ScyllaDB load balancing policy: {}", app_config.lb_policy);
+
     // Connect to ScyllaDB cluster with optimizations
+    println!("🗄️  ScyllaDB nodes: {} (keyspace {})", app_config.scylla_nodes.join(", "), app_config.scylla_keyspace);
+    let mut session_builder = SessionBuilder::new()
+        .known_nodes(&app_config.scylla_nodes)
+        .connection_timeout(std::time::Duration::from_secs(5))
+        .pool_size(PoolSize::PerHost(NonZeroUsize::new(app_config.scylla_pool_size).unwrap()))
+        .default_execution_profile_handle(lb_profile);
+    // Password authentication and TLS for secured clusters (SCYLLA_USERNAME, SCYLLA_TLS)
+    if let (Some(username), Some(password)) = (&app_config.scylla_username, &app_config.scylla_password) {
+        session_builder = session_builder.user(username, password);
+    }
+    #[cfg(feature = "scylla-tls")]
+    {
+        let tls_context = db::tls_context(app_config).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid ScyllaDB TLS settings: {}", e))
+        })?;
+        if tls_context.is_some() {
+            println!("🔒 Connecting to ScyllaDB over TLS");
+        }
+        session_builder = session_builder.ssl_context(tls_context);
+    }
+    // Scylla may still be starting; keep trying for DB_STARTUP_MAX_WAIT_SECS
     let session = Arc::new(
-        SessionBuilder::new()
-            .known_node("scylladb:9042") // Using docker-compose service name
-            .connection_timeout(std::time::Duration::from_secs(5))
-            .pool_size(PoolSize::PerHost(NonZeroUsize::new(8).unwrap()))  // 8 connections per host
-            .build()
+        db::retry_startup("Connecting to ScyllaDB", || session_builder.build())
             .await
-            .expect("Failed to connect to ScyllaDB")
+            .map_err(|e| io::Error::other(format!("Failed to connect to ScyllaDB: {}", e)))?
     );
 
-    // Initialize database
-    db::init_db(&session).await.expect("Failed to initialize database");
+    // Restore maintenance mode if the service was started with it enabled
+    if config::get().maintenance_mode {
+        println!("⚠️  Starting in maintenance mode: write requests will be rejected");
+        maintenance_middleware::set(true, false);
+    }
+
+    // Initialize database (every step is idempotent, so a failed attempt can simply run again)
+    db::retry_startup("Initializing the database", || db::init_db(&session))
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to initialize database: {}", e)))?;
     
     // Initialize prepared statements for better performance
-    routes::init_prepared_statements(&session).await.expect("Failed to initialize prepared statements");
+    db::retry_startup("Preparing statements", || routes::init_prepared_statements(&session))
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to initialize prepared statements: {}", e)))?;
+
+    // Probe the connection pool and refresh it after repeated failures (POOL_HEALTH_INTERVAL_SECS)
+    pool_health::spawn_checker(session.clone());
+
+    // Close the database circuit breaker once the cluster answers again (DB_BREAKER_*)
+    db_client::spawn_prober(session.clone());
+
+    // Re-rank each board's hot posts in the background (HOT_RANK_INTERVAL_SECS)
+    hot::spawn_ranker(session.clone());
+
+    // Connect the search engine posts and comments are mirrored into (SEARCH_BACKEND)
+    search::init();
 
     // Setup Prometheus metrics with custom labels and process metrics
     let mut labels = HashMap::new();
@@ -76,6 +226,32 @@ async fn main() -> io::Result<()> {
         &["cache_type", "result"] // result: hit, miss, expired
     ).unwrap();
     
+    let cache_entries_gauge = IntGaugeVec::new(
+        opts!("cache_entries", "Current number of entries per cache").namespace("forum_api"),
+        &["cache_type"]
+    ).unwrap();
+    
+    let data_integrity_errors_counter = IntCounterVec::new(
+        opts!("data_integrity_errors_total", "Stored rows with missing or invalid columns").namespace("forum_api"),
+        &["table", "field"]
+    ).unwrap();
+    
+    let banned_requests_counter = IntCounter::with_opts(
+        opts!("banned_requests_total", "Requests rejected because the client IP is banned").namespace("forum_api")
+    ).unwrap();
+    
+    let reprepares_counter = IntCounter::with_opts(
+        opts!("prepared_statement_reprepares_total", "Prepared statements re-prepared after the server reported them as unprepared").namespace("forum_api")
+    ).unwrap();
+    
+    let db_breaker_open_gauge = IntGauge::with_opts(
+        opts!("db_circuit_breaker_open", "1 while the database circuit breaker rejects calls, 0 otherwise").namespace("forum_api")
+    ).unwrap();
+    
+    let in_flight_requests_gauge = IntGauge::with_opts(
+        opts!("http_requests_in_flight", "Requests currently being handled").namespace("forum_api")
+    ).unwrap();
+    
     let cpu_intensive_operations_counter = Counter::with_opts(
         opts!("cpu_intensive_operations_total", "Total CPU intensive operations").namespace("forum_api")
     ).unwrap();
@@ -84,6 +260,10 @@ async fn main() -> io::Result<()> {
         opts!("process_memory_usage_bytes", "Current memory usage").namespace("forum_api")
     ).unwrap();
     
+    let cpu_usage_gauge = Gauge::with_opts(
+        opts!("process_cpu_usage_percent", "CPU usage of the process over the last sampling interval (100 = one core)").namespace("forum_api")
+    ).unwrap();
+    
     let slow_endpoint_duration = Histogram::with_opts(
         prometheus::HistogramOpts::new(
             "slow_endpoint_duration_seconds",
@@ -96,10 +276,35 @@ async fn main() -> io::Result<()> {
     // Register custom metrics with actix-web-prom registry
     prometheus.registry.register(Box::new(db_operations_counter.clone())).unwrap();
     prometheus.registry.register(Box::new(cache_operations_counter.clone())).unwrap();
+    prometheus.registry.register(Box::new(cache_entries_gauge.clone())).unwrap();
+    prometheus.registry.register(Box::new(data_integrity_errors_counter.clone())).unwrap();
+    prometheus.registry.register(Box::new(banned_requests_counter.clone())).unwrap();
+    prometheus.registry.register(Box::new(reprepares_counter.clone())).unwrap();
+    prometheus.registry.register(Box::new(db_breaker_open_gauge.clone())).unwrap();
+    prometheus.registry.register(Box::new(in_flight_requests_gauge.clone())).unwrap();
     prometheus.registry.register(Box::new(cpu_intensive_operations_counter.clone())).unwrap();
     prometheus.registry.register(Box::new(memory_usage_gauge.clone())).unwrap();
+    prometheus.registry.register(Box::new(cpu_usage_gauge.clone())).unwrap();
     prometheus.registry.register(Box::new(slow_endpoint_duration.clone())).unwrap();
 
+    routes::set_cache_entries_gauge(cache_entries_gauge);
+    routes::set_reprepares_counter(reprepares_counter);
+    db_client::set_open_gauge(db_breaker_open_gauge);
+
+    // Refresh process metrics in the background so they stay current between requests
+    process_metrics::spawn_updater(
+        memory_usage_gauge.clone(),
+        cpu_usage_gauge,
+        std::time::Duration::from_secs(config::get().process_metrics_interval_secs.max(1)),
+    );
+
+    // Shed cache entries when process memory crosses CACHE_MEMORY_HIGH_WATERMARK
+    cache_pressure::spawn_monitor(
+        memory_usage_gauge.clone(),
+        cache_operations_counter.clone(),
+        std::time::Duration::from_secs(config::get().process_metrics_interval_secs.max(1)),
+    );
+
     println!("Starting server at http://0.0.0.0:8080");
     println!("📚 Swagger API documentation: http://0.0.0.0:8080/swagger/");
     println!("📄 Russian documentation: http://0.0.0.0:8080/docs");
@@ -110,47 +315,115 @@ async fn main() -> io::Result<()> {
     // Generate OpenAPI documentation
     let openapi = api_docs::ApiDoc::openapi();
 
+    let shutdown_timeout_secs = config::get().shutdown_timeout_secs;
+    let in_flight = in_flight_requests_gauge.clone();
+    let keep_alive = match config::get().keep_alive_secs {
+        0 => KeepAlive::Disabled,
+        secs => KeepAlive::Timeout(std::time::Duration::from_secs(secs)),
+    };
+    let tcp_nodelay = config::get().tcp_nodelay;
+
     // Start web server
     let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(session.clone()))
             .app_data(web::Data::new(routes::DbCounter(db_operations_counter.clone())))
             .app_data(web::Data::new(routes::CacheCounter(cache_operations_counter.clone())))
+            .app_data(web::Data::new(routes::IntegrityCounter(data_integrity_errors_counter.clone())))
+            .app_data(web::Data::new(admin::MetricsRegistry(prometheus.registry.clone())))
             .app_data(web::Data::new(cpu_intensive_operations_counter.clone()))
             .app_data(web::Data::new(memory_usage_gauge.clone()))
             .app_data(web::Data::new(slow_endpoint_duration.clone()))
-            .wrap(prometheus.clone()) // Add actix-web-prom middleware - must be first!
-            .wrap(tracing_middleware::TracingLogger) // Add distributed tracing middleware
-            .wrap(Logger::default())
+            .app_data(json_errors::json_config())
+            .app_data(json_errors::query_config())
+            .app_data(json_errors::path_config())
+            .wrap(timeout_middleware::RequestTimeout) // Innermost, so 504s still pass through metrics, tracing and logging
+            .wrap(jwt_middleware::JwtAuth) // Inside metrics and tracing, so 401s are counted and traced
+            .wrap(api_key_middleware::ApiKeyAuth) // Outside JwtAuth, so a valid API key satisfies its check on writes
+            .wrap(prometheus.clone()) // Add actix-web-prom middleware - must wrap the handlers directly (only the timeout and auth sit inside it)
+            .wrap(tracing_middleware::TracingLogger) // Add distributed tracing and sampled access logging
+            .wrap(Condition::new(config::get().body_log_enabled, body_log_middleware::BodyLogger)) // Debug body logging, off unless BODY_LOG_ENABLED
+            .wrap(maintenance_middleware::MaintenanceGuard)
+            .wrap(compression_exemption_middleware::CompressionExemption) // Inside Compress: keeps it off pre-compressed bodies
             .wrap(Compress::default())
+            .wrap(cors_middleware::Cors) // Answers preflights before maintenance/timeout checks
+            .wrap(in_flight_middleware::InFlightTracker::new(in_flight_requests_gauge.clone()))
+            .wrap(ip_filter_middleware::IpBanFilter::new(banned_requests_counter.clone())) // Reject banned clients before anything else runs
+            .wrap(problem_json_middleware::ProblemJsonNegotiation) // Outermost: only picks the error body format for everything inside
             // Serve Swagger UI at /swagger
             .service(SwaggerUi::new("/swagger{_:.*}").url("/api-docs/openapi.json", openapi.clone()))
             // Serve HTML docs
             .service(html_docs)
             .service(html_docs_slash)
-            // Health endpoint (metrics endpoint is auto-registered by actix-web-prom at /metrics)
-            .service(routes::health_check)
-            // Board related endpoints
-            .service(routes::create_board)
-            .service(routes::get_boards)
-            .service(routes::get_board)
-            // Post related endpoints
-            .service(routes::create_post)
-            .service(routes::get_posts_by_board)
-            .service(routes::get_post)
-            // Comment related endpoints
-            .service(routes::create_comment)
-            .service(routes::get_comments_by_post)
-            // Artificial slow endpoint for testing alerts and profiling
-            .service(routes::slow_endpoint)
+            // Docs assets (CSS, images) from STATIC_DIR
+            .service(
+                Files::new("/static", config::get().static_dir.clone())
+                    .default_handler(fn_service(|req: ServiceRequest| async {
+                        let (req, _) = req.into_parts();
+                        let res = static_not_found(req.path());
+                        Ok(ServiceResponse::new(req, res))
+                    }))
+            )
+            // API endpoints, minus any listed in DISABLED_ENDPOINTS
+            .configure(endpoints::configure)
+            .configure(selftest::configure) // GET /admin/selftest, only with --features selftest
+            // Unmatched requests: 405 listing the supported methods if the path exists, else 404
+            .default_service(fn_service(method_not_allowed::default_handler))
     })
     .workers(4)  // Limit number of workers for stability
     .max_connections(1024)  // Limit max connections per worker  
     .client_request_timeout(std::time::Duration::from_secs(10))  // Request timeout
     .client_disconnect_timeout(std::time::Duration::from_secs(5))  // Disconnect timeout
+    .keep_alive(keep_alive)  // Idle time before a keep-alive connection is closed
+    .on_connect(move |conn, _| {
+        // actix-web has no builder option for TCP_NODELAY, so it is set per accepted socket
+        if let Some(stream) = conn.downcast_ref::<actix_web::rt::net::TcpStream>() {
+            if let Err(e) = stream.set_nodelay(tcp_nodelay) {
+                eprintln!("Failed to set TCP_NODELAY: {}", e);
+            }
+        }
+    })
+    .shutdown_timeout(shutdown_timeout_secs)  // Grace period for in-flight requests on stop
+    .disable_signals()  // Signals are handled below so draining can be logged
     .bind("0.0.0.0:8080")?
     .run();
-    
-    // Run server without capturing handle to reduce overhead
+
+    // Stop gracefully on SIGINT/SIGTERM, logging how much work was in flight and how long draining took
+    let handle = server.handle();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        let pending = in_flight.get();
+        println!(
+            "🛑 Shutdown requested: draining {} in-flight requests (grace period {}s)",
+            pending, shutdown_timeout_secs
+        );
+        let drain_start = std::time::Instant::now();
+        handle.stop(true).await;
+        let dropped = in_flight.get();
+        println!(
+            "✅ Shutdown complete: drained {} requests in {}ms, {} still in flight when workers stopped",
+            (pending - dropped).max(0), drain_start.elapsed().as_millis(), dropped
+        );
+    });
+
     server.await
 }
+
+/// Resolve on the first SIGINT (Ctrl-C) or, on Unix, SIGTERM
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => eprintln!("Failed to install SIGTERM handler: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,94 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{Error, ResponseError};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use tracing::debug;
+use crate::config;
+use crate::errors::ApiError;
+
+// Maintenance state shared by all workers, toggled through the admin endpoint
+static MAINTENANCE_ENABLED: AtomicBool = AtomicBool::new(false);
+static MAINTENANCE_BLOCK_READS: AtomicBool = AtomicBool::new(false);
+
+/// Whether maintenance mode is currently on
+pub fn is_enabled() -> bool {
+    MAINTENANCE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether maintenance mode also rejects read requests
+pub fn blocks_reads() -> bool {
+    MAINTENANCE_BLOCK_READS.load(Ordering::Relaxed)
+}
+
+/// Turn maintenance mode on or off
+pub fn set(enabled: bool, block_reads: bool) {
+    MAINTENANCE_BLOCK_READS.store(enabled && block_reads, Ordering::Relaxed);
+    MAINTENANCE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn is_read(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+// Middleware factory returning 503 for requests blocked by maintenance mode
+pub struct MaintenanceGuard;
+
+impl<S, B> Transform<S, ServiceRequest> for MaintenanceGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MaintenanceGuardMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MaintenanceGuardMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct MaintenanceGuardMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for MaintenanceGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // Admin and probe endpoints keep working so operators can turn maintenance off again
+        let exempt = req.path().starts_with("/admin/") || req.path() == "/health" || req.path() == "/metrics";
+
+        if is_enabled() && !exempt && (!is_read(req.method()) || blocks_reads()) {
+            debug!("Maintenance mode: rejecting {} {}", req.method(), req.path());
+            let response = ApiError::ServiceUnavailable {
+                message: "Service is in maintenance mode, please retry later".to_string(),
+                retry_after_secs: Some(config::get().maintenance_retry_after_secs),
+            }.error_response();
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
@@ -0,0 +1,79 @@
+//! Fallback for requests no route matched: 405 with the supported methods when the path
+//! exists under other methods, a plain 404 otherwise.
+
+use actix_web::dev::{ResourceDef, ServiceRequest, ServiceResponse};
+use actix_web::{Error, ResponseError};
+use std::sync::OnceLock;
+use utoipa::openapi::PathItemType;
+use utoipa::OpenApi;
+use crate::api_docs::ApiDoc;
+use crate::endpoints;
+use crate::errors::ApiError;
+
+type RouteTable = Vec<(ResourceDef, Vec<&'static str>)>;
+
+/// API paths with the methods each supports, built once from the OpenAPI document
+static ROUTES: OnceLock<RouteTable> = OnceLock::new();
+
+fn method_name(method: &PathItemType) -> &'static str {
+    match method {
+        PathItemType::Get => "GET",
+        PathItemType::Post => "POST",
+        PathItemType::Put => "PUT",
+        PathItemType::Delete => "DELETE",
+        PathItemType::Options => "OPTIONS",
+        PathItemType::Head => "HEAD",
+        PathItemType::Patch => "PATCH",
+        PathItemType::Trace => "TRACE",
+        PathItemType::Connect => "CONNECT",
+    }
+}
+
+/// Documented paths and their methods, leaving out endpoints switched off by `DISABLED_ENDPOINTS`
+fn routes() -> &'static RouteTable {
+    ROUTES.get_or_init(|| {
+        ApiDoc::openapi()
+            .paths
+            .paths
+            .into_iter()
+            .filter_map(|(path, item)| {
+                let methods: Vec<&'static str> = item
+                    .operations
+                    .iter()
+                    .filter(|(_, operation)| {
+                        !operation.operation_id.as_deref().is_some_and(endpoints::is_handler_disabled)
+                    })
+                    .map(|(method, _)| method_name(method))
+                    .collect();
+                if methods.is_empty() {
+                    None
+                } else {
+                    Some((ResourceDef::new(path), methods))
+                }
+            })
+            .collect()
+    })
+}
+
+/// Default service of the app: answers 405 with an `Allow` header and a JSON `ErrorResponse`
+/// listing the supported methods, or a JSON 404 when no route has this path at all
+pub async fn default_handler(req: ServiceRequest) -> Result<ServiceResponse, Error> {
+    let (req, _) = req.into_parts();
+    let mut allowed: Vec<&str> = routes()
+        .iter()
+        .filter(|(resource, _)| resource.is_match(req.path()))
+        .flat_map(|(_, methods)| methods.iter().copied())
+        .collect();
+    allowed.sort_unstable();
+    allowed.dedup();
+
+    let res = if allowed.is_empty() {
+        ApiError::not_found(format!("No resource at {}", req.path())).error_response()
+    } else {
+        ApiError::MethodNotAllowed {
+            message: format!("Method {} is not supported on {}", req.method(), req.path()),
+            allowed: allowed.iter().map(|method| method.to_string()).collect(),
+        }.error_response()
+    };
+    Ok(ServiceResponse::new(req, res))
+}
@@ -1,20 +1,187 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 use utoipa::ToSchema;
 
+/// Serde helpers that switch response timestamps between RFC 3339 strings and epoch milliseconds
+pub mod timestamp_format {
+    use chrono::{DateTime, Utc};
+    use serde::{Serialize, Serializer};
+    use std::cell::Cell;
+
+    thread_local! {
+        static EPOCH_MILLIS: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Serialize a response body to JSON with timestamps as integer epoch milliseconds
+    pub fn to_json_epoch_millis<T: Serialize>(value: &T) -> serde_json::Result<String> {
+        EPOCH_MILLIS.with(|flag| flag.set(true));
+        let result = serde_json::to_string(value);
+        EPOCH_MILLIS.with(|flag| flag.set(false));
+        result
+    }
+
+    pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        if EPOCH_MILLIS.with(|flag| flag.get()) {
+            serializer.serialize_i64(value.timestamp_millis())
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn serialize_option<S: Serializer>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Response timestamp format selected with `?ts=`
+#[derive(Debug, Default, Deserialize)]
+pub struct TimestampFormatParams {
+    /// `rfc3339` (default) or `epoch` for integer milliseconds
+    #[serde(default)]
+    pub ts: Option<String>,
+}
+
+impl TimestampFormatParams {
+    pub fn is_epoch(&self) -> bool {
+        matches!(self.ts.as_deref(), Some("epoch") | Some("epoch_millis"))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct Board {
     pub id: Uuid,
     pub name: String,
+    /// URL-friendly form of the name, unique across boards (`GET /boards/by-slug/{slug}`)
+    #[serde(default)]
+    pub slug: String,
     pub description: String,
+    #[serde(serialize_with = "timestamp_format::serialize")]
     pub created_at: DateTime<Utc>,
+    /// Maximum number of posts allowed on the board (null for unlimited)
+    pub max_posts: Option<i32>,
+    /// Page size of the board's post listing when the client sends no `limit`
+    #[serde(default)]
+    pub default_page_size: Option<i32>,
+    /// Sort of the board's post listing when the client sends no `sort`: a post field,
+    /// optionally followed by `:asc` or `:desc` (e.g. `title:asc`)
+    #[serde(default)]
+    pub default_sort: Option<String>,
+    /// Category the board is listed under (null if none)
+    #[serde(default)]
+    pub category_id: Option<Uuid>,
+    /// Soft-deleted by a moderator; deleted content is only listed with `include_deleted=true`
+    #[serde(default)]
+    pub is_deleted: bool,
+    /// When it was deleted (null unless `is_deleted`)
+    #[serde(default, serialize_with = "timestamp_format::serialize_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)] // A typo like `titel` is reported instead of silently dropped
 pub struct CreateBoardRequest {
     pub name: String,
     pub description: String,
+    /// Optional cap on the number of posts (e.g. a contest with N entries)
+    #[schema(minimum = 1)]
+    pub max_posts: Option<i32>,
+    /// Page size of the board's post listing when the client sends no `limit`
+    #[serde(default)]
+    #[schema(minimum = 1, maximum = 100)]
+    pub default_page_size: Option<i32>,
+    /// Sort of the board's post listing when the client sends no `sort`, e.g. `title:asc`
+    #[serde(default)]
+    pub default_sort: Option<String>,
+    /// Category to list the board under (must exist)
+    #[serde(default)]
+    pub category_id: Option<Uuid>,
+    /// Original creation time, for imports; only accepted with a valid `X-Admin-Token`
+    /// (server time is used when omitted)
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Request to update a board; omitted fields keep their value
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateBoardRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[schema(minimum = 1)]
+    pub max_posts: Option<i32>,
+    #[schema(minimum = 1, maximum = 100)]
+    pub default_page_size: Option<i32>,
+    pub default_sort: Option<String>,
+    /// Move the board to another category (must exist)
+    pub category_id: Option<Uuid>,
+}
+
+/// A group of boards, e.g. "General" or "Tech"
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Category {
+    pub id: Uuid,
+    pub name: String,
+    /// Categories are listed by ascending position, then by name
+    pub position: i32,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to create a category
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    /// Place in the category order (default 0)
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+/// Grouping of `GET /boards`, selected with `?group_by=`
+#[derive(Debug, Default, Deserialize)]
+pub struct BoardListParams {
+    /// `category` groups the page's boards by category
+    #[serde(default)]
+    pub group_by: Option<String>,
+}
+
+/// Boards of one category on a page of `GET /boards?group_by=category`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BoardGroup {
+    /// Null for boards without a category
+    pub category: Option<Category>,
+    pub boards: Vec<Board>,
+}
+
+/// A page of boards grouped by category, in category order
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GroupedBoardsResponse {
+    /// Pagination metadata; pages count boards, not groups
+    pub meta: PaginationMeta,
+    pub groups: Vec<BoardGroup>,
+}
+
+/// Request for statistics of several boards at once
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BoardStatsRequest {
+    /// Boards to report on (at most 100)
+    pub board_ids: Vec<Uuid>,
+}
+
+/// Post count and last activity for a single board
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct BoardStats {
+    pub board_id: Uuid,
+    /// Number of posts on the board
+    pub post_count: i64,
+    /// Most recent post update on the board (null if the board has no posts)
+    #[serde(serialize_with = "timestamp_format::serialize_option")]
+    pub last_activity: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -23,17 +190,108 @@ pub struct Post {
     pub board_id: Uuid,
     pub title: String,
     pub content: String,
+    #[serde(serialize_with = "timestamp_format::serialize")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "timestamp_format::serialize")]
     pub updated_at: DateTime<Utc>,
     pub author: String,
+    /// Tags attached to the post
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Soft-deleted by a moderator; deleted content is only listed with `include_deleted=true`
+    #[serde(default)]
+    pub is_deleted: bool,
+    /// When it was deleted (null unless `is_deleted`)
+    #[serde(default, serialize_with = "timestamp_format::serialize_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Locked by a moderator; a locked post takes no new comments
+    #[serde(default)]
+    pub is_locked: bool,
+    /// Upvotes minus downvotes
+    #[serde(default)]
+    pub score: i64,
+    /// Number of users who reacted with each emoji, most used first
+    #[serde(default)]
+    pub reactions: Vec<ReactionCount>,
+}
+
+/// Related data to embed in post responses, selected with `?include=`
+#[derive(Debug, Default, Deserialize)]
+pub struct IncludeParams {
+    /// Comma-separated list of relations; `board` embeds each post's board
+    #[serde(default)]
+    pub include: Option<String>,
+}
+
+impl IncludeParams {
+    pub fn board(&self) -> bool {
+        self.include
+            .as_deref()
+            .is_some_and(|include| include.split(',').any(|part| part.trim().eq_ignore_ascii_case("board")))
+    }
+}
+
+/// `?include_deleted=true` lists soft-deleted content too (moderators and the admin token only)
+#[derive(Debug, Default, Deserialize)]
+pub struct DeletedFilterParams {
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+/// `?purge=true` deletes permanently instead of soft-deleting (moderators and the admin token only)
+#[derive(Debug, Default, Deserialize)]
+pub struct PurgeParams {
+    #[serde(default)]
+    pub purge: bool,
+}
+
+/// A post with its board embedded (`?include=board`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PostWithBoard {
+    #[serde(flatten)]
+    pub post: Post,
+    /// Board the post belongs to (null if the board no longer exists)
+    pub board: Option<Board>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)] // A typo like `titel` is reported instead of silently dropped
 pub struct CreatePostRequest {
     pub board_id: Uuid,
     pub title: String,
     pub content: String,
+    /// Free-form author name; may be omitted when `user_id` is given
+    #[serde(default)]
     pub author: String,
+    /// Account posting; its username becomes the author. Must be the bearer token's user
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    /// Tags to categorize the post; lowercased, trimmed and de-duplicated before storing
+    /// (limits: `TAG_MAX_PER_POST`, `TAG_MAX_LENGTH`)
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// Original creation time, for imports; only accepted with a valid `X-Admin-Token`
+    /// (server time is used when omitted)
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Request to edit a post; omitted fields keep their value
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePostRequest {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    /// `updated_at` of the version being edited; the edit is refused with 409 if the post
+    /// changed since
+    pub expected_updated_at: Option<DateTime<Utc>>,
+}
+
+/// Request to lock or unlock a post
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LockPostRequest {
+    pub locked: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -41,15 +299,86 @@ pub struct Comment {
     pub id: Uuid,
     pub post_id: Uuid,
     pub content: String,
+    #[serde(serialize_with = "timestamp_format::serialize")]
     pub created_at: DateTime<Utc>,
     pub author: String,
+    /// When the content was last edited (null if never)
+    #[serde(default, serialize_with = "timestamp_format::serialize_option")]
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Soft-deleted by a moderator; deleted content is only listed with `include_deleted=true`
+    #[serde(default)]
+    pub is_deleted: bool,
+    /// When it was deleted (null unless `is_deleted`)
+    #[serde(default, serialize_with = "timestamp_format::serialize_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Upvotes minus downvotes
+    #[serde(default)]
+    pub score: i64,
+    /// Number of users who reacted with each emoji, most used first
+    #[serde(default)]
+    pub reactions: Vec<ReactionCount>,
+}
+
+/// Request to edit a comment
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateCommentRequest {
+    pub content: String,
+}
+
+/// A vote on a post or comment: 1 up, -1 down, 0 takes a previous vote back
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct VoteRequest {
+    pub value: i32,
+}
+
+/// The caller's vote and the resulting score
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct VoteResponse {
+    /// Post or comment voted on
+    pub id: Uuid,
+    pub vote: i32,
+    pub score: i64,
+}
+
+/// Number of users who reacted to a post or comment with one emoji
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: i64,
+}
+
+/// Reaction summary of a post or comment after adding or removing a reaction
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ReactionsResponse {
+    /// Post or comment reacted to
+    pub id: Uuid,
+    pub reactions: Vec<ReactionCount>,
+}
+
+/// Number of comments on a post
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CommentCount {
+    pub post_id: Uuid,
+    pub count: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)] // A typo like `titel` is reported instead of silently dropped
 pub struct CreateCommentRequest {
     pub post_id: Uuid,
     pub content: String,
+    /// Free-form author name; may be omitted when `user_id` is given
+    #[serde(default)]
     pub author: String,
+    /// Account commenting; its username becomes the author. Must be the bearer token's user
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    /// Original creation time, for imports; only accepted with a valid `X-Admin-Token`
+    /// (server time is used when omitted)
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -58,12 +387,87 @@ pub struct PaginationParams {
     #[serde(default = "default_page")]
     #[schema(default = 1, minimum = 1)]
     pub page: u32,
-    /// Number of items per page
-    #[serde(default = "default_limit")]
+    /// Number of items per page (see `limit()` for the default)
+    #[serde(default)]
     #[schema(default = 10, minimum = 1, maximum = 100)]
+    pub limit: Option<u32>,
+    /// Fill `meta.total` with a cheap approximate row count
+    #[serde(default)]
+    #[schema(default = false)]
+    pub estimate_total: bool,
+    /// Field to sort the page by (must be one of the endpoint's allowed fields)
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Sort direction: `asc` or `desc`
+    #[serde(default)]
+    pub order: Option<String>,
+}
+
+/// Filters for `GET /posts/{post_id}/comments`
+#[derive(Debug, Default, Deserialize)]
+pub struct CommentFilterParams {
+    /// Only return comments by this author (exact match)
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+/// Creation time range of `GET /boards/{board_id}/posts` and `GET /posts/{post_id}/comments`
+#[derive(Debug, Default, Deserialize)]
+pub struct DateRangeParams {
+    /// Only items created at or after this RFC 3339 timestamp
+    #[serde(default)]
+    pub since: Option<String>,
+    /// Only items created before this RFC 3339 timestamp
+    #[serde(default)]
+    pub until: Option<String>,
+}
+
+/// Query parameters for `GET /posts/changes`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PostChangesParams {
+    /// Return posts updated strictly after this RFC 3339 timestamp
+    pub since: String,
+    /// Maximum number of posts to return
+    #[serde(default = "default_changes_limit")]
+    #[schema(default = 50, minimum = 1, maximum = 500)]
     pub limit: u32,
 }
 
+fn default_changes_limit() -> u32 {
+    50
+}
+
+/// Posts changed since a sync point, oldest change first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PostChangesResponse {
+    pub data: Vec<Post>,
+    /// Value to pass as `since` on the next poll
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub next_since: DateTime<Utc>,
+    /// Whether more changes are waiting (poll again right away with `next_since`)
+    pub has_more: bool,
+}
+
+/// Query parameters for `GET /tags/popular`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PopularTagsParams {
+    /// Number of tags to return
+    #[serde(default = "default_popular_tags_limit")]
+    #[schema(default = 20, minimum = 1, maximum = 100)]
+    pub limit: u32,
+}
+
+fn default_popular_tags_limit() -> u32 {
+    20
+}
+
+/// A tag with the number of posts using it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagUsage {
+    pub tag: String,
+    pub uses: i64,
+}
+
 fn default_page() -> u32 {
     1
 }
@@ -72,6 +476,13 @@ fn default_limit() -> u32 {
     10
 }
 
+impl PaginationParams {
+    /// Requested page size, or the global default when the client sent none
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or_else(default_limit)
+    }
+}
+
 /// Metadata about pagination
 #[derive(Debug, Serialize, ToSchema)]
 pub struct PaginationMeta {
@@ -83,6 +494,14 @@ pub struct PaginationMeta {
     pub total: Option<u32>, // Optional as count might be expensive
     /// Total number of pages (if total is available)
     pub total_pages: Option<u32>,
+    /// Whether `total` is an approximation rather than an exact count
+    pub total_is_estimate: bool,
+    /// Effective sort field, on listings whose defaults can come from stored settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Effective sort direction (`asc` or `desc`), alongside `sort`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
 }
 
 /// Wrapper for paginated responses
@@ -94,12 +513,572 @@ pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
 }
 
+/// Error body of every failed request (see `errors::ApiError`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Machine-readable error code, e.g. `not_found` or `db_timeout`
+    pub code: String,
+    pub message: String,
+    /// OpenTelemetry trace id of the request, as in the `X-Trace-Id` header
+    pub trace_id: Option<String>,
+    /// Methods the path does support (only for `method_not_allowed`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_methods: Vec<String>,
+    /// Offending JSON field (only for `unknown_field`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Fields failing validation (only for `validation_failed`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// A request body field failing validation
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Name of the field in the request body, e.g. `title`
+    pub field: String,
+    pub message: String,
+}
+
+/// RFC 7807 error body, sent as `application/problem+json` to clients whose `Accept` prefers it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    /// URI identifying the kind of problem: `urn:forum-api:problem:` followed by the code
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Reason phrase of the status, e.g. `Not Found`
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// Path of the failed request
+    pub instance: String,
+    /// Same as `ErrorResponse.code`
+    pub code: String,
+    pub trace_id: Option<String>,
+    /// Methods the path does support (only for `method_not_allowed`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_methods: Vec<String>,
+    /// Offending JSON field (only for `unknown_field`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Fields failing validation (only for `validation_failed`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
 /// For metrics and health checks
 #[derive(Debug, Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
     pub timestamp: DateTime<Utc>,
+    /// Whether write requests are currently rejected for maintenance
+    pub maintenance: bool,
+}
+
+
+
+/// Kind of cached entity targeted by admin cache operations
+#[derive(Clone, Copy, Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheEntryType {
+    Board,
+    Post,
+}
+
+/// Request to refresh a single cache entry from the database
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CacheRefreshRequest {
+    #[serde(rename = "type")]
+    pub entry_type: CacheEntryType,
+    pub id: Uuid,
+}
+
+/// Request to toggle maintenance mode
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    /// Also reject read requests while maintenance mode is on
+    #[serde(default)]
+    pub block_reads: bool,
+}
+
+/// Current maintenance mode state
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub block_reads: bool,
+}
+
+/// Size and hit statistics of one in-memory cache
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStats {
+    /// Entries currently held (including expired ones not yet replaced)
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub expired: u64,
+}
+
+/// Statistics for all in-memory caches
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStatsResponse {
+    pub boards: CacheStats,
+    pub posts: CacheStats,
+    pub board_stats: CacheStats,
+}
+
+/// Latency percentiles of one histogram series, in seconds
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LatencyPercentiles {
+    /// Histogram metric name
+    pub metric: String,
+    /// Labels identifying the series (endpoint, method, status, ...)
+    pub labels: BTreeMap<String, String>,
+    /// Observations recorded since startup
+    pub count: u64,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
 }
 
+/// Latency percentiles for all histograms the service records
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LatencySnapshot {
+    /// Always true: figures are cumulative since the process started
+    pub since_startup: bool,
+    pub histograms: Vec<LatencyPercentiles>,
+}
+
+/// Request to subscribe to a board's new posts
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubscriptionRequest {
+    pub author: String,
+}
+
+/// Query parameters identifying the subscriber on unsubscribe
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubscriptionAuthorParams {
+    pub author: String,
+}
+
+/// An author's subscription to a board
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Subscription {
+    pub author: String,
+    pub board_id: Uuid,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for `GET /posts/{post_id}/full`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FullThreadParams {
+    /// Maximum number of comments to include (capped by `FULL_THREAD_MAX_COMMENTS_LIMIT`)
+    #[serde(default)]
+    pub max_comments: Option<u32>,
+}
+
+/// A post together with its comments, oldest comment first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FullThread {
+    pub post: Post,
+    pub comments: Vec<Comment>,
+    /// Number of comments on the post, including any left out
+    pub total_comments: u64,
+    /// Whether comments were left out because of `max_comments`
+    pub truncated: bool,
+}
+
+/// One ScyllaDB node known to the driver
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolNode {
+    pub address: String,
+    pub datacenter: Option<String>,
+    pub rack: Option<String>,
+    pub host_id: Uuid,
+    /// Whether the driver currently considers the node down
+    pub is_down: bool,
+    /// Connections the pool keeps open to the node (the configured per-host pool size)
+    pub connections: usize,
+}
+
+/// Connection pool state and the outcome of the background pool probes
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolStats {
+    pub nodes: Vec<PoolNode>,
+    #[serde(serialize_with = "timestamp_format::serialize_option")]
+    pub last_check_at: Option<DateTime<Utc>>,
+    /// Failed probe rounds in a row
+    pub consecutive_failures: u32,
+    /// Metadata refreshes triggered by failed probes since startup
+    pub refreshes: u64,
+    pub last_error: Option<String>,
+}
+
+/// A request the service is currently handling
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InFlightRequestInfo {
+    pub method: String,
+    pub path: String,
+    /// Absent when tracing skipped the path or the span isn't created yet
+    pub trace_id: Option<String>,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub started_at: DateTime<Utc>,
+    pub elapsed_ms: u64,
+}
+
+/// What a user may do beyond writing their own posts and comments, in increasing order
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    /// May moderate content (e.g. delete boards and posts, pin posts, ban users)
+    Moderator,
+    /// Everything a moderator may, plus administrative operations
+    Admin,
+}
 
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Role stored in the `users.role` column; unknown or missing values get the least privilege
+    pub fn from_column(value: Option<&str>) -> Self {
+        match value {
+            Some("moderator") => Role::Moderator,
+            Some("admin") => Role::Admin,
+            _ => Role::User,
+        }
+    }
+}
+
+/// A registered user account
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    pub role: Role,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to create a user account
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterRequest {
+    /// Unique (case-insensitive) name, also used as the author of the user's posts and comments
+    pub username: String,
+    #[schema(min_length = 8, max_length = 128)]
+    pub password: String,
+}
+
+/// Request to replace a user's password
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PasswordChangeRequest {
+    pub username: String,
+    pub current_password: String,
+    #[schema(min_length = 8, max_length = 128)]
+    pub new_password: String,
+}
+
+/// Request to check a user's credentials
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Access token issued by `POST /auth/token`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenResponse {
+    /// JWT to send as `Authorization: Bearer <token>`
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: String,
+    /// Seconds until the token expires
+    pub expires_in: u64,
+    /// Single-use token for `POST /auth/refresh`; each refresh returns a new one
+    pub refresh_token: String,
+    /// Seconds until the refresh token expires
+    pub refresh_expires_in: u64,
+}
+
+/// Request carrying a refresh token (`POST /auth/refresh`, `POST /auth/logout`)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// Query parameters the OAuth provider redirects back with
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OAuthCallbackParams {
+    /// Authorization code to exchange (absent when the user declined)
+    pub code: Option<String>,
+    /// State issued by `/auth/oauth/{provider}/start`
+    pub state: String,
+    /// Error code set by the provider, e.g. `access_denied`
+    pub error: Option<String>,
+}
+
+/// Request to change a user's role
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RoleUpdateRequest {
+    pub role: Role,
+}
+
+/// What an API key may do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    /// GET and HEAD requests
+    Read,
+    /// Every other method; only creating boards, posts and comments accepts a key in place of
+    /// a bearer token, so other writes still answer 401 to a key alone
+    Write,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiKeyScope::Read => "read",
+            ApiKeyScope::Write => "write",
+        }
+    }
+
+    /// Scope stored in the `api_keys.scopes` column; unknown values are dropped
+    pub fn from_column(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(ApiKeyScope::Read),
+            "write" => Some(ApiKeyScope::Write),
+            _ => None,
+        }
+    }
+}
+
+/// An API key for an automated client, without its secret
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ApiKey {
+    pub id: Uuid,
+    /// Who the key was issued to, e.g. the bot's name
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Requests allowed per minute (per instance)
+    pub rate_limit_per_minute: u32,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A newly created API key with its secret, which is only shown once
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    pub api_key: ApiKey,
+    /// Value for the `X-Api-Key` header
+    pub secret: String,
+}
+
+/// Request to create an API key
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Requests allowed per minute; defaults to `API_KEY_DEFAULT_RATE_LIMIT`
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// Request to change an API key; omitted fields keep their value
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateApiKeyRequest {
+    pub name: Option<String>,
+    pub scopes: Option<Vec<ApiKeyScope>>,
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// State of a background board deletion
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionJobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl DeletionJobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeletionJobStatus::Running => "running",
+            DeletionJobStatus::Completed => "completed",
+            DeletionJobStatus::Failed => "failed",
+        }
+    }
+
+    /// Status stored in the `deletion_jobs.status` column
+    pub fn from_column(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(DeletionJobStatus::Running),
+            "completed" => Some(DeletionJobStatus::Completed),
+            "failed" => Some(DeletionJobStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Progress of a board deletion started by `DELETE /boards/{board_id}`
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct DeletionJob {
+    pub id: Uuid,
+    pub board_id: Uuid,
+    pub board_name: String,
+    pub status: DeletionJobStatus,
+    /// Posts on the board when the deletion started
+    pub posts_total: i64,
+    pub posts_deleted: i64,
+    pub comments_deleted: i64,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub started_at: DateTime<Utc>,
+    #[serde(serialize_with = "timestamp_format::serialize_option")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Why the job failed; deleting the board again resumes it
+    pub error: Option<String>,
+}
+
+/// One version of a post, from `GET /posts/{post_id}/revisions`
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct PostRevision {
+    pub post_id: Uuid,
+    /// 1 is the post before its first edit; every edit adds one
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    /// Who saved this version (the post's author for revision 1); `null` for edits made with
+    /// the admin token
+    pub editor: Option<String>,
+    /// When this version was saved
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub edited_at: DateTime<Utc>,
+}
+
+/// Account erasure accepted by `DELETE /users/me`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountDeletionResponse {
+    pub user_id: Uuid,
+    /// Audit record of the request
+    pub audit_id: Uuid,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub requested_at: DateTime<Utc>,
+}
+
+/// Whether a search hit is a post or a comment
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Post,
+    Comment,
+}
+
+impl SearchKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchKind::Post => "post",
+            SearchKind::Comment => "comment",
+        }
+    }
+}
+
+/// Query of `GET /search`
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    /// Search terms
+    pub q: String,
+    /// Only `post` or only `comment` hits
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+    #[serde(default = "default_page")]
+    pub page: u32,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// Title and content of a search hit with the matched words wrapped in `<mark>`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHighlights {
+    /// Whole title; `null` for comments
+    pub title: Option<String>,
+    /// Excerpt of the content around the matches
+    pub content: Option<String>,
+}
+
+/// A post or comment matching a search, from `GET /search`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: Uuid,
+    /// The post itself, or the post the comment belongs to
+    pub post_id: Uuid,
+    /// Board of a post; `null` for comments
+    pub board_id: Option<Uuid>,
+    /// Title of a post; `null` for comments
+    pub title: Option<String>,
+    pub author: String,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub created_at: DateTime<Utc>,
+    pub highlights: SearchHighlights,
+}
+
+/// Query of `GET /boards/{board_id}/posts/search`
+#[derive(Debug, Deserialize)]
+pub struct TitleSearchParams {
+    /// Start of the title, compared ignoring case and runs of whitespace
+    pub prefix: String,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A post whose title starts with the searched prefix
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PostTitleMatch {
+    pub id: Uuid,
+    pub board_id: Uuid,
+    pub title: String,
+    #[serde(serialize_with = "timestamp_format::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn create_post_request_rejects_unknown_field() {
+        let body = json!({
+            "board_id": "00000000-0000-0000-0000-000000000001",
+            "title": "Hello",
+            "content": "World",
+            "author": "alice",
+        });
+        assert!(serde_json::from_value::<CreatePostRequest>(body.clone()).is_ok());
+
+        let mut typo = body;
+        typo["titel"] = json!("Hello");
+        let error = serde_json::from_value::<CreatePostRequest>(typo).unwrap_err();
+        assert!(error.to_string().contains("unknown field `titel`"), "{}", error);
+    }
+
+    #[test]
+    fn update_requests_reject_unknown_fields() {
+        assert!(serde_json::from_value::<UpdatePostRequest>(json!({ "title": "New" })).is_ok());
+        assert!(serde_json::from_value::<UpdatePostRequest>(json!({ "title": "New", "board_id": "x" })).is_err());
+        assert!(serde_json::from_value::<UpdateBoardRequest>(json!({ "name": "General" })).is_ok());
+        assert!(serde_json::from_value::<UpdateBoardRequest>(json!({ "nmae": "General" })).is_err());
+    }
+}
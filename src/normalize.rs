@@ -0,0 +1,132 @@
+/// Trim a single-line value and collapse every run of whitespace into one space
+pub fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Canonical form of a name for case- and spacing-insensitive comparisons
+pub fn name_key(value: &str) -> String {
+    collapse_whitespace(value).to_lowercase()
+}
+
+/// URL-friendly form of a name: lowercase letters and digits, with every other run of
+/// characters turned into a single `-` (e.g. "Rust & Go!" becomes "rust-go")
+pub fn slugify(value: &str) -> String {
+    let mut slug = String::with_capacity(value.len());
+    for c in value.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+/// Canonical form of a tag: trimmed, lowercased, inner whitespace collapsed
+pub fn tag_key(value: &str) -> String {
+    name_key(value)
+}
+
+/// Normalize a list of tags, dropping empty ones and duplicates while keeping first-seen order
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().map(|tag| tag_key(tag)) {
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Tidy multi-line content without touching intentional formatting inside lines
+///
+/// Trims the content, strips trailing whitespace from each line and keeps at most
+/// one blank line between paragraphs.
+pub fn normalize_content(value: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut blank_run = 0;
+
+    for line in value.trim().lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapse_whitespace_trims_and_joins_runs() {
+        let cases = [
+            ("", ""),
+            ("plain", "plain"),
+            ("  padded  ", "padded"),
+            ("a  b   c", "a b c"),
+            ("a\tb\nc\r\nd", "a b c d"),
+            ("a\u{a0}\u{2003}b", "a b"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(collapse_whitespace(value), expected, "value {:?}", value);
+        }
+    }
+
+    #[test]
+    fn normalize_content_keeps_lines_and_one_blank_line() {
+        let cases = [
+            ("", ""),
+            ("  one line  ", "one line"),
+            ("keeps  inner   spacing", "keeps  inner   spacing"),
+            ("trailing   \nspaces\t\n", "trailing\nspaces"),
+            ("a\n\nb", "a\n\nb"),
+            ("a\n\n\n\nb", "a\n\nb"),
+            ("a\n   \n\t\nb", "a\n\nb"),
+            ("\n\n  indented\n", "indented"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(normalize_content(value), expected, "value {:?}", value);
+        }
+    }
+
+    #[test]
+    fn tag_key_lowercases_and_collapses_whitespace() {
+        let cases = [
+            ("rust", "rust"),
+            ("  Rust  ", "rust"),
+            ("Web  Dev", "web dev"),
+            ("ASYNC\tIO", "async io"),
+            ("   ", ""),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(tag_key(value), expected, "value {:?}", value);
+        }
+    }
+
+    #[test]
+    fn normalize_tags_drops_empty_and_duplicate_tags_in_order() {
+        let cases: [(&[&str], &[&str]); 5] = [
+            (&[], &[]),
+            (&["Rust", "web"], &["rust", "web"]),
+            (&["rust", "RUST", " Rust "], &["rust"]),
+            (&["b", "a", "B"], &["b", "a"]),
+            (&["", "  ", "web  dev", "Web Dev"], &["web dev"]),
+        ];
+        for (tags, expected) in cases {
+            let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+            assert_eq!(normalize_tags(&tags), expected, "tags {:?}", tags);
+        }
+    }
+}
@@ -0,0 +1,455 @@
+//! Sign-in through external OAuth providers (GitHub, Google).
+//!
+//! `start` redirects the browser to the provider with a random `state` that is kept for ten
+//! minutes in `oauth_states`; `callback` consumes it (once), exchanges the code for a provider
+//! access token, fetches the provider account and finds its local user through
+//! `oauth_identities`, creating one on first sign-in. The response carries the forum's own
+//! access and refresh tokens, the same as `POST /auth/token`.
+
+use actix_web::{get, http::header, web, HttpResponse, Responder, ResponseError};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use reqwest::Url;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use serde::Deserialize;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::auth;
+use crate::config::{self, OAuthClient};
+use crate::errors::ApiError;
+use crate::models::{OAuthCallbackParams, User};
+use crate::routes::{self, execute_cached, record_db_operation, DbCounter};
+use crate::sessions;
+
+/// How long a sign-in may take between `start` and `callback`
+const STATE_TTL_SECS: i32 = 600;
+/// Numbered variants tried when the provider's username is taken locally
+const MAX_USERNAME_SUFFIX: u32 = 20;
+
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn http_client() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            // GitHub's API refuses requests without a user agent
+            .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build HTTP client")
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Provider {
+    GitHub,
+    Google,
+}
+
+impl Provider {
+    fn from_path(name: &str) -> Option<Self> {
+        match name {
+            "github" => Some(Self::GitHub),
+            "google" => Some(Self::Google),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::Google => "google",
+        }
+    }
+
+    fn client(self) -> Option<&'static OAuthClient> {
+        let config = config::get();
+        match self {
+            Self::GitHub => config.oauth_github.as_ref(),
+            Self::Google => config.oauth_google.as_ref(),
+        }
+    }
+
+    fn authorize_url(self) -> &'static str {
+        match self {
+            Self::GitHub => "https://github.com/login/oauth/authorize",
+            Self::Google => "https://accounts.google.com/o/oauth2/v2/auth",
+        }
+    }
+
+    fn token_url(self) -> &'static str {
+        match self {
+            Self::GitHub => "https://github.com/login/oauth/access_token",
+            Self::Google => "https://oauth2.googleapis.com/token",
+        }
+    }
+
+    fn user_url(self) -> &'static str {
+        match self {
+            Self::GitHub => "https://api.github.com/user",
+            Self::Google => "https://openidconnect.googleapis.com/v1/userinfo",
+        }
+    }
+
+    fn scope(self) -> &'static str {
+        match self {
+            Self::GitHub => "read:user",
+            Self::Google => "openid email profile",
+        }
+    }
+
+    /// Callback URL; it must match the one registered with the provider
+    fn redirect_uri(self) -> String {
+        format!("{}/auth/oauth/{}/callback", config::get().oauth_redirect_base_url, self.as_str())
+    }
+}
+
+/// The provider named in the path, if it is known and configured
+fn configured_provider(name: &str) -> Result<(Provider, &'static OAuthClient), ApiError> {
+    let provider = Provider::from_path(name)
+        .ok_or_else(|| ApiError::not_found(format!("Unknown OAuth provider '{}'", name)))?;
+    let client = provider
+        .client()
+        .ok_or_else(|| ApiError::not_found(format!("Sign-in with {} is not configured", name)))?;
+    Ok((provider, client))
+}
+
+#[derive(Deserialize)]
+struct ProviderToken {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    id: u64,
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GoogleUser {
+    sub: String,
+    email: Option<String>,
+    name: Option<String>,
+}
+
+/// Account at the provider: stable id and the preferred username
+struct ProviderAccount {
+    subject: String,
+    username: String,
+}
+
+/// Exchange the authorization code and fetch the provider account it grants access to
+async fn fetch_provider_account(provider: Provider, client: &OAuthClient, code: &str) -> Result<ProviderAccount, String> {
+    let redirect_uri = provider.redirect_uri();
+    let token: ProviderToken = http_client()
+        .post(provider.token_url())
+        .header("Accept", "application/json")
+        .form(&[
+            ("client_id", client.client_id.as_str()),
+            ("client_secret", client.client_secret.as_str()),
+            ("code", code),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("grant_type", "authorization_code"),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("token exchange failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("unexpected token response: {}", e))?;
+
+    let response = http_client()
+        .get(provider.user_url())
+        .bearer_auth(&token.access_token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("fetching the account failed: {}", e))?;
+
+    match provider {
+        Provider::GitHub => {
+            let user: GitHubUser = response.json().await.map_err(|e| format!("unexpected account response: {}", e))?;
+            Ok(ProviderAccount { subject: user.id.to_string(), username: user.login })
+        }
+        Provider::Google => {
+            let user: GoogleUser = response.json().await.map_err(|e| format!("unexpected account response: {}", e))?;
+            let username = user
+                .email
+                .as_deref()
+                .and_then(|email| email.split('@').next())
+                .map(str::to_string)
+                .or(user.name)
+                .unwrap_or_default();
+            Ok(ProviderAccount { subject: user.sub, username })
+        }
+    }
+}
+
+/// Consume a pending sign-in state; `false` when it is unknown, expired, already used or
+/// belongs to another provider
+async fn consume_state(session: &Session, provider: Provider, state: &str) -> Result<bool, QueryError> {
+    let result = execute_cached(
+        session,
+        "DELETE FROM oauth_states WHERE state = ? IF provider = ?",
+        (state, provider.as_str()),
+    ).await?;
+    Ok(result
+        .rows
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(false))
+}
+
+async fn find_linked_user(session: &Session, provider: Provider, subject: &str) -> Result<Option<Uuid>, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT user_id FROM oauth_identities WHERE provider = ? AND subject = ?",
+        (provider.as_str(), subject),
+    ).await?;
+    Ok(rows.maybe_first_row_typed::<(Uuid,)>().ok().flatten().map(|(user_id,)| user_id))
+}
+
+/// Usernames to try for a new account: the provider's, then numbered variants
+fn username_candidates(provider: Provider, preferred: &str) -> Vec<String> {
+    let base = match preferred.trim() {
+        name if routes::validate_author(name).is_ok() => name.to_string(),
+        _ => format!("{}user", provider.as_str()),
+    };
+    std::iter::once(base.clone())
+        .chain((2..=MAX_USERNAME_SUFFIX).map(|n| format!("{}{}", base, n)))
+        .filter(|name| routes::validate_author(name).is_ok())
+        .collect()
+}
+
+/// Create a local user for a provider account and link them
+///
+/// When a concurrent sign-in linked the account first, its user wins and the one created
+/// here is left unlinked.
+async fn create_linked_user(
+    session: &Session,
+    provider: Provider,
+    account: &ProviderAccount,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<Option<Uuid>, QueryError> {
+    let mut created = None;
+    for username in username_candidates(provider, &account.username) {
+        if let Some(user) = auth::create_user(session, username, None, db_counter).await? {
+            created = Some(user);
+            break;
+        }
+    }
+    let user = match created {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+
+    let result = execute_cached(
+        session,
+        "INSERT INTO oauth_identities (provider, subject, user_id, created_at) VALUES (?, ?, ?, ?) IF NOT EXISTS",
+        (provider.as_str(), &account.subject, user.id, Utc::now().timestamp_millis()),
+    ).await?;
+    let linked = result
+        .rows
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true);
+    if !linked {
+        warn!("{} account {} was linked concurrently; user {} stays unlinked", provider.as_str(), account.subject, user.id);
+        return find_linked_user(session, provider, &account.subject).await;
+    }
+
+    info!("Created user {} ({}) for {} account {}", user.username, user.id, provider.as_str(), account.subject);
+    Ok(Some(user.id))
+}
+
+/// Start OAuth sign-in
+///
+/// Redirects to the provider's consent page; the provider then redirects back to the callback.
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/{provider}/start",
+    params(
+        ("provider" = String, Path, description = "OAuth provider: github or google")
+    ),
+    responses(
+        (status = 302, description = "Redirect to the provider"),
+        (status = 404, description = "Unknown or unconfigured provider"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/auth/oauth/{provider}/start")]
+pub async fn start(
+    session: web::Data<Arc<Session>>,
+    provider: web::Path<String>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let (provider, client) = match configured_provider(&provider) {
+        Ok(configured) => configured,
+        Err(e) => return e.error_response(),
+    };
+
+    let mut bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut bytes);
+    let state = URL_SAFE_NO_PAD.encode(bytes);
+
+    if let Err(e) = execute_cached(
+        &session,
+        "INSERT INTO oauth_states (state, provider) VALUES (?, ?) USING TTL ?",
+        (&state, provider.as_str(), STATE_TTL_SECS),
+    ).await {
+        record_db_operation(&db_counter, "insert", "oauth_states", false);
+        error!("Error storing OAuth state: {}", e);
+        return ApiError::internal(format!("Error starting sign-in: {}", e)).error_response();
+    }
+    record_db_operation(&db_counter, "insert", "oauth_states", true);
+
+    let location = Url::parse_with_params(
+        provider.authorize_url(),
+        &[
+            ("client_id", client.client_id.as_str()),
+            ("redirect_uri", provider.redirect_uri().as_str()),
+            ("response_type", "code"),
+            ("scope", provider.scope()),
+            ("state", state.as_str()),
+        ],
+    );
+    match location {
+        Ok(location) => HttpResponse::Found()
+            .insert_header((header::LOCATION, location.to_string()))
+            .finish(),
+        Err(e) => {
+            error!("Error building {} authorize URL: {}", provider.as_str(), e);
+            ApiError::internal("Error starting sign-in").error_response()
+        }
+    }
+}
+
+/// Finish OAuth sign-in
+///
+/// Exchanges the provider's code and returns the forum's tokens for the linked user, creating
+/// the user on first sign-in (from the provider's username, numbered when taken). Such users
+/// have no password.
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/{provider}/callback",
+    params(
+        ("provider" = String, Path, description = "OAuth provider: github or google"),
+        ("code" = Option<String>, Query, description = "Authorization code from the provider"),
+        ("state" = String, Query, description = "State issued by the start endpoint"),
+        ("error" = Option<String>, Query, description = "Set by the provider when the user declined")
+    ),
+    responses(
+        (status = 200, description = "Signed in", body = TokenResponse),
+        (status = 400, description = "Missing code, or the user declined at the provider"),
+        (status = 401, description = "Unknown, expired or already used state"),
+        (status = 404, description = "Unknown or unconfigured provider"),
+        (status = 409, description = "No free username for the new user"),
+        (status = 502, description = "The provider rejected the code or is unreachable"),
+        (status = 503, description = "Token authentication is disabled (JWT_SECRET is not set)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/auth/oauth/{provider}/callback")]
+pub async fn callback(
+    session: web::Data<Arc<Session>>,
+    provider: web::Path<String>,
+    params: web::Query<OAuthCallbackParams>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    if let Some(response) = auth::tokens_disabled() {
+        return response;
+    }
+    let (provider, client) = match configured_provider(&provider) {
+        Ok(configured) => configured,
+        Err(e) => return e.error_response(),
+    };
+    let params = params.into_inner();
+
+    match consume_state(&session, provider, &params.state).await {
+        Ok(true) => record_db_operation(&db_counter, "delete", "oauth_states", true),
+        Ok(false) => {
+            record_db_operation(&db_counter, "delete", "oauth_states", true);
+            warn!("Rejecting {} callback with unknown state", provider.as_str());
+            return ApiError::unauthorized("Unknown or expired sign-in state").error_response();
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "oauth_states", false);
+            error!("Error checking OAuth state: {}", e);
+            return ApiError::internal(format!("Error checking sign-in state: {}", e)).error_response();
+        }
+    }
+
+    if let Some(reason) = params.error {
+        info!("{} sign-in declined: {}", provider.as_str(), reason);
+        return ApiError::bad_request(format!("Sign-in was declined: {}", reason)).error_response();
+    }
+    let code = match params.code {
+        Some(code) => code,
+        None => return ApiError::bad_request("Missing authorization code").error_response(),
+    };
+
+    let account = match fetch_provider_account(provider, client, &code).await {
+        Ok(account) => account,
+        Err(e) => {
+            warn!("{} sign-in failed: {}", provider.as_str(), e);
+            return ApiError::bad_gateway(format!("Sign-in with {} failed", provider.as_str())).error_response();
+        }
+    };
+
+    let user_id = match find_linked_user(&session, provider, &account.subject).await {
+        Ok(Some(user_id)) => Ok(Some(user_id)),
+        Ok(None) => create_linked_user(&session, provider, &account, &db_counter).await,
+        Err(e) => Err(e),
+    };
+    let user: Option<User> = match user_id {
+        Ok(Some(user_id)) => match auth::fetch_user(&session, user_id).await {
+            Ok(user) => user,
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "users", false);
+                error!("Error fetching user {}: {}", user_id, e);
+                return ApiError::internal(format!("Error fetching user: {}", e)).error_response();
+            }
+        },
+        Ok(None) => {
+            warn!("No free username for {} account {} ({:?})", provider.as_str(), account.subject, account.username);
+            return ApiError::conflict(format!("Username '{}' and its variants are taken", account.username)).error_response();
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "oauth_identities", false);
+            error!("Error resolving {} account {}: {}", provider.as_str(), account.subject, e);
+            return ApiError::internal(format!("Error signing in: {}", e)).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "oauth_identities", true);
+    let user = match user {
+        Some(user) => user,
+        None => {
+            error!("{} account {} is linked to a missing user", provider.as_str(), account.subject);
+            return ApiError::internal("Linked user no longer exists").error_response();
+        }
+    };
+
+    match sessions::create(&session, user.id).await {
+        Ok((session_id, refresh_token)) => {
+            record_db_operation(&db_counter, "insert", "sessions", true);
+            info!("User {} signed in with {}", user.id, provider.as_str());
+            auth::token_response(&user, session_id, refresh_token)
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "insert", "sessions", false);
+            error!("Error creating session for user {}: {}", user.id, e);
+            ApiError::internal(format!("Error creating session: {}", e)).error_response()
+        }
+    }
+}
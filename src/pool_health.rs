@@ -0,0 +1,97 @@
+//! Background probing of the ScyllaDB connection pool.
+//!
+//! After nodes are replaced (e.g. a rolling restart) the driver may keep using stale
+//! connections until it notices on its own. Every `POOL_HEALTH_INTERVAL_SECS` a cheap query is
+//! sent once per known node (the load balancing policy spreads unkeyed queries over the nodes);
+//! after `POOL_HEALTH_FAILURE_THRESHOLD` failed rounds in a row the cluster metadata is
+//! refreshed, which makes the driver reconnect to the current topology.
+
+use chrono::{DateTime, Utc};
+use scylla::Session;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use crate::config;
+
+/// Outcome of the most recent probe rounds, reported by `GET /admin/pool`
+#[derive(Clone, Debug, Default)]
+pub struct PoolHealth {
+    pub last_check_at: Option<DateTime<Utc>>,
+    /// Failed rounds in a row (reset by a clean round or a refresh)
+    pub consecutive_failures: u32,
+    /// Metadata refreshes triggered since startup
+    pub refreshes: u64,
+    pub last_error: Option<String>,
+}
+
+static HEALTH: Mutex<PoolHealth> = Mutex::new(PoolHealth {
+    last_check_at: None,
+    consecutive_failures: 0,
+    refreshes: 0,
+    last_error: None,
+});
+
+/// Current probe state
+pub fn snapshot() -> PoolHealth {
+    HEALTH.lock().map(|health| health.clone()).unwrap_or_default()
+}
+
+/// Start the probe loop (does nothing when `POOL_HEALTH_INTERVAL_SECS` is 0)
+pub fn spawn_checker(session: Arc<Session>) {
+    let config = config::get();
+    if config.pool_health_interval_secs == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(config.pool_health_interval_secs);
+    let threshold = config.pool_health_failure_threshold.max(1);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await; // The first tick fires immediately; the pool was just checked at startup
+        loop {
+            ticker.tick().await;
+            let error = probe(&session).await;
+
+            let failures = {
+                let mut health = HEALTH.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                health.last_check_at = Some(Utc::now());
+                match &error {
+                    Some(e) => {
+                        health.consecutive_failures += 1;
+                        health.last_error = Some(e.clone());
+                    }
+                    None => health.consecutive_failures = 0,
+                }
+                health.consecutive_failures
+            };
+
+            let Some(error) = error else {
+                continue;
+            };
+            warn!("Connection pool probe failed ({}/{}): {}", failures, threshold, error);
+            if failures < threshold {
+                continue;
+            }
+
+            warn!("Connection pool probe failed {} times in a row, refreshing cluster metadata", failures);
+            match session.refresh_metadata().await {
+                Ok(()) => info!("Cluster metadata refreshed, connection pools follow the current topology"),
+                Err(e) => warn!("Cluster metadata refresh failed: {}", e),
+            }
+            let mut health = HEALTH.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            health.refreshes += 1;
+            health.consecutive_failures = 0;
+        }
+    });
+}
+
+/// Send one cheap query per known node, returning the first error
+async fn probe(session: &Session) -> Option<String> {
+    let nodes = session.get_cluster_data().get_nodes_info().len().max(1);
+    for _ in 0..nodes {
+        if let Err(e) = session.query("SELECT now() FROM system.local", &[]).await {
+            return Some(e.to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,226 @@
+//! Post edit history (`GET /posts/{post_id}/revisions`).
+//!
+//! Every successful `PUT /posts/{post_id}` saves the new version to `post_revisions`, numbered
+//! from the `revision` column the edit bumps on the post itself (under the same lightweight
+//! transaction, so concurrent edits can't take the same number). Revision 1 is the post before
+//! its first edit and is saved along with revision 2; a post that was never edited has no rows,
+//! and its history is the post itself.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use actix_web::web::Query;
+use chrono::{TimeZone, Utc};
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+use crate::errors::ApiError;
+use crate::jwt_middleware::AuthenticatedUser;
+use crate::models::{DeletedFilterParams, Post, PostRevision, TimestampFormatParams};
+use crate::routes::{self, execute_cached, record_db_operation, respond_json, DbCounter, IntegrityCounter};
+
+/// Save one version of a post; `editor_id` links it to the editor's account for erasure
+pub(crate) async fn insert(session: &Session, revision: &PostRevision, editor_id: Option<Uuid>) -> Result<(), QueryError> {
+    execute_cached(
+        session,
+        "INSERT INTO post_revisions (post_id, revision, title, content, editor, editor_id, edited_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        (
+            revision.post_id,
+            revision.revision,
+            &revision.title,
+            &revision.content,
+            &revision.editor,
+            editor_id,
+            revision.edited_at.timestamp_millis(),
+        ),
+    ).await?;
+    Ok(())
+}
+
+/// Drop a post's history, when the post is purged
+pub(crate) async fn remove(session: &Session, post_id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM post_revisions WHERE post_id = ?", (post_id,)).await?;
+    Ok(())
+}
+
+/// Replace the editor of every version saved by the user, returning how many there were
+pub(crate) async fn anonymize(session: &Session, user_id: Uuid, editor: &str) -> Result<usize, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT post_id, revision FROM post_revisions WHERE editor_id = ?",
+        (user_id,),
+    ).await?;
+    let revisions: Vec<(Uuid, i32)> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    for (post_id, revision) in &revisions {
+        execute_cached(
+            session,
+            "UPDATE post_revisions SET editor = ?, editor_id = null WHERE post_id = ? AND revision = ?",
+            (editor, *post_id, *revision),
+        ).await?;
+    }
+    Ok(revisions.len())
+}
+
+/// Every version of the post, oldest first
+async fn fetch_revisions(session: &Session, post: &Post) -> Result<Vec<PostRevision>, QueryError> {
+    let rows = execute_cached(
+        session,
+        "SELECT revision, title, content, editor, edited_at FROM post_revisions WHERE post_id = ?",
+        (post.id,),
+    ).await?;
+    let mut revisions = Vec::new();
+    for row in rows.rows_typed_or_empty::<(i32, Option<String>, Option<String>, Option<String>, Option<i64>)>() {
+        let (revision, title, content, editor, edited_at) = match row {
+            Ok(row) => row,
+            Err(e) => {
+                error!("Error reading revision of post {}: {}", post.id, e);
+                continue;
+            }
+        };
+        let Some(edited_at) = edited_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()) else {
+            warn!("Invalid timestamp for revision {} of post {}", revision, post.id);
+            continue;
+        };
+        revisions.push(PostRevision {
+            post_id: post.id,
+            revision,
+            title: title.unwrap_or_default(),
+            content: content.unwrap_or_default(),
+            editor,
+            edited_at,
+        });
+    }
+
+    if revisions.is_empty() {
+        revisions.push(PostRevision {
+            post_id: post.id,
+            revision: 1,
+            title: post.title.clone(),
+            content: post.content.clone(),
+            editor: Some(post.author.clone()),
+            edited_at: post.updated_at,
+        });
+    }
+    Ok(revisions)
+}
+
+/// The post's revisions, or the error to answer with when the post can't be shown
+async fn load(
+    req: &HttpRequest,
+    session: &Session,
+    post_id: Uuid,
+    deleted: &DeletedFilterParams,
+    user: Option<&AuthenticatedUser>,
+    db_counter: &web::Data<DbCounter>,
+    integrity_counter: &web::Data<IntegrityCounter>,
+) -> Result<Vec<PostRevision>, ApiError> {
+    let include_deleted = routes::include_deleted(req, user, deleted)?;
+    let post = match routes::fetch_post_from_db(session, post_id, integrity_counter).await {
+        Ok(Some(post)) if !post.is_deleted || include_deleted => post,
+        Ok(_) => {
+            record_db_operation(db_counter, "select", "posts", true);
+            return Err(ApiError::not_found(format!("Post with id {} not found", post_id)));
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "posts", false);
+            error!("Error fetching post {}: {}", post_id, e);
+            return Err(ApiError::internal(format!("Error fetching post: {}", e)));
+        }
+    };
+    record_db_operation(db_counter, "select", "posts", true);
+
+    match fetch_revisions(session, &post).await {
+        Ok(revisions) => {
+            record_db_operation(db_counter, "select", "post_revisions", true);
+            Ok(revisions)
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "post_revisions", false);
+            error!("Error fetching revisions of post {}: {}", post_id, e);
+            Err(ApiError::internal(format!("Error fetching revisions: {}", e)))
+        }
+    }
+}
+
+/// List a post's revisions
+///
+/// Every saved version of the post, oldest first; the last one is the current post. A post
+/// that was never edited has a single revision.
+#[utoipa::path(
+    get,
+    path = "/posts/{post_id}/revisions",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Revisions of the post", body = Vec<PostRevision>),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 404, description = "Post not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{post_id}/revisions")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_post_revisions(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let post_id = path.into_inner();
+    match load(&req, &session, post_id, &deleted, user.as_ref(), &db_counter, &integrity_counter).await {
+        Ok(revisions) => respond_json(&mut HttpResponse::Ok(), &revisions, &ts),
+        Err(e) => e.error_response(),
+    }
+}
+
+/// Get one revision of a post
+#[utoipa::path(
+    get,
+    path = "/posts/{post_id}/revisions/{revision}",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("revision" = i32, Path, description = "Revision number, starting at 1"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "The revision", body = PostRevision),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 404, description = "Post or revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{post_id}/revisions/{revision}")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_post_revision(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<(Uuid, i32)>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let (post_id, number) = path.into_inner();
+    let revisions = match load(&req, &session, post_id, &deleted, user.as_ref(), &db_counter, &integrity_counter).await {
+        Ok(revisions) => revisions,
+        Err(e) => return e.error_response(),
+    };
+    match revisions.iter().find(|revision| revision.revision == number) {
+        Some(revision) => respond_json(&mut HttpResponse::Ok(), revision, &ts),
+        None => ApiError::not_found(format!("Revision {} of post {} not found", number, post_id)).error_response(),
+    }
+}
@@ -0,0 +1,88 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, Header};
+use actix_web::Error;
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+tokio::task_local! {
+    // Path of the request being handled, set when its client asked for problem+json errors
+    static PROBLEM_INSTANCE: Option<String>;
+}
+
+/// The `instance` of an RFC 7807 body when the request being handled asked for
+/// `application/problem+json` errors, `None` for the plain JSON `ErrorResponse`
+pub fn problem_instance() -> Option<String> {
+    PROBLEM_INSTANCE.try_with(Clone::clone).ok().flatten()
+}
+
+/// Whether `Accept` ranks `application/problem+json` at least as high as `application/json`
+fn wants_problem_json(req: &ServiceRequest) -> bool {
+    let Ok(accept) = header::Accept::parse(req) else {
+        return false;
+    };
+    let quality_of = |essence: &str| {
+        accept.iter()
+            .filter(|item| item.item.essence_str() == essence)
+            .map(|item| item.quality)
+            .max()
+    };
+    match (quality_of(PROBLEM_JSON), quality_of("application/json")) {
+        (Some(problem), Some(json)) => problem > header::Quality::ZERO && problem >= json,
+        (Some(problem), None) => problem > header::Quality::ZERO,
+        (None, _) => false,
+    }
+}
+
+// Middleware factory letting clients pick RFC 7807 error bodies with `Accept`. Outermost, so
+// errors raised by the other middleware are covered too.
+pub struct ProblemJsonNegotiation;
+
+impl<S, B> Transform<S, ServiceRequest> for ProblemJsonNegotiation
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ProblemJsonNegotiationMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ProblemJsonNegotiationMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct ProblemJsonNegotiationMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ProblemJsonNegotiationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let instance = wants_problem_json(&req).then(|| req.path().to_string());
+        let service = Rc::clone(&self.service);
+        // The inner services are called inside the scope too: some middleware answer errors
+        // straight from `call`
+        Box::pin(PROBLEM_INSTANCE.scope(instance, async move { service.call(req).await }))
+    }
+}
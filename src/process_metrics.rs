@@ -0,0 +1,155 @@
+use prometheus::Gauge;
+use std::time::Duration;
+
+/// Linux: read the process's own entries under `/proc` (no extra dependency in the container)
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::Path;
+    use std::time::Instant;
+
+    const PROC_STATUS_PATH: &str = "/proc/self/status";
+    const PROC_STAT_PATH: &str = "/proc/self/stat";
+
+    // Kernel clock ticks per second (USER_HZ); 100 on all mainstream Linux builds
+    const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+    /// Whether process metrics can be read at all (`/proc` may be missing, e.g. in some sandboxes)
+    pub fn available() -> Result<(), String> {
+        if Path::new(PROC_STATUS_PATH).exists() {
+            Ok(())
+        } else {
+            Err(format!("{} is not available", PROC_STATUS_PATH))
+        }
+    }
+
+    /// Read the resident set size of this process in bytes
+    pub fn read_memory_usage_bytes() -> Option<f64> {
+        // Get memory usage from /proc/self/status
+        let status = std::fs::read_to_string(PROC_STATUS_PATH).ok()?;
+        for line in status.lines() {
+            if line.starts_with("VmRSS:") {
+                if let Some(kb_str) = line.split_whitespace().nth(1) {
+                    if let Ok(kb) = kb_str.parse::<f64>() {
+                        return Some(kb * 1024.0); // Convert KB to bytes
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Read the total CPU time (user + system) consumed by this process, in clock ticks
+    fn read_cpu_ticks() -> Option<u64> {
+        let stat = std::fs::read_to_string(PROC_STAT_PATH).ok()?;
+        // The command name may contain spaces, so parse from after its closing parenthesis.
+        // Remaining fields start at field 3 (state); utime and stime are fields 14 and 15.
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        Some(utime + stime)
+    }
+
+    /// Tracks CPU time between samples to derive a usage percentage
+    pub struct CpuSampler {
+        last_ticks: u64,
+        last_sample: Instant,
+    }
+
+    impl CpuSampler {
+        pub fn new() -> Option<Self> {
+            Some(Self {
+                last_ticks: read_cpu_ticks()?,
+                last_sample: Instant::now(),
+            })
+        }
+
+        /// CPU usage since the previous sample, where 100% is one fully busy core
+        pub fn sample(&mut self) -> Option<f64> {
+            let ticks = read_cpu_ticks()?;
+            let now = Instant::now();
+            let elapsed = now.duration_since(self.last_sample).as_secs_f64();
+            let delta_ticks = ticks.saturating_sub(self.last_ticks);
+
+            self.last_ticks = ticks;
+            self.last_sample = now;
+
+            if elapsed <= 0.0 {
+                return None;
+            }
+            Some(delta_ticks as f64 / CLOCK_TICKS_PER_SEC / elapsed * 100.0)
+        }
+    }
+}
+
+/// macOS, Windows and other development platforms without `/proc`: ask the OS through `sysinfo`
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use sysinfo::{get_current_pid, Pid, System};
+
+    fn current_pid() -> Option<Pid> {
+        get_current_pid().ok()
+    }
+
+    /// Whether process metrics can be read at all
+    pub fn available() -> Result<(), String> {
+        current_pid().map(|_| ()).ok_or_else(|| "the current process id is not available".to_string())
+    }
+
+    /// Read the resident set size of this process in bytes
+    pub fn read_memory_usage_bytes() -> Option<f64> {
+        let pid = current_pid()?;
+        let mut system = System::new();
+        system.refresh_process(pid);
+        system.process(pid).map(|process| process.memory() as f64)
+    }
+
+    /// Keeps a `System` between samples, since `sysinfo` derives CPU usage from the previous refresh
+    pub struct CpuSampler {
+        system: System,
+        pid: Pid,
+    }
+
+    impl CpuSampler {
+        pub fn new() -> Option<Self> {
+            let pid = current_pid()?;
+            let mut system = System::new();
+            system.refresh_process(pid);
+            Some(Self { system, pid })
+        }
+
+        /// CPU usage since the previous sample, where 100% is one fully busy core
+        pub fn sample(&mut self) -> Option<f64> {
+            self.system.refresh_process(self.pid);
+            self.system.process(self.pid).map(|process| process.cpu_usage() as f64)
+        }
+    }
+}
+
+pub use platform::read_memory_usage_bytes;
+
+/// Update memory usage metric
+pub fn update_memory_usage(memory_gauge: &Gauge) {
+    if let Some(bytes) = read_memory_usage_bytes() {
+        memory_gauge.set(bytes);
+    }
+}
+
+/// Keep process metrics fresh between scrapes, independent of request traffic
+pub fn spawn_updater(memory_gauge: Gauge, cpu_gauge: Gauge, interval: Duration) {
+    if let Err(reason) = platform::available() {
+        println!("⚠️  {}, background process metrics disabled", reason);
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut cpu_sampler = platform::CpuSampler::new();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            update_memory_usage(&memory_gauge);
+            if let Some(percent) = cpu_sampler.as_mut().and_then(|sampler| sampler.sample()) {
+                cpu_gauge.set(percent);
+            }
+        }
+    });
+}
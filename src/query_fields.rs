@@ -0,0 +1,98 @@
+//! Allowlists of the fields clients may sort or filter by.
+//!
+//! Every user-supplied field name is resolved through these tables to a fixed column
+//! name, so no client string is ever interpolated into a CQL query.
+
+use std::cmp::Ordering;
+
+/// Sortable/filterable board fields mapped to their columns
+pub const BOARD_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("name", "name"),
+];
+
+/// Sortable/filterable post fields mapped to their columns
+pub const POST_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("updated_at", "updated_at"),
+    ("title", "title"),
+    ("author", "author"),
+    ("score", "score"),
+];
+
+/// Sortable/filterable comment fields mapped to their columns
+pub const COMMENT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("updated_at", "updated_at"),
+    ("author", "author"),
+    ("score", "score"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Apply the direction to an ascending comparison
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// Resolve a client field name to its column, rejecting anything not on the allowlist
+pub fn resolve_field(allowed: &[(&str, &'static str)], field: &str) -> Result<&'static str, String> {
+    allowed
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, column)| *column)
+        .ok_or_else(|| {
+            let names: Vec<&str> = allowed.iter().map(|(name, _)| *name).collect();
+            format!("Unknown field '{}', expected one of: {}", field, names.join(", "))
+        })
+}
+
+/// Resolve a stored sort setting such as `title` or `title:asc` (the direction defaults to
+/// the endpoint's own)
+pub fn resolve_sort_setting(
+    allowed: &[(&str, &'static str)],
+    setting: &str,
+    default: (&'static str, SortOrder),
+) -> Result<(&'static str, SortOrder), String> {
+    let (field, order) = match setting.split_once(':') {
+        Some((field, order)) => (field, Some(order)),
+        None => (setting, None),
+    };
+    resolve_sort(allowed, Some(field), order, default)
+}
+
+/// Resolve the `sort`/`order` query parameters, falling back to the endpoint's default ordering
+pub fn resolve_sort(
+    allowed: &[(&str, &'static str)],
+    sort: Option<&str>,
+    order: Option<&str>,
+    default: (&'static str, SortOrder),
+) -> Result<(&'static str, SortOrder), String> {
+    let column = match sort {
+        Some(field) => resolve_field(allowed, field)?,
+        None => default.0,
+    };
+    let order = match order {
+        None => default.1,
+        Some("asc") => SortOrder::Asc,
+        Some("desc") => SortOrder::Desc,
+        Some(other) => return Err(format!("Unknown sort order '{}', expected asc or desc", other)),
+    };
+    Ok((column, order))
+}
@@ -0,0 +1,359 @@
+//! Emoji reactions on posts and comments (`PUT`/`DELETE /posts/{post_id}/reactions/{emoji}`,
+//! `PUT`/`DELETE /comments/{comment_id}/reactions/{emoji}`).
+//!
+//! Each reaction is a row in `reactions`, keyed by (content_id, emoji, user_id), so a user can
+//! react with several emoji but only once with each. Adding and removing are lightweight
+//! transactions (`IF NOT EXISTS` / `IF EXISTS`), and only an applied one moves the per-emoji
+//! counter in `reaction_counts`, so repeating a request never counts twice. Post and comment
+//! responses embed the counters as their reaction summary.
+
+use actix_web::{delete, put, web, HttpResponse, Responder, ResponseError};
+use chrono::Utc;
+use scylla::frame::value::Counter as CqlCounter;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+use crate::errors::ApiError;
+use crate::jwt_middleware::AuthenticatedUser;
+use crate::models::{Comment, Post, ReactionCount, ReactionsResponse};
+use crate::routes::{self, execute_cached, record_db_operation, DbCounter};
+
+/// Longest accepted emoji, in bytes (enough for ZWJ sequences such as family emoji)
+const MAX_EMOJI_BYTES: usize = 32;
+
+#[derive(Clone, Copy)]
+enum Target {
+    Post,
+    Comment,
+}
+
+impl Target {
+    fn kind(self) -> &'static str {
+        match self {
+            Target::Post => "post",
+            Target::Comment => "comment",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Target::Post => "Post",
+            Target::Comment => "Comment",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Target::Post => "posts",
+            Target::Comment => "comments",
+        }
+    }
+
+    fn select_deleted(self) -> &'static str {
+        match self {
+            Target::Post => "SELECT is_deleted FROM posts WHERE id = ?",
+            Target::Comment => "SELECT is_deleted FROM comments WHERE id = ?",
+        }
+    }
+}
+
+/// Whether the `[applied]` column of a lightweight transaction says it went through
+fn applied(result: &scylla::QueryResult) -> bool {
+    result
+        .rows
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true)
+}
+
+/// An emoji is a short run of non-ASCII characters; letters, digits and `:shortcodes:` are
+/// refused so the summary can't be filled with arbitrary text
+fn validate_emoji(emoji: &str) -> Result<(), String> {
+    if emoji.is_empty() || emoji.len() > MAX_EMOJI_BYTES {
+        return Err(format!("emoji must be between 1 and {} bytes", MAX_EMOJI_BYTES));
+    }
+    if emoji.chars().any(|c| c.is_ascii() || c.is_whitespace() || c.is_control()) {
+        return Err("emoji must consist of emoji characters only".to_string());
+    }
+    Ok(())
+}
+
+/// Reaction summaries of the given posts or comments, most used emoji first; ids without
+/// reactions are left out
+async fn summaries(session: &Session, ids: Vec<Uuid>) -> Result<HashMap<Uuid, Vec<ReactionCount>>, QueryError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = execute_cached(session, "SELECT content_id, emoji, count FROM reaction_counts WHERE content_id IN ?", (ids,)).await?;
+    let mut summaries: HashMap<Uuid, Vec<ReactionCount>> = HashMap::new();
+    for (id, emoji, count) in rows
+        .rows_typed_or_empty::<(Uuid, String, Option<CqlCounter>)>()
+        .filter_map(Result::ok)
+    {
+        let count = count.map_or(0, |CqlCounter(count)| count);
+        // Counters of reactions that were all taken back stay at 0
+        if count > 0 {
+            summaries.entry(id).or_default().push(ReactionCount { emoji, count });
+        }
+    }
+    for summary in summaries.values_mut() {
+        summary.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emoji.cmp(&b.emoji)));
+    }
+    Ok(summaries)
+}
+
+/// Fill in `reactions` of the posts; on failure they keep their current summary
+pub(crate) async fn fill_post_reactions(session: &Session, posts: &mut [Post]) {
+    let ids = posts.iter().map(|post| post.id).collect();
+    match summaries(session, ids).await {
+        Ok(mut summaries) => {
+            for post in posts {
+                post.reactions = summaries.remove(&post.id).unwrap_or_default();
+            }
+        }
+        Err(e) => warn!("Error fetching post reactions: {}", e),
+    }
+}
+
+/// Fill in `reactions` of the comments; on failure they keep their current summary
+pub(crate) async fn fill_comment_reactions(session: &Session, comments: &mut [Comment]) {
+    let ids = comments.iter().map(|comment| comment.id).collect();
+    match summaries(session, ids).await {
+        Ok(mut summaries) => {
+            for comment in comments {
+                comment.reactions = summaries.remove(&comment.id).unwrap_or_default();
+            }
+        }
+        Err(e) => warn!("Error fetching comment reactions: {}", e),
+    }
+}
+
+/// Drop the reactions and their counters of a purged post or comment
+pub(crate) async fn remove_reactions(session: &Session, content_id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM reactions WHERE content_id = ?", (content_id,)).await?;
+    execute_cached(session, "DELETE FROM reaction_counts WHERE content_id = ?", (content_id,)).await?;
+    Ok(())
+}
+
+/// Delete every reaction of the user, returning how many there were; the counters keep them,
+/// so summaries don't change
+pub(crate) async fn forget_user(session: &Session, user_id: Uuid) -> Result<usize, QueryError> {
+    let rows = execute_cached(session, "SELECT content_id, emoji FROM reactions WHERE user_id = ?", (user_id,)).await?;
+    let reactions: Vec<(Uuid, String)> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    for (content_id, emoji) in &reactions {
+        execute_cached(
+            session,
+            "DELETE FROM reactions WHERE content_id = ? AND emoji = ? AND user_id = ?",
+            (*content_id, emoji, user_id),
+        ).await?;
+    }
+    Ok(reactions.len())
+}
+
+async fn react(
+    session: &Session,
+    target: Target,
+    id: Uuid,
+    emoji: &str,
+    user: &AuthenticatedUser,
+    add: bool,
+    db_counter: &web::Data<DbCounter>,
+) -> HttpResponse {
+    let kind = target.kind();
+    if let Err(message) = validate_emoji(emoji) {
+        return ApiError::bad_request(message).error_response();
+    }
+
+    match execute_cached(session, target.select_deleted(), (id,)).await {
+        Ok(rows) => {
+            record_db_operation(db_counter, "select", target.table(), true);
+            match rows.maybe_first_row_typed::<(Option<bool>,)>() {
+                Ok(Some((is_deleted,))) if is_deleted != Some(true) => {}
+                _ => return ApiError::not_found(format!("{} with id {} not found", target.label(), id)).error_response(),
+            }
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", target.table(), false);
+            error!("Error fetching {} {}: {}", kind, id, e);
+            return ApiError::internal(format!("Error fetching {}: {}", kind, e)).error_response();
+        }
+    }
+
+    let result = if add {
+        execute_cached(
+            session,
+            "INSERT INTO reactions (content_id, emoji, user_id, content_type, reacted_at) VALUES (?, ?, ?, ?, ?) IF NOT EXISTS",
+            (id, emoji, user.user_id, kind, Utc::now().timestamp_millis()),
+        ).await
+    } else {
+        execute_cached(
+            session,
+            "DELETE FROM reactions WHERE content_id = ? AND emoji = ? AND user_id = ? IF EXISTS",
+            (id, emoji, user.user_id),
+        ).await
+    };
+    let changed = match result {
+        Ok(result) => applied(&result),
+        Err(e) => {
+            record_db_operation(db_counter, if add { "insert" } else { "delete" }, "reactions", false);
+            error!("Error changing reaction {} of user {} on {} {}: {}", emoji, user.user_id, kind, id, e);
+            return ApiError::internal(format!("Error changing reaction: {}", e)).error_response();
+        }
+    };
+    record_db_operation(db_counter, if add { "insert" } else { "delete" }, "reactions", true);
+
+    if changed {
+        let delta = CqlCounter(if add { 1 } else { -1 });
+        if let Err(e) = execute_cached(
+            session,
+            "UPDATE reaction_counts SET count = count + ? WHERE content_id = ? AND emoji = ?",
+            (delta, id, emoji),
+        ).await {
+            record_db_operation(db_counter, "update", "reaction_counts", false);
+            error!("Reaction {} of user {} on {} {} changed but its count was not: {}", emoji, user.user_id, kind, id, e);
+            return ApiError::internal(format!("Error updating reaction count: {}", e)).error_response();
+        }
+        record_db_operation(db_counter, "update", "reaction_counts", true);
+        if matches!(target, Target::Post) {
+            routes::invalidate_post_cache(id).await;
+        }
+    }
+
+    match summaries(session, vec![id]).await {
+        Ok(mut summaries) => HttpResponse::Ok().json(ReactionsResponse {
+            id,
+            reactions: summaries.remove(&id).unwrap_or_default(),
+        }),
+        Err(e) => {
+            record_db_operation(db_counter, "select", "reaction_counts", false);
+            error!("Error fetching reactions of {} {}: {}", kind, id, e);
+            ApiError::internal(format!("Error fetching reactions: {}", e)).error_response()
+        }
+    }
+}
+
+/// React to a post
+///
+/// Adds the caller's reaction with the emoji; reacting again with the same emoji changes
+/// nothing. Needs a bearer token.
+#[utoipa::path(
+    put,
+    path = "/posts/{post_id}/reactions/{emoji}",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("emoji" = String, Path, description = "Emoji, percent-encoded")
+    ),
+    responses(
+        (status = 200, description = "Reaction added; the post's reaction summary", body = ReactionsResponse),
+        (status = 400, description = "Not an emoji"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Post not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/posts/{post_id}/reactions/{emoji}")]
+pub async fn add_post_reaction(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<(Uuid, String)>,
+    user: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let (post_id, emoji) = path.into_inner();
+    react(&session, Target::Post, post_id, &emoji, &user, true, &db_counter).await
+}
+
+/// Take back a reaction to a post
+///
+/// Removes the caller's reaction with the emoji, if any. Needs a bearer token.
+#[utoipa::path(
+    delete,
+    path = "/posts/{post_id}/reactions/{emoji}",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("emoji" = String, Path, description = "Emoji, percent-encoded")
+    ),
+    responses(
+        (status = 200, description = "Reaction removed; the post's reaction summary", body = ReactionsResponse),
+        (status = 400, description = "Not an emoji"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Post not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/posts/{post_id}/reactions/{emoji}")]
+pub async fn remove_post_reaction(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<(Uuid, String)>,
+    user: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let (post_id, emoji) = path.into_inner();
+    react(&session, Target::Post, post_id, &emoji, &user, false, &db_counter).await
+}
+
+/// React to a comment
+///
+/// Same as reacting to a post. Needs a bearer token.
+#[utoipa::path(
+    put,
+    path = "/comments/{comment_id}/reactions/{emoji}",
+    params(
+        ("comment_id" = uuid::Uuid, Path, description = "Comment ID"),
+        ("emoji" = String, Path, description = "Emoji, percent-encoded")
+    ),
+    responses(
+        (status = 200, description = "Reaction added; the comment's reaction summary", body = ReactionsResponse),
+        (status = 400, description = "Not an emoji"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Comment not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/comments/{comment_id}/reactions/{emoji}")]
+pub async fn add_comment_reaction(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<(Uuid, String)>,
+    user: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let (comment_id, emoji) = path.into_inner();
+    react(&session, Target::Comment, comment_id, &emoji, &user, true, &db_counter).await
+}
+
+/// Take back a reaction to a comment
+///
+/// Removes the caller's reaction with the emoji, if any. Needs a bearer token.
+#[utoipa::path(
+    delete,
+    path = "/comments/{comment_id}/reactions/{emoji}",
+    params(
+        ("comment_id" = uuid::Uuid, Path, description = "Comment ID"),
+        ("emoji" = String, Path, description = "Emoji, percent-encoded")
+    ),
+    responses(
+        (status = 200, description = "Reaction removed; the comment's reaction summary", body = ReactionsResponse),
+        (status = 400, description = "Not an emoji"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Comment not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/comments/{comment_id}/reactions/{emoji}")]
+pub async fn remove_comment_reaction(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<(Uuid, String)>,
+    user: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let (comment_id, emoji) = path.into_inner();
+    react(&session, Target::Comment, comment_id, &emoji, &user, false, &db_counter).await
+}
@@ -1,21 +1,50 @@
-use actix_web::{get, post, web, HttpResponse, Responder, web::Query};
-use scylla::{Session, prepared_statement::PreparedStatement};
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder, web::Query, ResponseError};
+use actix_web::http::header::ContentType;
+use serde::Serialize;
+use scylla::{Session, prepared_statement::PreparedStatement, QueryResult};
+use scylla::serialize::row::SerializeRow;
+use scylla::transport::errors::{DbError, QueryError};
+use scylla::batch::BatchType;
+use scylla::frame::value::Counter as CqlCounter;
 use futures::stream::StreamExt;
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use uuid::Uuid;
 use std::time::{Instant, Duration};
 use std::sync::Arc;
-use prometheus::{IntCounterVec, Histogram, Gauge, Counter};
+use prometheus::{IntCounter, IntCounterVec, IntGaugeVec, Histogram, Gauge, Counter};
 use std::sync::OnceLock;
 use tracing::{info, warn, error, debug, instrument};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tokio::sync::RwLock;
 use serde_json;
+use crate::admin;
+use crate::auth;
+use crate::categories;
+use crate::config::{self, DuplicateNameStrategy};
+use crate::counts;
+use crate::db;
+use crate::db_client;
+use crate::errors::ApiError;
+use crate::hot;
+use crate::jwt_middleware::{AuthenticatedUser, Moderator};
+use crate::list_order::{self, SortKey};
+use crate::maintenance_middleware;
+use crate::normalize;
+use crate::post_revisions;
+use crate::process_metrics::update_memory_usage;
+use crate::query_fields::{self, SortOrder};
+use crate::reactions;
+use crate::search;
+use crate::title_search;
+use crate::validation;
+use crate::votes;
 use crate::models::{
-    Board, CreateBoardRequest, 
-    Post, CreatePostRequest, 
-    Comment, CreateCommentRequest,
-    HealthResponse, PaginationParams, PaginatedResponse, PaginationMeta
+    Board, CreateBoardRequest, UpdateBoardRequest, BoardStatsRequest, BoardStats, BoardListParams, GroupedBoardsResponse,
+    Post, PostRevision, PostWithBoard, IncludeParams, CreatePostRequest, UpdatePostRequest, LockPostRequest, PostChangesParams, PostChangesResponse,
+    PopularTagsParams, TagUsage, FullThreadParams, FullThread, 
+    Comment, CreateCommentRequest, UpdateCommentRequest, CommentCount,
+    HealthResponse, PaginationParams, PaginatedResponse, PaginationMeta,
+    CommentFilterParams, DateRangeParams, DeletedFilterParams, PurgeParams, TimestampFormatParams, timestamp_format, Role,
 };
 
 // Wrapper types for different metric counters to avoid injection conflicts
@@ -25,6 +54,9 @@ pub struct DbCounter(pub IntCounterVec);
 #[derive(Clone)]
 pub struct CacheCounter(pub IntCounterVec);
 
+#[derive(Clone)]
+pub struct IntegrityCounter(pub IntCounterVec);
+
 // Cache structure for performance optimization
 #[derive(Clone)]
 pub struct CacheEntry<T> {
@@ -54,12 +86,38 @@ impl<T> CacheEntry<T> {
 // In-memory cache for frequently accessed data
 pub type BoardsCache = Arc<RwLock<HashMap<String, CacheEntry<Vec<Board>>>>>;
 pub type PostsCache = Arc<RwLock<HashMap<String, CacheEntry<Vec<Post>>>>>;
+pub type BoardStatsCache = Arc<RwLock<HashMap<Uuid, CacheEntry<BoardStats>>>>;
+pub type CommentCountCache = Arc<RwLock<HashMap<Uuid, CacheEntry<i64>>>>;
+
+// Limits for the bulk board stats endpoint
+const MAX_STATS_BOARDS: usize = 100;
+const STATS_QUERY_CONCURRENCY: usize = 8;
+const BOARD_STATS_TTL: Duration = Duration::from_secs(30);
+const COMMENT_COUNT_TTL: Duration = Duration::from_secs(15);
+const MAX_CHANGES_LIMIT: u32 = 500;
+const MAX_BOARD_NAME_SUFFIX: u32 = 100;
+const MAX_CHANGES_WINDOW_DAYS: i64 = 30;
+const CHANGES_SETTLE_WINDOW_SECS: i64 = 5;
+
+/// Names of the `PreparedStatements` fields, as used by `CONSISTENCY_OVERRIDES`
+pub const PREPARED_STATEMENT_NAMES: &[&str] = &[
+    "get_boards",
+    "get_board_by_id",
+    "create_board",
+    "create_board_by_created",
+    "get_posts_by_board",
+    "get_post_by_id",
+    "create_post",
+    "get_comments_by_post",
+    "create_comment",
+];
 
 // Prepared statements for better performance
 pub struct PreparedStatements {
     pub get_boards: PreparedStatement,
     pub get_board_by_id: PreparedStatement,
     pub create_board: PreparedStatement,
+    pub create_board_by_created: PreparedStatement,
     pub get_posts_by_board: PreparedStatement,
     pub get_post_by_id: PreparedStatement,
     pub create_post: PreparedStatement,
@@ -67,9 +125,21 @@ pub struct PreparedStatements {
     pub create_comment: PreparedStatement,
 }
 
+// Statements prepared on first use, keyed by their CQL text
+type LazyStatements = Arc<RwLock<HashMap<&'static str, PreparedStatement>>>;
+
 static PREPARED_STATEMENTS: OnceLock<PreparedStatements> = OnceLock::new();
 static BOARDS_CACHE: OnceLock<BoardsCache> = OnceLock::new();
 static POSTS_CACHE: OnceLock<PostsCache> = OnceLock::new();
+static BOARD_STATS_CACHE: OnceLock<BoardStatsCache> = OnceLock::new();
+static COMMENT_COUNT_CACHE: OnceLock<CommentCountCache> = OnceLock::new();
+static LAZY_STATEMENTS: OnceLock<LazyStatements> = OnceLock::new();
+
+// Gauge of current entries per cache, set by main once metrics are registered
+static CACHE_ENTRIES_GAUGE: OnceLock<IntGaugeVec> = OnceLock::new();
+
+// Counter of statements re-prepared after an "unprepared" error, set by main
+static REPREPARES_COUNTER: OnceLock<IntCounter> = OnceLock::new();
 
 // Individual prepared statement references for easier access
 static CREATE_BOARD_STMT: OnceLock<PreparedStatement> = OnceLock::new();
@@ -77,7 +147,7 @@ static GET_BOARDS_STMT: OnceLock<PreparedStatement> = OnceLock::new();
 static GET_BOARD_STMT: OnceLock<PreparedStatement> = OnceLock::new();
 
 /// Helper function to record database operation metrics
-fn record_db_operation(
+pub(crate) fn record_db_operation(
     db_counter: &web::Data<DbCounter>,
     operation: &str,
     table: &str,
@@ -88,38 +158,374 @@ fn record_db_operation(
 }
 
 /// Helper function to record cache metrics
-fn record_cache_metric(cache_counter: &web::Data<CacheCounter>, cache_type: &str, result: &str) {
+pub(crate) fn record_cache_metric(cache_counter: &web::Data<CacheCounter>, cache_type: &str, result: &str) {
     cache_counter.0.with_label_values(&[cache_type, result]).inc();
 }
 
-/// Update memory usage metric
-fn update_memory_usage(memory_gauge: &web::Data<Gauge>) {
-    // Get memory usage from /proc/self/status
-    if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
-        for line in status.lines() {
-            if line.starts_with("VmRSS:") {
-                if let Some(kb_str) = line.split_whitespace().nth(1) {
-                    if let Ok(kb) = kb_str.parse::<f64>() {
-                        memory_gauge.set(kb * 1024.0); // Convert KB to bytes
-                        break;
-                    }
+/// Substitute the configured placeholder for a missing column and count the integrity error
+fn author_or_placeholder(
+    author: Option<String>,
+    integrity_counter: &web::Data<IntegrityCounter>,
+    table: &str,
+    id: Uuid,
+) -> String {
+    match author {
+        Some(author) => author,
+        None => {
+            warn!("Missing author for {} row {}, using placeholder", table, id);
+            integrity_counter.0.with_label_values(&[table, "author"]).inc();
+            config::get().missing_author_placeholder.clone()
+        }
+    }
+}
+
+/// Validate an author name before it reaches storage, logs and span attributes
+pub(crate) fn validate_author(author: &str) -> Result<(), String> {
+    let config = config::get();
+
+    if author.trim().is_empty() {
+        return Err("author must not be empty".to_string());
+    }
+    if author.chars().count() > config.author_max_length {
+        return Err(format!("author must be at most {} characters", config.author_max_length));
+    }
+    if author.chars().any(|c| c.is_control()) {
+        return Err("author must not contain control characters".to_string());
+    }
+    if let Some(allowed) = &config.author_allowed_punctuation {
+        if let Some(c) = author.chars().find(|c| !c.is_alphanumeric() && !allowed.contains(*c)) {
+            return Err(format!("author contains disallowed character {:?}", c));
+        }
+    }
+    Ok(())
+}
+
+/// Register the gauge tracking the number of entries per cache
+pub fn set_cache_entries_gauge(gauge: IntGaugeVec) {
+    let _ = CACHE_ENTRIES_GAUGE.set(gauge);
+}
+
+/// Register the counter of statement re-preparations
+pub fn set_reprepares_counter(counter: IntCounter) {
+    let _ = REPREPARES_COUNTER.set(counter);
+}
+
+/// Update the entries gauge after a cache was modified
+fn record_cache_size(cache_type: &str, entries: usize) {
+    if let Some(gauge) = CACHE_ENTRIES_GAUGE.get() {
+        gauge.with_label_values(&[cache_type]).set(entries as i64);
+    }
+}
+
+/// Drop the oldest `fraction` of entries from a cache map, returning how many were removed
+fn evict_oldest<K: Clone + Eq + std::hash::Hash, T>(map: &mut HashMap<K, CacheEntry<T>>, fraction: f64) -> usize {
+    let count = ((map.len() as f64) * fraction).ceil() as usize;
+    if count == 0 {
+        return 0;
+    }
+    let mut by_age: Vec<(Instant, K)> = map.iter().map(|(key, entry)| (entry.timestamp, key.clone())).collect();
+    by_age.sort_by_key(|(timestamp, _)| *timestamp);
+    for (_, key) in by_age.into_iter().take(count) {
+        map.remove(&key);
+    }
+    count
+}
+
+/// Evict the oldest `fraction` of every cache to relieve memory pressure
+///
+/// Returns the number of entries removed per cache type.
+pub(crate) async fn evict_oldest_cache_entries(fraction: f64) -> Vec<(&'static str, usize)> {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let mut evicted = Vec::new();
+    if let Some(cache) = BOARDS_CACHE.get() {
+        let mut cache = cache.write().await;
+        evicted.push(("boards", evict_oldest(&mut cache, fraction)));
+        record_cache_size("boards", cache.len());
+    }
+    if let Some(cache) = POSTS_CACHE.get() {
+        let mut cache = cache.write().await;
+        evicted.push(("posts", evict_oldest(&mut cache, fraction)));
+        record_cache_size("posts", cache.len());
+    }
+    if let Some(cache) = BOARD_STATS_CACHE.get() {
+        let mut cache = cache.write().await;
+        evicted.push(("board_stats", evict_oldest(&mut cache, fraction)));
+        record_cache_size("board_stats", cache.len());
+    }
+    if let Some(cache) = COMMENT_COUNT_CACHE.get() {
+        let mut cache = cache.write().await;
+        evicted.push(("comment_counts", evict_oldest(&mut cache, fraction)));
+        record_cache_size("comment_counts", cache.len());
+    }
+    evicted
+}
+
+/// Current number of entries in the boards, posts and board stats caches
+pub(crate) async fn cache_entry_counts() -> (usize, usize, usize) {
+    let boards = match BOARDS_CACHE.get() {
+        Some(cache) => cache.read().await.len(),
+        None => 0,
+    };
+    let posts = match POSTS_CACHE.get() {
+        Some(cache) => cache.read().await.len(),
+        None => 0,
+    };
+    let board_stats = match BOARD_STATS_CACHE.get() {
+        Some(cache) => cache.read().await.len(),
+        None => 0,
+    };
+    (boards, posts, board_stats)
+}
+
+/// Check whether a board name is on the reserved list
+fn is_reserved_board_name(name: &str) -> bool {
+    let key = normalize::name_key(name);
+    config::get().reserved_board_names.iter().any(|reserved| normalize::name_key(reserved) == key)
+}
+
+/// Check a board's `max_posts`, `default_page_size` and `default_sort`; `Err` is the 400 to answer with
+fn validate_board_settings(
+    max_posts: Option<i32>,
+    default_page_size: Option<i32>,
+    default_sort: Option<&str>,
+) -> Result<(), ApiError> {
+    if let Some(max_posts) = max_posts {
+        if max_posts < 1 {
+            warn!("Rejecting board with invalid max_posts: {}", max_posts);
+            return Err(ApiError::bad_request("max_posts must be at least 1"));
+        }
+    }
+
+    if let Some(page_size) = default_page_size {
+        if !(1..=100).contains(&page_size) {
+            warn!("Rejecting board with invalid default_page_size: {}", page_size);
+            return Err(ApiError::bad_request("default_page_size must be between 1 and 100"));
+        }
+    }
+
+    if let Some(sort) = default_sort {
+        if let Err(message) = query_fields::resolve_sort_setting(query_fields::POST_FIELDS, sort, ("created_at", SortOrder::Desc)) {
+            warn!("Rejecting board with invalid default_sort: {}", message);
+            return Err(ApiError::bad_request(format!("Invalid default_sort: {}", message)));
+        }
+    }
+    Ok(())
+}
+
+/// Apply the configured whitespace normalization to a post's title and content
+fn normalize_post_text(title: &str, content: &str) -> (String, String) {
+    let config = config::get();
+    let title = if config.normalize_whitespace {
+        normalize::collapse_whitespace(title)
+    } else {
+        title.to_string()
+    };
+    let content = if config.normalize_content_whitespace {
+        normalize::normalize_content(content)
+    } else {
+        content.to_string()
+    };
+    (title, content)
+}
+
+/// Normalize post tags and check them against `TAG_MAX_PER_POST` / `TAG_MAX_LENGTH`
+fn validate_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let config = config::get();
+    let tags = normalize::normalize_tags(tags);
+    if let Some(tag) = tags.iter().find(|tag| tag.chars().count() > config.tag_max_length) {
+        return Err(format!("tag '{}' exceeds the maximum length of {} characters", tag, config.tag_max_length));
+    }
+    if tags.len() > config.tag_max_per_post {
+        return Err(format!("a post can have at most {} tags", config.tag_max_per_post));
+    }
+    Ok(tags)
+}
+
+/// Serialized size of the UUID and BIGINT columns of a post row, plus per-value framing
+const POST_ROW_FIXED_BYTES: usize = 2 * 16 + 2 * 8 + 9 * 4;
+
+/// Write a post's rows into `posts_by_tag`, one per tag
+///
+/// Every row repeats the post's content, so rows are split over several batches when
+/// one would exceed `BATCH_MAX_BYTES`.
+async fn index_post_tags(session: &Session, post: &Post) -> Result<(), QueryError> {
+    let prepared = get_or_prepare(session, "INSERT INTO posts_by_tag (tag, created_at, id, board_id, title, content, author, updated_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)").await?;
+    let created_at_millis = post.created_at.timestamp_millis();
+    let updated_at_millis = post.updated_at.timestamp_millis();
+    let row_bytes = POST_ROW_FIXED_BYTES
+        + post.title.len()
+        + post.content.len()
+        + post.author.len()
+        + post.tags.iter().map(|tag| tag.len() + 4).sum::<usize>();
+    for tags in db::chunk_by_bytes(post.tags.iter().collect(), |tag| tag.len() + row_bytes) {
+        let mut batch = db::new_batch(BatchType::Logged);
+        let values: Vec<_> = tags
+            .into_iter()
+            .map(|tag| {
+                batch.append_statement(prepared.clone());
+                (tag, created_at_millis, post.id, post.board_id, &post.title, &post.content, &post.author, updated_at_millis, &post.tags)
+            })
+            .collect();
+        db_client::call(session.batch(&batch, values)).await?;
+    }
+    Ok(())
+}
+
+/// Adjust the usage counters of `tags` by `delta` (+1 when a post is created, -1 when one is removed)
+///
+/// Counter updates can't be mixed with regular writes in a batch, so they run in their own
+/// counter batch after the post is stored.
+async fn update_tag_counts(session: &Session, tags: &[String], delta: i64) -> Result<(), QueryError> {
+    let prepared = get_or_prepare(session, "UPDATE tags SET uses = uses + ? WHERE tag = ?").await?;
+    for tags in db::chunk_by_bytes(tags.iter().collect(), |tag| tag.len() + 16) {
+        let mut batch = db::new_batch(BatchType::Counter);
+        let values: Vec<_> = tags
+            .into_iter()
+            .map(|tag| {
+                batch.append_statement(prepared.clone());
+                (CqlCounter(delta), tag)
+            })
+            .collect();
+        db_client::call(session.batch(&batch, values)).await?;
+    }
+    Ok(())
+}
+
+/// Creation time of a new board, post or comment
+///
+/// A client-supplied `created_at` keeps original timestamps when importing data, so it is only
+/// accepted from admins and may be at most `CREATED_AT_MAX_FUTURE_SECS` ahead of server time.
+fn resolve_created_at(req: &HttpRequest, requested: Option<DateTime<Utc>>) -> Result<DateTime<Utc>, ApiError> {
+    let now = Utc::now();
+    let Some(created_at) = requested else {
+        return Ok(now);
+    };
+
+    admin::require_admin(req)?;
+    let max_future = config::get().created_at_max_future_secs;
+    if created_at.timestamp() > now.timestamp() + max_future {
+        warn!("Rejecting created_at {} more than {}s in the future", created_at, max_future);
+        return Err(ApiError::bad_request(format!(
+            "created_at may be at most {} seconds in the future",
+            max_future
+        )));
+    }
+    info!("Using client-supplied created_at {}", created_at);
+    Ok(created_at)
+}
+
+/// Finish a JSON response, writing timestamps as epoch milliseconds when `?ts=epoch` is set
+pub(crate) fn respond_json<T: Serialize>(builder: &mut HttpResponseBuilder, body: &T, ts: &TimestampFormatParams) -> HttpResponse {
+    if !ts.is_epoch() {
+        return builder.json(body);
+    }
+    match timestamp_format::to_json_epoch_millis(body) {
+        Ok(json) => builder.content_type(ContentType::json()).body(json),
+        Err(e) => {
+            error!("Error serializing response: {}", e);
+            ApiError::internal(format!("Error serializing response: {}", e)).error_response()
+        }
+    }
+}
+
+/// Whether the request carries `Cache-Control: no-cache` (or `no-store`) to skip the read cache
+fn cache_bypass_requested(req: &HttpRequest) -> bool {
+    req.headers()
+        .get_all(actix_web::http::header::CACHE_CONTROL)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|directive| matches!(directive.trim().to_lowercase().as_str(), "no-cache" | "no-store"))
+}
+
+/// Decide whether an empty listing is answered with 204 instead of 200 + `data: []`
+///
+/// The `X-Empty-List-Status` request header overrides the `EMPTY_LIST_STATUS` default
+pub(crate) fn empty_list_no_content(req: &HttpRequest) -> bool {
+    let status = req.headers()
+        .get("X-Empty-List-Status")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u16>().ok())
+        .unwrap_or(config::get().empty_list_status);
+    status == 204
+}
+
+
+/// Get a prepared statement for `cql`, preparing it on first use
+///
+/// Only the hot statements are prepared eagerly at startup; everything else goes through
+/// here. At most `PREPARED_STATEMENT_CACHE_MAX` statements are kept, past that they are
+/// prepared per call (and a warning is logged) rather than growing the cache.
+pub(crate) async fn get_or_prepare(session: &Session, cql: &'static str) -> Result<PreparedStatement, QueryError> {
+    let cache = match LAZY_STATEMENTS.get() {
+        Some(cache) => cache,
+        None => return prepare(session, None, cql).await,
+    };
+
+    if let Some(stmt) = cache.read().await.get(cql) {
+        return Ok(stmt.clone());
+    }
+
+    let stmt = prepare(session, None, cql).await?;
+    let mut statements = cache.write().await;
+    if statements.len() < config::get().prepared_statement_cache_max {
+        statements.entry(cql).or_insert_with(|| stmt.clone());
+        debug!("Prepared and cached statement ({} cached): {}", statements.len(), cql);
+    } else if !statements.contains_key(cql) {
+        warn!("Prepared statement cache is full, not caching: {}", cql);
+    }
+    Ok(stmt)
+}
+
+/// Execute a lazily prepared statement, re-preparing it once if the server no longer knows it
+///
+/// The driver re-prepares on "unprepared" errors by itself, on the connection that saw them;
+/// the error only gets here when that failed (typically while the cluster restarts or the
+/// schema changes). The cached statement is then replaced and the query retried once, and
+/// `prepared_statement_reprepares_total` is incremented.
+pub(crate) async fn execute_cached(
+    session: &Session,
+    cql: &'static str,
+    values: impl SerializeRow,
+) -> Result<QueryResult, QueryError> {
+    let prepared = get_or_prepare(session, cql).await?;
+    match db_client::call(session.execute(&prepared, &values)).await {
+        Err(QueryError::DbError(DbError::Unprepared { .. }, message)) => {
+            warn!("Statement was unprepared on the server ({}), re-preparing: {}", message, cql);
+            if let Some(counter) = REPREPARES_COUNTER.get() {
+                counter.inc();
+            }
+            let prepared = prepare(session, None, cql).await?;
+            if let Some(cache) = LAZY_STATEMENTS.get() {
+                let mut statements = cache.write().await;
+                if let Some(cached) = statements.get_mut(cql) {
+                    *cached = prepared.clone();
                 }
             }
+            db_client::call(session.execute(&prepared, &values)).await
         }
+        result => result,
     }
 }
 
+/// Prepare a statement with its configured consistency; `name` is its registry name, if any
+async fn prepare(session: &Session, name: Option<&str>, cql: &str) -> Result<PreparedStatement, QueryError> {
+    let mut prepared = session.prepare(cql).await?;
+    db::apply_consistency(&mut prepared, name);
+    Ok(prepared)
+}
+
 // Function to initialize prepared statements
 pub async fn init_prepared_statements(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
     let prepared = PreparedStatements {
-        get_boards: session.prepare("SELECT id, name, description, created_at FROM boards").await?,
-        get_board_by_id: session.prepare("SELECT id, name, description, created_at FROM boards WHERE id = ?").await?,
-        create_board: session.prepare("INSERT INTO boards (id, name, description, created_at) VALUES (?, ?, ?, ?)").await?,
-        get_posts_by_board: session.prepare("SELECT id, board_id, title, content, author, created_at, updated_at FROM posts WHERE board_id = ? ALLOW FILTERING").await?,
-        get_post_by_id: session.prepare("SELECT id, board_id, title, content, author, created_at, updated_at FROM posts WHERE id = ?  ").await?,
-        create_post: session.prepare("INSERT INTO posts (id, board_id, title, content, author, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)").await?,
-        get_comments_by_post: session.prepare("SELECT id, post_id, content, author, created_at FROM comments WHERE post_id = ? ALLOW FILTERING").await?,
-        create_comment: session.prepare("INSERT INTO comments (id, post_id, content, author, created_at) VALUES (?, ?, ?, ?, ?)").await?,
+        get_boards: prepare(session, Some("get_boards"), "SELECT id, name, description, created_at, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug FROM boards_by_created WHERE bucket = 0").await?,
+        get_board_by_id: prepare(session, Some("get_board_by_id"), "SELECT id, name, description, created_at, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug FROM boards WHERE id = ?").await?,
+        create_board: prepare(session, Some("create_board"), "INSERT INTO boards (id, name, description, created_at, max_posts, default_page_size, default_sort, category_id, slug) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)").await?,
+        create_board_by_created: prepare(session, Some("create_board_by_created"), "INSERT INTO boards_by_created (bucket, created_at, id, name, description, max_posts, default_page_size, default_sort, category_id, slug) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)").await?,
+        get_posts_by_board: prepare(session, Some("get_posts_by_board"), "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked FROM posts_by_board WHERE board_id = ?").await?,
+        get_post_by_id: prepare(session, Some("get_post_by_id"), "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked FROM posts WHERE id = ?  ").await?,
+        create_post: prepare(session, Some("create_post"), "INSERT INTO posts (id, board_id, title, content, author, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)").await?,
+        get_comments_by_post: prepare(session, Some("get_comments_by_post"), "SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments_by_post WHERE post_id = ?").await?,
+        create_comment: prepare(session, Some("create_comment"), "INSERT INTO comments (id, post_id, content, author, created_at) VALUES (?, ?, ?, ?, ?)").await?,
     };
     
     // Set individual statements for easier access
@@ -128,8 +534,11 @@ pub async fn init_prepared_statements(session: &Session) -> Result<(), Box<dyn s
     GET_BOARD_STMT.set(prepared.get_board_by_id.clone()).map_err(|_| "Failed to set get board statement")?;
     
     PREPARED_STATEMENTS.set(prepared).map_err(|_| "Failed to set prepared statements")?;
+    LAZY_STATEMENTS.set(Arc::new(RwLock::new(HashMap::new()))).map_err(|_| "Failed to set lazy statement cache")?;
     BOARDS_CACHE.set(Arc::new(RwLock::new(HashMap::new()))).map_err(|_| "Failed to set boards cache")?;
     POSTS_CACHE.set(Arc::new(RwLock::new(HashMap::new()))).map_err(|_| "Failed to set posts cache")?;
+    BOARD_STATS_CACHE.set(Arc::new(RwLock::new(HashMap::new()))).map_err(|_| "Failed to set board stats cache")?;
+    COMMENT_COUNT_CACHE.set(Arc::new(RwLock::new(HashMap::new()))).map_err(|_| "Failed to set comment count cache")?;
     
     info!("Prepared statements and caches initialized successfully");
     Ok(())
@@ -157,6 +566,7 @@ pub async fn health_check(
         status: "OK".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         timestamp: Utc::now(),
+        maintenance: maintenance_middleware::is_enabled(),
     };
     
     info!("Health check successful");
@@ -171,45 +581,147 @@ pub async fn health_check(
     post,
     path = "/boards",
     request_body = CreateBoardRequest,
+    params(
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
     responses(
         (status = 201, description = "Board created successfully", body = Board),
+        (status = 200, description = "A board with this name exists and DUPLICATE_NAME_STRATEGY=return_existing", body = Board),
+        (status = 400, description = "Reserved board name, invalid max_posts, default_page_size or default_sort, unknown category_id, created_at too far in the future, or unknown field in the body"),
+        (status = 401, description = "created_at supplied without a valid X-Admin-Token, or missing or invalid bearer token while JWT_SECRET is set"),
+        (status = 403, description = "created_at supplied while admin endpoints are disabled"),
+        (status = 409, description = "A board with this name exists and DUPLICATE_NAME_STRATEGY=reject (the default), or another board holds its slug"),
+        (status = 422, description = "Empty or too long name, or too long description; every failing field is listed in errors"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[post("/boards")]
 // #[instrument(name = "create_board", skip(session, db_counter), fields(board_name = %board_data.name))]
 pub async fn create_board(
+    req: HttpRequest,
     session: web::Data<Arc<Session>>,
     board_data: web::Json<CreateBoardRequest>,
     db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
 ) -> impl Responder {
     let start = Instant::now();
 
+    if let Err(e) = validation::create_board(&board_data) {
+        warn!("Rejecting invalid board: {}", e);
+        return e.error_response();
+    }
+
     info!("Creating new board: {}", board_data.name);
-        
+
+    if is_reserved_board_name(&board_data.name) {
+        warn!("Rejecting reserved board name: {}", board_data.name);
+        return ApiError::bad_request(format!("Board name '{}' is reserved", board_data.name)).error_response();
+    }
+
+    if let Err(e) = validate_board_settings(board_data.max_posts, board_data.default_page_size, board_data.default_sort.as_deref()) {
+        return e.error_response();
+    }
+
+    let created_at = match resolve_created_at(&req, board_data.created_at) {
+        Ok(created_at) => created_at,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Some(category_id) = board_data.category_id {
+        if let Err(e) = check_category(&session, category_id, &db_counter).await {
+            return e.error_response();
+        }
+    }
+
+    let mut name = board_data.name.clone();
+    let strategy = config::get().duplicate_name_strategy;
+    if strategy != DuplicateNameStrategy::Allow {
+        // Name lookups and the insert aren't atomic, so concurrent creations can still collide
+        let existing = match find_board_by_name(&session, &name).await {
+            Ok(existing) => existing,
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "boards", false);
+                error!("Error checking board name '{}': {}", name, e);
+                return ApiError::database("Error checking board name", &e).error_response();
+            }
+        };
+        record_db_operation(&db_counter, "select", "boards", true);
+
+        if let Some(existing) = existing {
+            match strategy {
+                DuplicateNameStrategy::Reject => {
+                    warn!("Rejecting duplicate board name: {}", name);
+                    return ApiError::conflict(format!("A board named '{}' already exists", name)).error_response();
+                }
+                DuplicateNameStrategy::ReturnExisting => {
+                    info!("Board '{}' already exists, returning {}", name, existing.id);
+                    return respond_json(&mut HttpResponse::Ok(), &existing, &ts);
+                }
+                DuplicateNameStrategy::Suffix => match next_free_board_name(&session, &name).await {
+                    Ok(Some(free)) => {
+                        info!("Board name '{}' is taken, using '{}'", name, free);
+                        name = free;
+                    }
+                    Ok(None) => {
+                        warn!("No free suffix for board name: {}", name);
+                        return ApiError::conflict(format!("A board named '{}' already exists", name)).error_response();
+                    }
+                    Err(e) => {
+                        error!("Error probing board names for '{}': {}", name, e);
+                        return ApiError::database("Error checking board name", &e).error_response();
+                    }
+                },
+                DuplicateNameStrategy::Allow => {}
+            }
+        }
+    }
+
+    // The slug is claimed before the board is written, so two boards can't end up with it
+    let board_id = config::get().id_scheme.new_id();
+    let slug = match claim_board_slug(&session, &name, board_id, &db_counter).await {
+        Ok(slug) => slug,
+        Err(e) => return e.error_response(),
+    };
+
     let board = Board {
-        id: Uuid::new_v4(),
-        name: board_data.name.clone(),
+        id: board_id,
+        name,
+        slug,
         description: board_data.description.clone(),
-        created_at: Utc::now(),
+        created_at,
+        max_posts: board_data.max_posts,
+        default_page_size: board_data.default_page_size,
+        default_sort: board_data.default_sort.clone(),
+        category_id: board_data.category_id,
+        is_deleted: false,
+        deleted_at: None,
     };
     
     debug!("Generated board ID: {}", board.id);
     
-    // Use prepared statement for better performance
-    let result = if let Some(stmt) = CREATE_BOARD_STMT.get() {
-        session.execute(
-            stmt,
-            (board.id, &board.name, &board.description, board.created_at.timestamp_millis()),
-        ).await
-    } else {
-        // Fallback to regular query if prepared statement not ready
-        warn!("Prepared statement not available, using regular query");
-        session.query(
-            "INSERT INTO boards (id, name, description, created_at) VALUES (?, ?, ?, ?)",
-            (board.id, &board.name, &board.description, board.created_at.timestamp_millis()),
-        ).await
-    };
+    // Write the board and its ordered-listing row atomically in a logged batch
+    let mut batch = db::new_batch(BatchType::Logged);
+    match (CREATE_BOARD_STMT.get(), PREPARED_STATEMENTS.get()) {
+        (Some(stmt), Some(prepared)) => {
+            batch.append_statement(stmt.clone());
+            batch.append_statement(prepared.create_board_by_created.clone());
+        }
+        _ => {
+            // Fallback to regular queries if prepared statements not ready
+            warn!("Prepared statement not available, using regular query");
+            batch.append_statement("INSERT INTO boards (id, name, description, created_at, max_posts, default_page_size, default_sort, category_id, slug) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)");
+            batch.append_statement("INSERT INTO boards_by_created (bucket, created_at, id, name, description, max_posts, default_page_size, default_sort, category_id, slug) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)");
+        }
+    }
+
+    let created_at_millis = board.created_at.timestamp_millis();
+    let result = db_client::call(session.batch(
+        &batch,
+        (
+            (board.id, &board.name, &board.description, created_at_millis, board.max_posts, board.default_page_size, &board.default_sort, board.category_id, &board.slug),
+            (db::BOARDS_BUCKET, created_at_millis, board.id, &board.name, &board.description, board.max_posts, board.default_page_size, &board.default_sort, board.category_id, &board.slug),
+        ),
+    )).await;
     
     let _duration = start.elapsed();
 
@@ -217,51 +729,98 @@ pub async fn create_board(
         Ok(_) => {
             info!("Board created successfully: {}", board.name);
             record_db_operation(&db_counter, "insert", "boards", true);
-            HttpResponse::Created().json(board)
+            respond_json(&mut HttpResponse::Created(), &board, &ts)
         },
         Err(e) => {
             error!("Error creating board: {}", e);
             record_db_operation(&db_counter, "insert", "boards", false);
-            HttpResponse::InternalServerError().body(format!("Error creating board: {}", e))
+            release_board_slug(&session, &board.slug, board.id).await;
+            ApiError::database("Error creating board", &e).error_response()
         },
     }
 }
 
 /// Get all boards with pagination
 ///
-/// Returns a paginated list of all discussion boards
+/// Returns a paginated list of all discussion boards, oldest first
 #[utoipa::path(
     get,
     path = "/boards",
     params(
         ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
-        ("limit" = Option<u32>, Query, description = "Number of items per page", example = 10)
+        ("limit" = Option<u32>, Query, description = "Number of items per page", example = 10),
+        ("sort" = Option<String>, Query, description = "Sort field within the page: created_at, name"),
+        ("order" = Option<String>, Query, description = "Sort direction: asc or desc"),
+        ("estimate_total" = Option<bool>, Query, description = "Fill meta.total with an approximate count from Scylla size estimates", example = false),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)"),
+        ("group_by" = Option<String>, Query, description = "`category` groups the page's boards by category (response is a GroupedBoardsResponse)")
     ),
     responses(
-        (status = 200, description = "Paginated list of boards retrieved successfully", body = PaginatedResponse<Board>),
+        (status = 200, description = "Paginated list of boards retrieved successfully (a GroupedBoardsResponse with group_by=category)", body = PaginatedResponse<Board>),
+        (status = 204, description = "No boards on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 400, description = "Unknown sort field, order or group_by"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/boards")]
 // #[instrument(name = "get_boards", skip(session, db_counter))]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_boards(
+    req: HttpRequest,
     session: web::Data<Arc<Session>>,
     pagination: Query<PaginationParams>,
+    deleted: Query<DeletedFilterParams>,
+    grouping: Query<BoardListParams>,
+    user: Option<AuthenticatedUser>,
     db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
 ) -> impl Responder {
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    let group_by_category = match grouping.group_by.as_deref() {
+        None => false,
+        Some("category") => true,
+        Some(other) => {
+            warn!("Rejecting boards listing: unknown group_by '{}'", other);
+            return ApiError::bad_request(format!("Unknown group_by '{}', expected: category", other)).error_response();
+        }
+    };
     let page = pagination.page.max(1); // Ensure page >= 1
-    let limit = pagination.limit.max(1).min(100); // Ensure 1 <= limit <= 100
+    let limit = pagination.limit().max(1).min(100); // Ensure 1 <= limit <= 100
+
+    // Sort fields are resolved through the allowlist; unknown names are rejected
+    let (sort_column, sort_order) = match query_fields::resolve_sort(
+        query_fields::BOARD_FIELDS,
+        pagination.sort.as_deref(),
+        pagination.order.as_deref(),
+        ("created_at", SortOrder::Asc),
+    ) {
+        Ok(sort) => sort,
+        Err(message) => {
+            warn!("Rejecting boards listing: {}", message);
+            return ApiError::bad_request(message).error_response();
+        }
+    };
 
     info!("Fetching boards (page: {}, limit: {})", page, limit);
     let start = Instant::now();
 
-    // Prepare statement with page size
-    let mut prepared = match session.prepare("SELECT id, name, description, created_at FROM boards").await {
-        Ok(stmt) => stmt,
-        Err(e) => {
-            record_db_operation(&db_counter, "select", "boards", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
-        }
+    // Read from the creation-ordered table so pages don't overlap or skip boards
+    let mut prepared = match GET_BOARDS_STMT.get() {
+        Some(stmt) => stmt.clone(),
+        None => match get_or_prepare(&session, "SELECT id, name, description, created_at, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug FROM boards_by_created WHERE bucket = 0").await {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "boards", false);
+                return ApiError::database("Error preparing query", &e).error_response();
+            }
+        },
     };
     
     // Set page size for efficient pagination
@@ -270,62 +829,32 @@ pub async fn get_boards(
     let _db_start = Instant::now();
     
     // Use execute_iter for paginated results
-    let row_iterator = match session.execute_iter(prepared, &[]).await {
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, &[])).await {
         Ok(iterator) => iterator,
         Err(e) => {
             record_db_operation(&db_counter, "select", "boards", false);
-            return HttpResponse::InternalServerError().body(format!("Error executing query: {}", e));
+            return ApiError::database("Error executing query", &e).error_response();
         }
     };
 
-    let mut boards = Vec::new();
-    let mut total_fetched = 0u32;
-
-    // Skip to the requested page
-    let skip_count = (page - 1) * limit;
-    let mut skipped = 0u32;
-
-    // Convert iterator to stream and iterate through pages
-    let mut rows_stream = row_iterator.into_typed::<(uuid::Uuid, String, String, i64)>();
-    
-    while let Some(next_row_res) = rows_stream.next().await {
-        match next_row_res {
-            Ok((id, name, description, created_at_millis)) => {
-                // Skip rows until we reach the desired page
-                if skipped < skip_count {
-                    skipped += 1;
-                    continue;
-                }
-                
-                // Stop if we have enough items for this page
-                if total_fetched >= limit {
-                    break;
-                }
-
-                // Convert timestamp
-                let created_at = match Utc.timestamp_millis_opt(created_at_millis).single() {
-                    Some(dt) => dt,
-                    None => {
-                        warn!("Invalid timestamp for board {}: {}", id, created_at_millis);
-                        continue;
-                    }
-                };
-
-                boards.push(Board {
-                    id,
-                    name,
-                    description,
-                    created_at,
-                });
-
-                total_fetched += 1;
-            },
-            Err(e) => {
-                error!("Error reading row: {}", e);
-                record_db_operation(&db_counter, "select", "boards", false);
-                return HttpResponse::InternalServerError().body(format!("Error reading row: {}", e));
-            }
+    let mut window = PageWindow::new(page, limit);
+    let mut rows_stream = row_iterator.into_typed::<BoardListRow>();
+    let mut boards = match read_board_page(&read, &mut rows_stream, &mut window, include_deleted).await {
+        Ok(boards) => boards,
+        Err(e) => {
+            error!("Error reading row: {}", e);
+            record_db_operation(&db_counter, "select", "boards", false);
+            return ApiError::database("Error reading row", &e).error_response();
         }
+    };
+
+    // Boards arrive oldest first from boards_by_created; only re-sort when asked to
+    if pagination.sort.is_some() || pagination.order.is_some() {
+        boards.sort_by(|a, b| sort_order.apply(match sort_column {
+            "name" => a.name.cmp(&b.name),
+            _ => a.created_at.cmp(&b.created_at),
+        }));
     }
 
     let duration = start.elapsed();
@@ -333,13 +862,45 @@ pub async fn get_boards(
 
     // For pagination metadata, we'll estimate total pages
     // In a production system, you might want to maintain a separate count
+    let total_fetched = window.taken;
     let has_more = total_fetched == limit; // If we got a full page, there might be more
+
+    // Optionally fill in an approximate total from size estimates instead of a full COUNT(*)
+    let estimated_total = if pagination.estimate_total {
+        match db::estimate_table_rows(&session, "boards").await {
+            Ok(estimate) => {
+                // Size estimates lag behind recent writes, so never report fewer rows than we've seen
+                let seen = window.skip + total_fetched + has_more as u32;
+                Some((estimate.min(u32::MAX as u64) as u32).max(seen))
+            }
+            Err(e) => {
+                warn!("Failed to estimate boards total: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
     
-    let meta = PaginationMeta {
-        page,
-        limit,
-        total: None, // We don't have exact total count without additional query
-        total_pages: if has_more { None } else { Some(page) }, // If no more data, current page is last
+    let meta = match estimated_total {
+        Some(total) => PaginationMeta {
+            page,
+            limit,
+            total: Some(total),
+            total_pages: Some(total.div_ceil(limit).max(1)),
+            total_is_estimate: true,
+            sort: None,
+            order: None,
+        },
+        None => PaginationMeta {
+            page,
+            limit,
+            total: None, // We don't have exact total count without additional query
+            total_pages: if has_more { None } else { Some(page) }, // If no more data, current page is last
+            total_is_estimate: false,
+            sort: None,
+            order: None,
+        },
     };
 
     let response = PaginatedResponse {
@@ -347,11 +908,43 @@ pub async fn get_boards(
         data: boards,
     };
 
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        info!("No boards found (page: {}, limit: {}), returning 204", page, limit);
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
+    }
+
     info!("Successfully fetched {} boards (page: {}, limit: {}, duration: {}ms)", response.data.len(), page, limit, duration.as_millis());
-    HttpResponse::Ok()
-        .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
-        .append_header(("X-Has-More", has_more.to_string()))
-        .json(response)
+    if group_by_category {
+        let categories = match categories::fetch_categories(&session).await {
+            Ok(categories) => categories,
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "categories", false);
+                error!("Error fetching categories: {}", e);
+                return ApiError::database("Error fetching categories", &e).error_response();
+            }
+        };
+        record_db_operation(&db_counter, "select", "categories", true);
+        return respond_json(
+            HttpResponse::Ok()
+                .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+                .append_header(("X-Has-More", has_more.to_string())),
+            &GroupedBoardsResponse {
+                meta: response.meta,
+                groups: categories::group_boards(response.data, categories),
+            },
+            &ts,
+        );
+    }
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
 }
 
 /// Get board by ID
@@ -361,35 +954,57 @@ pub async fn get_boards(
     get,
     path = "/boards/{board_id}",
     params(
-        ("board_id" = uuid::Uuid, Path, description = "Board ID")
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("Cache-Control" = Option<String>, Header, description = "`no-cache` skips the read cache (the fresh result is still cached)"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
     ),
     responses(
         (status = 200, description = "Board retrieved successfully", body = Board),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
         (status = 404, description = "Board not found"),
+        (status = 410, description = "Board was deleted (see include_deleted)"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/boards/{board_id}")]
 // #[instrument(name = "get_board", skip(session, db_counter, cache_counter), fields(board_id = %path))]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_board(
+    req: HttpRequest,
     session: web::Data<Arc<Session>>,
     path: web::Path<Uuid>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
     db_counter: web::Data<DbCounter>,
     cache_counter: web::Data<CacheCounter>,
+    ts: Query<TimestampFormatParams>,
 ) -> impl Responder {
     let start = Instant::now();
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
     
     let board_id = path.into_inner();
     info!("Fetching board with ID: {}", board_id);
         
-    // Check cache first
-    let board_cache_key = board_id.to_string();
-    if let Some(boards_cache) = BOARDS_CACHE.get() {
-        if let Some(cached_board) = boards_cache.read().await.get(&board_cache_key) {
+    // Check cache first, unless the client asked for fresh data
+    if cache_bypass_requested(&req) {
+        info!("Cache bypass requested for board ID: {}", board_id);
+        record_cache_metric(&cache_counter, "boards", "bypass_requested");
+    } else if let Some(boards_cache) = BOARDS_CACHE.get() {
+        if let Some(cached_board) = boards_cache.read().await.get(&board_cache_key(board_id)) {
             if !cached_board.is_expired() {
                 info!("Cache hit for board ID: {}", board_id);
                 record_cache_metric(&cache_counter, "boards", "hit");
-                return HttpResponse::Ok().json(cached_board.get_data());
+                if let Some(board) = cached_board.get_data().first() {
+                    if board.is_deleted && !include_deleted {
+                        return ApiError::gone(format!("Board with id {} was deleted", board_id)).error_response();
+                    }
+                    return respond_json(&mut HttpResponse::Ok(), board, &ts);
+                }
             } else {
                 info!("Cache expired for board ID: {}, fetching fresh data", board_id);
                 record_cache_metric(&cache_counter, "boards", "expired");
@@ -403,211 +1018,3711 @@ pub async fn get_board(
         record_cache_metric(&cache_counter, "boards", "miss");
     }
     
-    // Use prepared statement for better performance
-    let result = if let Some(stmt) = GET_BOARD_STMT.get() {
-        session.execute(stmt, (board_id,)).await
-    } else {
-        // Fallback to regular query if prepared statement not ready
-        warn!("Prepared statement not available, using regular query");
-        session.query("SELECT id, name, description, created_at FROM boards WHERE id = ?", (board_id,)).await
-    };
-    
-    let _db_duration = start.elapsed();
-    
-    match result {
-        Ok(rows) => {
-            if let Some(row) = rows.rows.as_ref().and_then(|r| r.first()) {
-                if let (Some(id), Some(name), Some(description)) = (
-                    row.columns[0].as_ref().and_then(|c| c.as_uuid()),
-                    row.columns[1].as_ref().and_then(|c| c.as_text()),
-                    row.columns[2].as_ref().and_then(|c| c.as_text()),
-                ) {
-                    // Handle bigint timestamps
-                    let created_at = if let Some(millis) = row.columns[3].as_ref().and_then(|c| c.as_bigint()) {
-                        Utc.timestamp_millis_opt(millis).single().unwrap_or_else(|| Utc::now())
-                    } else {
-                        Utc::now()
-                    };
-                    
-                    let board = Board {
-                        id,
-                        name: name.to_string(),
-                        description: description.to_string(),
-                        created_at,
-                    };
-                    
-                    // Update cache
-                    let cache_entry = CacheEntry::new(vec![board.clone()], Duration::from_secs(300)); // 5 minutes TTL
-                    if let Some(boards_cache) = BOARDS_CACHE.get() {
-                        boards_cache.write().await.insert(board_cache_key, cache_entry);
-                    }
-
-                    record_db_operation(&db_counter, "select", "boards", true);
-                    info!("Board found: {}", board.name);
-                    return HttpResponse::Ok().json(board);
-                }
-            }
-            
+    match fetch_board_from_db(&session, board_id).await {
+        Ok(Some(board)) if board.is_deleted && !include_deleted => {
+            cache_board(&board).await;
+            record_db_operation(&db_counter, "select", "boards", true);
+            info!("Board {} is deleted", board_id);
+            ApiError::gone(format!("Board with id {} was deleted", board_id)).error_response()
+        }
+        Ok(Some(board)) => {
+            cache_board(&board).await;
+            record_db_operation(&db_counter, "select", "boards", true);
+            info!("Board found: {} ({}ms)", board.name, start.elapsed().as_millis());
+            respond_json(&mut HttpResponse::Ok(), &board, &ts)
+        }
+        Ok(None) => {
             record_db_operation(&db_counter, "select", "boards", true);
             warn!("Board with id {} not found", board_id);
-            HttpResponse::NotFound().body(format!("Board with id {} not found", board_id))
+            ApiError::not_found(format!("Board with id {} not found", board_id)).error_response()
         }
         Err(e) => {
             record_db_operation(&db_counter, "select", "boards", false);
             error!("Error fetching board: {}", e);
-            HttpResponse::InternalServerError().body(format!("Error fetching board: {}", e))
+            ApiError::database("Error fetching board", &e).error_response()
         },
     }
 }
 
-// Post related endpoints
-/// Create a new post
+/// Get board by slug
 ///
-/// Creates a new post on a specific board
+/// Returns the board whose `slug` (derived from its name) matches, e.g. `/boards/by-slug/rust-go`
 #[utoipa::path(
-    post,
+    get,
+    path = "/boards/by-slug/{slug}",
+    params(
+        ("slug" = String, Path, description = "Board slug"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "Board retrieved successfully", body = Board),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 404, description = "No board has this slug"),
+        (status = 410, description = "Board was deleted (see include_deleted)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/boards/by-slug/{slug}")]
+pub async fn get_board_by_slug(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<String>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    let slug = path.into_inner().to_lowercase();
+
+    let board_id = match execute_cached(&session, "SELECT board_id FROM boards_by_slug WHERE slug = ?", (&slug,)).await {
+        Ok(rows) => {
+            record_db_operation(&db_counter, "select", "boards_by_slug", true);
+            match rows.maybe_first_row_typed::<(Option<Uuid>,)>() {
+                Ok(Some((Some(board_id),))) => board_id,
+                _ => return ApiError::not_found(format!("Board with slug '{}' not found", slug)).error_response(),
+            }
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "boards_by_slug", false);
+            error!("Error looking up board slug '{}': {}", slug, e);
+            return ApiError::database("Error fetching board", &e).error_response();
+        }
+    };
+
+    match fetch_board_from_db(&session, board_id).await {
+        // A slug claimed by a creation that failed midway has no board behind it
+        Ok(Some(board)) if !board.is_deleted || include_deleted => {
+            record_db_operation(&db_counter, "select", "boards", true);
+            cache_board(&board).await;
+            respond_json(&mut HttpResponse::Ok(), &board, &ts)
+        }
+        Ok(Some(_)) => {
+            record_db_operation(&db_counter, "select", "boards", true);
+            ApiError::gone(format!("Board with slug '{}' was deleted", slug)).error_response()
+        }
+        Ok(None) => {
+            record_db_operation(&db_counter, "select", "boards", true);
+            ApiError::not_found(format!("Board with slug '{}' not found", slug)).error_response()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "boards", false);
+            error!("Error fetching board {}: {}", board_id, e);
+            ApiError::database("Error fetching board", &e).error_response()
+        }
+    }
+}
+
+/// Offset pagination over rows read in listing order: the rows of earlier pages are skipped,
+/// then up to `limit` rows are taken
+struct PageWindow {
+    skip: u32,
+    limit: u32,
+    skipped: u32,
+    /// Rows added to the page, counted by the caller
+    taken: u32,
+}
+
+/// What to do with the next listed row of a `PageWindow`
+#[derive(Debug, PartialEq)]
+enum PageStep {
+    /// The row belongs to an earlier page
+    Skip,
+    /// The row belongs to this page
+    Take,
+    /// The page is complete
+    Full,
+}
+
+impl PageWindow {
+    fn new(page: u32, limit: u32) -> Self {
+        Self { skip: (page.max(1) - 1) * limit, limit, skipped: 0, taken: 0 }
+    }
+
+    fn next_row(&mut self) -> PageStep {
+        if self.skipped < self.skip {
+            self.skipped += 1;
+            PageStep::Skip
+        } else if self.taken >= self.limit {
+            PageStep::Full
+        } else {
+            PageStep::Take
+        }
+    }
+}
+
+/// A `boards_by_created` row as `get_boards` selects it
+type BoardListRow = (Uuid, String, String, i64, Option<i32>, Option<i32>, Option<String>, Option<bool>, Option<i64>, Option<Uuid>, Option<String>);
+
+/// Read the boards of `window`'s page from `rows`, listed in `boards_by_created` order;
+/// deleted boards are skipped and don't count towards pages unless `include_deleted`
+async fn read_board_page<S, E>(
+    read: &db_client::PagedRead,
+    rows: &mut S,
+    window: &mut PageWindow,
+    include_deleted: bool,
+) -> Result<Vec<Board>, QueryError>
+where
+    S: futures::Stream<Item = Result<BoardListRow, E>> + Unpin,
+    E: Into<db_client::RowError>,
+{
+    let mut boards = Vec::new();
+    while let Some(row) = read.next(rows).await {
+        let (id, name, description, created_at_millis, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug) = row?;
+        let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+        if is_deleted && !include_deleted {
+            continue;
+        }
+
+        match window.next_row() {
+            PageStep::Skip => continue,
+            PageStep::Full => break,
+            PageStep::Take => {}
+        }
+
+        let created_at = match Utc.timestamp_millis_opt(created_at_millis).single() {
+            Some(dt) => dt,
+            None => {
+                warn!("Invalid timestamp for board {}: {}", id, created_at_millis);
+                continue;
+            }
+        };
+
+        boards.push(Board {
+            id,
+            slug: slug.unwrap_or_default(),
+            name,
+            description,
+            created_at,
+            max_posts,
+            default_page_size,
+            default_sort,
+            category_id,
+            is_deleted,
+            deleted_at,
+        });
+        window.taken += 1;
+    }
+    Ok(boards)
+}
+
+/// Soft-delete state of a row from its `is_deleted` and `deleted_at` columns
+pub(crate) fn deletion_state(is_deleted: Option<bool>, deleted_at: Option<i64>) -> (bool, Option<DateTime<Utc>>) {
+    (
+        is_deleted.unwrap_or(false),
+        deleted_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+    )
+}
+
+/// Whether a read lists soft-deleted content: `?include_deleted=true` from a moderator (bearer
+/// token) or with the admin token
+pub(crate) fn include_deleted(
+    req: &HttpRequest,
+    user: Option<&AuthenticatedUser>,
+    params: &DeletedFilterParams,
+) -> Result<bool, ApiError> {
+    if !params.include_deleted {
+        return Ok(false);
+    }
+    require_role(req, user, Role::Moderator, None, "list deleted content", "Only moderators may list deleted content").map(|()| true)
+}
+
+/// Creation time range from `since` (inclusive) and `until` (exclusive) in epoch milliseconds,
+/// or `None` when neither is given
+fn created_range(params: &DateRangeParams) -> Result<Option<std::ops::Range<i64>>, ApiError> {
+    let parse = |name: &str, value: &Option<String>| match value {
+        Some(value) => chrono::DateTime::parse_from_rfc3339(value)
+            .map(|timestamp| Some(timestamp.timestamp_millis()))
+            .map_err(|e| ApiError::bad_request(format!("Invalid {} timestamp '{}': {}", name, value, e))),
+        None => Ok(None),
+    };
+    let since = parse("since", &params.since)?;
+    let until = parse("until", &params.until)?;
+    if let (Some(since), Some(until)) = (since, until) {
+        if since > until {
+            return Err(ApiError::bad_request("since must not be after until"));
+        }
+    }
+    if since.is_none() && until.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(since.unwrap_or(i64::MIN)..until.unwrap_or(i64::MAX)))
+}
+
+/// Allow a moderation action by a moderator (bearer token) or with the admin token: changes to
+/// boards, which have no owner, and restoring or purging deleted content
+pub(crate) fn authorize_moderation(
+    req: &HttpRequest,
+    user: Option<&AuthenticatedUser>,
+    kind: &str,
+    id: Uuid,
+    action: &str,
+) -> Result<(), ApiError> {
+    require_role(
+        req,
+        user,
+        Role::Moderator,
+        None,
+        &format!("{} {} {}", action, kind, id),
+        &format!("Only moderators may {} {}s", action, kind),
+    )
+}
+
+/// The role check behind every moderation, owner and `include_deleted` decision
+///
+/// A bearer token passes with at least the `required` role or when it belongs to `owner`,
+/// and any other token gets 403 with the `forbidden` message (`attempt` is only logged).
+/// Without a token, the `X-Admin-Token` header stands in for an admin; with neither the
+/// answer is 401.
+fn require_role(
+    req: &HttpRequest,
+    user: Option<&AuthenticatedUser>,
+    required: Role,
+    owner: Option<Uuid>,
+    attempt: &str,
+    forbidden: &str,
+) -> Result<(), ApiError> {
+    match user {
+        Some(user) if user.role >= required || owner == Some(user.user_id) => Ok(()),
+        Some(user) => {
+            warn!("User {} ({}) may not {}", user.user_id, user.role.as_str(), attempt);
+            Err(ApiError::forbidden(forbidden))
+        }
+        None if req.headers().contains_key("X-Admin-Token") => admin::require_admin(req),
+        None => Err(ApiError::unauthorized("Authentication required")),
+    }
+}
+
+/// Update a board
+///
+/// Changes a board's name, description or listing settings; omitted fields keep their value.
+/// Boards have no owner, so this takes a moderator's bearer token or the admin token.
+#[utoipa::path(
+    patch,
+    path = "/boards/{board_id}",
+    request_body = UpdateBoardRequest,
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Board updated", body = Board),
+        (status = 400, description = "Reserved board name, invalid max_posts, default_page_size or default_sort, unknown category_id, or unknown field in the body"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Board not found"),
+        (status = 409, description = "Another board has this name and DUPLICATE_NAME_STRATEGY is not allow, or another board holds its slug"),
+        (status = 422, description = "Empty or too long name, or too long description; every failing field is listed in errors"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[patch("/boards/{board_id}")]
+pub async fn update_board(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    update: web::Json<UpdateBoardRequest>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let board_id = path.into_inner();
+    let update = update.into_inner();
+
+    if let Err(e) = authorize_moderation(&req, user.as_ref(), "board", board_id, "edit") {
+        return e.error_response();
+    }
+
+    if let Err(e) = validation::update_board(&update) {
+        warn!("Rejecting invalid edit of board {}: {}", board_id, e);
+        return e.error_response();
+    }
+
+    if let Err(e) = validate_board_settings(update.max_posts, update.default_page_size, update.default_sort.as_deref()) {
+        return e.error_response();
+    }
+
+    let mut board = match fetch_board_from_db(&session, board_id).await {
+        Ok(Some(board)) => board,
+        Ok(None) => {
+            record_db_operation(&db_counter, "select", "boards", true);
+            return ApiError::not_found(format!("Board with id {} not found", board_id)).error_response();
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "boards", false);
+            error!("Error fetching board {}: {}", board_id, e);
+            return ApiError::database("Error fetching board", &e).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "boards", true);
+
+    if let Some(name) = update.name.filter(|name| *name != board.name) {
+        if is_reserved_board_name(&name) {
+            warn!("Rejecting reserved board name: {}", name);
+            return ApiError::bad_request(format!("Board name '{}' is reserved", name)).error_response();
+        }
+        if config::get().duplicate_name_strategy != DuplicateNameStrategy::Allow {
+            // Same race as on creation: the lookup and the write aren't atomic
+            match find_board_by_name(&session, &name).await {
+                Ok(Some(existing)) if existing.id != board_id => {
+                    warn!("Rejecting rename of board {} to taken name: {}", board_id, name);
+                    return ApiError::conflict(format!("A board named '{}' already exists", name)).error_response();
+                }
+                Ok(_) => record_db_operation(&db_counter, "select", "boards", true),
+                Err(e) => {
+                    record_db_operation(&db_counter, "select", "boards", false);
+                    error!("Error checking board name '{}': {}", name, e);
+                    return ApiError::database("Error checking board name", &e).error_response();
+                }
+            }
+        }
+        board.name = name;
+    }
+    let previous_slug = board.slug.clone();
+    if board_slug(&board.name, board_id) != board.slug {
+        match claim_board_slug(&session, &board.name, board_id, &db_counter).await {
+            Ok(slug) => board.slug = slug,
+            Err(e) => return e.error_response(),
+        }
+    }
+    if let Some(description) = update.description {
+        board.description = description;
+    }
+    if update.max_posts.is_some() {
+        board.max_posts = update.max_posts;
+    }
+    if update.default_page_size.is_some() {
+        board.default_page_size = update.default_page_size;
+    }
+    if update.default_sort.is_some() {
+        board.default_sort = update.default_sort;
+    }
+    if let Some(category_id) = update.category_id.filter(|id| board.category_id != Some(*id)) {
+        if let Err(e) = check_category(&session, category_id, &db_counter).await {
+            return e.error_response();
+        }
+        board.category_id = Some(category_id);
+    }
+
+    // The board and its ordered-listing row change together, as on creation
+    let mut batch = db::new_batch(BatchType::Logged);
+    for cql in [
+        "UPDATE boards SET name = ?, description = ?, max_posts = ?, default_page_size = ?, default_sort = ?, category_id = ?, slug = ? WHERE id = ?",
+        "UPDATE boards_by_created SET name = ?, description = ?, max_posts = ?, default_page_size = ?, default_sort = ?, category_id = ?, slug = ? WHERE bucket = ? AND created_at = ? AND id = ?",
+    ] {
+        match get_or_prepare(&session, cql).await {
+            Ok(prepared) => batch.append_statement(prepared),
+            Err(e) => {
+                record_db_operation(&db_counter, "update", "boards", false);
+                return ApiError::database("Error preparing query", &e).error_response();
+            }
+        }
+    }
+    let result = db_client::call(session.batch(
+        &batch,
+        (
+            (&board.name, &board.description, board.max_posts, board.default_page_size, &board.default_sort, board.category_id, &board.slug, board_id),
+            (&board.name, &board.description, board.max_posts, board.default_page_size, &board.default_sort, board.category_id, &board.slug, db::BOARDS_BUCKET, board.created_at.timestamp_millis(), board_id),
+        ),
+    )).await;
+
+    match result {
+        Ok(_) => {
+            record_db_operation(&db_counter, "update", "boards", true);
+            if board.slug != previous_slug {
+                release_board_slug(&session, &previous_slug, board_id).await;
+            }
+            invalidate_board_cache(board_id).await;
+            info!("Board {} updated: {}", board_id, board.name);
+            respond_json(&mut HttpResponse::Ok(), &board, &ts)
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "update", "boards", false);
+            if board.slug != previous_slug {
+                release_board_slug(&session, &board.slug, board_id).await;
+            }
+            error!("Error updating board {}: {}", board_id, e);
+            ApiError::database("Error updating board", &e).error_response()
+        }
+    }
+}
+
+/// Slug of a board name; names without letters or digits fall back to the board's id
+pub(crate) fn board_slug(name: &str, board_id: Uuid) -> String {
+    let slug = normalize::slugify(name);
+    if slug.is_empty() {
+        board_id.to_string()
+    } else {
+        slug
+    }
+}
+
+/// Reserve the slug of `name` for the board in `boards_by_slug`, returning it; a slug another
+/// board holds is 409, whatever `DUPLICATE_NAME_STRATEGY` says
+async fn claim_board_slug(session: &Session, name: &str, board_id: Uuid, db_counter: &web::Data<DbCounter>) -> Result<String, ApiError> {
+    let slug = board_slug(name, board_id);
+    let result = execute_cached(
+        session,
+        "INSERT INTO boards_by_slug (slug, board_id) VALUES (?, ?) IF NOT EXISTS",
+        (&slug, board_id),
+    ).await;
+    match result {
+        Ok(result) => {
+            record_db_operation(db_counter, "insert", "boards_by_slug", true);
+            slug_claim(&result, slug, board_id)
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "insert", "boards_by_slug", false);
+            error!("Error claiming slug '{}' for board {}: {}", slug, board_id, e);
+            Err(ApiError::database("Error claiming board slug", &e))
+        }
+    }
+}
+
+/// Outcome of the `boards_by_slug` insert: the slug is the board's if the insert applied or the
+/// row already names the board (a retry)
+fn slug_claim(result: &QueryResult, slug: String, board_id: Uuid) -> Result<String, ApiError> {
+    // When not applied, the existing row follows `[applied]`: slug, then board_id
+    let row = result.rows.as_ref().and_then(|rows| rows.first());
+    let applied = row
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true);
+    let holder = row.and_then(|row| row.columns.get(2)).and_then(|c| c.as_ref()).and_then(|c| c.as_uuid());
+    if applied || holder == Some(board_id) {
+        return Ok(slug);
+    }
+    warn!("Rejecting board {}: slug '{}' is held by board {:?}", board_id, slug, holder);
+    Err(ApiError::conflict(format!("A board with the slug '{}' already exists", slug)))
+}
+
+/// Give up a board's claim on a slug; failures are logged and leave the slug reserved
+pub(crate) async fn release_board_slug(session: &Session, slug: &str, board_id: Uuid) {
+    if slug.is_empty() {
+        return;
+    }
+    if let Err(e) = execute_cached(session, "DELETE FROM boards_by_slug WHERE slug = ? IF board_id = ?", (slug, board_id)).await {
+        warn!("Error releasing slug '{}' of board {}: {}", slug, board_id, e);
+    }
+}
+
+/// Reject a `category_id` that names no category
+async fn check_category(session: &Session, category_id: Uuid, db_counter: &web::Data<DbCounter>) -> Result<(), ApiError> {
+    match categories::category_exists(session, category_id).await {
+        Ok(true) => {
+            record_db_operation(db_counter, "select", "categories", true);
+            Ok(())
+        }
+        Ok(false) => {
+            record_db_operation(db_counter, "select", "categories", true);
+            warn!("Rejecting unknown category {}", category_id);
+            Err(ApiError::bad_request(format!("Category with id {} not found", category_id)))
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "categories", false);
+            error!("Error checking category {}: {}", category_id, e);
+            Err(ApiError::database("Error checking category", &e))
+        }
+    }
+}
+
+/// Load a board straight from the database, bypassing the cache
+/// Find a board with exactly this name (through `boards_name_idx`)
+async fn find_board_by_name(session: &Session, name: &str) -> Result<Option<Board>, QueryError> {
+    let rows = execute_cached(session, "SELECT id, name, description, created_at, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug FROM boards WHERE name = ? LIMIT 1", (name,)).await?;
+    let row = rows
+        .maybe_first_row_typed::<(Uuid, Option<String>, Option<String>, Option<i64>, Option<i32>, Option<i32>, Option<String>, Option<bool>, Option<i64>, Option<Uuid>, Option<String>)>()
+        .ok()
+        .flatten();
+    Ok(row.map(|(id, name, description, created_at, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug)| {
+        let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+        Board {
+            id,
+            name: name.unwrap_or_default(),
+            slug: slug.unwrap_or_default(),
+            description: description.unwrap_or_default(),
+            created_at: created_at
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+                .unwrap_or_else(Utc::now),
+            max_posts,
+            default_page_size,
+            default_sort,
+            category_id,
+            is_deleted,
+            deleted_at,
+        }
+    }))
+}
+
+/// Names `DuplicateNameStrategy::Suffix` tries, in order: "Name (2)", "Name (3)", ...
+fn suffixed_board_names(name: &str) -> impl Iterator<Item = String> + '_ {
+    (2..=MAX_BOARD_NAME_SUFFIX).map(move |suffix| format!("{} ({})", name, suffix))
+}
+
+/// Probe the suffixed names of `name` for the first one no board uses yet
+async fn next_free_board_name(session: &Session, name: &str) -> Result<Option<String>, QueryError> {
+    for candidate in suffixed_board_names(name) {
+        if find_board_by_name(session, &candidate).await?.is_none() {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) async fn fetch_board_from_db(session: &Session, board_id: Uuid) -> Result<Option<Board>, QueryError> {
+    // Use prepared statement for better performance
+    let rows = if let Some(stmt) = GET_BOARD_STMT.get() {
+        db_client::call(session.execute(stmt, (board_id,))).await?
+    } else {
+        // Fallback to regular query if prepared statement not ready
+        warn!("Prepared statement not available, using regular query");
+        db_client::call(session.query("SELECT id, name, description, created_at, max_posts, default_page_size, default_sort, is_deleted, deleted_at, category_id, slug FROM boards WHERE id = ?", (board_id,))).await?
+    };
+
+    let row = match rows.rows.as_ref().and_then(|r| r.first()) {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+
+    if let (Some(id), Some(name), Some(description)) = (
+        row.columns[0].as_ref().and_then(|c| c.as_uuid()),
+        row.columns[1].as_ref().and_then(|c| c.as_text()),
+        row.columns[2].as_ref().and_then(|c| c.as_text()),
+    ) {
+        // Handle bigint timestamps
+        let created_at = if let Some(millis) = row.columns[3].as_ref().and_then(|c| c.as_bigint()) {
+            Utc.timestamp_millis_opt(millis).single().unwrap_or_else(Utc::now)
+        } else {
+            Utc::now()
+        };
+
+        return Ok(Some(Board {
+            id,
+            name: name.to_string(),
+            slug: row.columns.get(10).and_then(|c| c.as_ref()).and_then(|c| c.as_text()).cloned().unwrap_or_default(),
+            description: description.to_string(),
+            created_at,
+            max_posts: row.columns[4].as_ref().and_then(|c| c.as_int()),
+            default_page_size: row.columns[5].as_ref().and_then(|c| c.as_int()),
+            default_sort: row.columns[6].as_ref().and_then(|c| c.as_text()).cloned(),
+            category_id: row.columns.get(9).and_then(|c| c.as_ref()).and_then(|c| c.as_uuid()),
+            is_deleted: row.columns[7].as_ref().and_then(|c| c.as_boolean()).unwrap_or(false),
+            deleted_at: row.columns[8]
+                .as_ref()
+                .and_then(|c| c.as_bigint())
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+        }));
+    }
+
+    Ok(None)
+}
+
+fn board_cache_key(board_id: Uuid) -> String {
+    board_id.to_string()
+}
+
+/// Store a board in the read cache
+pub(crate) async fn cache_board(board: &Board) {
+    let cache_entry = CacheEntry::new(vec![board.clone()], Duration::from_secs(300)); // 5 minutes TTL
+    if let Some(boards_cache) = BOARDS_CACHE.get() {
+        let mut cache = boards_cache.write().await;
+        cache.insert(board_cache_key(board.id), cache_entry);
+        record_cache_size("boards", cache.len());
+    }
+}
+
+/// Drop a board from the read cache, returning whether an entry was present
+pub(crate) async fn invalidate_board_cache(board_id: Uuid) -> bool {
+    match BOARDS_CACHE.get() {
+        Some(boards_cache) => {
+            let mut cache = boards_cache.write().await;
+            let removed = cache.remove(&board_cache_key(board_id)).is_some();
+            record_cache_size("boards", cache.len());
+            removed
+        }
+        None => false,
+    }
+}
+
+/// Look up the boards of a set of posts for `?include=board`
+///
+/// Each distinct board is resolved once: from the boards cache where possible, with the
+/// misses fetched from the database concurrently and cached for the next request.
+async fn lookup_boards(
+    session: &Session,
+    board_ids: impl IntoIterator<Item = Uuid>,
+    db_counter: &web::Data<DbCounter>,
+    cache_counter: &web::Data<CacheCounter>,
+) -> Result<HashMap<Uuid, Board>, QueryError> {
+    let mut wanted: Vec<Uuid> = board_ids.into_iter().collect();
+    wanted.sort();
+    wanted.dedup();
+
+    let mut boards = HashMap::with_capacity(wanted.len());
+    let mut missing = Vec::new();
+    match BOARDS_CACHE.get() {
+        Some(boards_cache) => {
+            let cache = boards_cache.read().await;
+            for board_id in wanted {
+                let cached = cache
+                    .get(&board_cache_key(board_id))
+                    .filter(|entry| !entry.is_expired())
+                    .and_then(|entry| entry.get_data().first());
+                match cached {
+                    Some(board) => {
+                        record_cache_metric(cache_counter, "boards", "hit");
+                        boards.insert(board_id, board.clone());
+                    }
+                    None => {
+                        record_cache_metric(cache_counter, "boards", "miss");
+                        missing.push(board_id);
+                    }
+                }
+            }
+        }
+        None => missing = wanted,
+    }
+
+    if missing.is_empty() {
+        return Ok(boards);
+    }
+
+    let fetched: Vec<Result<Option<Board>, QueryError>> = futures::stream::iter(missing)
+        .map(|board_id| fetch_board_from_db(session, board_id))
+        .buffered(STATS_QUERY_CONCURRENCY)
+        .collect()
+        .await;
+    for result in fetched {
+        match result {
+            Ok(Some(board)) => {
+                cache_board(&board).await;
+                boards.insert(board.id, board);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                record_db_operation(db_counter, "select", "boards", false);
+                return Err(e);
+            }
+        }
+    }
+    record_db_operation(db_counter, "select", "boards", true);
+    Ok(boards)
+}
+
+/// Pair each post with its board from a `lookup_boards` result
+fn attach_boards(posts: Vec<Post>, boards: &HashMap<Uuid, Board>) -> Vec<PostWithBoard> {
+    posts
+        .into_iter()
+        .map(|post| {
+            let board = boards.get(&post.board_id).cloned();
+            PostWithBoard { post, board }
+        })
+        .collect()
+}
+
+/// Get statistics for several boards
+///
+/// Returns the post count and last activity timestamp for each requested board in one call
+#[utoipa::path(
+    post,
+    path = "/boards/stats",
+    request_body = BoardStatsRequest,
+    params(
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Board statistics retrieved successfully", body = Vec<BoardStats>),
+        (status = 400, description = "Too many board IDs requested"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/boards/stats")]
+pub async fn get_boards_stats(
+    session: web::Data<Arc<Session>>,
+    stats_request: web::Json<BoardStatsRequest>,
+    db_counter: web::Data<DbCounter>,
+    cache_counter: web::Data<CacheCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let start = Instant::now();
+
+    // Deduplicate while keeping the order requested by the client
+    let mut board_ids: Vec<Uuid> = Vec::with_capacity(stats_request.board_ids.len());
+    for board_id in &stats_request.board_ids {
+        if !board_ids.contains(board_id) {
+            board_ids.push(*board_id);
+        }
+    }
+
+    if board_ids.len() > MAX_STATS_BOARDS {
+        warn!("Board stats requested for {} boards, limit is {}", board_ids.len(), MAX_STATS_BOARDS);
+        return ApiError::bad_request(format!("At most {} board IDs can be requested at once", MAX_STATS_BOARDS)).error_response();
+    }
+
+    info!("Fetching stats for {} boards", board_ids.len());
+
+    // Run the per-board count queries with bounded concurrency, preserving order
+    let results: Vec<Result<BoardStats, String>> = futures::stream::iter(board_ids)
+        .map(|board_id| fetch_board_stats(&session, board_id, &db_counter, &cache_counter))
+        .buffered(STATS_QUERY_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut stats = Vec::with_capacity(results.len());
+    for result in results {
+        match result {
+            Ok(board_stats) => stats.push(board_stats),
+            Err(e) => {
+                error!("Error fetching board stats: {}", e);
+                return ApiError::internal(format!("Error fetching board stats: {}", e)).error_response();
+            }
+        }
+    }
+
+    let duration = start.elapsed();
+    info!("Successfully fetched stats for {} boards (duration: {}ms)", stats.len(), duration.as_millis());
+    respond_json(
+        HttpResponse::Ok().append_header(("X-Processing-Time-Ms", duration.as_millis().to_string())),
+        &stats,
+        &ts,
+    )
+}
+
+/// Count the posts on a board and find its most recent activity (uncached)
+async fn query_board_post_stats(session: &Session, board_id: Uuid) -> Result<(i64, Option<i64>), String> {
+    execute_cached(session, "SELECT COUNT(*), MAX(updated_at) FROM posts_by_board WHERE board_id = ?", (board_id,)).await
+        .map_err(|e| e.to_string())?
+        .first_row_typed::<(i64, Option<i64>)>()
+        .map_err(|e| e.to_string())
+}
+
+/// Count the posts currently on a board (uncached)
+pub(crate) async fn count_board_posts(session: &Session, board_id: Uuid) -> Result<i64, String> {
+    query_board_post_stats(session, board_id).await.map(|(post_count, _)| post_count)
+}
+
+/// Fetch stats for a single board, serving from the short-lived stats cache when possible
+async fn fetch_board_stats(
+    session: &Session,
+    board_id: Uuid,
+    db_counter: &web::Data<DbCounter>,
+    cache_counter: &web::Data<CacheCounter>,
+) -> Result<BoardStats, String> {
+    if let Some(stats_cache) = BOARD_STATS_CACHE.get() {
+        match stats_cache.read().await.get(&board_id) {
+            Some(cached) if !cached.is_expired() => {
+                record_cache_metric(cache_counter, "board_stats", "hit");
+                return Ok(cached.get_data().clone());
+            }
+            Some(_) => record_cache_metric(cache_counter, "board_stats", "expired"),
+            None => record_cache_metric(cache_counter, "board_stats", "miss"),
+        }
+    }
+
+    let (post_count, last_activity_millis) = match query_board_post_stats(session, board_id).await {
+        Ok(row) => row,
+        Err(e) => {
+            record_db_operation(db_counter, "count", "posts", false);
+            return Err(e);
+        }
+    };
+    record_db_operation(db_counter, "count", "posts", true);
+
+    let board_stats = BoardStats {
+        board_id,
+        post_count,
+        last_activity: last_activity_millis.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+    };
+
+    if let Some(stats_cache) = BOARD_STATS_CACHE.get() {
+        let mut cache = stats_cache.write().await;
+        cache.insert(board_id, CacheEntry::new(board_stats.clone(), BOARD_STATS_TTL));
+        record_cache_size("board_stats", cache.len());
+    }
+
+    Ok(board_stats)
+}
+
+// Post related endpoints
+/// Create a new post
+///
+/// Creates a new post on a specific board
+#[utoipa::path(
+    post,
     path = "/posts",
     request_body = CreatePostRequest,
+    params(
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 201, description = "Post created successfully", body = Post),
+        (status = 400, description = "Board not found, unknown user_id or author not matching it, invalid tags, created_at too far in the future, or unknown field in the body"),
+        (status = 422, description = "Empty or too long title or content, or missing or invalid author; every failing field is listed in errors"),
+        (status = 401, description = "created_at supplied without a valid X-Admin-Token, or missing or invalid bearer token while JWT_SECRET is set"),
+        (status = 403, description = "created_at supplied while admin endpoints are disabled, or user_id without a bearer token of that user"),
+        (status = 403, description = "Board has reached its max_posts limit"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/posts")]
+// #[instrument(name = "create_post", skip(session, db_counter), fields(board_id = %post_data.board_id, title = %post_data.title, author = %post_data.author))]
+pub async fn create_post(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    post_data: web::Json<CreatePostRequest>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    if let Err(e) = validation::create_post(&post_data, user.is_some()) {
+        warn!("Rejecting invalid post on board {}: {}", post_data.board_id, e);
+        return e.error_response();
+    }
+
+    let (author, user_id) = match auth::resolve_author(&session, user.as_ref(), post_data.user_id, &post_data.author, &db_counter).await {
+        Ok(resolved) => resolved,
+        Err(e) => return e.error_response(),
+    };
+
+    info!("Creating new post: '{}' by {} on board {}", post_data.title, author, post_data.board_id);
+    
+    let start = Instant::now();
+    
+    // First check if the board exists
+    debug!("Checking if board exists: {}", post_data.board_id);
+    let board_check = match get_or_prepare(&session, "SELECT id, max_posts, is_deleted FROM boards WHERE id = ?").await {
+        Ok(p) => {
+            debug!("Board check query prepared successfully");
+            p
+        },
+        Err(e) => {
+            error!("Error preparing board check query: {}", e);
+            record_db_operation(&db_counter, "select", "boards", false);
+            return ApiError::database("Error preparing query", &e).error_response();
+        }
+    };
+    
+    let board_result = db_client::call(session.execute(&board_check, (post_data.board_id,))).await;
+    
+    let max_posts = match board_result {
+        Ok(rows) => {
+            let rows = rows.rows.unwrap_or_default();
+            let is_deleted = rows.first().and_then(|row| row.columns[2].as_ref()).and_then(|c| c.as_boolean()).unwrap_or(false);
+            if rows.is_empty() || is_deleted {
+                warn!("Board with id {} not found", post_data.board_id);
+                record_db_operation(&db_counter, "select", "boards", true);
+                return ApiError::bad_request(format!("Board with id {} not found", post_data.board_id)).error_response();
+            } else {
+                debug!("Board exists, proceeding with post creation");
+                record_db_operation(&db_counter, "select", "boards", true);
+                rows[0].columns[1].as_ref().and_then(|c| c.as_int())
+            }
+        },
+        Err(e) => {
+            error!("Error checking board existence: {}", e);
+            record_db_operation(&db_counter, "select", "boards", false);
+            return ApiError::database("Error checking board", &e).error_response();
+        }
+    };
+
+    // Enforce the board's post cap when it has one. The count and the insert are not atomic,
+    // so concurrent creations racing past the check can overshoot the limit slightly.
+    if let Some(max_posts) = max_posts {
+        match count_board_posts(&session, post_data.board_id).await {
+            Ok(post_count) if post_count >= max_posts as i64 => {
+                record_db_operation(&db_counter, "count", "posts", true);
+                warn!("Board {} is full ({} of {} posts)", post_data.board_id, post_count, max_posts);
+                return ApiError::forbidden(format!("Board with id {} has reached its limit of {} posts", post_data.board_id, max_posts)).error_response();
+            }
+            Ok(_) => record_db_operation(&db_counter, "count", "posts", true),
+            Err(e) => {
+                error!("Error counting posts for board {}: {}", post_data.board_id, e);
+                record_db_operation(&db_counter, "count", "posts", false);
+                return ApiError::internal(format!("Error checking board capacity: {}", e)).error_response();
+            }
+        }
+    }
+    
+    let (title, content) = normalize_post_text(&post_data.title, &post_data.content);
+    if title.trim().is_empty() {
+        warn!("Rejecting post with empty title on board {}", post_data.board_id);
+        return ApiError::bad_request("title must not be empty").error_response();
+    }
+
+    let tags = match validate_tags(post_data.tags.as_deref().unwrap_or_default()) {
+        Ok(tags) => tags,
+        Err(message) => {
+            warn!("Rejecting post tags on board {}: {}", post_data.board_id, message);
+            return ApiError::bad_request(message).error_response();
+        }
+    };
+
+    let created_at = match resolve_created_at(&req, post_data.created_at) {
+        Ok(created_at) => created_at,
+        Err(e) => return e.error_response(),
+    };
+
+    // An imported post still counts as changed now, so delta sync clients pick it up
+    let post = Post {
+        id: config::get().id_scheme.new_id(),
+        board_id: post_data.board_id,
+        title,
+        content,
+        created_at,
+        updated_at: created_at.max(Utc::now()),
+        author,
+        tags,
+        is_deleted: false,
+        deleted_at: None,
+        is_locked: false,
+        score: 0,
+        reactions: Vec::new(),
+    };
+    
+    debug!("Generated post ID: {}", post.id);
+    
+    // Write the post, its board, change-feed and author copies and its ordering rows atomically in a logged batch. It is never split
+    // by BATCH_MAX_BYTES (that would lose the atomicity), so very long posts can still trip
+    // Scylla's batch size warning.
+    let mut batch = db::new_batch(BatchType::Logged);
+    for cql in [
+        "INSERT INTO posts (id, board_id, title, content, author, created_at, updated_at, tags, user_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO posts_by_board (board_id, created_at, id, title, content, author, updated_at, tags, user_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO posts_by_updated (day, updated_at, id, board_id, title, content, author, created_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO posts_by_author (author, created_at, id, board_id, title, content, updated_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO post_order (board_id, sort, value, id) VALUES (?, ?, ?, ?)",
+        "INSERT INTO post_order (board_id, sort, value, id) VALUES (?, ?, ?, ?)",
+    ] {
+        match get_or_prepare(&session, cql).await {
+            Ok(p) => batch.append_statement(p),
+            Err(e) => {
+                error!("Error preparing post insert query: {}", e);
+                record_db_operation(&db_counter, "insert", "posts", false);
+                return ApiError::database("Error preparing query", &e).error_response();
+            }
+        }
+    }
+    
+    // Use timestamp_millis directly for ScyllaDB BIGINT
+    debug!("Executing post insert batch");
+    let created_at_millis = post.created_at.timestamp_millis();
+    let updated_at_millis = post.updated_at.timestamp_millis();
+    let result = db_client::call(session
+        .batch(
+            &batch,
+            (
+                (post.id, post.board_id, &post.title, &post.content, &post.author, created_at_millis, updated_at_millis, &post.tags, user_id),
+                (post.board_id, created_at_millis, post.id, &post.title, &post.content, &post.author, updated_at_millis, &post.tags, user_id),
+                (db::updated_day(updated_at_millis), updated_at_millis, post.id, post.board_id, &post.title, &post.content, &post.author, created_at_millis, &post.tags),
+                (&post.author, created_at_millis, post.id, post.board_id, &post.title, &post.content, updated_at_millis, &post.tags),
+                (post.board_id, SortKey::UpdatedAt.as_str(), updated_at_millis, post.id),
+                (post.board_id, SortKey::Score.as_str(), 0i64, post.id),
+            ),
+        ))
+        .await;
+
+    // Index the post under each tag. This runs after the post itself is stored: the rows
+    // differ per tag, so they can't share the fixed-shape batch above.
+    if result.is_ok() && !post.tags.is_empty() {
+        if let Err(e) = index_post_tags(&session, &post).await {
+            error!("Post {} created but tag index update failed: {}", post.id, e);
+            record_db_operation(&db_counter, "insert", "posts_by_tag", false);
+        } else {
+            record_db_operation(&db_counter, "insert", "posts_by_tag", true);
+        }
+        if let Err(e) = update_tag_counts(&session, &post.tags, 1).await {
+            error!("Post {} created but tag usage counts were not updated: {}", post.id, e);
+            record_db_operation(&db_counter, "update", "tags", false);
+        } else {
+            record_db_operation(&db_counter, "update", "tags", true);
+        }
+    }
+
+    if result.is_ok() {
+        if let Err(e) = title_search::index(&session, post.board_id, post.id, &post.title, post.created_at.timestamp_millis()).await {
+            error!("Post {} created but its title was not indexed: {}", post.id, e);
+            record_db_operation(&db_counter, "insert", "posts_by_title_prefix", false);
+        } else {
+            record_db_operation(&db_counter, "insert", "posts_by_title_prefix", true);
+        }
+    }
+
+    let duration = start.elapsed();
+
+    match result {
+        Ok(_) => {
+            info!("Post created successfully: '{}' (duration: {}ms)", post.title, duration.as_millis());
+            record_db_operation(&db_counter, "insert", "posts", true);
+            counts::add_posts(&session, post.board_id, 1, &db_counter).await;
+            search::index_post(&post);
+            respond_json(
+                HttpResponse::Created().append_header(("X-Processing-Time-Ms", duration.as_millis().to_string())),
+                &post,
+                &ts,
+            )
+        },
+        Err(e) => {
+            error!("Error creating post: {}", e);
+            record_db_operation(&db_counter, "insert", "posts", false);
+            ApiError::database("Error creating post", &e).error_response()
+        },
+    }
+}
+
+/// Get posts changed since a timestamp
+///
+/// Returns posts whose `updated_at` is after `since`, oldest change first, for clients that
+/// keep a local copy and sync deltas. Poll again with the returned `next_since`.
+///
+/// Reads are eventually consistent: a write still in flight when the poll runs can land with
+/// an `updated_at` slightly before the poll time. `next_since` is therefore held back by a few
+/// seconds when the feed is caught up, so clients may see a post twice and should
+/// de-duplicate by `id`.
+#[utoipa::path(
+    get,
+    path = "/posts/changes",
+    params(
+        ("since" = String, Query, description = "RFC 3339 timestamp; posts updated after it are returned", example = "2025-01-01T00:00:00Z"),
+        ("limit" = Option<u32>, Query, description = "Maximum number of posts to return (1-500)", example = 50),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "Changed posts", body = PostChangesResponse),
+        (status = 400, description = "Invalid `since` or `since` older than the change feed retention"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/changes")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_post_changes(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    params: Query<PostChangesParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    let since = match chrono::DateTime::parse_from_rfc3339(&params.since) {
+        Ok(since) => since.with_timezone(&Utc),
+        Err(e) => {
+            return ApiError::bad_request(format!("Invalid since timestamp '{}': {}", params.since, e)).error_response();
+        }
+    };
+    let limit = params.limit.clamp(1, MAX_CHANGES_LIMIT);
+
+    // Captured before querying so nothing written during the scan is skipped next time
+    let now = Utc::now();
+    if now - since > chrono::Duration::days(MAX_CHANGES_WINDOW_DAYS) {
+        return ApiError::bad_request(format!(
+            "since is more than {} days old; fetch boards and posts in full instead",
+            MAX_CHANGES_WINDOW_DAYS
+        )).error_response();
+    }
+
+    info!("Fetching post changes since {} (limit: {})", since, limit);
+    let start = Instant::now();
+
+    let prepared = match get_or_prepare(&session, "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at FROM posts_by_updated WHERE day = ? AND updated_at > ? LIMIT ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_updated", false);
+            return ApiError::database("Error preparing query", &e).error_response();
+        }
+    };
+
+    // Walk the daily partitions from `since` to today, fetching one extra row to detect more
+    let since_millis = since.timestamp_millis();
+    let mut posts: Vec<Post> = Vec::new();
+    for day in db::updated_day(since_millis)..=db::updated_day(now.timestamp_millis()) {
+        let remaining = (limit as usize + 1 - posts.len()) as i32;
+        let rows = match db_client::call(session.execute(&prepared, (day, since_millis, remaining))).await {
+            Ok(result) => result.rows_typed_or_empty::<(Uuid, Uuid, String, String, Option<String>, i64, i64, Option<Vec<String>>, Option<bool>, Option<i64>)>(),
+            Err(e) => {
+                error!("Error fetching post changes for day {}: {}", day, e);
+                record_db_operation(&db_counter, "select", "posts_by_updated", false);
+                return ApiError::database("Error executing query", &e).error_response();
+            }
+        };
+
+        for row in rows {
+            let (id, board_id, title, content, author, created_at_millis, updated_at_millis, tags, is_deleted, deleted_at) = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    error!("Error reading row: {}", e);
+                    record_db_operation(&db_counter, "select", "posts_by_updated", false);
+                    return ApiError::internal(format!("Error reading row: {}", e)).error_response();
+                }
+            };
+            let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+            if is_deleted && !include_deleted {
+                continue;
+            }
+            let (Some(created_at), Some(updated_at)) = (
+                Utc.timestamp_millis_opt(created_at_millis).single(),
+                Utc.timestamp_millis_opt(updated_at_millis).single(),
+            ) else {
+                warn!("Invalid timestamps for post {}: {}, {}", id, created_at_millis, updated_at_millis);
+                continue;
+            };
+            posts.push(Post {
+                id,
+                board_id,
+                title,
+                content,
+                author: author_or_placeholder(author, &integrity_counter, "posts_by_updated", id),
+                created_at,
+                updated_at,
+                tags: tags.unwrap_or_default(),
+                is_deleted,
+                deleted_at,
+                is_locked: false,
+                score: 0,
+                reactions: Vec::new(),
+            });
+        }
+
+        if posts.len() > limit as usize {
+            break;
+        }
+    }
+
+    let has_more = posts.len() > limit as usize;
+    let next_since = if has_more {
+        posts.truncate(limit as usize);
+        // Don't split posts sharing a millisecond across polls, or the rest would be skipped
+        // by the strict `updated_at >` filter; only keep them all when the page is nothing else
+        let last = posts[posts.len() - 1].updated_at;
+        if posts.iter().any(|p| p.updated_at != last) {
+            posts.retain(|p| p.updated_at != last);
+        }
+        posts[posts.len() - 1].updated_at
+    } else {
+        // Leave room for writes that were still in flight during this scan
+        (now - chrono::Duration::seconds(CHANGES_SETTLE_WINDOW_SECS)).max(since)
+    };
+
+    fill_post_locks(&session, &mut posts).await;
+    votes::fill_post_scores(&session, &mut posts).await;
+    reactions::fill_post_reactions(&session, &mut posts).await;
+
+    let duration = start.elapsed();
+    record_db_operation(&db_counter, "select", "posts_by_updated", true);
+    info!("Found {} changed posts (has_more: {}, duration: {}ms)", posts.len(), has_more, duration.as_millis());
+
+    let response = PostChangesResponse {
+        data: posts,
+        next_since,
+        has_more,
+    };
+
+    respond_json(
+        HttpResponse::Ok().append_header(("X-Processing-Time-Ms", duration.as_millis().to_string())),
+        &response,
+        &ts,
+    )
+}
+
+/// Read one page of a board's posts in creation order from its `posts_by_board` partition,
+/// limited to posts created within `range` (epoch milliseconds)
+#[allow(clippy::too_many_arguments)]
+async fn fetch_board_posts_page(
+    session: &Session,
+    board_id: Uuid,
+    order: SortOrder,
+    range: std::ops::Range<i64>,
+    page: u32,
+    limit: u32,
+    include_deleted: bool,
+    db_counter: &web::Data<DbCounter>,
+    integrity_counter: &web::Data<IntegrityCounter>,
+) -> Result<Vec<Post>, ApiError> {
+    // The partition is clustered newest first; reversing the clustering order serves asc
+    let cql = match order {
+        SortOrder::Desc => "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked FROM posts_by_board WHERE board_id = ? AND created_at >= ? AND created_at < ?",
+        SortOrder::Asc => "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked FROM posts_by_board WHERE board_id = ? AND created_at >= ? AND created_at < ? ORDER BY created_at ASC, id DESC",
+    };
+    let mut prepared = match get_or_prepare(session, cql).await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(db_counter, "select", "posts_by_board", false);
+            return Err(ApiError::database("Error preparing query", &e));
+        }
+    };
+    
+    // Set page size for efficient pagination
+    prepared.set_page_size(limit as i32);
+    
+    // Use execute_iter for paginated results
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, (board_id, range.start, range.end))).await {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            record_db_operation(db_counter, "select", "posts_by_board", false);
+            return Err(ApiError::database("Error executing query", &e));
+        }
+    };
+
+    let mut posts = Vec::new();
+    let mut total_fetched = 0u32;
+
+    // Skip to the requested page
+    let skip_count = (page - 1) * limit;
+    let mut skipped = 0u32;
+
+    // Convert iterator to stream and iterate through pages
+    // Author is read as optional so a single corrupt row doesn't fail the whole listing
+    let mut rows_stream = row_iterator.into_typed::<(uuid::Uuid, uuid::Uuid, String, String, Option<String>, i64, i64, Option<Vec<String>>, Option<bool>, Option<i64>, Option<bool>)>();
+    
+    while let Some(next_row_res) = read.next(&mut rows_stream).await {
+        match next_row_res {
+            Ok((id, board_id, title, content, author, created_at_millis, updated_at_millis, tags, is_deleted, deleted_at, is_locked)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                // Deleted posts don't count towards pages
+                if is_deleted && !include_deleted {
+                    continue;
+                }
+
+                // Skip rows until we reach the desired page
+                if skipped < skip_count {
+                    skipped += 1;
+                    continue;
+                }
+                
+                // Stop if we have enough items for this page
+                if total_fetched >= limit {
+                    break;
+                }
+
+                // Convert timestamps
+                let created_at = match Utc.timestamp_millis_opt(created_at_millis).single() {
+                    Some(dt) => dt,
+                    None => {
+                        warn!("Invalid created_at timestamp for post {}: {}", id, created_at_millis);
+                        continue;
+                    }
+                };
+                
+                let updated_at = match Utc.timestamp_millis_opt(updated_at_millis).single() {
+                    Some(dt) => dt,
+                    None => {
+                        warn!("Invalid updated_at timestamp for post {}: {}", id, updated_at_millis);
+                        continue;
+                    }
+                };
+
+                posts.push(Post {
+                    id,
+                    board_id,
+                    title,
+                    content,
+                    author: author_or_placeholder(author, integrity_counter, "posts", id),
+                    created_at,
+                    updated_at,
+                    tags: tags.unwrap_or_default(),
+                    is_deleted,
+                    deleted_at,
+                    is_locked: is_locked.unwrap_or(false),
+                    score: 0,
+                    reactions: Vec::new(),
+                });
+
+                total_fetched += 1;
+            },
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(db_counter, "select", "posts_by_board", false);
+                return Err(ApiError::database("Error reading row", &e));
+            }
+        }
+    }
+    record_db_operation(db_counter, "select", "posts_by_board", true);
+    Ok(posts)
+}
+
+/// Get posts by board with pagination
+///
+/// Returns paginated posts for a specific board using ScyllaDB native pagination
+#[utoipa::path(
+    get,
+    path = "/boards/{board_id}/posts",
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
+        ("limit" = Option<u32>, Query, description = "Number of items per page (defaults to the board's default_page_size, then 10)", example = 10),
+        ("sort" = Option<String>, Query, description = "Sort field: created_at, updated_at, score (ordered in the database), title, author (within the page); defaults to the board's default_sort. `hot` lists the board's hot ranking instead"),
+        ("order" = Option<String>, Query, description = "Sort direction: asc or desc"),
+        ("since" = Option<String>, Query, description = "Only posts created at or after this RFC 3339 timestamp (sorts by created_at)"),
+        ("until" = Option<String>, Query, description = "Only posts created before this RFC 3339 timestamp (sorts by created_at)"),
+        ("include" = Option<String>, Query, description = "`board` embeds the board in each post as `board`"),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "Paginated posts retrieved successfully (items are PostWithBoard with include=board)", body = PaginatedResponse<Post>),
+        (status = 204, description = "No posts on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 400, description = "Unknown sort field or order, invalid since/until, or since/until with a sort other than created_at"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/boards/{board_id}/posts")]
+// #[instrument(name = "get_posts_by_board", skip(session, db_counter), fields(board_id = %path))]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_posts_by_board(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    pagination: Query<PaginationParams>,
+    range: Query<DateRangeParams>,
+    include: Query<IncludeParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    cache_counter: web::Data<CacheCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    let board_id = path.into_inner();
+    let page = pagination.page.max(1); // Ensure page >= 1
+    let created = match created_range(&range) {
+        Ok(created) => created,
+        Err(e) => return e.error_response(),
+    };
+
+    // Omitted limit/sort fall back to the board's stored defaults before the global ones; the
+    // board is also needed to hide the posts of a deleted board
+    let board = if !include_deleted || pagination.limit.is_none() || pagination.sort.is_none() {
+        match lookup_boards(&session, [board_id], &db_counter, &cache_counter).await {
+            Ok(mut boards) => boards.remove(&board_id),
+            Err(e) => {
+                error!("Error fetching board {} settings: {}", board_id, e);
+                return ApiError::database("Error fetching board", &e).error_response();
+            }
+        }
+    } else {
+        None
+    };
+    if board.as_ref().is_some_and(|board| board.is_deleted) && !include_deleted {
+        return ApiError::not_found(format!("Board with id {} not found", board_id)).error_response();
+    }
+
+    let limit = match (pagination.limit, board.as_ref().and_then(|board| board.default_page_size)) {
+        (None, Some(page_size)) => page_size.clamp(1, 100) as u32,
+        _ => pagination.limit().max(1).min(100), // Ensure 1 <= limit <= 100
+    };
+
+    // Sort fields are resolved through the allowlist; unknown names are rejected. `hot` is not
+    // a field but the stored ranking (see the hot module).
+    let hot = pagination.sort.as_deref() == Some("hot");
+    let default_sort = ("created_at", SortOrder::Desc);
+    let sort = match (pagination.sort.as_deref(), board.as_ref().and_then(|board| board.default_sort.as_deref())) {
+        _ if hot => Ok(("hot", SortOrder::Desc)),
+        (None, Some(setting)) => query_fields::resolve_sort_setting(query_fields::POST_FIELDS, setting, default_sort)
+            .and_then(|(column, order)| {
+                // An explicit `order` still overrides the stored direction
+                query_fields::resolve_sort(query_fields::POST_FIELDS, Some(column), pagination.order.as_deref(), (column, order))
+            }),
+        _ => query_fields::resolve_sort(
+            query_fields::POST_FIELDS,
+            pagination.sort.as_deref(),
+            pagination.order.as_deref(),
+            default_sort,
+        ),
+    };
+    let (sort_column, sort_order) = match sort {
+        // A creation time range is a range of the created_at order; it overrides the board's
+        // default sort but not an explicit one
+        Ok((column, _)) if created.is_some() && column != "created_at" => {
+            if pagination.sort.is_some() {
+                return ApiError::bad_request("since and until need sort=created_at").error_response();
+            }
+            match query_fields::resolve_sort(query_fields::POST_FIELDS, None, pagination.order.as_deref(), default_sort) {
+                Ok(sort) => sort,
+                Err(message) => return ApiError::bad_request(message).error_response(),
+            }
+        }
+        Ok(sort) => sort,
+        Err(message) => {
+            warn!("Rejecting posts listing: {}", message);
+            return ApiError::bad_request(message).error_response();
+        }
+    };
+
+    info!("Fetching posts for board {} (page: {}, limit: {}, sort: {})", board_id, page, limit, sort_column);
+    let start = Instant::now();
+
+    // created_at is the board partition's own order; updated_at and score come from post_order
+    let db_order = SortKey::from_column(sort_column).filter(|key| *key != SortKey::CreatedAt);
+    let (mut posts, has_more) = if hot {
+        match hot::fetch_page(&session, board_id, page, limit, include_deleted).await {
+            Ok((posts, ranked)) => {
+                record_db_operation(&db_counter, "select", "hot_posts", true);
+                // Deleted posts can make a page short without ending the ranking
+                (posts, ranked == limit)
+            }
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "hot_posts", false);
+                error!("Error fetching hot posts of board {}: {}", board_id, e);
+                return ApiError::database("Error fetching hot posts", &e).error_response();
+            }
+        }
+    } else if let Some(key) = db_order {
+        let range = created.clone().unwrap_or(i64::MIN..i64::MAX);
+        match list_order::post_page(&session, board_id, key, sort_order, range, page, limit, include_deleted).await {
+            Ok(posts) => {
+                record_db_operation(&db_counter, "select", "post_order", true);
+                let has_more = posts.len() as u32 == limit;
+                (posts, has_more)
+            }
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "post_order", false);
+                error!("Error fetching posts of board {} by {}: {}", board_id, key.as_str(), e);
+                return ApiError::database("Error fetching posts", &e).error_response();
+            }
+        }
+    } else {
+        // Title and author pages are cut from the newest-first order and sorted below
+        let (order, range) = match sort_column {
+            "created_at" => (sort_order, created.clone().unwrap_or(i64::MIN..i64::MAX)),
+            _ => (SortOrder::Desc, i64::MIN..i64::MAX),
+        };
+        match fetch_board_posts_page(&session, board_id, order, range, page, limit, include_deleted, &db_counter, &integrity_counter).await {
+            Ok(posts) => {
+                let has_more = posts.len() as u32 == limit; // If we got a full page, there might be more
+                (posts, has_more)
+            }
+            Err(e) => return e.error_response(),
+        }
+    };
+
+    votes::fill_post_scores(&session, &mut posts).await;
+    reactions::fill_post_reactions(&session, &mut posts).await;
+
+    // Only title and author are sorted within the page; the hot ranking and the database
+    // orders come sorted
+    if !hot && matches!(sort_column, "title" | "author") {
+        posts.sort_by(|a, b| sort_order.apply(match sort_column {
+            "title" => a.title.cmp(&b.title),
+            _ => a.author.cmp(&b.author),
+        }));
+    }
+
+    let duration = start.elapsed();
+
+    // The board's post count covers the plain listing only: it leaves out deleted posts, and
+    // neither the hot ranking nor a creation time range lists every post
+    let total = if hot || created.is_some() || include_deleted {
+        None
+    } else {
+        match counts::board_posts(&session, board_id).await {
+            Ok(total) => {
+                record_db_operation(&db_counter, "select", "board_post_counts", true);
+                Some(total)
+            }
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "board_post_counts", false);
+                warn!("Error fetching the post count of board {}: {}", board_id, e);
+                None
+            }
+        }
+    };
+    let meta = PaginationMeta {
+        page,
+        limit,
+        total: total.map(|total| total.min(u32::MAX as u64) as u32),
+        // Without a count, a short page is the last one
+        total_pages: total.map_or(if has_more { None } else { Some(page) }, |total| Some(counts::total_pages(total, limit))),
+        total_is_estimate: false,
+        sort: Some(sort_column.to_string()),
+        order: Some(sort_order.as_str().to_string()),
+    };
+
+    let response = PaginatedResponse {
+        meta,
+        data: posts,
+    };
+
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        info!("No posts found (page: {}, limit: {}), returning 204", page, limit);
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
+    }
+
+    info!("Successfully fetched {} posts for board {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), board_id, page, limit, duration.as_millis());
+
+    if include.board() {
+        let boards = match lookup_boards(&session, response.data.iter().map(|post| post.board_id), &db_counter, &cache_counter).await {
+            Ok(boards) => boards,
+            Err(e) => {
+                error!("Error fetching boards for posts: {}", e);
+                return ApiError::database("Error fetching boards", &e).error_response();
+            }
+        };
+        let response = PaginatedResponse {
+            meta: response.meta,
+            data: attach_boards(response.data, &boards),
+        };
+        return respond_json(
+            HttpResponse::Ok()
+                .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+                .append_header(("X-Has-More", has_more.to_string())),
+            &response,
+            &ts,
+        );
+    }
+
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
+}
+
+/// Get the most used tags
+///
+/// Returns tags ordered by how many posts use them, for tag clouds
+#[utoipa::path(
+    get,
+    path = "/tags/popular",
+    params(
+        ("limit" = Option<u32>, Query, description = "Number of tags to return (1-100)", example = 20)
+    ),
+    responses(
+        (status = 200, description = "Most used tags", body = Vec<TagUsage>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags/popular")]
+pub async fn get_popular_tags(
+    session: web::Data<Arc<Session>>,
+    params: Query<PopularTagsParams>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let limit = params.limit.clamp(1, 100) as usize;
+    let start = Instant::now();
+
+    // Counters can't be ordered server-side, so the (small) tag table is scanned and ranked here
+    let read = db_client::PagedRead::start();
+    let mut rows = match read.call(session.query_iter("SELECT tag, uses FROM tags", &[])).await {
+        Ok(iterator) => iterator.into_typed::<(String, Option<CqlCounter>)>(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "tags", false);
+            return ApiError::database("Error executing query", &e).error_response();
+        }
+    };
+
+    let mut tags = Vec::new();
+    while let Some(row) = read.next(&mut rows).await {
+        match row {
+            Ok((tag, uses)) => {
+                let uses = uses.map(|c| c.0).unwrap_or(0);
+                if uses > 0 {
+                    tags.push(TagUsage { tag, uses });
+                }
+            }
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(&db_counter, "select", "tags", false);
+                return ApiError::database("Error reading row", &e).error_response();
+            }
+        }
+    }
+
+    tags.sort_by(|a, b| b.uses.cmp(&a.uses).then_with(|| a.tag.cmp(&b.tag)));
+    tags.truncate(limit);
+
+    let duration = start.elapsed();
+    record_db_operation(&db_counter, "select", "tags", true);
+    HttpResponse::Ok()
+        .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+        .json(tags)
+}
+
+/// Get posts by tag with pagination
+///
+/// Returns posts carrying the given tag, newest first
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}/posts",
+    params(
+        ("tag" = String, Path, description = "Tag name"),
+        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
+        ("limit" = Option<u32>, Query, description = "Number of items per page", example = 10),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "Paginated posts retrieved successfully", body = PaginatedResponse<Post>),
+        (status = 204, description = "No posts on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags/{tag}/posts")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_posts_by_tag(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<String>,
+    pagination: Query<PaginationParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    let tag = normalize::tag_key(&path.into_inner());
+    let page = pagination.page.max(1); // Ensure page >= 1
+    let limit = pagination.limit().clamp(1, 100);
+
+    info!("Fetching posts tagged '{}' (page: {}, limit: {})", tag, page, limit);
+    let start = Instant::now();
+
+    let mut prepared = match get_or_prepare(&session, "SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at FROM posts_by_tag WHERE tag = ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_tag", false);
+            return ApiError::database("Error preparing query", &e).error_response();
+        }
+    };
+    prepared.set_page_size(limit as i32);
+
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, (&tag,))).await {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_tag", false);
+            return ApiError::database("Error executing query", &e).error_response();
+        }
+    };
+
+    let mut posts = Vec::new();
+    let skip_count = (page - 1) * limit;
+    let mut skipped = 0u32;
+
+    // Rows are clustered newest first, so pages come out in display order
+    let mut rows_stream = row_iterator.into_typed::<(uuid::Uuid, uuid::Uuid, String, String, Option<String>, i64, i64, Option<Vec<String>>, Option<bool>, Option<i64>)>();
+    while let Some(next_row_res) = read.next(&mut rows_stream).await {
+        match next_row_res {
+            Ok((id, board_id, title, content, author, created_at_millis, updated_at_millis, tags, is_deleted, deleted_at)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                if is_deleted && !include_deleted {
+                    continue;
+                }
+                if skipped < skip_count {
+                    skipped += 1;
+                    continue;
+                }
+                if posts.len() as u32 >= limit {
+                    break;
+                }
+
+                let (Some(created_at), Some(updated_at)) = (
+                    Utc.timestamp_millis_opt(created_at_millis).single(),
+                    Utc.timestamp_millis_opt(updated_at_millis).single(),
+                ) else {
+                    warn!("Invalid timestamps for post {}: {}, {}", id, created_at_millis, updated_at_millis);
+                    continue;
+                };
+
+                posts.push(Post {
+                    id,
+                    board_id,
+                    title,
+                    content,
+                    author: author_or_placeholder(author, &integrity_counter, "posts_by_tag", id),
+                    created_at,
+                    updated_at,
+                    tags: tags.unwrap_or_default(),
+                    is_deleted,
+                    deleted_at,
+                    is_locked: false,
+                    score: 0,
+                    reactions: Vec::new(),
+                });
+            },
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(&db_counter, "select", "posts_by_tag", false);
+                return ApiError::database("Error reading row", &e).error_response();
+            }
+        }
+    }
+
+    fill_post_locks(&session, &mut posts).await;
+    votes::fill_post_scores(&session, &mut posts).await;
+    reactions::fill_post_reactions(&session, &mut posts).await;
+
+    let duration = start.elapsed();
+    record_db_operation(&db_counter, "select", "posts_by_tag", true);
+
+    let has_more = posts.len() as u32 == limit;
+    let response = PaginatedResponse {
+        meta: PaginationMeta {
+            page,
+            limit,
+            total: None,
+            total_pages: if has_more { None } else { Some(page) },
+            total_is_estimate: false,
+            sort: None,
+            order: None,
+        },
+        data: posts,
+    };
+
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
+    }
+
+    info!("Fetched {} posts tagged '{}' (page: {}, duration: {}ms)", response.data.len(), tag, page, duration.as_millis());
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
+}
+
+/// Get post by ID
+///
+/// Returns a single post with the specified ID
+#[utoipa::path(
+    get,
+    path = "/posts/{post_id}",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("include" = Option<String>, Query, description = "`board` embeds the post's board as `board`"),
+        ("Cache-Control" = Option<String>, Header, description = "`no-cache` skips the read cache (the fresh result is still cached)"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "Post retrieved successfully (PostWithBoard with include=board)", body = Post),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 404, description = "Post not found"),
+        (status = 410, description = "Post was deleted (see include_deleted)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{post_id}")]
+// #[instrument(name = "get_post", skip(session, db_counter, cache_counter), fields(post_id = %path))]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_post(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    db_counter: web::Data<DbCounter>,
+    cache_counter: web::Data<CacheCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    include: Query<IncludeParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let start = Instant::now();
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    
+    let post_id = path.into_inner();
+    let mut cached = None;
+    
+    // Check cache first, unless the client asked for fresh data
+    if cache_bypass_requested(&req) {
+        info!("Cache bypass requested for post ID: {}", post_id);
+        record_cache_metric(&cache_counter, "posts", "bypass_requested");
+    } else if let Some(posts_cache) = POSTS_CACHE.get() {
+        if let Some(cached_post) = posts_cache.read().await.get(&post_cache_key(post_id)) {
+            if !cached_post.is_expired() {
+                info!("Cache hit for post ID: {}", post_id);
+                record_cache_metric(&cache_counter, "posts", "hit");
+                cached = cached_post.get_data().first().cloned();
+            } else {
+                info!("Cache expired for post ID: {}, fetching fresh data", post_id);
+                record_cache_metric(&cache_counter, "posts", "expired");
+            }
+        } else {
+            info!("No cache entry for post ID: {}, fetching data", post_id);
+            record_cache_metric(&cache_counter, "posts", "miss");
+        }
+    } else {
+        warn!("Posts cache not initialized, fetching data from database");
+        record_cache_metric(&cache_counter, "posts", "miss");
+    }
+
+    if let Some(post) = cached {
+        if post.is_deleted && !include_deleted {
+            return ApiError::gone(format!("Post with id {} was deleted", post_id)).error_response();
+        }
+        return respond_post(&session, post, &include, &db_counter, &cache_counter, &mut HttpResponse::Ok(), &ts).await;
+    }
+    
+    let result = fetch_post_from_db(&session, post_id, &integrity_counter).await;
+    
+    let duration = start.elapsed();
+    
+    match result {
+        Ok(Some(post)) if post.is_deleted && !include_deleted => {
+            cache_post(&post).await;
+            record_db_operation(&db_counter, "select", "posts", true);
+            ApiError::gone(format!("Post with id {} was deleted", post_id)).error_response()
+        }
+        Ok(Some(post)) => {
+            cache_post(&post).await;
+            record_db_operation(&db_counter, "select", "posts", true);
+            respond_post(
+                &session,
+                post,
+                &include,
+                &db_counter,
+                &cache_counter,
+                HttpResponse::Ok().append_header(("X-Processing-Time-Ms", duration.as_millis().to_string())),
+                &ts,
+            )
+            .await
+        }
+        Ok(None) => {
+            record_db_operation(&db_counter, "select", "posts", true);
+            ApiError::not_found(format!("Post with id {} not found", post_id)).error_response()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts", false);
+            ApiError::database("Error fetching post", &e).error_response()
+        }
+    }
+}
+
+/// Respond with a single post, embedding its board when `?include=board` is set
+async fn respond_post(
+    session: &Session,
+    post: Post,
+    include: &IncludeParams,
+    db_counter: &web::Data<DbCounter>,
+    cache_counter: &web::Data<CacheCounter>,
+    builder: &mut HttpResponseBuilder,
+    ts: &TimestampFormatParams,
+) -> HttpResponse {
+    if !include.board() {
+        return respond_json(builder, &post, ts);
+    }
+    match lookup_boards(session, [post.board_id], db_counter, cache_counter).await {
+        Ok(boards) => {
+            let board = boards.get(&post.board_id).cloned();
+            respond_json(builder, &PostWithBoard { post, board }, ts)
+        }
+        Err(e) => {
+            error!("Error fetching board for post {}: {}", post.id, e);
+            ApiError::database("Error fetching board", &e).error_response()
+        }
+    }
+}
+
+/// Get a post with its comments
+///
+/// Returns the post and its oldest comments in one response. The number of comments is
+/// capped (`max_comments`, bounded by the server's limit) and `truncated` is set when some
+/// were left out; page through `GET /posts/{post_id}/comments` for the rest.
+#[utoipa::path(
+    get,
+    path = "/posts/{post_id}/full",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("max_comments" = Option<u32>, Query, description = "Maximum number of comments to include"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
+    ),
+    responses(
+        (status = 200, description = "Post with comments", body = FullThread),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
+        (status = 404, description = "Post not found"),
+        (status = 410, description = "Post was deleted (see include_deleted)"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{post_id}/full")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_full_post(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    params: Query<FullThreadParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+    let post_id = path.into_inner();
+    let config = config::get();
+    let max_comments = params.max_comments
+        .unwrap_or(config.full_thread_max_comments)
+        .min(config.full_thread_max_comments_limit) as usize;
+    let start = Instant::now();
+
+    let post = match fetch_post_from_db(&session, post_id, &integrity_counter).await {
+        Ok(Some(post)) if !post.is_deleted || include_deleted => {
+            record_db_operation(&db_counter, "select", "posts", true);
+            post
+        }
+        Ok(Some(_)) => {
+            record_db_operation(&db_counter, "select", "posts", true);
+            return ApiError::gone(format!("Post with id {} was deleted", post_id)).error_response();
+        }
+        Ok(None) => {
+            record_db_operation(&db_counter, "select", "posts", true);
+            return ApiError::not_found(format!("Post with id {} not found", post_id)).error_response();
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts", false);
+            return ApiError::database("Error fetching post", &e).error_response();
+        }
+    };
+
+    let prepared = match get_or_prepare(&session, "SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments_by_post WHERE post_id = ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "comments_by_post", false);
+            return ApiError::database("Error preparing query", &e).error_response();
+        }
+    };
+
+    let read = db_client::PagedRead::start();
+    let mut rows = match read.call(session.execute_iter(prepared, (post_id,))).await {
+        Ok(iterator) => iterator.into_typed::<(uuid::Uuid, uuid::Uuid, String, String, i64, Option<i64>, Option<bool>, Option<i64>)>(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "comments_by_post", false);
+            return ApiError::database("Error executing query", &e).error_response();
+        }
+    };
+
+    // Rows arrive oldest first, so the first `max_comments` are kept; the rest are only counted
+    let mut comments: Vec<Comment> = Vec::new();
+    let mut total_comments = 0u64;
+    while let Some(row) = read.next(&mut rows).await {
+        match row {
+            Ok((id, post_id, content, author, created_at_millis, edited_at_millis, is_deleted, deleted_at)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                if is_deleted && !include_deleted {
+                    continue;
+                }
+                total_comments += 1;
+                if comments.len() >= max_comments {
+                    continue;
+                }
+                let Some(created_at) = Utc.timestamp_millis_opt(created_at_millis).single() else {
+                    warn!("Invalid timestamp for comment {}: {}", id, created_at_millis);
+                    continue;
+                };
+                let edited_at = edited_at_millis.and_then(|millis| Utc.timestamp_millis_opt(millis).single());
+                comments.push(Comment { id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at, score: 0, reactions: Vec::new() });
+            }
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(&db_counter, "select", "comments_by_post", false);
+                return ApiError::database("Error reading row", &e).error_response();
+            }
+        }
+    }
+    record_db_operation(&db_counter, "select", "comments_by_post", true);
+
+    let truncated = total_comments as usize > max_comments;
+    votes::fill_comment_scores(&session, &mut comments).await;
+    reactions::fill_comment_reactions(&session, &mut comments).await;
+
+    let duration = start.elapsed();
+    info!("Fetched full thread for post {} ({} of {} comments, {}ms)", post_id, comments.len(), total_comments, duration.as_millis());
+    respond_json(
+        HttpResponse::Ok().append_header(("X-Processing-Time-Ms", duration.as_millis().to_string())),
+        &FullThread {
+            post,
+            comments,
+            total_comments,
+            truncated,
+        },
+        &ts,
+    )
+}
+
+/// Load a post straight from the database, bypassing the cache
+pub(crate) async fn fetch_post_from_db(
+    session: &Session,
+    post_id: Uuid,
+    integrity_counter: &web::Data<IntegrityCounter>,
+) -> Result<Option<Post>, QueryError> {
+    let rows = if let Some(prepared) = PREPARED_STATEMENTS.get() {
+        db_client::call(session.execute(&prepared.get_post_by_id, (post_id,))).await?
+    } else {
+        warn!("Prepared statement not available, using regular query");
+        db_client::call(session.query("SELECT id, board_id, title, content, author, created_at, updated_at, tags, is_deleted, deleted_at, is_locked FROM posts WHERE id = ?", (post_id,))).await?
+    };
+
+    let row = match rows.first_row() {
+        Ok(row) => row,
+        Err(_) => return Ok(None),
+    };
+
+    let id_res = row.columns[0].as_ref().and_then(|c| c.as_uuid());
+    let board_id_res = row.columns[1].as_ref().and_then(|c| c.as_uuid());
+    let title_res = row.columns[2].as_ref().and_then(|c| c.as_text());
+    let content_res = row.columns[3].as_ref().and_then(|c| c.as_text());
+    let author_res = row.columns[4].as_ref().and_then(|c| c.as_text());
+    let tags = row.columns.get(7)
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_set())
+        .map(|values| values.iter().filter_map(|v| v.as_text().cloned()).collect())
+        .unwrap_or_default();
+    
+    // Handle bigint timestamps from database
+    let created_at = if let Some(millis) = row.columns[5].as_ref().and_then(|c| c.as_bigint()) {
+        Utc.timestamp_millis_opt(millis).single().unwrap_or_else(Utc::now)
+    } else {
+        Utc::now()
+    };
+
+    let updated_at = if let Some(millis) = row.columns[6].as_ref().and_then(|c| c.as_bigint()) {
+        Utc.timestamp_millis_opt(millis).single().unwrap_or_else(Utc::now)
+    } else {
+        Utc::now()
+    };
+    
+    if let (Some(id), Some(board_id), Some(title), Some(content)) = 
+        (id_res, board_id_res, title_res, content_res) {
+        let mut post = Post {
+            id,
+            board_id,
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at,
+            updated_at,
+            author: author_or_placeholder(author_res.map(|a| a.to_string()), integrity_counter, "posts", id),
+            tags,
+            is_deleted: row.columns.get(8).and_then(|c| c.as_ref()).and_then(|c| c.as_boolean()).unwrap_or(false),
+            deleted_at: row.columns.get(9)
+                .and_then(|c| c.as_ref())
+                .and_then(|c| c.as_bigint())
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+            is_locked: row.columns.get(10).and_then(|c| c.as_ref()).and_then(|c| c.as_boolean()).unwrap_or(false),
+            score: 0,
+            reactions: Vec::new(),
+        };
+        votes::fill_post_scores(session, std::slice::from_mut(&mut post)).await;
+        reactions::fill_post_reactions(session, std::slice::from_mut(&mut post)).await;
+        return Ok(Some(post));
+    }
+
+    Ok(None)
+}
+
+/// Fill in `is_locked` of posts read from a denormalized copy, which doesn't carry the lock;
+/// on failure they stay unlocked
+pub(crate) async fn fill_post_locks(session: &Session, posts: &mut [Post]) {
+    if posts.is_empty() {
+        return;
+    }
+    let ids: Vec<Uuid> = posts.iter().map(|post| post.id).collect();
+    match execute_cached(session, "SELECT id, is_locked FROM posts WHERE id IN ?", (ids,)).await {
+        Ok(rows) => {
+            let locked: HashSet<Uuid> = rows
+                .rows_typed_or_empty::<(Uuid, Option<bool>)>()
+                .filter_map(Result::ok)
+                .filter(|(_, is_locked)| *is_locked == Some(true))
+                .map(|(id, _)| id)
+                .collect();
+            for post in posts {
+                post.is_locked = locked.contains(&post.id);
+            }
+        }
+        Err(e) => warn!("Error fetching post locks: {}", e),
+    }
+}
+
+fn post_cache_key(post_id: Uuid) -> String {
+    format!("post_{}", post_id)
+}
+
+/// Store a post in the read cache
+pub(crate) async fn cache_post(post: &Post) {
+    let cache_entry = CacheEntry::new(vec![post.clone()], Duration::from_secs(300)); // 5 minutes TTL
+    if let Some(posts_cache) = POSTS_CACHE.get() {
+        let mut cache = posts_cache.write().await;
+        cache.insert(post_cache_key(post.id), cache_entry);
+        record_cache_size("posts", cache.len());
+    }
+}
+
+/// Drop a post from the read cache, returning whether an entry was present
+pub(crate) async fn invalidate_post_cache(post_id: Uuid) -> bool {
+    match POSTS_CACHE.get() {
+        Some(posts_cache) => {
+            let mut cache = posts_cache.write().await;
+            let removed = cache.remove(&post_cache_key(post_id)).is_some();
+            record_cache_size("posts", cache.len());
+            removed
+        }
+        None => false,
+    }
+}
+
+/// Allow a change to a post or comment by its owner or a moderator (bearer token), or with
+/// the admin token
+fn authorize_owner_change(
+    req: &HttpRequest,
+    user: Option<&AuthenticatedUser>,
+    owner: Option<Uuid>,
+    kind: &str,
+    id: Uuid,
+    action: &str,
+) -> Result<(), ApiError> {
+    require_role(
+        req,
+        user,
+        Role::Moderator,
+        owner,
+        &format!("{} {} {} of {:?}", action, kind, id, owner),
+        &format!("Only the {}'s author or a moderator may {} it", kind, action),
+    )
+}
+
+/// Edit a post
+///
+/// Changes the title and/or content (normalized like on creation) and bumps `updated_at`, so
+/// delta sync clients pick the edit up. The write only applies if the post wasn't changed
+/// since it was read; pass `expected_updated_at` to also detect edits made since the client
+/// loaded the post. Every edit is saved to the post's history (`GET /posts/{post_id}/revisions`).
+/// Allowed for the post's owner and moderators (bearer token), or with the admin token.
+#[utoipa::path(
+    put,
+    path = "/posts/{post_id}",
+    request_body = UpdatePostRequest,
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Post updated", body = Post),
+        (status = 400, description = "Neither title nor content given, empty title, or unknown field in the body"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of someone other than the owner or a moderator"),
+        (status = 404, description = "Post not found"),
+        (status = 409, description = "The post was changed concurrently or since expected_updated_at"),
+        (status = 422, description = "Empty or too long title or content; every failing field is listed in errors"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/posts/{post_id}")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_post(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    update: web::Json<UpdatePostRequest>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let post_id = path.into_inner();
+    let update = update.into_inner();
+    if update.title.is_none() && update.content.is_none() {
+        return ApiError::bad_request("title or content must be given").error_response();
+    }
+    if let Err(e) = validation::update_post(&update) {
+        warn!("Rejecting invalid edit of post {}: {}", post_id, e);
+        return e.error_response();
+    }
+
+    let row = execute_cached(
+        &session,
+        "SELECT board_id, title, content, author, created_at, updated_at, tags, user_id, is_deleted, revision, is_locked FROM posts WHERE id = ?",
+        (post_id,),
+    ).await;
+    type PostRow = (Option<Uuid>, Option<String>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>, Option<Uuid>, Option<bool>, Option<i32>, Option<bool>);
+    let (board_id, title, content, author, created_at, updated_at, tags, owner, revision, is_locked) = match row.map(|rows| rows.maybe_first_row_typed::<PostRow>()) {
+        // Deleted posts can't be edited until a moderator restores them
+        Ok(Ok(Some((board_id, title, content, author, created_at, updated_at, tags, owner, is_deleted, revision, is_locked)))) if is_deleted != Some(true) => {
+            (board_id, title, content, author, created_at, updated_at, tags, owner, revision, is_locked)
+        }
+        Ok(Ok(_)) => {
+            record_db_operation(&db_counter, "select", "posts", true);
+            return ApiError::not_found(format!("Post with id {} not found", post_id)).error_response();
+        }
+        Ok(Err(e)) => {
+            record_db_operation(&db_counter, "select", "posts", false);
+            error!("Error reading post {}: {}", post_id, e);
+            return ApiError::internal(format!("Error fetching post: {}", e)).error_response();
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts", false);
+            error!("Error fetching post {}: {}", post_id, e);
+            return ApiError::database("Error fetching post", &e).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "posts", true);
+    let (board_id, created_at, previous_updated_at) = match (board_id, created_at, updated_at) {
+        (Some(board_id), Some(created_at), Some(updated_at)) => (board_id, created_at, updated_at),
+        _ => {
+            error!("Post {} is missing board_id, created_at or updated_at", post_id);
+            integrity_counter.0.with_label_values(&["posts", "updated_at"]).inc();
+            return ApiError::internal(format!("Post {} is incomplete and can't be edited", post_id)).error_response();
+        }
+    };
+
+    if let Err(e) = authorize_owner_change(&req, user.as_ref(), owner, "post", post_id, "edit") {
+        return e.error_response();
+    }
+
+    if let Some(expected) = update.expected_updated_at {
+        if expected.timestamp_millis() != previous_updated_at {
+            info!("Rejecting stale edit of post {}", post_id);
+            return ApiError::conflict(format!("Post {} was changed since {}", post_id, expected.to_rfc3339())).error_response();
+        }
+    }
+
+    // Posts that were never edited have no revision number yet: they are revision 1, which is
+    // saved to the history along with this edit
+    let replaced = revision.is_none().then(|| PostRevision {
+        post_id,
+        revision: 1,
+        title: title.clone().unwrap_or_default(),
+        content: content.clone().unwrap_or_default(),
+        editor: author.clone(),
+        edited_at: Utc.timestamp_millis_opt(previous_updated_at).single().unwrap_or_default(),
+    });
+    let revision = revision.unwrap_or(1) + 1;
+    let previous_title = title.clone().unwrap_or_default();
+
+    let (title, content) = normalize_post_text(
+        update.title.as_deref().or(title.as_deref()).unwrap_or_default(),
+        update.content.as_deref().or(content.as_deref()).unwrap_or_default(),
+    );
+    if title.trim().is_empty() {
+        return ApiError::bad_request("title must not be empty").error_response();
+    }
+
+    // Strictly later than the previous version, so the change feed orders edits correctly
+    let updated_at = Utc::now().timestamp_millis().max(previous_updated_at + 1);
+    let result = execute_cached(
+        &session,
+        "UPDATE posts SET title = ?, content = ?, updated_at = ?, revision = ? WHERE id = ? IF updated_at = ?",
+        (&title, &content, updated_at, revision, post_id, previous_updated_at),
+    ).await;
+    let applied = match result {
+        Ok(result) => result
+            .rows
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.columns.first())
+            .and_then(|c| c.as_ref())
+            .and_then(|c| c.as_boolean())
+            .unwrap_or(true),
+        Err(e) => {
+            record_db_operation(&db_counter, "update", "posts", false);
+            error!("Error updating post {}: {}", post_id, e);
+            return ApiError::database("Error updating post", &e).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "update", "posts", true);
+    if !applied {
+        info!("Concurrent edit of post {} detected", post_id);
+        return ApiError::conflict(format!("Post {} was changed concurrently, reload it and retry", post_id)).error_response();
+    }
+
+    let mut post = Post {
+        id: post_id,
+        board_id,
+        title,
+        content,
+        created_at: Utc.timestamp_millis_opt(created_at).single().unwrap_or_default(),
+        updated_at: Utc.timestamp_millis_opt(updated_at).single().unwrap_or_default(),
+        author: author_or_placeholder(author, &integrity_counter, "posts", post_id),
+        tags: tags.unwrap_or_default(),
+        is_deleted: false,
+        deleted_at: None,
+        is_locked: is_locked.unwrap_or(false),
+        score: 0,
+        reactions: Vec::new(),
+    };
+    votes::fill_post_scores(&session, std::slice::from_mut(&mut post)).await;
+    reactions::fill_post_reactions(&session, std::slice::from_mut(&mut post)).await;
+    invalidate_post_cache(post_id).await;
+
+    // Like the change feed below, the history is written after the post is saved, so failures
+    // are logged rather than reported
+    let saved = PostRevision {
+        post_id,
+        revision,
+        title: post.title.clone(),
+        content: post.content.clone(),
+        editor: user.as_ref().map(|user| user.username.clone()),
+        edited_at: post.updated_at,
+    };
+    let mut versions = Vec::with_capacity(2);
+    if let Some(replaced) = &replaced {
+        versions.push((replaced, owner));
+    }
+    versions.push((&saved, user.as_ref().map(|user| user.user_id)));
+    for (version, editor_id) in versions {
+        match post_revisions::insert(&session, version, editor_id).await {
+            Ok(()) => record_db_operation(&db_counter, "insert", "post_revisions", true),
+            Err(e) => {
+                error!("Post {} updated but revision {} was not saved: {}", post_id, version.revision, e);
+                record_db_operation(&db_counter, "insert", "post_revisions", false);
+            }
+        }
+    }
+
+    // Update the board copy and move the change-feed row and the updated_at ordering row to the
+    // new updated_at. The post itself is already saved, so failures here are logged rather than
+    // reported.
+    let mut batch = db::new_batch(BatchType::Logged);
+    let mut prepared_all = true;
+    for cql in [
+        "UPDATE posts_by_board SET title = ?, content = ?, updated_at = ? WHERE board_id = ? AND created_at = ? AND id = ?",
+        "DELETE FROM posts_by_updated WHERE day = ? AND updated_at = ? AND id = ?",
+        "INSERT INTO posts_by_updated (day, updated_at, id, board_id, title, content, author, created_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "DELETE FROM post_order WHERE board_id = ? AND sort = ? AND value = ? AND id = ?",
+        "INSERT INTO post_order (board_id, sort, value, id) VALUES (?, ?, ?, ?)",
+    ] {
+        match get_or_prepare(&session, cql).await {
+            Ok(prepared) => batch.append_statement(prepared),
+            Err(e) => {
+                error!("Error preparing change feed update for post {}: {}", post_id, e);
+                prepared_all = false;
+            }
+        }
+    }
+    if prepared_all {
+        let moved = db_client::call(session.batch(
+            &batch,
+            (
+                (&post.title, &post.content, updated_at, board_id, created_at, post_id),
+                (db::updated_day(previous_updated_at), previous_updated_at, post_id),
+                (db::updated_day(updated_at), updated_at, post_id, board_id, &post.title, &post.content, &post.author, created_at, &post.tags),
+                (board_id, SortKey::UpdatedAt.as_str(), previous_updated_at, post_id),
+                (board_id, SortKey::UpdatedAt.as_str(), updated_at, post_id),
+            ),
+        )).await;
+        match moved {
+            Ok(_) => record_db_operation(&db_counter, "update", "posts_by_updated", true),
+            Err(e) => {
+                error!("Post {} updated but its change feed row was not: {}", post_id, e);
+                record_db_operation(&db_counter, "update", "posts_by_updated", false);
+            }
+        }
+    } else {
+        record_db_operation(&db_counter, "update", "posts_by_updated", false);
+    }
+
+    if !post.tags.is_empty() {
+        if let Err(e) = index_post_tags(&session, &post).await {
+            error!("Post {} updated but tag index update failed: {}", post_id, e);
+            record_db_operation(&db_counter, "insert", "posts_by_tag", false);
+        } else {
+            record_db_operation(&db_counter, "insert", "posts_by_tag", true);
+        }
+    }
+
+    if let Err(e) = execute_cached(
+        &session,
+        "UPDATE posts_by_author SET title = ?, content = ?, updated_at = ? WHERE author = ? AND created_at = ? AND id = ? IF EXISTS",
+        (&post.title, &post.content, updated_at, &post.author, created_at, post_id),
+    ).await {
+        error!("Post {} updated but its author copy was not: {}", post_id, e);
+        record_db_operation(&db_counter, "update", "posts_by_author", false);
+    } else {
+        record_db_operation(&db_counter, "update", "posts_by_author", true);
+    }
+
+    if let Err(e) = title_search::retitle(&session, board_id, post_id, &previous_title, &post.title, created_at).await {
+        error!("Post {} updated but its title index was not: {}", post_id, e);
+        record_db_operation(&db_counter, "update", "posts_by_title_prefix", false);
+    } else {
+        record_db_operation(&db_counter, "update", "posts_by_title_prefix", true);
+    }
+
+    search::index_post(&post);
+    info!("Post {} edited", post_id);
+    respond_json(&mut HttpResponse::Ok(), &post, &ts)
+}
+
+/// Delete a post with its comments, tag index and change-feed rows, and drop it from the
+/// caches; returns the number of comments removed
+///
+/// A soft-deleted post no longer counts towards its tags' usage, so their counts are left alone.
+pub(crate) async fn remove_post(
+    session: &Session,
+    post_id: Uuid,
+    post: &PostDeletionRow,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<usize, QueryError> {
+    let (board_id, created_at, updated_at, tags) = (post.board_id, post.created_at, post.updated_at, &post.tags);
+    // Comments go first, so an interrupted delete leaves a post that can be deleted again
+    let comments = match execute_cached(session, "SELECT id, created_at, author FROM comments_by_post WHERE post_id = ?", (post_id,)).await {
+        Ok(rows) => rows
+            .rows_typed::<(Uuid, i64, Option<String>)>()
+            .map(|rows| rows.filter_map(Result::ok).collect::<Vec<_>>())
+            .unwrap_or_default(),
+        Err(e) => {
+            record_db_operation(db_counter, "select", "comments_by_post", false);
+            error!("Error listing comments of post {}: {}", post_id, e);
+            return Err(e);
+        }
+    };
+    record_db_operation(db_counter, "select", "comments_by_post", true);
+
+    for (comment_id, comment_created_at, comment_author) in &comments {
+        if let Some(author) = comment_author {
+            if let Err(e) = execute_cached(
+                session,
+                "DELETE FROM comments_by_author WHERE author = ? AND created_at = ? AND id = ?",
+                (author, *comment_created_at, *comment_id),
+            ).await {
+                record_db_operation(db_counter, "delete", "comments_by_author", false);
+                error!("Error deleting comment {} of post {}: {}", comment_id, post_id, e);
+                return Err(e);
+            }
+        }
+        if let Some(board_id) = board_id {
+            if let Err(e) = execute_cached(
+                session,
+                "DELETE FROM comments_by_board WHERE board_id = ? AND created_at = ? AND id = ?",
+                (board_id, *comment_created_at, *comment_id),
+            ).await {
+                record_db_operation(db_counter, "delete", "comments_by_board", false);
+                error!("Error deleting comment {} of post {}: {}", comment_id, post_id, e);
+                return Err(e);
+            }
+        }
+        if let Err(e) = execute_cached(session, "DELETE FROM comments WHERE id = ?", (*comment_id,)).await {
+            record_db_operation(db_counter, "delete", "comments", false);
+            error!("Error deleting comment {} of post {}: {}", comment_id, post_id, e);
+            return Err(e);
+        }
+        if let Err(e) = votes::remove_comment_votes(session, *comment_id).await {
+            record_db_operation(db_counter, "delete", "votes", false);
+            error!("Error deleting votes on comment {} of post {}: {}", comment_id, post_id, e);
+            return Err(e);
+        }
+        if let Err(e) = reactions::remove_reactions(session, *comment_id).await {
+            record_db_operation(db_counter, "delete", "reactions", false);
+            error!("Error deleting reactions to comment {} of post {}: {}", comment_id, post_id, e);
+            return Err(e);
+        }
+    }
+    if !comments.is_empty() {
+        record_db_operation(db_counter, "delete", "comments", true);
+    }
+    // The post's copies go last: they are how a retried delete finds the comments left
+    if let Err(e) = execute_cached(session, "DELETE FROM comments_by_post WHERE post_id = ?", (post_id,)).await {
+        record_db_operation(db_counter, "delete", "comments_by_post", false);
+        error!("Error deleting the comments of post {}: {}", post_id, e);
+        return Err(e);
+    }
+    if let Err(e) = list_order::remove_comments(session, post_id).await {
+        record_db_operation(db_counter, "delete", "comment_order", false);
+        error!("Error deleting the comment ordering rows of post {}: {}", post_id, e);
+        return Err(e);
+    }
+
+    if let Some(created_at) = created_at {
+        for tag in tags {
+            if let Err(e) = execute_cached(
+                session,
+                "DELETE FROM posts_by_tag WHERE tag = ? AND created_at = ? AND id = ?",
+                (tag, created_at, post_id),
+            ).await {
+                record_db_operation(db_counter, "delete", "posts_by_tag", false);
+                error!("Error removing post {} from tag {}: {}", post_id, tag, e);
+                return Err(e);
+            }
+        }
+    }
+
+    if let (Some(author), Some(created_at)) = (&post.author, created_at) {
+        if let Err(e) = execute_cached(
+            session,
+            "DELETE FROM posts_by_author WHERE author = ? AND created_at = ? AND id = ?",
+            (author, created_at, post_id),
+        ).await {
+            record_db_operation(db_counter, "delete", "posts_by_author", false);
+            error!("Error removing post {} from its author's posts: {}", post_id, e);
+            return Err(e);
+        }
+    }
+
+    // A soft-deleted post has no title rows left
+    if let (Some(board_id), Some(created_at), false) = (board_id, created_at, post.is_deleted) {
+        if let Err(e) = title_search::remove(session, board_id, post_id, &post.title, created_at).await {
+            record_db_operation(db_counter, "delete", "posts_by_title_prefix", false);
+            error!("Error removing the title index of post {}: {}", post_id, e);
+            return Err(e);
+        }
+    }
+
+    // The score row is keyed by the score, so it has to be read before the votes go. The board
+    // copy goes with the ordering rows: board deletion finds the remaining posts by it
+    if let (Some(board_id), Some(created_at)) = (board_id, created_at) {
+        let score = match votes::post_scores(session, vec![post_id]).await {
+            Ok(scores) => scores.get(&post_id).copied().unwrap_or(0),
+            Err(e) => {
+                record_db_operation(db_counter, "select", "post_score", false);
+                error!("Error fetching the score of post {}: {}", post_id, e);
+                return Err(e);
+            }
+        };
+        let values = [
+            (SortKey::UpdatedAt, updated_at.unwrap_or(created_at)),
+            (SortKey::Score, score),
+        ];
+        for (key, value) in values {
+            if let Err(e) = execute_cached(
+                session,
+                "DELETE FROM post_order WHERE board_id = ? AND sort = ? AND value = ? AND id = ?",
+                (board_id, key.as_str(), value, post_id),
+            ).await {
+                record_db_operation(db_counter, "delete", "post_order", false);
+                error!("Error removing post {} from the board's orderings: {}", post_id, e);
+                return Err(e);
+            }
+        }
+        if let Err(e) = execute_cached(
+            session,
+            "DELETE FROM posts_by_board WHERE board_id = ? AND created_at = ? AND id = ?",
+            (board_id, created_at, post_id),
+        ).await {
+            record_db_operation(db_counter, "delete", "posts_by_board", false);
+            error!("Error removing post {} from its board's posts: {}", post_id, e);
+            return Err(e);
+        }
+    }
+
+    if let Err(e) = post_revisions::remove(session, post_id).await {
+        record_db_operation(db_counter, "delete", "post_revisions", false);
+        error!("Error deleting revisions of post {}: {}", post_id, e);
+        return Err(e);
+    }
+    if let Err(e) = votes::remove_post_votes(session, post_id).await {
+        record_db_operation(db_counter, "delete", "votes", false);
+        error!("Error deleting votes on post {}: {}", post_id, e);
+        return Err(e);
+    }
+    if let Err(e) = reactions::remove_reactions(session, post_id).await {
+        record_db_operation(db_counter, "delete", "reactions", false);
+        error!("Error deleting reactions to post {}: {}", post_id, e);
+        return Err(e);
+    }
+
+    // The post and its change-feed row go together
+    let mut batch = db::new_batch(BatchType::Logged);
+    for cql in [
+        "DELETE FROM posts_by_updated WHERE day = ? AND updated_at = ? AND id = ?",
+        "DELETE FROM posts WHERE id = ?",
+    ] {
+        match get_or_prepare(session, cql).await {
+            Ok(prepared) => batch.append_statement(prepared),
+            Err(e) => {
+                record_db_operation(db_counter, "delete", "posts", false);
+                return Err(e);
+            }
+        }
+    }
+    let updated_at = updated_at.unwrap_or_default();
+    if let Err(e) = db_client::call(session.batch(&batch, ((db::updated_day(updated_at), updated_at, post_id), (post_id,)))).await {
+        record_db_operation(db_counter, "delete", "posts", false);
+        error!("Error deleting post {}: {}", post_id, e);
+        return Err(e);
+    }
+    record_db_operation(db_counter, "delete", "posts", true);
+
+    if !tags.is_empty() && !post.is_deleted {
+        if let Err(e) = update_tag_counts(session, tags, -1).await {
+            error!("Post {} deleted but tag usage counts were not updated: {}", post_id, e);
+            record_db_operation(db_counter, "update", "tags", false);
+        } else {
+            record_db_operation(db_counter, "update", "tags", true);
+        }
+    }
+    if let (Some(board_id), false) = (board_id, post.is_deleted) {
+        counts::add_posts(session, board_id, -1, db_counter).await;
+    }
+    if let Err(e) = counts::remove_post(session, post_id).await {
+        error!("Post {} deleted but its comment count was not: {}", post_id, e);
+        record_db_operation(db_counter, "delete", "post_comment_counts", false);
+    }
+
+    invalidate_post_cache(post_id).await;
+    search::remove(post_id);
+    for (comment_id, _, _) in &comments {
+        search::remove(*comment_id);
+    }
+    if let Some(count_cache) = COMMENT_COUNT_CACHE.get() {
+        let mut cache = count_cache.write().await;
+        cache.remove(&post_id);
+        record_cache_size("comment_counts", cache.len());
+    }
+    if let (Some(board_id), Some(stats_cache)) = (board_id, BOARD_STATS_CACHE.get()) {
+        let mut cache = stats_cache.write().await;
+        cache.remove(&board_id);
+        record_cache_size("board_stats", cache.len());
+    }
+
+    Ok(comments.len())
+}
+
+/// Mark a post deleted at `deleted_at`, or restore it with `None`, on the post and its copies
+///
+/// The copies are keyed by timestamps, so they are updated with IF EXISTS to keep a mismatch
+/// from creating stray rows; the post itself goes last.
+async fn set_post_deleted(
+    session: &Session,
+    post_id: Uuid,
+    post: &PostDeletionRow,
+    deleted_at: Option<i64>,
+) -> Result<(), QueryError> {
+    let (created_at, updated_at, tags) = (post.created_at, post.updated_at, &post.tags);
+    let is_deleted = deleted_at.is_some();
+    if let (Some(board_id), Some(created_at)) = (post.board_id, created_at) {
+        execute_cached(
+            session,
+            "UPDATE posts_by_board SET is_deleted = ?, deleted_at = ? WHERE board_id = ? AND created_at = ? AND id = ? IF EXISTS",
+            (is_deleted, deleted_at, board_id, created_at, post_id),
+        ).await?;
+    }
+    if let Some(updated_at) = updated_at {
+        execute_cached(
+            session,
+            "UPDATE posts_by_updated SET is_deleted = ?, deleted_at = ? WHERE day = ? AND updated_at = ? AND id = ? IF EXISTS",
+            (is_deleted, deleted_at, db::updated_day(updated_at), updated_at, post_id),
+        ).await?;
+    }
+    if let Some(created_at) = created_at {
+        for tag in tags {
+            execute_cached(
+                session,
+                "UPDATE posts_by_tag SET is_deleted = ?, deleted_at = ? WHERE tag = ? AND created_at = ? AND id = ? IF EXISTS",
+                (is_deleted, deleted_at, tag, created_at, post_id),
+            ).await?;
+        }
+    }
+    if let (Some(author), Some(created_at)) = (&post.author, created_at) {
+        execute_cached(
+            session,
+            "UPDATE posts_by_author SET is_deleted = ?, deleted_at = ? WHERE author = ? AND created_at = ? AND id = ? IF EXISTS",
+            (is_deleted, deleted_at, author, created_at, post_id),
+        ).await?;
+    }
+    execute_cached(
+        session,
+        "UPDATE posts SET is_deleted = ?, deleted_at = ? WHERE id = ?",
+        (is_deleted, deleted_at, post_id),
+    ).await?;
+    invalidate_post_cache(post_id).await;
+    Ok(())
+}
+
+/// Fields of a post needed to delete or restore it
+pub(crate) struct PostDeletionRow {
+    pub board_id: Option<Uuid>,
+    pub title: String,
+    pub author: Option<String>,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+    pub tags: Vec<String>,
+    pub owner: Option<Uuid>,
+    pub is_deleted: bool,
+}
+
+async fn fetch_post_for_deletion(
+    session: &Session,
+    post_id: Uuid,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<PostDeletionRow, ApiError> {
+    let row = execute_cached(
+        session,
+        "SELECT board_id, title, author, created_at, updated_at, tags, user_id, is_deleted FROM posts WHERE id = ?",
+        (post_id,),
+    ).await;
+    type Row = (Option<Uuid>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Vec<String>>, Option<Uuid>, Option<bool>);
+    match row.map(|rows| rows.maybe_first_row_typed::<Row>()) {
+        Ok(Ok(Some((board_id, title, author, created_at, updated_at, tags, owner, is_deleted)))) => {
+            record_db_operation(db_counter, "select", "posts", true);
+            Ok(PostDeletionRow {
+                board_id,
+                title: title.unwrap_or_default(),
+                author,
+                created_at,
+                updated_at,
+                tags: tags.unwrap_or_default(),
+                owner,
+                is_deleted: is_deleted.unwrap_or(false),
+            })
+        }
+        Ok(Ok(None)) => {
+            record_db_operation(db_counter, "select", "posts", true);
+            Err(ApiError::not_found(format!("Post with id {} not found", post_id)))
+        }
+        Ok(Err(e)) => {
+            record_db_operation(db_counter, "select", "posts", false);
+            error!("Error reading post {}: {}", post_id, e);
+            Err(ApiError::internal(format!("Error fetching post: {}", e)))
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "posts", false);
+            error!("Error fetching post {}: {}", post_id, e);
+            Err(ApiError::database("Error fetching post", &e))
+        }
+    }
+}
+
+/// Delete a post
+///
+/// Soft-deletes the post: it is hidden from reads (see `include_deleted`) until a moderator
+/// restores it. Allowed for the post's owner and moderators (bearer token), or with the admin
+/// token. With `purge=true` (moderators and the admin token only) the post is removed for good
+/// together with its comments, edit history, tag index and change-feed rows.
+#[utoipa::path(
+    delete,
+    path = "/posts/{post_id}",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("purge" = Option<bool>, Query, description = "Delete permanently, with the comments (moderator token or X-Admin-Token)"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token")
+    ),
     responses(
-        (status = 201, description = "Post created successfully", body = Post),
-        (status = 400, description = "Board not found"),
+        (status = 204, description = "Post deleted (or purged with its comments)"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of someone other than the owner or a moderator, or purge without moderator rights"),
+        (status = 404, description = "Post not found or already deleted"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[post("/posts")]
-// #[instrument(name = "create_post", skip(session, db_counter), fields(board_id = %post_data.board_id, title = %post_data.title, author = %post_data.author))]
-pub async fn create_post(
+#[delete("/posts/{post_id}")]
+pub async fn delete_post(
+    req: HttpRequest,
     session: web::Data<Arc<Session>>,
-    post_data: web::Json<CreatePostRequest>,
+    path: web::Path<Uuid>,
+    purge: Query<PurgeParams>,
+    user: Option<AuthenticatedUser>,
     db_counter: web::Data<DbCounter>,
 ) -> impl Responder {
-    info!("Creating new post: '{}' by {} on board {}", post_data.title, post_data.author, post_data.board_id);
-    
+    let post_id = path.into_inner();
+    let post = match fetch_post_for_deletion(&session, post_id, &db_counter).await {
+        Ok(post) => post,
+        Err(e) => return e.error_response(),
+    };
+
+    if purge.purge {
+        if let Err(e) = authorize_moderation(&req, user.as_ref(), "post", post_id, "purge") {
+            return e.error_response();
+        }
+        let comments = match remove_post(&session, post_id, &post, &db_counter).await {
+            Ok(comments) => comments,
+            Err(e) => return ApiError::database("Error deleting post", &e).error_response(),
+        };
+        info!("Post {} purged with {} comments", post_id, comments);
+        return HttpResponse::NoContent().finish();
+    }
+
+    if let Err(e) = authorize_owner_change(&req, user.as_ref(), post.owner, "post", post_id, "delete") {
+        return e.error_response();
+    }
+    if post.is_deleted {
+        return ApiError::not_found(format!("Post with id {} not found", post_id)).error_response();
+    }
+
+    let deleted_at = Utc::now().timestamp_millis();
+    if let Err(e) = set_post_deleted(&session, post_id, &post, Some(deleted_at)).await {
+        record_db_operation(&db_counter, "update", "posts", false);
+        error!("Error deleting post {}: {}", post_id, e);
+        return ApiError::database("Error deleting post", &e).error_response();
+    }
+    record_db_operation(&db_counter, "update", "posts", true);
+    search::remove(post_id);
+    if let Some(board_id) = post.board_id {
+        counts::add_posts(&session, board_id, -1, &db_counter).await;
+    }
+    if let (Some(board_id), Some(created_at)) = (post.board_id, post.created_at) {
+        if let Err(e) = title_search::remove(&session, board_id, post_id, &post.title, created_at).await {
+            error!("Post {} deleted but its title is still indexed: {}", post_id, e);
+            record_db_operation(&db_counter, "delete", "posts_by_title_prefix", false);
+        } else {
+            record_db_operation(&db_counter, "delete", "posts_by_title_prefix", true);
+        }
+    }
+
+    if !post.tags.is_empty() {
+        if let Err(e) = update_tag_counts(&session, &post.tags, -1).await {
+            error!("Post {} deleted but tag usage counts were not updated: {}", post_id, e);
+            record_db_operation(&db_counter, "update", "tags", false);
+        } else {
+            record_db_operation(&db_counter, "update", "tags", true);
+        }
+    }
+
+    info!("Post {} deleted", post_id);
+    HttpResponse::NoContent().finish()
+}
+
+/// Restore a deleted post
+///
+/// Undoes `DELETE /posts/{post_id}` (not a purge). Takes a moderator's bearer token or the
+/// admin token.
+#[utoipa::path(
+    post,
+    path = "/posts/{post_id}/restore",
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Post restored (or was not deleted)", body = Post),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Post not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/posts/{post_id}/restore")]
+pub async fn restore_post(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let post_id = path.into_inner();
+    if let Err(e) = authorize_moderation(&req, user.as_ref(), "post", post_id, "restore") {
+        return e.error_response();
+    }
+    let post = match fetch_post_for_deletion(&session, post_id, &db_counter).await {
+        Ok(post) => post,
+        Err(e) => return e.error_response(),
+    };
+
+    if post.is_deleted {
+        if let Err(e) = set_post_deleted(&session, post_id, &post, None).await {
+            record_db_operation(&db_counter, "update", "posts", false);
+            error!("Error restoring post {}: {}", post_id, e);
+            return ApiError::database("Error restoring post", &e).error_response();
+        }
+        record_db_operation(&db_counter, "update", "posts", true);
+        if let Some(board_id) = post.board_id {
+            counts::add_posts(&session, board_id, 1, &db_counter).await;
+        }
+
+        if !post.tags.is_empty() {
+            if let Err(e) = update_tag_counts(&session, &post.tags, 1).await {
+                error!("Post {} restored but tag usage counts were not updated: {}", post_id, e);
+                record_db_operation(&db_counter, "update", "tags", false);
+            } else {
+                record_db_operation(&db_counter, "update", "tags", true);
+            }
+        }
+        if let (Some(board_id), Some(created_at)) = (post.board_id, post.created_at) {
+            if let Err(e) = title_search::index(&session, board_id, post_id, &post.title, created_at).await {
+                error!("Post {} restored but its title was not indexed: {}", post_id, e);
+                record_db_operation(&db_counter, "insert", "posts_by_title_prefix", false);
+            } else {
+                record_db_operation(&db_counter, "insert", "posts_by_title_prefix", true);
+            }
+        }
+        info!("Post {} restored", post_id);
+    }
+
+    match fetch_post_from_db(&session, post_id, &integrity_counter).await {
+        Ok(Some(post)) => {
+            search::index_post(&post);
+            respond_json(&mut HttpResponse::Ok(), &post, &ts)
+        }
+        Ok(None) => ApiError::not_found(format!("Post with id {} not found", post_id)).error_response(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts", false);
+            ApiError::database("Error fetching post", &e).error_response()
+        }
+    }
+}
+
+/// Lock or unlock a post
+///
+/// A locked post takes no new comments (`POST /comments` answers 403); existing comments stay.
+/// Takes a moderator's bearer token or the admin token.
+#[utoipa::path(
+    put,
+    path = "/posts/{post_id}/lock",
+    request_body = LockPostRequest,
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Lock state changed (or already as requested)", body = Post),
+        (status = 400, description = "Unknown field in the body"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Post not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/posts/{post_id}/lock")]
+pub async fn lock_post(
+    _moderator: Moderator,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    request: web::Json<LockPostRequest>,
+    db_counter: web::Data<DbCounter>,
+    integrity_counter: web::Data<IntegrityCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let post_id = path.into_inner();
+    let locked = request.locked;
+    let action = if locked { "lock" } else { "unlock" };
+
+    let mut post = match fetch_post_from_db(&session, post_id, &integrity_counter).await {
+        Ok(Some(post)) if !post.is_deleted => post,
+        Ok(_) => return ApiError::not_found(format!("Post with id {} not found", post_id)).error_response(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts", false);
+            error!("Error fetching post {}: {}", post_id, e);
+            return ApiError::database("Error fetching post", &e).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "posts", true);
+
+    if post.is_locked != locked {
+        // The board copy first (IF EXISTS, like the other copies), so a failure leaves both as they were
+        if let Err(e) = execute_cached(
+            &session,
+            "UPDATE posts_by_board SET is_locked = ? WHERE board_id = ? AND created_at = ? AND id = ? IF EXISTS",
+            (locked, post.board_id, post.created_at.timestamp_millis(), post_id),
+        ).await {
+            record_db_operation(&db_counter, "update", "posts_by_board", false);
+            error!("Error changing lock of post {}: {}", post_id, e);
+            return ApiError::database("Error changing lock", &e).error_response();
+        }
+        if let Err(e) = execute_cached(&session, "UPDATE posts SET is_locked = ? WHERE id = ?", (locked, post_id)).await {
+            record_db_operation(&db_counter, "update", "posts", false);
+            error!("Error changing lock of post {}: {}", post_id, e);
+            return ApiError::database("Error changing lock", &e).error_response();
+        }
+        record_db_operation(&db_counter, "update", "posts", true);
+        invalidate_post_cache(post_id).await;
+        post.is_locked = locked;
+        info!("Post {} {}ed", post_id, action);
+    }
+
+    respond_json(&mut HttpResponse::Ok(), &post, &ts)
+}
+
+// Comment related endpoints
+/// Create a new comment
+///
+/// Creates a new comment on a specific post
+#[utoipa::path(
+    post,
+    path = "/comments",
+    request_body = CreateCommentRequest,
+    params(
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 201, description = "Comment created successfully", body = Comment),
+        (status = 400, description = "Post not found, unknown user_id or author not matching it, created_at too far in the future, or unknown field in the body"),
+        (status = 422, description = "Empty or too long content, or missing or invalid author; every failing field is listed in errors"),
+        (status = 401, description = "created_at supplied without a valid X-Admin-Token, or missing or invalid bearer token while JWT_SECRET is set"),
+        (status = 403, description = "The post is locked, created_at supplied while admin endpoints are disabled, or user_id without a bearer token of that user"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/comments")]
+// #[instrument(name = "create_comment", skip(session, db_counter), fields(post_id = %comment_data.post_id, author = %comment_data.author))]
+pub async fn create_comment(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    comment_data: web::Json<CreateCommentRequest>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    if let Err(e) = validation::create_comment(&comment_data, user.is_some()) {
+        warn!("Rejecting invalid comment on post {}: {}", comment_data.post_id, e);
+        return e.error_response();
+    }
+
+    let (author, user_id) = match auth::resolve_author(&session, user.as_ref(), comment_data.user_id, &comment_data.author, &db_counter).await {
+        Ok(resolved) => resolved,
+        Err(e) => return e.error_response(),
+    };
+
+    info!("Creating comment for post_id: {}, author: {}", comment_data.post_id, author);
+
+    let created_at = match resolve_created_at(&req, comment_data.created_at) {
+        Ok(created_at) => created_at,
+        Err(e) => return e.error_response(),
+    };
+
     let start = Instant::now();
     
-    // First check if the board exists
-    debug!("Checking if board exists: {}", post_data.board_id);
-    let board_check = match session.prepare("SELECT id FROM boards WHERE id = ?").await {
-        Ok(p) => {
-            debug!("Board check query prepared successfully");
-            p
-        },
-        Err(e) => {
-            error!("Error preparing board check query: {}", e);
-            record_db_operation(&db_counter, "select", "boards", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
+    // First check if the post exists, and find its board for the per-board comment index
+    let post_check = match get_or_prepare(&session, "SELECT board_id, is_deleted, is_locked FROM posts WHERE id = ?").await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("Error preparing query: {}", e);
+            record_db_operation(&db_counter, "select", "posts", false);
+            return ApiError::database("Error preparing query", &e).error_response();
         }
     };
     
-    let board_result = session.execute(&board_check, (post_data.board_id,)).await;
+    let post_result = db_client::call(session.execute(&post_check, (comment_data.post_id,))).await;
     
-    match board_result {
+    let board_id = match post_result {
         Ok(rows) => {
-            if rows.rows.unwrap_or_default().is_empty() {
-                warn!("Board with id {} not found", post_data.board_id);
-                record_db_operation(&db_counter, "select", "boards", true);
-                return HttpResponse::BadRequest().body(format!("Board with id {} not found", post_data.board_id));
-            } else {
-                debug!("Board exists, proceeding with post creation");
-                record_db_operation(&db_counter, "select", "boards", true);
+            record_db_operation(&db_counter, "select", "posts", true);
+            match rows.maybe_first_row_typed::<(Option<Uuid>, Option<bool>, Option<bool>)>() {
+                Ok(Some((_, is_deleted, Some(true)))) if is_deleted != Some(true) => {
+                    info!("Rejected comment on locked post {}", comment_data.post_id);
+                    return ApiError::forbidden(format!("Post {} is locked", comment_data.post_id)).error_response();
+                }
+                Ok(Some((board_id, is_deleted, _))) if is_deleted != Some(true) => board_id,
+                Ok(_) => {
+                    error!("Post with id {} not found", comment_data.post_id);
+                    return ApiError::bad_request(format!("Post with id {} not found", comment_data.post_id)).error_response();
+                }
+                Err(e) => {
+                    error!("Error reading post: {}", e);
+                    return ApiError::internal(format!("Error checking post: {}", e)).error_response();
+                }
             }
         },
         Err(e) => {
-            error!("Error checking board existence: {}", e);
-            record_db_operation(&db_counter, "select", "boards", false);
-            return HttpResponse::InternalServerError().body(format!("Error checking board: {}", e));
+            error!("Error checking post: {}", e);
+            record_db_operation(&db_counter, "select", "posts", false);
+            return ApiError::database("Error checking post", &e).error_response();
         }
-    }
-    
-    let now = Utc::now();
-    let post = Post {
-        id: Uuid::new_v4(),
-        board_id: post_data.board_id,
-        title: post_data.title.clone(),
-        content: post_data.content.clone(),
-        created_at: now,
-        updated_at: now,
-        author: post_data.author.clone(),
     };
     
-    debug!("Generated post ID: {}", post.id);
+    let comment = Comment {
+        id: config::get().id_scheme.new_id(),
+        post_id: comment_data.post_id,
+        content: comment_data.content.clone(),
+        created_at,
+        author,
+        edited_at: None,
+        is_deleted: false,
+        deleted_at: None,
+        score: 0,
+        reactions: Vec::new(),
+    };
     
-    let prepared = match session.prepare("INSERT INTO posts (id, board_id, title, content, author, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)").await {
-        Ok(p) => {
-            debug!("Post insert query prepared successfully");
-            p
-        },
-        Err(e) => {
-            error!("Error preparing post insert query: {}", e);
-            record_db_operation(&db_counter, "insert", "posts", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
+    // Write the comment, its post and author copies, its ordering rows and its per-board row
+    // atomically in a logged batch
+    let mut statements = Vec::with_capacity(6);
+    for cql in [
+        "INSERT INTO comments (id, post_id, content, author, created_at, user_id) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO comments_by_post (post_id, created_at, id, content, author, user_id) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO comments_by_author (author, created_at, id, post_id, content) VALUES (?, ?, ?, ?, ?)",
+        "INSERT INTO comment_order (post_id, sort, value, id) VALUES (?, ?, ?, ?)",
+        "INSERT INTO comment_order (post_id, sort, value, id) VALUES (?, ?, ?, ?)",
+        "INSERT INTO comments_by_board (board_id, created_at, id, post_id, content, author) VALUES (?, ?, ?, ?, ?, ?)",
+    ] {
+        match get_or_prepare(&session, cql).await {
+            Ok(p) => statements.push(p),
+            Err(e) => {
+                error!("Error preparing query: {}", e);
+                record_db_operation(&db_counter, "insert", "comments", false);
+                return ApiError::database("Error preparing query", &e).error_response();
+            }
         }
-    };
+    }
     
     // Use timestamp_millis directly for ScyllaDB BIGINT
-    debug!("Executing post insert query");
-    let result = session
-        .execute(
-            &prepared,
-            (post.id, post.board_id, &post.title, &post.content, &post.author, post.created_at.timestamp_millis(), post.updated_at.timestamp_millis()),
-        )
-        .await;
+    let created_at_millis = comment.created_at.timestamp_millis();
+    let comment_row = (comment.id, comment.post_id, &comment.content, &comment.author, created_at_millis, user_id);
+    let post_row = (comment.post_id, created_at_millis, comment.id, &comment.content, &comment.author, user_id);
+    let author_row = (&comment.author, created_at_millis, comment.id, comment.post_id, &comment.content);
+    let order_rows = (
+        (comment.post_id, SortKey::UpdatedAt.as_str(), created_at_millis, comment.id),
+        (comment.post_id, SortKey::Score.as_str(), 0i64, comment.id),
+    );
+    let mut batch = db::new_batch(BatchType::Logged);
+    let result = match board_id {
+        Some(board_id) => {
+            for statement in statements {
+                batch.append_statement(statement);
+            }
+            db_client::call(session.batch(
+                &batch,
+                (comment_row, post_row, author_row, order_rows.0, order_rows.1, (board_id, created_at_millis, comment.id, comment.post_id, &comment.content, &comment.author)),
+            ))
+            .await
+        }
+        None => {
+            // A post without a board can't be indexed per board; store the comment without that row
+            warn!("Post {} has no board_id, comment {} is not added to comments_by_board", comment.post_id, comment.id);
+            for statement in statements.into_iter().take(5) {
+                batch.append_statement(statement);
+            }
+            db_client::call(session.batch(&batch, (comment_row, post_row, author_row, order_rows.0, order_rows.1))).await
+        }
+    };
 
     let duration = start.elapsed();
 
     match result {
         Ok(_) => {
-            info!("Post created successfully: '{}' (duration: {}ms)", post.title, duration.as_millis());
-            record_db_operation(&db_counter, "insert", "posts", true);
-            HttpResponse::Created()
-                .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
-                .json(post)
+            record_db_operation(&db_counter, "insert", "comments", true);
+            counts::add_comments(&session, comment.post_id, 1, &db_counter).await;
+            search::index_comment(&comment);
+            respond_json(
+                HttpResponse::Created().append_header(("X-Processing-Time-Ms", duration.as_millis().to_string())),
+                &comment,
+                &ts,
+            )
         },
         Err(e) => {
-            error!("Error creating post: {}", e);
-            record_db_operation(&db_counter, "insert", "posts", false);
-            HttpResponse::InternalServerError().body(format!("Error creating post: {}", e))
-        },
+            error!("Error creating comment: {}", e);
+            record_db_operation(&db_counter, "insert", "comments", false);
+            ApiError::database("Error creating comment", &e).error_response()
+        }
     }
 }
 
-/// Get posts by board with pagination
+/// Comment fields needed to change it: post, board (of its post), creation time and owner
+async fn fetch_comment_for_change(
+    session: &Session,
+    comment_id: Uuid,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<(Comment, Option<Uuid>, Option<Uuid>), ApiError> {
+    let row = execute_cached(
+        session,
+        "SELECT post_id, content, author, created_at, edited_at, user_id, is_deleted, deleted_at FROM comments WHERE id = ?",
+        (comment_id,),
+    ).await;
+    type CommentRow = (Option<Uuid>, Option<String>, Option<String>, Option<i64>, Option<i64>, Option<Uuid>, Option<bool>, Option<i64>);
+    let (post_id, content, author, created_at, edited_at, owner, is_deleted, deleted_at) = match row.map(|rows| rows.maybe_first_row_typed::<CommentRow>()) {
+        Ok(Ok(Some(row))) => row,
+        Ok(Ok(None)) => {
+            record_db_operation(db_counter, "select", "comments", true);
+            return Err(ApiError::not_found(format!("Comment with id {} not found", comment_id)));
+        }
+        Ok(Err(e)) => {
+            record_db_operation(db_counter, "select", "comments", false);
+            error!("Error reading comment {}: {}", comment_id, e);
+            return Err(ApiError::internal(format!("Error fetching comment: {}", e)));
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "comments", false);
+            error!("Error fetching comment {}: {}", comment_id, e);
+            return Err(ApiError::database("Error fetching comment", &e));
+        }
+    };
+    record_db_operation(db_counter, "select", "comments", true);
+
+    let (post_id, created_at) = match (post_id, created_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single())) {
+        (Some(post_id), Some(created_at)) => (post_id, created_at),
+        _ => {
+            error!("Comment {} is missing post_id or created_at", comment_id);
+            return Err(ApiError::internal(format!("Comment {} is incomplete", comment_id)));
+        }
+    };
+
+    let board_id = match execute_cached(session, "SELECT board_id FROM posts WHERE id = ?", (post_id,)).await {
+        Ok(rows) => {
+            record_db_operation(db_counter, "select", "posts", true);
+            rows.maybe_first_row_typed::<(Option<Uuid>,)>().ok().flatten().and_then(|(board_id,)| board_id)
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", "posts", false);
+            error!("Error fetching post {} of comment {}: {}", post_id, comment_id, e);
+            return Err(ApiError::database("Error fetching post", &e));
+        }
+    };
+
+    let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+    let mut comment = Comment {
+        id: comment_id,
+        post_id,
+        content: content.unwrap_or_default(),
+        created_at,
+        author: author.unwrap_or_else(|| config::get().missing_author_placeholder.clone()),
+        edited_at: edited_at.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+        is_deleted,
+        deleted_at,
+        score: 0,
+        reactions: Vec::new(),
+    };
+    votes::fill_comment_scores(session, std::slice::from_mut(&mut comment)).await;
+    reactions::fill_comment_reactions(session, std::slice::from_mut(&mut comment)).await;
+    Ok((comment, board_id, owner))
+}
+
+/// Mark a comment deleted at `deleted_at`, or restore it with `None`, on the comment and its
+/// per-post, per-board and author copies (IF EXISTS, like the post copies)
+async fn set_comment_deleted(
+    session: &Session,
+    comment: &Comment,
+    board_id: Option<Uuid>,
+    deleted_at: Option<i64>,
+) -> Result<(), QueryError> {
+    let is_deleted = deleted_at.is_some();
+    execute_cached(
+        session,
+        "UPDATE comments_by_post SET is_deleted = ?, deleted_at = ? WHERE post_id = ? AND created_at = ? AND id = ? IF EXISTS",
+        (is_deleted, deleted_at, comment.post_id, comment.created_at.timestamp_millis(), comment.id),
+    ).await?;
+    if let Some(board_id) = board_id {
+        execute_cached(
+            session,
+            "UPDATE comments_by_board SET is_deleted = ?, deleted_at = ? WHERE board_id = ? AND created_at = ? AND id = ? IF EXISTS",
+            (is_deleted, deleted_at, board_id, comment.created_at.timestamp_millis(), comment.id),
+        ).await?;
+    }
+    execute_cached(
+        session,
+        "UPDATE comments_by_author SET is_deleted = ?, deleted_at = ? WHERE author = ? AND created_at = ? AND id = ? IF EXISTS",
+        (is_deleted, deleted_at, &comment.author, comment.created_at.timestamp_millis(), comment.id),
+    ).await?;
+    execute_cached(
+        session,
+        "UPDATE comments SET is_deleted = ?, deleted_at = ? WHERE id = ?",
+        (is_deleted, deleted_at, comment.id),
+    ).await?;
+    if let Some(count_cache) = COMMENT_COUNT_CACHE.get() {
+        let mut cache = count_cache.write().await;
+        cache.remove(&comment.post_id);
+        record_cache_size("comment_counts", cache.len());
+    }
+    Ok(())
+}
+
+/// Edit a comment
 ///
-/// Returns paginated posts for a specific board using ScyllaDB native pagination
+/// Replaces the content and sets `edited_at`. Allowed for the comment's owner and moderators
+/// (bearer token), or with the admin token.
+#[utoipa::path(
+    put,
+    path = "/comments/{comment_id}",
+    request_body = UpdateCommentRequest,
+    params(
+        ("comment_id" = uuid::Uuid, Path, description = "Comment ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Comment updated", body = Comment),
+        (status = 400, description = "Unknown field in the body"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of someone other than the owner or a moderator"),
+        (status = 404, description = "Comment not found or deleted"),
+        (status = 422, description = "Empty or too long content; every failing field is listed in errors"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/comments/{comment_id}")]
+pub async fn update_comment(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    update: web::Json<UpdateCommentRequest>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+    if let Err(e) = validation::update_comment(&update) {
+        warn!("Rejecting invalid edit of comment {}: {}", comment_id, e);
+        return e.error_response();
+    }
+    let (mut comment, board_id, owner) = match fetch_comment_for_change(&session, comment_id, &db_counter).await {
+        Ok(found) => found,
+        Err(e) => return e.error_response(),
+    };
+    if let Err(e) = authorize_owner_change(&req, user.as_ref(), owner, "comment", comment_id, "edit") {
+        return e.error_response();
+    }
+    if comment.is_deleted {
+        return ApiError::not_found(format!("Comment with id {} not found", comment_id)).error_response();
+    }
+
+    let edited_at = Utc::now();
+    let previous_updated_at = comment.edited_at.unwrap_or(comment.created_at).timestamp_millis();
+    comment.content = update.into_inner().content;
+    comment.edited_at = Some(edited_at);
+
+    let edited_at_millis = edited_at.timestamp_millis();
+    let created_at_millis = comment.created_at.timestamp_millis();
+    let mut statements = Vec::with_capacity(6);
+    for cql in [
+        "UPDATE comments SET content = ?, edited_at = ? WHERE id = ?",
+        "UPDATE comments_by_post SET content = ?, edited_at = ? WHERE post_id = ? AND created_at = ? AND id = ?",
+        "UPDATE comments_by_author SET content = ?, edited_at = ? WHERE author = ? AND created_at = ? AND id = ?",
+        "DELETE FROM comment_order WHERE post_id = ? AND sort = ? AND value = ? AND id = ?",
+        "INSERT INTO comment_order (post_id, sort, value, id) VALUES (?, ?, ?, ?)",
+        "UPDATE comments_by_board SET content = ?, edited_at = ? WHERE board_id = ? AND created_at = ? AND id = ?",
+    ] {
+        match get_or_prepare(&session, cql).await {
+            Ok(prepared) => statements.push(prepared),
+            Err(e) => {
+                record_db_operation(&db_counter, "update", "comments", false);
+                return ApiError::database("Error preparing query", &e).error_response();
+            }
+        }
+    }
+    let comment_row = (&comment.content, edited_at_millis, comment_id);
+    let post_row = (&comment.content, edited_at_millis, comment.post_id, created_at_millis, comment_id);
+    let author_row = (&comment.content, edited_at_millis, &comment.author, created_at_millis, comment_id);
+    let unordered_row = (comment.post_id, SortKey::UpdatedAt.as_str(), previous_updated_at, comment_id);
+    let ordered_row = (comment.post_id, SortKey::UpdatedAt.as_str(), edited_at_millis, comment_id);
+    let mut batch = db::new_batch(BatchType::Logged);
+    let result = match board_id {
+        Some(board_id) => {
+            for statement in statements {
+                batch.append_statement(statement);
+            }
+            db_client::call(session.batch(
+                &batch,
+                (comment_row, post_row, author_row, unordered_row, ordered_row, (&comment.content, edited_at_millis, board_id, created_at_millis, comment_id)),
+            )).await.map(|_| ())
+        }
+        None => {
+            for statement in statements.into_iter().take(5) {
+                batch.append_statement(statement);
+            }
+            db_client::call(session.batch(&batch, (comment_row, post_row, author_row, unordered_row, ordered_row))).await.map(|_| ())
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            record_db_operation(&db_counter, "update", "comments", true);
+            search::index_comment(&comment);
+            info!("Comment {} edited", comment_id);
+            respond_json(&mut HttpResponse::Ok(), &comment, &ts)
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "update", "comments", false);
+            error!("Error updating comment {}: {}", comment_id, e);
+            ApiError::database("Error updating comment", &e).error_response()
+        }
+    }
+}
+
+/// Delete a comment
+///
+/// Soft-deletes the comment until a moderator restores it. Allowed for the comment's owner and
+/// moderators (bearer token), or with the admin token. With `purge=true` (moderators and the
+/// admin token only) the comment is removed for good.
+#[utoipa::path(
+    delete,
+    path = "/comments/{comment_id}",
+    params(
+        ("comment_id" = uuid::Uuid, Path, description = "Comment ID"),
+        ("purge" = Option<bool>, Query, description = "Delete permanently (moderator token or X-Admin-Token)"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token")
+    ),
+    responses(
+        (status = 204, description = "Comment deleted (or purged)"),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of someone other than the owner or a moderator, or purge without moderator rights"),
+        (status = 404, description = "Comment not found or already deleted"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/comments/{comment_id}")]
+pub async fn delete_comment(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    purge: Query<PurgeParams>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+    let (comment, board_id, owner) = match fetch_comment_for_change(&session, comment_id, &db_counter).await {
+        Ok(found) => found,
+        Err(e) => return e.error_response(),
+    };
+
+    if !purge.purge {
+        if let Err(e) = authorize_owner_change(&req, user.as_ref(), owner, "comment", comment_id, "delete") {
+            return e.error_response();
+        }
+        if comment.is_deleted {
+            return ApiError::not_found(format!("Comment with id {} not found", comment_id)).error_response();
+        }
+        return match set_comment_deleted(&session, &comment, board_id, Some(Utc::now().timestamp_millis())).await {
+            Ok(()) => {
+                record_db_operation(&db_counter, "update", "comments", true);
+                counts::add_comments(&session, comment.post_id, -1, &db_counter).await;
+                search::remove(comment_id);
+                info!("Comment {} on post {} deleted", comment_id, comment.post_id);
+                HttpResponse::NoContent().finish()
+            }
+            Err(e) => {
+                record_db_operation(&db_counter, "update", "comments", false);
+                error!("Error deleting comment {}: {}", comment_id, e);
+                ApiError::database("Error deleting comment", &e).error_response()
+            }
+        };
+    }
+
+    if let Err(e) = authorize_moderation(&req, user.as_ref(), "comment", comment_id, "purge") {
+        return e.error_response();
+    }
+    let created_at_millis = comment.created_at.timestamp_millis();
+    let mut statements = Vec::with_capacity(6);
+    for cql in [
+        "DELETE FROM comments_by_author WHERE author = ? AND created_at = ? AND id = ?",
+        "DELETE FROM comments WHERE id = ?",
+        "DELETE FROM comments_by_post WHERE post_id = ? AND created_at = ? AND id = ?",
+        "DELETE FROM comment_order WHERE post_id = ? AND sort = ? AND value = ? AND id = ?",
+        "DELETE FROM comment_order WHERE post_id = ? AND sort = ? AND value = ? AND id = ?",
+        "DELETE FROM comments_by_board WHERE board_id = ? AND created_at = ? AND id = ?",
+    ] {
+        match get_or_prepare(&session, cql).await {
+            Ok(prepared) => statements.push(prepared),
+            Err(e) => {
+                record_db_operation(&db_counter, "delete", "comments", false);
+                return ApiError::database("Error preparing query", &e).error_response();
+            }
+        }
+    }
+    let author_row = (&comment.author, created_at_millis, comment_id);
+    // The score was read with the comment; a vote racing the purge can leave its row behind
+    let post_row = (comment.post_id, created_at_millis, comment_id);
+    let order_rows = (
+        (comment.post_id, SortKey::UpdatedAt.as_str(), comment.edited_at.unwrap_or(comment.created_at).timestamp_millis(), comment_id),
+        (comment.post_id, SortKey::Score.as_str(), comment.score, comment_id),
+    );
+    let mut batch = db::new_batch(BatchType::Logged);
+    let result = match board_id {
+        Some(board_id) => {
+            for statement in statements {
+                batch.append_statement(statement);
+            }
+            db_client::call(session.batch(&batch, (author_row, (comment_id,), post_row, order_rows.0, order_rows.1, (board_id, created_at_millis, comment_id)))).await.map(|_| ())
+        }
+        None => {
+            for statement in statements.into_iter().take(5) {
+                batch.append_statement(statement);
+            }
+            db_client::call(session.batch(&batch, (author_row, (comment_id,), post_row, order_rows.0, order_rows.1))).await.map(|_| ())
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            record_db_operation(&db_counter, "delete", "comments", true);
+            if !comment.is_deleted {
+                counts::add_comments(&session, comment.post_id, -1, &db_counter).await;
+            }
+            if let Some(count_cache) = COMMENT_COUNT_CACHE.get() {
+                let mut cache = count_cache.write().await;
+                cache.remove(&comment.post_id);
+                record_cache_size("comment_counts", cache.len());
+            }
+            if let Err(e) = votes::remove_comment_votes(&session, comment_id).await {
+                record_db_operation(&db_counter, "delete", "votes", false);
+                error!("Comment {} purged but its votes were not: {}", comment_id, e);
+            }
+            if let Err(e) = reactions::remove_reactions(&session, comment_id).await {
+                record_db_operation(&db_counter, "delete", "reactions", false);
+                error!("Comment {} purged but its reactions were not: {}", comment_id, e);
+            }
+            search::remove(comment_id);
+            info!("Comment {} on post {} purged", comment_id, comment.post_id);
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "comments", false);
+            error!("Error deleting comment {}: {}", comment_id, e);
+            ApiError::database("Error deleting comment", &e).error_response()
+        }
+    }
+}
+
+/// Restore a deleted comment
+///
+/// Undoes `DELETE /comments/{comment_id}` (not a purge). Takes a moderator's bearer token or
+/// the admin token.
+#[utoipa::path(
+    post,
+    path = "/comments/{comment_id}/restore",
+    params(
+        ("comment_id" = uuid::Uuid, Path, description = "Comment ID"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token (ADMIN_TOKEN), instead of a bearer token"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Comment restored (or was not deleted)", body = Comment),
+        (status = 401, description = "Neither a bearer token nor a valid X-Admin-Token"),
+        (status = 403, description = "Bearer token of a user below moderator"),
+        (status = 404, description = "Comment not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/comments/{comment_id}/restore")]
+pub async fn restore_comment(
+    req: HttpRequest,
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+    if let Err(e) = authorize_moderation(&req, user.as_ref(), "comment", comment_id, "restore") {
+        return e.error_response();
+    }
+    let (mut comment, board_id, _) = match fetch_comment_for_change(&session, comment_id, &db_counter).await {
+        Ok(found) => found,
+        Err(e) => return e.error_response(),
+    };
+
+    if comment.is_deleted {
+        if let Err(e) = set_comment_deleted(&session, &comment, board_id, None).await {
+            record_db_operation(&db_counter, "update", "comments", false);
+            error!("Error restoring comment {}: {}", comment_id, e);
+            return ApiError::database("Error restoring comment", &e).error_response();
+        }
+        record_db_operation(&db_counter, "update", "comments", true);
+        counts::add_comments(&session, comment.post_id, 1, &db_counter).await;
+        comment.is_deleted = false;
+        comment.deleted_at = None;
+        search::index_comment(&comment);
+        info!("Comment {} restored", comment_id);
+    }
+
+    respond_json(&mut HttpResponse::Ok(), &comment, &ts)
+}
+
+/// Count comments on a post
+///
+/// Returns the number of comments on a post without fetching them, read from the post's
+/// comment counter; soft-deleted comments aren't counted
 #[utoipa::path(
     get,
-    path = "/boards/{board_id}/posts",
+    path = "/posts/{post_id}/comments/count",
     params(
-        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
-        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
-        ("limit" = Option<u32>, Query, description = "Number of items per page", example = 10)
+        ("post_id" = uuid::Uuid, Path, description = "Post ID")
     ),
     responses(
-        (status = 200, description = "Paginated posts retrieved successfully", body = PaginatedResponse<Post>),
+        (status = 200, description = "Comment count retrieved successfully", body = CommentCount),
         (status = 500, description = "Internal server error")
     )
 )]
-#[get("/boards/{board_id}/posts")]
-// #[instrument(name = "get_posts_by_board", skip(session, db_counter), fields(board_id = %path))]
-pub async fn get_posts_by_board(
+#[get("/posts/{post_id}/comments/count")]
+pub async fn count_comments_by_post(
     session: web::Data<Arc<Session>>,
     path: web::Path<Uuid>,
-    pagination: Query<PaginationParams>,
     db_counter: web::Data<DbCounter>,
+    cache_counter: web::Data<CacheCounter>,
 ) -> impl Responder {
-    let board_id = path.into_inner();
-    let page = pagination.page.max(1); // Ensure page >= 1
-    let limit = pagination.limit.max(1).min(100); // Ensure 1 <= limit <= 100
+    let post_id = path.into_inner();
 
-    info!("Fetching posts for board {} (page: {}, limit: {})", board_id, page, limit);
-    let start = Instant::now();
+    // Counts are cached briefly, so a fresh comment may take a few seconds to show up
+    if let Some(count_cache) = COMMENT_COUNT_CACHE.get() {
+        match count_cache.read().await.get(&post_id) {
+            Some(cached) if !cached.is_expired() => {
+                record_cache_metric(&cache_counter, "comment_counts", "hit");
+                return HttpResponse::Ok().json(CommentCount { post_id, count: *cached.get_data() });
+            }
+            Some(_) => record_cache_metric(&cache_counter, "comment_counts", "expired"),
+            None => record_cache_metric(&cache_counter, "comment_counts", "miss"),
+        }
+    }
+
+    // The counter already leaves soft-deleted comments out
+    let count = match counts::post_comments(&session, post_id).await {
+        Ok(count) => count as i64,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "post_comment_counts", false);
+            error!("Error counting comments for post {}: {}", post_id, e);
+            return ApiError::database("Error counting comments", &e).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "select", "post_comment_counts", true);
+
+    if let Some(count_cache) = COMMENT_COUNT_CACHE.get() {
+        let mut cache = count_cache.write().await;
+        cache.insert(post_id, CacheEntry::new(count, COMMENT_COUNT_TTL));
+        record_cache_size("comment_counts", cache.len());
+    }
+
+    HttpResponse::Ok().json(CommentCount { post_id, count })
+}
 
-    // Prepare statement with page size for efficient pagination
-    let mut prepared = match session.prepare("SELECT id, board_id, title, content, author, created_at, updated_at FROM posts WHERE board_id = ? ALLOW FILTERING").await {
+/// Read one page of a post's comments in creation order from its `comments_by_post`
+/// partition, limited to comments created within `range` (epoch milliseconds)
+#[allow(clippy::too_many_arguments)]
+async fn fetch_post_comments_page(
+    session: &Session,
+    post_id: Uuid,
+    order: SortOrder,
+    range: std::ops::Range<i64>,
+    page: u32,
+    limit: u32,
+    include_deleted: bool,
+    author_filter: Option<&str>,
+    db_counter: &web::Data<DbCounter>,
+) -> Result<Vec<Comment>, ApiError> {
+    // The partition is clustered oldest first; reversing the clustering order serves desc
+    let cql = match order {
+        SortOrder::Asc => "SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments_by_post WHERE post_id = ? AND created_at >= ? AND created_at < ?",
+        SortOrder::Desc => "SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments_by_post WHERE post_id = ? AND created_at >= ? AND created_at < ? ORDER BY created_at DESC, id DESC",
+    };
+    let mut prepared = match get_or_prepare(session, cql).await {
         Ok(stmt) => stmt,
         Err(e) => {
-            record_db_operation(&db_counter, "select", "posts", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
+            record_db_operation(db_counter, "select", "comments_by_post", false);
+            return Err(ApiError::database("Error preparing query", &e));
         }
     };
     
@@ -615,15 +4730,16 @@ pub async fn get_posts_by_board(
     prepared.set_page_size(limit as i32);
     
     // Use execute_iter for paginated results
-    let row_iterator = match session.execute_iter(prepared, (board_id,)).await {
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, (post_id, range.start, range.end))).await {
         Ok(iterator) => iterator,
         Err(e) => {
-            record_db_operation(&db_counter, "select", "posts", false);
-            return HttpResponse::InternalServerError().body(format!("Error executing query: {}", e));
+            record_db_operation(db_counter, "select", "comments_by_post", false);
+            return Err(ApiError::database("Error executing query", &e));
         }
     };
 
-    let mut posts = Vec::new();
+    let mut comments = Vec::new();
     let mut total_fetched = 0u32;
 
     // Skip to the requested page
@@ -631,11 +4747,17 @@ pub async fn get_posts_by_board(
     let mut skipped = 0u32;
 
     // Convert iterator to stream and iterate through pages
-    let mut rows_stream = row_iterator.into_typed::<(uuid::Uuid, uuid::Uuid, String, String, String, i64, i64)>();
+    let mut rows_stream = row_iterator.into_typed::<(uuid::Uuid, uuid::Uuid, String, String, i64, Option<i64>, Option<bool>, Option<i64>)>();
     
-    while let Some(next_row_res) = rows_stream.next().await {
+    while let Some(next_row_res) = read.next(&mut rows_stream).await {
         match next_row_res {
-            Ok((id, board_id, title, content, author, created_at_millis, updated_at_millis)) => {
+            Ok((id, post_id, content, author, created_at_millis, edited_at_millis, is_deleted, deleted_at)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                // Filtered-out comments don't count towards pages
+                if author_filter.is_some_and(|wanted| wanted != author) || (is_deleted && !include_deleted) {
+                    continue;
+                }
+
                 // Skip rows until we reach the desired page
                 if skipped < skip_count {
                     skipped += 1;
@@ -647,369 +4769,302 @@ pub async fn get_posts_by_board(
                     break;
                 }
 
-                // Convert timestamps
+                // Convert timestamp
                 let created_at = match Utc.timestamp_millis_opt(created_at_millis).single() {
                     Some(dt) => dt,
                     None => {
-                        warn!("Invalid created_at timestamp for post {}: {}", id, created_at_millis);
-                        continue;
-                    }
-                };
-                
-                let updated_at = match Utc.timestamp_millis_opt(updated_at_millis).single() {
-                    Some(dt) => dt,
-                    None => {
-                        warn!("Invalid updated_at timestamp for post {}: {}", id, updated_at_millis);
+                        warn!("Invalid timestamp for comment {}: {}", id, created_at_millis);
                         continue;
                     }
                 };
 
-                posts.push(Post {
+                comments.push(Comment {
                     id,
-                    board_id,
-                    title,
+                    post_id,
                     content,
                     author,
-                    created_at,
-                    updated_at,
-                });
-
-                total_fetched += 1;
-            },
-            Err(e) => {
-                error!("Error reading row: {}", e);
-                record_db_operation(&db_counter, "select", "posts", false);
-                return HttpResponse::InternalServerError().body(format!("Error reading row: {}", e));
-            }
-        }
-    }
-
-    // Sort posts by created_at in descending order (newest first)
-    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-
-    let duration = start.elapsed();
-    record_db_operation(&db_counter, "select", "posts", true);
-
-    // For pagination metadata, we'll estimate total pages
-    // In a production system, you might want to maintain a separate count
-    let has_more = total_fetched == limit; // If we got a full page, there might be more
-    
-    let meta = PaginationMeta {
-        page,
-        limit,
-        total: None, // We don't have exact total count without additional query
-        total_pages: if has_more { None } else { Some(page) }, // If no more data, current page is last
-    };
-
-    let response = PaginatedResponse {
-        meta,
-        data: posts,
-    };
+                    created_at,
+                    edited_at: edited_at_millis.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                    is_deleted,
+                    deleted_at,
+                    score: 0,
+                    reactions: Vec::new(),
+                });
 
-    info!("Successfully fetched {} posts for board {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), board_id, page, limit, duration.as_millis());
-    HttpResponse::Ok()
-        .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
-        .append_header(("X-Has-More", has_more.to_string()))
-        .json(response)
+                total_fetched += 1;
+            },
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(db_counter, "select", "comments_by_post", false);
+                return Err(ApiError::database("Error reading row", &e));
+            }
+        }
+    }
+    record_db_operation(db_counter, "select", "comments_by_post", true);
+    Ok(comments)
 }
 
-/// Get post by ID
+/// Get comments by post with pagination
 ///
-/// Returns a single post with the specified ID
+/// Returns paginated comments for a specific post using ScyllaDB native pagination
 #[utoipa::path(
     get,
-    path = "/posts/{post_id}",
+    path = "/posts/{post_id}/comments",
     params(
-        ("post_id" = uuid::Uuid, Path, description = "Post ID")
+        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
+        ("limit" = Option<u32>, Query, description = "Number of items per page", example = 10),
+        ("author" = Option<String>, Query, description = "Only comments by this author (pages count matching comments only)"),
+        ("sort" = Option<String>, Query, description = "Sort field: created_at (default), updated_at (edit time, else creation), score (ordered in the database), author (within the page)"),
+        ("order" = Option<String>, Query, description = "Sort direction: asc or desc"),
+        ("since" = Option<String>, Query, description = "Only comments created at or after this RFC 3339 timestamp (sort must be created_at)"),
+        ("until" = Option<String>, Query, description = "Only comments created before this RFC 3339 timestamp (sort must be created_at)"),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
     ),
     responses(
-        (status = 200, description = "Post retrieved successfully", body = Post),
-        (status = 404, description = "Post not found"),
+        (status = 200, description = "Paginated comments retrieved successfully", body = PaginatedResponse<Comment>),
+        (status = 204, description = "No comments on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 400, description = "Unknown sort field or order, invalid author or since/until, or since/until with a sort other than created_at"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[get("/posts/{post_id}")]
-// #[instrument(name = "get_post", skip(session, db_counter, cache_counter), fields(post_id = %path))]
-pub async fn get_post(
+#[get("/posts/{post_id}/comments")]
+// #[instrument(name = "get_comments_by_post", skip(session, db_counter), fields(post_id = %path))]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_comments_by_post(
+    req: HttpRequest,
     session: web::Data<Arc<Session>>,
     path: web::Path<Uuid>,
+    pagination: Query<PaginationParams>,
+    filter: Query<CommentFilterParams>,
+    range: Query<DateRangeParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
     db_counter: web::Data<DbCounter>,
-    cache_counter: web::Data<CacheCounter>,
+    ts: Query<TimestampFormatParams>,
 ) -> impl Responder {
     let start = Instant::now();
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
     
     let post_id = path.into_inner();
-    
-    // Check cache first
-    let post_cache_key = format!("post_{}", post_id);
-    if let Some(posts_cache) = POSTS_CACHE.get() {
-        if let Some(cached_post) = posts_cache.read().await.get(&post_cache_key) {
-            if !cached_post.is_expired() {
-                info!("Cache hit for post ID: {}", post_id);
-                record_cache_metric(&cache_counter, "posts", "hit");
-                if let Some(post) = cached_post.get_data().first() {
-                    return HttpResponse::Ok().json(post);
-                }
-            } else {
-                info!("Cache expired for post ID: {}, fetching fresh data", post_id);
-                record_cache_metric(&cache_counter, "posts", "expired");
-            }
-        } else {
-            info!("No cache entry for post ID: {}, fetching data", post_id);
-            record_cache_metric(&cache_counter, "posts", "miss");
+
+    let author_filter = filter.into_inner().author;
+    if let Some(author) = &author_filter {
+        if let Err(message) = validate_author(author) {
+            warn!("Rejecting comments filter with invalid author {:?}: {}", author, message);
+            return ApiError::bad_request(message).error_response();
         }
-    } else {
-        warn!("Posts cache not initialized, fetching data from database");
-        record_cache_metric(&cache_counter, "posts", "miss");
     }
-    
-    let prepared = match session.prepare("SELECT id, board_id, title, content, author, created_at, updated_at FROM posts WHERE id = ?").await {
-        Ok(p) => p,
-        Err(e) => {
-            record_db_operation(&db_counter, "select", "posts", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
-        }
+    let page = pagination.page.max(1); // Ensure page >= 1
+    let limit = pagination.limit().max(1).min(100); // Ensure 1 <= limit <= 100
+    let created = match created_range(&range) {
+        Ok(created) => created,
+        Err(e) => return e.error_response(),
     };
-    
-    let result = session.execute(&prepared, (post_id,)).await;
-    
-    let duration = start.elapsed();
-    
-    match result {
-        Ok(rows) => {
-            match rows.first_row() {
-                Ok(row) => {
-                    let id_res = row.columns[0].as_ref().and_then(|c| c.as_uuid());
-                    let board_id_res = row.columns[1].as_ref().and_then(|c| c.as_uuid());
-                    let title_res = row.columns[2].as_ref().and_then(|c| c.as_text());
-                    let content_res = row.columns[3].as_ref().and_then(|c| c.as_text());
-                    let author_res = row.columns[4].as_ref().and_then(|c| c.as_text());
-                    
-                    // Handle bigint timestamps from database
-                    let created_at = if let Some(millis) = row.columns[5].as_ref().and_then(|c| c.as_bigint()) {
-                        Utc.timestamp_millis_opt(millis).single().unwrap_or_else(|| Utc::now())
-                    } else {
-                        Utc::now()
-                    };
-
-                    let updated_at = if let Some(millis) = row.columns[6].as_ref().and_then(|c| c.as_bigint()) {
-                        Utc.timestamp_millis_opt(millis).single().unwrap_or_else(|| Utc::now())
-                    } else {
-                        Utc::now()
-                    };
-                    
-                    if let (Some(id), Some(board_id), Some(title), Some(content), Some(author)) = 
-                        (id_res, board_id_res, title_res, content_res, author_res) {
-                        
-                        let post = Post {
-                            id,
-                            board_id,
-                            title: title.to_string(),
-                            content: content.to_string(),
-                            created_at,
-                            updated_at,
-                            author: author.to_string(),
-                        };
-                        
-                        // Update cache
-                        let cache_entry = CacheEntry::new(vec![post.clone()], Duration::from_secs(300)); // 5 minutes TTL
-                        if let Some(posts_cache) = POSTS_CACHE.get() {
-                            posts_cache.write().await.insert(post_cache_key, cache_entry);
-                        }
-
-                        record_db_operation(&db_counter, "select", "posts", true);
-                        return HttpResponse::Ok()
-                            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
-                            .json(post);
-                    }
-                },
-                Err(_) => {}
-            }
-            
-            record_db_operation(&db_counter, "select", "posts", true);
-            HttpResponse::NotFound().body(format!("Post with id {} not found", post_id))
-        }
-        Err(e) => {
-            record_db_operation(&db_counter, "select", "posts", false);
-            HttpResponse::InternalServerError().body(format!("Error fetching post: {}", e))
+
+    // Sort fields are resolved through the allowlist; unknown names are rejected
+    let (sort_column, sort_order) = match query_fields::resolve_sort(
+        query_fields::COMMENT_FIELDS,
+        pagination.sort.as_deref(),
+        pagination.order.as_deref(),
+        ("created_at", SortOrder::Asc),
+    ) {
+        Ok(sort) => sort,
+        Err(message) => {
+            warn!("Rejecting comments listing: {}", message);
+            return ApiError::bad_request(message).error_response();
         }
+    };
+    // A creation time range is a range of the created_at order
+    if created.is_some() && sort_column != "created_at" {
+        return ApiError::bad_request("since and until need sort=created_at").error_response();
     }
-}
 
-// Comment related endpoints
-/// Create a new comment
-///
-/// Creates a new comment on a specific post
-#[utoipa::path(
-    post,
-    path = "/comments",
-    request_body = CreateCommentRequest,
-    responses(
-        (status = 201, description = "Comment created successfully", body = Comment),
-        (status = 400, description = "Post not found"),
-        (status = 500, description = "Internal server error")
-    )
-)]
-#[post("/comments")]
-// #[instrument(name = "create_comment", skip(session, db_counter), fields(post_id = %comment_data.post_id, author = %comment_data.author))]
-pub async fn create_comment(
-    session: web::Data<Arc<Session>>,
-    comment_data: web::Json<CreateCommentRequest>,
-    db_counter: web::Data<DbCounter>,
-) -> impl Responder {
-    info!("Creating comment for post_id: {}, author: {}", comment_data.post_id, comment_data.author);
+    info!("Fetching comments for post {} (page: {}, limit: {}, sort: {})", post_id, page, limit, sort_column);
 
-    let start = Instant::now();
-    
-    // First check if the post exists
-    let post_check = match session.prepare("SELECT id FROM posts WHERE id = ?").await {
-        Ok(p) => p,
-        Err(e) => {
-            error!("Error preparing query: {}", e);
-            record_db_operation(&db_counter, "select", "posts", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
-        }
-    };
-    
-    let post_result = session.execute(&post_check, (comment_data.post_id,)).await;
-    
-    match post_result {
-        Ok(rows) => {
-            if rows.rows.unwrap_or_default().is_empty() {
-                error!("Post with id {} not found", comment_data.post_id);
-                record_db_operation(&db_counter, "select", "posts", true);
-                return HttpResponse::BadRequest().body(format!("Post with id {} not found", comment_data.post_id));
-            } else {
-                record_db_operation(&db_counter, "select", "posts", true);
+    // created_at is the post partition's own order; updated_at and score come from comment_order
+    let db_order = SortKey::from_column(sort_column).filter(|key| *key != SortKey::CreatedAt);
+    let mut comments = match db_order {
+        Some(key) => match list_order::comment_page(&session, post_id, key, sort_order, created.clone().unwrap_or(i64::MIN..i64::MAX), page, limit, include_deleted, author_filter.as_deref()).await {
+            Ok(comments) => {
+                record_db_operation(&db_counter, "select", "comment_order", true);
+                comments
+            }
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "comment_order", false);
+                error!("Error fetching comments of post {} by {}: {}", post_id, key.as_str(), e);
+                return ApiError::database("Error fetching comments", &e).error_response();
             }
         },
-        Err(e) => {
-            error!("Error checking post: {}", e);
-            record_db_operation(&db_counter, "select", "posts", false);
-            return HttpResponse::InternalServerError().body(format!("Error checking post: {}", e));
-        }
-    }
-    
-    let comment = Comment {
-        id: Uuid::new_v4(),
-        post_id: comment_data.post_id,
-        content: comment_data.content.clone(),
-        created_at: Utc::now(),
-        author: comment_data.author.clone(),
-    };
-    
-    let prepared = match session.prepare("INSERT INTO comments (id, post_id, content, author, created_at) VALUES (?, ?, ?, ?, ?)").await {
-        Ok(p) => p,
-        Err(e) => {
-            error!("Error preparing query: {}", e);
-            record_db_operation(&db_counter, "insert", "comments", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
+        None => {
+            // Author pages are cut from the oldest-first order and sorted below
+            let (order, range) = match sort_column {
+                "created_at" => (sort_order, created.clone().unwrap_or(i64::MIN..i64::MAX)),
+                _ => (SortOrder::Asc, i64::MIN..i64::MAX),
+            };
+            match fetch_post_comments_page(&session, post_id, order, range, page, limit, include_deleted, author_filter.as_deref(), &db_counter).await {
+                Ok(comments) => comments,
+                Err(e) => return e.error_response(),
+            }
         }
     };
-    
-    // Use timestamp_millis directly for ScyllaDB BIGINT
-    let result = session
-        .execute(
-            &prepared,
-            (comment.id, comment.post_id, &comment.content, &comment.author, comment.created_at.timestamp_millis()),
-        )
-        .await;
+
+    votes::fill_comment_scores(&session, &mut comments).await;
+    reactions::fill_comment_reactions(&session, &mut comments).await;
+
+    // Only author is sorted within the page; the database orders come sorted
+    if sort_column == "author" {
+        comments.sort_by(|a, b| sort_order.apply(a.author.cmp(&b.author)));
+    }
 
     let duration = start.elapsed();
 
-    match result {
-        Ok(_) => {
-            record_db_operation(&db_counter, "insert", "comments", true);
-            HttpResponse::Created()
-                .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
-                .json(comment)
-        },
-        Err(e) => {
-            error!("Error creating comment: {}", e);
-            record_db_operation(&db_counter, "insert", "comments", false);
-            HttpResponse::InternalServerError().body(format!("Error creating comment: {}", e))
+    let has_more = comments.len() as u32 == limit; // If we got a full page, there might be more
+
+    // The post's comment count covers the plain listing only: it leaves out deleted comments
+    let total = if author_filter.is_some() || created.is_some() || include_deleted {
+        None
+    } else {
+        match counts::post_comments(&session, post_id).await {
+            Ok(total) => {
+                record_db_operation(&db_counter, "select", "post_comment_counts", true);
+                Some(total)
+            }
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "post_comment_counts", false);
+                warn!("Error fetching the comment count of post {}: {}", post_id, e);
+                None
+            }
         }
+    };
+    let meta = PaginationMeta {
+        page,
+        limit,
+        total: total.map(|total| total.min(u32::MAX as u64) as u32),
+        // Without a count, a short page is the last one
+        total_pages: total.map_or(if has_more { None } else { Some(page) }, |total| Some(counts::total_pages(total, limit))),
+        total_is_estimate: false,
+        sort: None,
+        order: None,
+    };
+
+    let response = PaginatedResponse {
+        meta,
+        data: comments,
+    };
+
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        info!("No comments found (page: {}, limit: {}), returning 204", page, limit);
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
     }
+
+    info!("Successfully fetched {} comments for post {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), post_id, page, limit, duration.as_millis());
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
 }
 
-/// Get comments by post with pagination
+/// Get the latest comments on a board
 ///
-/// Returns paginated comments for a specific post using ScyllaDB native pagination
+/// Returns the newest comments on any post of the board, newest first, for a "latest
+/// activity" view. Served from `comments_by_board`, a copy of each comment partitioned by
+/// its post's board, instead of scanning the comments of every post.
 #[utoipa::path(
     get,
-    path = "/posts/{post_id}/comments",
+    path = "/boards/{board_id}/comments/recent",
     params(
-        ("post_id" = uuid::Uuid, Path, description = "Post ID"),
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
         ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
-        ("limit" = Option<u32>, Query, description = "Number of items per page", example = 10)
+        ("limit" = Option<u32>, Query, description = "Number of comments per page (1-100)", example = 10),
+        ("X-Empty-List-Status" = Option<u16>, Header, description = "Status for an empty page: 200 (default) or 204"),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)"),
+        ("include_deleted" = Option<bool>, Query, description = "Include soft-deleted content (moderator token or X-Admin-Token)")
     ),
     responses(
-        (status = 200, description = "Paginated comments retrieved successfully", body = PaginatedResponse<Comment>),
+        (status = 200, description = "Latest comments on the board", body = PaginatedResponse<Comment>),
+        (status = 204, description = "No comments on this page (when requested via X-Empty-List-Status: 204)"),
+        (status = 401, description = "include_deleted without a bearer token or valid X-Admin-Token"),
+        (status = 403, description = "include_deleted with a bearer token below moderator"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[get("/posts/{post_id}/comments")]
-// #[instrument(name = "get_comments_by_post", skip(session, db_counter), fields(post_id = %path))]
-pub async fn get_comments_by_post(
+#[get("/boards/{board_id}/comments/recent")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_recent_board_comments(
+    req: HttpRequest,
     session: web::Data<Arc<Session>>,
     path: web::Path<Uuid>,
     pagination: Query<PaginationParams>,
+    deleted: Query<DeletedFilterParams>,
+    user: Option<AuthenticatedUser>,
     db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
 ) -> impl Responder {
     let start = Instant::now();
-    
-    let post_id = path.into_inner();
+    let include_deleted = match include_deleted(&req, user.as_ref(), &deleted) {
+        Ok(include_deleted) => include_deleted,
+        Err(e) => return e.error_response(),
+    };
+
+    let board_id = path.into_inner();
     let page = pagination.page.max(1); // Ensure page >= 1
-    let limit = pagination.limit.max(1).min(100); // Ensure 1 <= limit <= 100
+    let limit = pagination.limit().clamp(1, 100);
 
-    info!("Fetching comments for post {} (page: {}, limit: {})", post_id, page, limit);
+    info!("Fetching recent comments for board {} (page: {}, limit: {})", board_id, page, limit);
 
-    // Prepare statement with page size for efficient pagination
-    let mut prepared = match session.prepare("SELECT id, post_id, content, author, created_at FROM comments WHERE post_id = ? ALLOW FILTERING").await {
+    let mut prepared = match get_or_prepare(&session, "SELECT id, post_id, content, author, created_at, edited_at, is_deleted, deleted_at FROM comments_by_board WHERE board_id = ?").await {
         Ok(stmt) => stmt,
         Err(e) => {
-            record_db_operation(&db_counter, "select", "comments", false);
-            return HttpResponse::InternalServerError().body(format!("Error preparing query: {}", e));
+            record_db_operation(&db_counter, "select", "comments_by_board", false);
+            return ApiError::database("Error preparing query", &e).error_response();
         }
     };
-    
-    // Set page size for efficient pagination
     prepared.set_page_size(limit as i32);
-    
-    // Use execute_iter for paginated results
-    let row_iterator = match session.execute_iter(prepared, (post_id,)).await {
+
+    let read = db_client::PagedRead::start();
+    let row_iterator = match read.call(session.execute_iter(prepared, (board_id,))).await {
         Ok(iterator) => iterator,
         Err(e) => {
-            record_db_operation(&db_counter, "select", "comments", false);
-            return HttpResponse::InternalServerError().body(format!("Error executing query: {}", e));
+            record_db_operation(&db_counter, "select", "comments_by_board", false);
+            return ApiError::database("Error executing query", &e).error_response();
         }
     };
 
     let mut comments = Vec::new();
-    let mut total_fetched = 0u32;
-
-    // Skip to the requested page
     let skip_count = (page - 1) * limit;
     let mut skipped = 0u32;
 
-    // Convert iterator to stream and iterate through pages
-    let mut rows_stream = row_iterator.into_typed::<(uuid::Uuid, uuid::Uuid, String, String, i64)>();
-    
-    while let Some(next_row_res) = rows_stream.next().await {
+    // Rows are clustered newest first, so the first `limit` rows after the skip are the page
+    let mut rows_stream = row_iterator.into_typed::<(Uuid, Uuid, String, String, i64, Option<i64>, Option<bool>, Option<i64>)>();
+    while let Some(next_row_res) = read.next(&mut rows_stream).await {
         match next_row_res {
-            Ok((id, post_id, content, author, created_at_millis)) => {
-                // Skip rows until we reach the desired page
+            Ok((id, post_id, content, author, created_at_millis, edited_at_millis, is_deleted, deleted_at)) => {
+                let (is_deleted, deleted_at) = deletion_state(is_deleted, deleted_at);
+                if is_deleted && !include_deleted {
+                    continue;
+                }
                 if skipped < skip_count {
                     skipped += 1;
                     continue;
                 }
-                
-                // Stop if we have enough items for this page
-                if total_fetched >= limit {
+                if comments.len() as u32 >= limit {
                     break;
                 }
 
-                // Convert timestamp
                 let created_at = match Utc.timestamp_millis_opt(created_at_millis).single() {
                     Some(dt) => dt,
                     None => {
@@ -1024,45 +5079,57 @@ pub async fn get_comments_by_post(
                     content,
                     author,
                     created_at,
+                    edited_at: edited_at_millis.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                    is_deleted,
+                    deleted_at,
+                    score: 0,
+                    reactions: Vec::new(),
                 });
-
-                total_fetched += 1;
-            },
+            }
             Err(e) => {
                 error!("Error reading row: {}", e);
-                record_db_operation(&db_counter, "select", "comments", false);
-                return HttpResponse::InternalServerError().body(format!("Error reading row: {}", e));
+                record_db_operation(&db_counter, "select", "comments_by_board", false);
+                return ApiError::database("Error reading row", &e).error_response();
             }
         }
     }
 
-    // Sort comments by created_at in ascending order (oldest first)
-    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    votes::fill_comment_scores(&session, &mut comments).await;
+    reactions::fill_comment_reactions(&session, &mut comments).await;
 
     let duration = start.elapsed();
-    record_db_operation(&db_counter, "select", "comments", true);
-
-    // For pagination metadata, we'll estimate total pages
-    // In a production system, you might want to maintain a separate count
-    let has_more = total_fetched == limit; // If we got a full page, there might be more
-    
-    let meta = PaginationMeta {
-        page,
-        limit,
-        total: None, // We don't have exact total count without additional query
-        total_pages: if has_more { None } else { Some(page) }, // If no more data, current page is last
-    };
+    record_db_operation(&db_counter, "select", "comments_by_board", true);
 
+    let has_more = comments.len() as u32 == limit; // If we got a full page, there might be more
     let response = PaginatedResponse {
-        meta,
+        meta: PaginationMeta {
+            page,
+            limit,
+            total: None,
+            total_pages: if has_more { None } else { Some(page) },
+            total_is_estimate: false,
+            sort: None,
+            order: None,
+        },
         data: comments,
     };
 
-    info!("Successfully fetched {} comments for post {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), post_id, page, limit, duration.as_millis());
-    HttpResponse::Ok()
-        .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
-        .append_header(("X-Has-More", has_more.to_string()))
-        .json(response)
+    if response.data.is_empty() && empty_list_no_content(&req) {
+        info!("No recent comments for board {} (page: {}, limit: {}), returning 204", board_id, page, limit);
+        return HttpResponse::NoContent()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", "false"))
+            .finish();
+    }
+
+    info!("Fetched {} recent comments for board {} (page: {}, limit: {}, duration: {}ms)", response.data.len(), board_id, page, limit, duration.as_millis());
+    respond_json(
+        HttpResponse::Ok()
+            .append_header(("X-Processing-Time-Ms", duration.as_millis().to_string()))
+            .append_header(("X-Has-More", has_more.to_string())),
+        &response,
+        &ts,
+    )
 }
 
 /// Intentionally slow endpoint with CPU-intensive operations
@@ -1228,4 +5295,207 @@ fn matrix_multiplication_result() -> u64 {
     }
     
     diagonal_sum
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use prometheus::Opts;
+
+    fn integrity_counter() -> web::Data<IntegrityCounter> {
+        let counter = IntCounterVec::new(Opts::new("data_integrity_errors_total", "test"), &["table", "field"]).unwrap();
+        web::Data::new(IntegrityCounter(counter))
+    }
+
+    #[test]
+    fn author_or_placeholder_keeps_stored_author() {
+        let counter = integrity_counter();
+        let author = author_or_placeholder(Some("alice".to_string()), &counter, "posts", Uuid::new_v4());
+        assert_eq!(author, "alice");
+        assert_eq!(counter.0.with_label_values(&["posts", "author"]).get(), 0);
+    }
+
+    #[test]
+    fn author_or_placeholder_substitutes_and_counts_missing_author() {
+        let counter = integrity_counter();
+        for _ in 0..2 {
+            let author = author_or_placeholder(None, &counter, "posts", Uuid::new_v4());
+            assert_eq!(author, config::get().missing_author_placeholder);
+        }
+        assert_eq!(counter.0.with_label_values(&["posts", "author"]).get(), 2);
+        assert_eq!(counter.0.with_label_values(&["comments", "author"]).get(), 0);
+    }
+
+    #[test]
+    fn normalize_post_text_collapses_title_whitespace() {
+        // Defaults: NORMALIZE_WHITESPACE on, NORMALIZE_CONTENT_WHITESPACE off
+        let cases = [
+            ("Hello world", "Hello world"),
+            ("  Hello world  ", "Hello world"),
+            ("Hello   world", "Hello world"),
+            ("Hello\t\nworld", "Hello world"),
+            ("\u{a0}Hello\u{2003}world\u{a0}", "Hello world"),
+            ("   ", ""),
+        ];
+        for (title, expected) in cases {
+            let (normalized, _) = normalize_post_text(title, "");
+            assert_eq!(normalized, expected, "title {:?}", title);
+        }
+    }
+
+    #[test]
+    fn normalize_post_text_keeps_content_by_default() {
+        let content = "  First line  \n\n\n\nSecond   line\t";
+        let (_, normalized) = normalize_post_text("Title", content);
+        assert_eq!(normalized, content);
+    }
+
+    fn user_with_role(role: Role) -> AuthenticatedUser {
+        AuthenticatedUser { user_id: Uuid::new_v4(), username: "alice".to_string(), role, session_id: None }
+    }
+
+    #[test]
+    fn require_role_admits_required_role_and_owner() {
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let check = |user: &AuthenticatedUser, owner: Option<Uuid>| {
+            require_role(&req, Some(user), Role::Moderator, owner, "edit post", "denied").map_err(|e| e.status_code())
+        };
+
+        let member = user_with_role(Role::User);
+        assert_eq!(check(&member, None), Err(StatusCode::FORBIDDEN));
+        assert_eq!(check(&member, Some(Uuid::new_v4())), Err(StatusCode::FORBIDDEN));
+        assert_eq!(check(&member, Some(member.user_id)), Ok(()));
+        assert_eq!(check(&user_with_role(Role::Moderator), None), Ok(()));
+        assert_eq!(check(&user_with_role(Role::Admin), None), Ok(()));
+    }
+
+    #[test]
+    fn require_role_without_token_needs_admin_token() {
+        let anonymous = actix_web::test::TestRequest::default().to_http_request();
+        let status = require_role(&anonymous, None, Role::Moderator, None, "edit board", "denied").map_err(|e| e.status_code());
+        assert_eq!(status, Err(StatusCode::UNAUTHORIZED));
+
+        let params = DeletedFilterParams { include_deleted: false };
+        assert_eq!(include_deleted(&anonymous, None, &params).ok(), Some(false));
+        let params = DeletedFilterParams { include_deleted: true };
+        let member = user_with_role(Role::User);
+        assert!(include_deleted(&anonymous, Some(&member), &params).is_err());
+        assert_eq!(include_deleted(&anonymous, Some(&user_with_role(Role::Moderator)), &params).ok(), Some(true));
+    }
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn validate_tags_stores_normalized_unique_tags() {
+        let validated = validate_tags(&tags(&["Rust", " rust ", "", "Web  Dev", "web dev"]));
+        assert_eq!(validated, Ok(tags(&["rust", "web dev"])));
+    }
+
+    #[test]
+    fn validate_tags_counts_tags_after_dedupe() {
+        // Defaults: TAG_MAX_PER_POST=10, TAG_MAX_LENGTH=32
+        let repeated: Vec<String> = (0..15).map(|i| if i % 2 == 0 { "Rust".to_string() } else { "rust".to_string() }).collect();
+        assert_eq!(validate_tags(&repeated), Ok(tags(&["rust"])));
+
+        let distinct: Vec<String> = (0..11).map(|i| format!("tag{}", i)).collect();
+        assert!(validate_tags(&distinct).is_err());
+        assert!(validate_tags(&distinct[..10]).is_ok());
+    }
+
+    #[test]
+    fn validate_tags_rejects_long_tags() {
+        assert!(validate_tags(&["a".repeat(33)]).is_err());
+        assert!(validate_tags(&["a".repeat(32)]).is_ok());
+        // Length is measured after whitespace is collapsed
+        assert!(validate_tags(&[format!("  {}  ", "a".repeat(32))]).is_ok());
+    }
+
+    #[test]
+    fn duplicate_name_strategy_reject_is_default() {
+        assert_eq!("Reject".parse(), Ok(DuplicateNameStrategy::Reject));
+        assert_eq!(config::get().duplicate_name_strategy, DuplicateNameStrategy::Reject);
+        assert_eq!("RETURN_EXISTING".parse(), Ok(DuplicateNameStrategy::ReturnExisting));
+        assert!("return-existing".parse::<DuplicateNameStrategy>().is_err());
+    }
+
+    #[test]
+    fn duplicate_name_strategy_suffix_numbers_names() {
+        assert_eq!("suffix".parse(), Ok(DuplicateNameStrategy::Suffix));
+        let names: Vec<String> = suffixed_board_names("General").collect();
+        assert_eq!(names.len(), MAX_BOARD_NAME_SUFFIX as usize - 1);
+        assert_eq!(names.first().map(String::as_str), Some("General (2)"));
+        assert_eq!(names.last(), Some(&format!("General ({})", MAX_BOARD_NAME_SUFFIX)));
+        // Suffixed names get their own slugs
+        assert_eq!(board_slug("General (2)", Uuid::nil()), "general-2");
+    }
+
+    /// Result of `INSERT INTO boards_by_slug ... IF NOT EXISTS`; `holder` is the board already
+    /// holding the slug when the insert didn't apply
+    fn slug_insert_result(holder: Option<Uuid>) -> QueryResult {
+        use scylla::frame::response::result::{CqlValue, Row};
+        let columns = match holder {
+            None => vec![Some(CqlValue::Boolean(true))],
+            Some(holder) => vec![Some(CqlValue::Boolean(false)), Some(CqlValue::Text("general".to_string())), Some(CqlValue::Uuid(holder))],
+        };
+        let mut result = QueryResult::default();
+        result.rows = Some(vec![Row { columns }]);
+        result
+    }
+
+    #[test]
+    fn duplicate_board_slug_is_a_conflict() {
+        let board_id = Uuid::new_v4();
+        let claim = |holder| slug_claim(&slug_insert_result(holder), "general".to_string(), board_id).map_err(|e| e.status_code());
+        assert_eq!(claim(None), Ok("general".to_string()));
+        // A second board named "General" (or "general!") finds the slug taken
+        assert_eq!(claim(Some(Uuid::new_v4())), Err(StatusCode::CONFLICT));
+        // A retried claim by the same board keeps it
+        assert_eq!(claim(Some(board_id)), Ok("general".to_string()));
+    }
+
+    /// One page of `rows` (listed in `boards_by_created` order, `true` for deleted boards) as
+    /// `read_board_page` reads it, by board number
+    async fn board_page(rows: &[(u32, bool)], page: u32, limit: u32) -> Vec<u32> {
+        let rows: Vec<Result<BoardListRow, QueryError>> = rows
+            .iter()
+            .map(|&(id, is_deleted)| {
+                let created_at = 1_700_000_000_000 + id as i64;
+                Ok((Uuid::from_u128(id as u128), format!("board {}", id), String::new(), created_at, None, None, None, Some(is_deleted), None, None, None))
+            })
+            .collect();
+        let mut rows = futures::stream::iter(rows);
+        let mut window = PageWindow::new(page, limit);
+        let boards = read_board_page(&db_client::PagedRead::start(), &mut rows, &mut window, false).await.unwrap();
+        assert_eq!(window.taken as usize, boards.len());
+        boards.iter().map(|board| board.id.as_u128() as u32).collect()
+    }
+
+    #[actix_web::test]
+    async fn board_pages_list_every_board_once() {
+        let rows: Vec<(u32, bool)> = (0..23).map(|id| (id, id % 5 == 3)).collect();
+        let listed: Vec<u32> = rows.iter().filter(|(_, is_deleted)| !is_deleted).map(|(id, _)| *id).collect();
+
+        for limit in [1, 2, 3, 7, 10, 100] {
+            let mut seen = Vec::new();
+            for page in 1.. {
+                let boards = board_page(&rows, page, limit).await;
+                assert!(boards.len() <= limit as usize);
+                let last = boards.len() < limit as usize;
+                seen.extend(boards);
+                if last {
+                    break;
+                }
+            }
+            assert_eq!(seen, listed, "limit {}", limit);
+        }
+    }
+
+    #[actix_web::test]
+    async fn board_page_past_the_end_is_empty() {
+        let rows: Vec<(u32, bool)> = (0..5).map(|id| (id, false)).collect();
+        assert_eq!(board_page(&rows, 2, 5).await, Vec::<u32>::new());
+        assert_eq!(board_page(&rows, 3, 2).await, vec![4]);
+    }
+}
@@ -0,0 +1,416 @@
+//! Full-text search over posts and comments (`GET /search?q=`).
+//!
+//! Scylla has no full-text index, so posts and comments are mirrored into an external search
+//! engine selected with `SEARCH_BACKEND`, behind the `SearchIndex` trait. Mirroring happens in
+//! the background after the write is stored: a search engine that is down or slow never fails
+//! a write, it only leaves the index behind (logged as a warning). Soft-deleted and purged
+//! content is removed from the index and restored content is added back.
+
+use actix_web::{get, HttpResponse, Responder, ResponseError};
+use actix_web::web::Query;
+use chrono::{DateTime, TimeZone, Utc};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::config::{self, SearchBackend};
+use crate::errors::ApiError;
+use crate::models::{
+    Comment, PaginatedResponse, PaginationMeta, Post, SearchHighlights, SearchHit, SearchKind, SearchParams,
+    TimestampFormatParams,
+};
+use crate::routes::respond_json;
+
+/// Longest accepted query, in characters
+const MAX_QUERY_CHARS: usize = 200;
+/// Marks around matched words in highlights
+const HIGHLIGHT_PRE_TAG: &str = "<mark>";
+const HIGHLIGHT_POST_TAG: &str = "</mark>";
+/// Words of content kept around the matches in a highlight
+const HIGHLIGHT_CROP_WORDS: u32 = 30;
+
+/// A search engine request that failed
+#[derive(Debug)]
+pub struct SearchError(String);
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<reqwest::Error> for SearchError {
+    fn from(e: reqwest::Error) -> Self {
+        SearchError(e.to_string())
+    }
+}
+
+/// A post or comment as stored in the search index
+#[derive(Clone, Debug)]
+pub struct SearchDocument {
+    pub id: Uuid,
+    pub kind: SearchKind,
+    /// The post itself, or the post a comment belongs to
+    pub post_id: Uuid,
+    /// Only known for posts
+    pub board_id: Option<Uuid>,
+    pub title: Option<String>,
+    pub content: String,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SearchDocument {
+    fn from_post(post: &Post) -> Self {
+        SearchDocument {
+            id: post.id,
+            kind: SearchKind::Post,
+            post_id: post.id,
+            board_id: Some(post.board_id),
+            title: Some(post.title.clone()),
+            content: post.content.clone(),
+            author: post.author.clone(),
+            created_at: post.created_at,
+        }
+    }
+
+    fn from_comment(comment: &Comment) -> Self {
+        SearchDocument {
+            id: comment.id,
+            kind: SearchKind::Comment,
+            post_id: comment.post_id,
+            board_id: None,
+            title: None,
+            content: comment.content.clone(),
+            author: comment.author.clone(),
+            created_at: comment.created_at,
+        }
+    }
+}
+
+/// A search engine holding the mirrored posts and comments
+pub trait SearchIndex: Send + Sync {
+    /// Add the document, or replace the one with the same id
+    fn upsert(&self, document: SearchDocument) -> BoxFuture<'_, Result<(), SearchError>>;
+
+    /// Replace the author of the document with this id (account erasure)
+    fn set_author(&self, id: Uuid, author: String) -> BoxFuture<'_, Result<(), SearchError>>;
+
+    /// Drop the document with this id, if indexed
+    fn remove(&self, id: Uuid) -> BoxFuture<'_, Result<(), SearchError>>;
+
+    /// Best matches first, with highlighted title and content, and the estimated number of
+    /// matches overall
+    fn search(
+        &self,
+        query: String,
+        kind: Option<SearchKind>,
+        offset: u32,
+        limit: u32,
+    ) -> BoxFuture<'_, Result<(Vec<SearchHit>, u64), SearchError>>;
+}
+
+/// Meilisearch (https://www.meilisearch.com), with all documents in one index
+struct Meilisearch {
+    client: reqwest::Client,
+    url: String,
+    index: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MeilisearchResponse {
+    /// Kept raw: a partial update of a document that was never indexed leaves a stub with only
+    /// `id` and `author`, which is skipped instead of failing the whole search
+    hits: Vec<serde_json::Value>,
+    #[serde(rename = "estimatedTotalHits", default)]
+    estimated_total_hits: u64,
+}
+
+#[derive(Deserialize)]
+struct MeilisearchHit {
+    id: Uuid,
+    kind: SearchKind,
+    post_id: Uuid,
+    board_id: Option<Uuid>,
+    title: Option<String>,
+    author: String,
+    created_at: i64,
+    #[serde(rename = "_formatted", default)]
+    formatted: Option<MeilisearchFormatted>,
+}
+
+#[derive(Default, Deserialize)]
+struct MeilisearchFormatted {
+    title: Option<String>,
+    content: Option<String>,
+}
+
+impl Meilisearch {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}/indexes/{}{}", self.url, self.index, path));
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Make `kind` filterable and limit matching to the text fields
+    async fn configure(&self) -> Result<(), SearchError> {
+        self.request(reqwest::Method::PATCH, "/settings")
+            .json(&json!({
+                "filterableAttributes": ["kind", "board_id", "post_id"],
+                "searchableAttributes": ["title", "content", "author"],
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl SearchIndex for Meilisearch {
+    fn upsert(&self, document: SearchDocument) -> BoxFuture<'_, Result<(), SearchError>> {
+        Box::pin(async move {
+            self.request(reqwest::Method::PUT, "/documents?primaryKey=id")
+                .json(&json!([{
+                    "id": document.id,
+                    "kind": document.kind,
+                    "post_id": document.post_id,
+                    "board_id": document.board_id,
+                    "title": document.title,
+                    "content": document.content,
+                    "author": document.author,
+                    "created_at": document.created_at.timestamp_millis(),
+                }]))
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+
+    fn set_author(&self, id: Uuid, author: String) -> BoxFuture<'_, Result<(), SearchError>> {
+        Box::pin(async move {
+            // POST merges the fields into the stored document
+            self.request(reqwest::Method::POST, "/documents?primaryKey=id")
+                .json(&json!([{ "id": id, "author": author }]))
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+
+    fn remove(&self, id: Uuid) -> BoxFuture<'_, Result<(), SearchError>> {
+        Box::pin(async move {
+            self.request(reqwest::Method::DELETE, &format!("/documents/{}", id))
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+
+    fn search(
+        &self,
+        query: String,
+        kind: Option<SearchKind>,
+        offset: u32,
+        limit: u32,
+    ) -> BoxFuture<'_, Result<(Vec<SearchHit>, u64), SearchError>> {
+        Box::pin(async move {
+            let filter = kind.map(|kind| format!("kind = {}", kind.as_str()));
+            let response: MeilisearchResponse = self
+                .request(reqwest::Method::POST, "/search")
+                .json(&json!({
+                    "q": query,
+                    "offset": offset,
+                    "limit": limit,
+                    "filter": filter,
+                    "attributesToHighlight": ["title", "content"],
+                    "attributesToCrop": ["content"],
+                    "cropLength": HIGHLIGHT_CROP_WORDS,
+                    "highlightPreTag": HIGHLIGHT_PRE_TAG,
+                    "highlightPostTag": HIGHLIGHT_POST_TAG,
+                }))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            let hits = response
+                .hits
+                .into_iter()
+                .filter_map(|hit| serde_json::from_value::<MeilisearchHit>(hit).ok())
+                .map(|hit| {
+                    let formatted = hit.formatted.unwrap_or_default();
+                    SearchHit {
+                        kind: hit.kind,
+                        id: hit.id,
+                        post_id: hit.post_id,
+                        board_id: hit.board_id,
+                        title: hit.title,
+                        author: hit.author,
+                        created_at: Utc.timestamp_millis_opt(hit.created_at).single().unwrap_or_default(),
+                        highlights: SearchHighlights {
+                            title: formatted.title,
+                            content: formatted.content,
+                        },
+                    }
+                })
+                .collect();
+            Ok((hits, response.estimated_total_hits))
+        })
+    }
+}
+
+static INDEX: OnceLock<Option<Arc<dyn SearchIndex>>> = OnceLock::new();
+
+fn index() -> Option<Arc<dyn SearchIndex>> {
+    INDEX.get().cloned().flatten()
+}
+
+/// Connect the search backend configured with `SEARCH_BACKEND`, and apply its index settings
+/// in the background
+pub fn init() {
+    let config = config::get();
+    let index: Option<Arc<dyn SearchIndex>> = match config.search_backend {
+        SearchBackend::None => None,
+        SearchBackend::Meilisearch => {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.search_timeout_secs))
+                .build()
+                .expect("Failed to build HTTP client");
+            let meilisearch = Arc::new(Meilisearch {
+                client,
+                url: config.search_url.trim_end_matches('/').to_string(),
+                index: config.search_index.clone(),
+                api_key: config.search_api_key.clone(),
+            });
+            let configuring = meilisearch.clone();
+            tokio::spawn(async move {
+                match configuring.configure().await {
+                    Ok(()) => info!("Search index '{}' configured", configuring.index),
+                    Err(e) => warn!("Error configuring search index '{}': {}", configuring.index, e),
+                }
+            });
+            Some(meilisearch)
+        }
+    };
+    if INDEX.set(index).is_err() {
+        warn!("Search backend initialized twice");
+    }
+}
+
+fn mirror(document: SearchDocument) {
+    let Some(index) = index() else { return };
+    tokio::spawn(async move {
+        let (kind, id) = (document.kind, document.id);
+        if let Err(e) = index.upsert(document).await {
+            warn!("Error indexing {} {} for search: {}", kind.as_str(), id, e);
+        }
+    });
+}
+
+/// Mirror a created, edited or restored post into the search index
+pub(crate) fn index_post(post: &Post) {
+    mirror(SearchDocument::from_post(post));
+}
+
+/// Mirror a created, edited or restored comment into the search index
+pub(crate) fn index_comment(comment: &Comment) {
+    mirror(SearchDocument::from_comment(comment));
+}
+
+/// Show the erased author on an anonymized post or comment in the search index
+pub(crate) fn set_author(id: Uuid, author: &str) {
+    let Some(index) = index() else { return };
+    let author = author.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = index.set_author(id, author).await {
+            warn!("Error anonymizing {} in the search index: {}", id, e);
+        }
+    });
+}
+
+/// Drop a deleted post or comment from the search index
+pub(crate) fn remove(id: Uuid) {
+    let Some(index) = index() else { return };
+    tokio::spawn(async move {
+        if let Err(e) = index.remove(id).await {
+            warn!("Error removing {} from the search index: {}", id, e);
+        }
+    });
+}
+
+/// Search posts and comments
+///
+/// Full-text search over the titles, content and authors of posts and comments, best matches
+/// first. Each hit carries its title and an excerpt of its content with the matched words
+/// wrapped in `<mark>`. Needs a search engine (`SEARCH_BACKEND`); recent writes show up after
+/// the engine has indexed them, usually within a second.
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(
+        ("q" = String, Query, description = "Search terms"),
+        ("type" = Option<String>, Query, description = "Only `post` or only `comment` hits"),
+        ("page" = Option<u32>, Query, description = "Page number (starts at 1)", example = 1),
+        ("limit" = Option<u32>, Query, description = "Number of hits per page", example = 10),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Matching posts and comments, best first; meta.total is the engine's estimate", body = PaginatedResponse<SearchHit>),
+        (status = 400, description = "Empty or too long q, or unknown type"),
+        (status = 502, description = "The search engine failed"),
+        (status = 503, description = "Search is disabled (SEARCH_BACKEND is none)")
+    )
+)]
+#[get("/search")]
+pub async fn search(params: Query<SearchParams>, ts: Query<TimestampFormatParams>) -> impl Responder {
+    let Some(index) = index() else {
+        return ApiError::unavailable("Search is disabled (SEARCH_BACKEND is none)").error_response();
+    };
+    let query = params.q.trim();
+    if query.is_empty() || query.chars().count() > MAX_QUERY_CHARS {
+        return ApiError::bad_request(format!("q must be between 1 and {} characters", MAX_QUERY_CHARS)).error_response();
+    }
+    let kind = match params.kind.as_deref() {
+        None => None,
+        Some("post") => Some(SearchKind::Post),
+        Some("comment") => Some(SearchKind::Comment),
+        Some(other) => return ApiError::bad_request(format!("Unknown type '{}', expected post or comment", other)).error_response(),
+    };
+    let page = params.page.max(1);
+    let limit = params.limit.unwrap_or(10).clamp(1, 100);
+
+    match index.search(query.to_string(), kind, (page - 1) * limit, limit).await {
+        Ok((hits, total)) => {
+            let total = total.min(u32::MAX as u64) as u32;
+            info!("Search for {:?} found {} hits (page {})", query, total, page);
+            let response = PaginatedResponse {
+                meta: PaginationMeta {
+                    page,
+                    limit,
+                    total: Some(total),
+                    total_pages: Some(total.div_ceil(limit).max(1)),
+                    total_is_estimate: true,
+                    sort: None,
+                    order: None,
+                },
+                data: hits,
+            };
+            respond_json(&mut HttpResponse::Ok(), &response, &ts)
+        }
+        Err(e) => {
+            error!("Search for {:?} failed: {}", query, e);
+            ApiError::bad_gateway(format!("Search failed: {}", e)).error_response()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! `GET /admin/selftest`: an end-to-end write/read/delete check against the live database.
+//!
+//! Only compiled with `--features selftest`, so production builds don't carry an endpoint
+//! that writes test data.
+
+use actix_web::web;
+
+/// Register the self-test endpoint when the `selftest` feature is enabled
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    #[cfg(feature = "selftest")]
+    cfg.service(handler::run_selftest);
+    #[cfg(not(feature = "selftest"))]
+    let _ = cfg;
+}
+
+#[cfg(feature = "selftest")]
+mod handler {
+    use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+    use chrono::Utc;
+    use scylla::Session;
+    use serde::Serialize;
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tracing::{info, warn};
+    use uuid::Uuid;
+    use crate::admin::require_admin;
+    use crate::db;
+    use crate::routes::{self, IntegrityCounter};
+
+    #[derive(Debug, Serialize)]
+    struct SelfTestStep {
+        name: &'static str,
+        success: bool,
+        duration_ms: u128,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct SelfTestReport {
+        success: bool,
+        steps: Vec<SelfTestStep>,
+    }
+
+    /// Time one step and record its outcome, returning its value on success
+    async fn step<T, F>(steps: &mut Vec<SelfTestStep>, name: &'static str, fut: F) -> Option<T>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let start = Instant::now();
+        let result = fut.await;
+        let duration_ms = start.elapsed().as_millis();
+        match result {
+            Ok(value) => {
+                steps.push(SelfTestStep { name, success: true, duration_ms, error: None });
+                Some(value)
+            }
+            Err(error) => {
+                warn!("Self-test step {} failed: {}", name, error);
+                steps.push(SelfTestStep { name, success: false, duration_ms, error: Some(error) });
+                None
+            }
+        }
+    }
+
+    async fn execute(session: &Session, cql: &'static str, values: impl scylla::serialize::row::SerializeRow) -> Result<(), String> {
+        routes::execute_cached(session, cql, values).await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Run a self-test
+    ///
+    /// Creates a board, a post and a comment, reads each back (through the prepared statements
+    /// and caches the API uses), then deletes everything it created. Cleanup runs even when an
+    /// earlier step fails.
+    #[get("/admin/selftest")]
+    pub async fn run_selftest(
+        req: HttpRequest,
+        session: web::Data<Arc<Session>>,
+        integrity_counter: web::Data<IntegrityCounter>,
+    ) -> impl Responder {
+        if let Err(e) = require_admin(&req) {
+            return e.error_response();
+        }
+
+        let session: &Session = &session;
+        let mut steps = Vec::new();
+        let now = Utc::now().timestamp_millis();
+        let board_id = Uuid::new_v4();
+        let post_id = Uuid::new_v4();
+        let comment_id = Uuid::new_v4();
+        let board_name = format!("__selftest__ {}", board_id);
+        info!("Running self-test with board {}", board_id);
+
+        let board_created = step(&mut steps, "create_board", async {
+            execute(session, "INSERT INTO boards (id, name, description, created_at, max_posts) VALUES (?, ?, ?, ?, ?)",
+                (board_id, &board_name, "self-test", now, None::<i32>)).await?;
+            execute(session, "INSERT INTO boards_by_created (bucket, created_at, id, name, description, max_posts) VALUES (?, ?, ?, ?, ?, ?)",
+                (db::BOARDS_BUCKET, now, board_id, &board_name, "self-test", None::<i32>)).await
+        }).await.is_some();
+
+        if board_created {
+            step(&mut steps, "read_board", async {
+                let board = routes::fetch_board_from_db(session, board_id).await
+                    .map_err(|e| e.to_string())?
+                    .ok_or("board not found after insert")?;
+                routes::cache_board(&board).await;
+                Ok(())
+            }).await;
+        }
+
+        let post_created = board_created && step(&mut steps, "create_post", async {
+            execute(session, "INSERT INTO posts (id, board_id, title, content, author, created_at, updated_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                (post_id, board_id, "self-test", "self-test", "selftest", now, now, Vec::<String>::new())).await?;
+            execute(session, "INSERT INTO posts_by_updated (day, updated_at, id, board_id, title, content, author, created_at, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                (db::updated_day(now), now, post_id, board_id, "self-test", "self-test", "selftest", now, Vec::<String>::new())).await
+        }).await.is_some();
+
+        if post_created {
+            step(&mut steps, "read_post", async {
+                let post = routes::fetch_post_from_db(session, post_id, &integrity_counter).await
+                    .map_err(|e| e.to_string())?
+                    .ok_or("post not found after insert")?;
+                routes::cache_post(&post).await;
+                Ok(())
+            }).await;
+        }
+
+        let comment_created = post_created && step(&mut steps, "create_comment", async {
+            execute(session, "INSERT INTO comments (id, post_id, content, author, created_at) VALUES (?, ?, ?, ?, ?)",
+                (comment_id, post_id, "self-test", "selftest", now)).await
+        }).await.is_some();
+
+        if comment_created {
+            step(&mut steps, "read_comment", async {
+                let rows = routes::execute_cached(session, "SELECT id FROM comments WHERE id = ?", (comment_id,)).await.map_err(|e| e.to_string())?;
+                if rows.rows_num().unwrap_or(0) == 0 {
+                    return Err("comment not found after insert".to_string());
+                }
+                Ok(())
+            }).await;
+        }
+
+        // Cleanup: always attempted for whatever may have been written
+        if comment_created {
+            step(&mut steps, "delete_comment", execute(session, "DELETE FROM comments WHERE id = ?", (comment_id,))).await;
+        }
+        if post_created {
+            step(&mut steps, "delete_post", async {
+                execute(session, "DELETE FROM posts WHERE id = ?", (post_id,)).await?;
+                execute(session, "DELETE FROM posts_by_updated WHERE day = ? AND updated_at = ? AND id = ?", (db::updated_day(now), now, post_id)).await?;
+                routes::invalidate_post_cache(post_id).await;
+                Ok(())
+            }).await;
+        }
+        if board_created {
+            step(&mut steps, "delete_board", async {
+                execute(session, "DELETE FROM boards WHERE id = ?", (board_id,)).await?;
+                execute(session, "DELETE FROM boards_by_created WHERE bucket = ? AND created_at = ? AND id = ?", (db::BOARDS_BUCKET, now, board_id)).await?;
+                routes::invalidate_board_cache(board_id).await;
+                Ok(())
+            }).await;
+        }
+
+        let success = steps.iter().all(|s| s.success);
+        info!("Self-test finished (success: {})", success);
+        let report = SelfTestReport { success, steps };
+        if success {
+            HttpResponse::Ok().json(report)
+        } else {
+            HttpResponse::InternalServerError().json(report)
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Login sessions backing refresh tokens.
+//!
+//! A refresh token is `<session id>.<secret>`; only a SHA-256 of the secret is stored, in a
+//! `sessions` row that expires with the token (`REFRESH_TOKEN_TTL_SECS`). Every refresh
+//! rotates the secret, and presenting an already-rotated secret revokes the session, since
+//! it means the token was copied; the hashes of rotated secrets are kept in the row's
+//! `rotated_hashes` for that. A secret the session never had is just refused. Access tokens
+//! carry the session id, so revoking a session (logout) also invalidates its access tokens.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use uuid::Uuid;
+use crate::config;
+use crate::routes::execute_cached;
+
+/// How long a session seen in the database is trusted without checking again, so a logout
+/// on another instance takes at most this long to reject its access tokens
+const ACTIVE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+static ACTIVE: OnceLock<Mutex<HashMap<Uuid, Instant>>> = OnceLock::new();
+
+fn active() -> MutexGuard<'static, HashMap<Uuid, Instant>> {
+    ACTIVE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Why a refresh token was refused
+pub enum RefreshError {
+    /// Malformed, expired or revoked token
+    Invalid,
+    /// An already-rotated token was presented; the session has been revoked
+    Reused,
+    Query(QueryError),
+}
+
+impl From<QueryError> for RefreshError {
+    fn from(e: QueryError) -> Self {
+        RefreshError::Query(e)
+    }
+}
+
+pub(crate) fn new_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub(crate) fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn parse_token(token: &str) -> Option<(Uuid, &str)> {
+    let (id, secret) = token.split_once('.')?;
+    Some((Uuid::parse_str(id).ok()?, secret))
+}
+
+/// Start a session for the user, returning its id and refresh token
+pub async fn create(session: &Session, user_id: Uuid) -> Result<(Uuid, String), QueryError> {
+    let session_id = Uuid::new_v4();
+    let secret = new_secret();
+    let ttl = config::get().refresh_token_ttl_secs as i32;
+    execute_cached(
+        session,
+        "INSERT INTO sessions (id, user_id, token_hash, created_at) VALUES (?, ?, ?, ?) USING TTL ?",
+        (session_id, user_id, hash_secret(&secret), Utc::now().timestamp_millis(), ttl),
+    ).await?;
+    active().insert(session_id, Instant::now());
+    Ok((session_id, format!("{}.{}", session_id, secret)))
+}
+
+/// Exchange a refresh token for a new one, returning the session id, its user and the token
+pub async fn rotate(session: &Session, token: &str) -> Result<(Uuid, Uuid, String), RefreshError> {
+    let (session_id, secret) = parse_token(token).ok_or(RefreshError::Invalid)?;
+    let rows = execute_cached(
+        session,
+        "SELECT user_id, token_hash, created_at, rotated_hashes FROM sessions WHERE id = ?",
+        (session_id,),
+    ).await?;
+    let (user_id, token_hash, created_at, rotated_hashes) = rows
+        .maybe_first_row_typed::<(Uuid, String, i64, Option<Vec<String>>)>()
+        .ok()
+        .flatten()
+        .ok_or(RefreshError::Invalid)?;
+
+    let presented = hash_secret(secret);
+    if presented != token_hash {
+        if !rotated_hashes.unwrap_or_default().contains(&presented) {
+            warn!("Refresh token with an unknown secret for session {} of user {}", session_id, user_id);
+            return Err(RefreshError::Invalid);
+        }
+        warn!("Refresh token reuse on session {} of user {}, revoking it", session_id, user_id);
+        revoke(session, session_id).await?;
+        return Err(RefreshError::Reused);
+    }
+
+    // Compare-and-set, so two concurrent refreshes with the same token can't both succeed
+    let next = new_secret();
+    let ttl = config::get().refresh_token_ttl_secs as i32;
+    let result = execute_cached(
+        session,
+        "UPDATE sessions USING TTL ? SET user_id = ?, token_hash = ?, created_at = ?, rotated_hashes = rotated_hashes + ? WHERE id = ? IF token_hash = ?",
+        (ttl, user_id, hash_secret(&next), created_at, vec![presented.clone()], session_id, &presented),
+    ).await?;
+    let applied = result
+        .rows
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true);
+    if !applied {
+        warn!("Concurrent refresh on session {} of user {}, revoking it", session_id, user_id);
+        revoke(session, session_id).await?;
+        return Err(RefreshError::Reused);
+    }
+
+    active().insert(session_id, Instant::now());
+    Ok((session_id, user_id, format!("{}.{}", session_id, next)))
+}
+
+/// Revoke the session a refresh token belongs to, if the token is its current one (other
+/// tokens are ignored, so knowing a session id is not enough to end it)
+pub async fn revoke_token(session: &Session, token: &str) -> Result<(), QueryError> {
+    let Some((session_id, secret)) = parse_token(token) else {
+        return Ok(());
+    };
+    let rows = execute_cached(session, "SELECT token_hash FROM sessions WHERE id = ?", (session_id,)).await?;
+    let current = rows.maybe_first_row_typed::<(String,)>().ok().flatten();
+    if current.is_some_and(|(token_hash,)| token_hash == hash_secret(secret)) {
+        revoke(session, session_id).await?;
+    }
+    Ok(())
+}
+
+/// Revoke every session of a user but `keep`, returning how many were revoked
+pub async fn revoke_all(session: &Session, user_id: Uuid, keep: Option<Uuid>) -> Result<usize, QueryError> {
+    let rows = execute_cached(session, "SELECT id FROM sessions WHERE user_id = ?", (user_id,)).await?;
+    let session_ids: Vec<Uuid> = rows
+        .rows_typed::<(Uuid,)>()
+        .map(|rows| rows.filter_map(Result::ok).map(|(id,)| id).collect())
+        .unwrap_or_default();
+    let revoked = sessions_to_revoke(session_ids, keep);
+    for &session_id in &revoked {
+        revoke(session, session_id).await?;
+    }
+    Ok(revoked.len())
+}
+
+fn sessions_to_revoke(session_ids: Vec<Uuid>, keep: Option<Uuid>) -> Vec<Uuid> {
+    session_ids.into_iter().filter(|&session_id| Some(session_id) != keep).collect()
+}
+
+async fn revoke(session: &Session, session_id: Uuid) -> Result<(), QueryError> {
+    active().remove(&session_id);
+    execute_cached(session, "DELETE FROM sessions WHERE id = ?", (session_id,)).await?;
+    Ok(())
+}
+
+/// Whether the session an access token was issued for still exists
+pub async fn is_active(session: &Session, session_id: Uuid) -> Result<bool, QueryError> {
+    let cached = active()
+        .get(&session_id)
+        .is_some_and(|checked| checked.elapsed() < ACTIVE_CACHE_TTL);
+    if cached {
+        return Ok(true);
+    }
+
+    let rows = execute_cached(session, "SELECT id FROM sessions WHERE id = ?", (session_id,)).await?;
+    let exists = rows.rows.is_some_and(|rows| !rows.is_empty());
+    let mut active = active();
+    if exists {
+        active.insert(session_id, Instant::now());
+    } else {
+        active.remove(&session_id);
+    }
+    // Drop stale entries now and then so the map tracks only recently used sessions
+    if active.len() > 10_000 {
+        active.retain(|_, checked| checked.elapsed() < ACTIVE_CACHE_TTL);
+    }
+    Ok(exists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revoke_all_keeps_only_the_given_session() {
+        let sessions: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        assert_eq!(sessions_to_revoke(sessions.clone(), None), sessions);
+        assert_eq!(sessions_to_revoke(sessions.clone(), Some(sessions[1])), [sessions[0], sessions[2]]);
+        // A session of someone else (or an already revoked one) keeps nothing back
+        assert_eq!(sessions_to_revoke(sessions.clone(), Some(Uuid::new_v4())), sessions);
+    }
+}
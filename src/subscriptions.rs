@@ -0,0 +1,216 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, ResponseError};
+use actix_web::web::Query;
+use chrono::{TimeZone, Utc};
+use scylla::Session;
+use std::sync::Arc;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+use crate::db_client;
+use crate::errors::ApiError;
+use crate::models::{Subscription, SubscriptionAuthorParams, SubscriptionRequest};
+use crate::routes::{self, get_or_prepare, record_db_operation, DbCounter};
+
+/// Subscribe an author to a board
+///
+/// Records that the author wants to hear about new posts on the board. Subscribing again
+/// is harmless and keeps the original subscription time.
+#[utoipa::path(
+    post,
+    path = "/boards/{board_id}/subscribe",
+    request_body = SubscriptionRequest,
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 201, description = "Subscribed", body = Subscription),
+        (status = 200, description = "Already subscribed", body = Subscription),
+        (status = 400, description = "Invalid author"),
+        (status = 404, description = "Board not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/boards/{board_id}/subscribe")]
+pub async fn subscribe_to_board(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    subscription: web::Json<SubscriptionRequest>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let board_id = path.into_inner();
+    let author = subscription.author.trim();
+    if let Err(message) = routes::validate_author(author) {
+        warn!("Rejecting subscription to board {}: {}", board_id, message);
+        return ApiError::bad_request(message).error_response();
+    }
+
+    match routes::fetch_board_from_db(&session, board_id).await {
+        Ok(Some(board)) if !board.is_deleted => record_db_operation(&db_counter, "select", "boards", true),
+        Ok(_) => {
+            record_db_operation(&db_counter, "select", "boards", true);
+            return ApiError::not_found(format!("Board with id {} not found", board_id)).error_response();
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "boards", false);
+            error!("Error checking board {}: {}", board_id, e);
+            return ApiError::database("Error checking board", &e).error_response();
+        }
+    }
+
+    let prepared = match get_or_prepare(&session, "INSERT INTO subscriptions (author, board_id, created_at) VALUES (?, ?, ?) IF NOT EXISTS").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "insert", "subscriptions", false);
+            return ApiError::internal(format!("Error preparing query: {}", e)).error_response();
+        }
+    };
+
+    let now = Utc::now();
+    let result = match db_client::call(session.execute(&prepared, (author, board_id, now.timestamp_millis()))).await {
+        Ok(result) => result,
+        Err(e) => {
+            record_db_operation(&db_counter, "insert", "subscriptions", false);
+            error!("Error subscribing {} to board {}: {}", author, board_id, e);
+            return ApiError::database("Error creating subscription", &e).error_response();
+        }
+    };
+    record_db_operation(&db_counter, "insert", "subscriptions", true);
+
+    // A lightweight transaction answers with `[applied]` and, when not applied, the existing row
+    let row = result.rows.as_ref().and_then(|rows| rows.first());
+    let applied = row
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true);
+
+    if applied {
+        info!("{} subscribed to board {}", author, board_id);
+        return HttpResponse::Created().json(Subscription {
+            author: author.to_string(),
+            board_id,
+            created_at: now,
+        });
+    }
+
+    let created_at = row
+        .and_then(|row| row.columns.iter().rev().find_map(|c| c.as_ref().and_then(|c| c.as_bigint())))
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+        .unwrap_or(now);
+    HttpResponse::Ok().json(Subscription {
+        author: author.to_string(),
+        board_id,
+        created_at,
+    })
+}
+
+/// Unsubscribe an author from a board
+#[utoipa::path(
+    delete,
+    path = "/boards/{board_id}/subscribe",
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("author" = String, Query, description = "Subscribed author")
+    ),
+    responses(
+        (status = 204, description = "Unsubscribed (also when there was no subscription)"),
+        (status = 400, description = "Invalid author"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/boards/{board_id}/subscribe")]
+pub async fn unsubscribe_from_board(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    params: Query<SubscriptionAuthorParams>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let board_id = path.into_inner();
+    let author = params.author.trim();
+    if let Err(message) = routes::validate_author(author) {
+        return ApiError::bad_request(message).error_response();
+    }
+
+    let prepared = match get_or_prepare(&session, "DELETE FROM subscriptions WHERE author = ? AND board_id = ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "subscriptions", false);
+            return ApiError::internal(format!("Error preparing query: {}", e)).error_response();
+        }
+    };
+
+    match db_client::call(session.execute(&prepared, (author, board_id))).await {
+        Ok(_) => {
+            record_db_operation(&db_counter, "delete", "subscriptions", true);
+            info!("{} unsubscribed from board {}", author, board_id);
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => {
+            record_db_operation(&db_counter, "delete", "subscriptions", false);
+            error!("Error unsubscribing {} from board {}: {}", author, board_id, e);
+            ApiError::database("Error deleting subscription", &e).error_response()
+        }
+    }
+}
+
+/// List an author's board subscriptions
+#[utoipa::path(
+    get,
+    path = "/authors/{author}/subscriptions",
+    params(
+        ("author" = String, Path, description = "Author name")
+    ),
+    responses(
+        (status = 200, description = "Boards the author is subscribed to", body = Vec<Subscription>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/authors/{author}/subscriptions")]
+pub async fn get_author_subscriptions(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<String>,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    let author = path.into_inner().trim().to_string();
+
+    let prepared = match get_or_prepare(&session, "SELECT board_id, created_at FROM subscriptions WHERE author = ?").await {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "subscriptions", false);
+            return ApiError::internal(format!("Error preparing query: {}", e)).error_response();
+        }
+    };
+
+    let read = db_client::PagedRead::start();
+    let mut rows = match read.call(session.execute_iter(prepared, (&author,))).await {
+        Ok(iterator) => iterator.into_typed::<(Uuid, i64)>(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "subscriptions", false);
+            return ApiError::database("Error executing query", &e).error_response();
+        }
+    };
+
+    let mut subscriptions = Vec::new();
+    while let Some(row) = read.next(&mut rows).await {
+        match row {
+            Ok((board_id, created_at_millis)) => {
+                let Some(created_at) = Utc.timestamp_millis_opt(created_at_millis).single() else {
+                    warn!("Invalid timestamp for subscription {} -> {}: {}", author, board_id, created_at_millis);
+                    continue;
+                };
+                subscriptions.push(Subscription {
+                    author: author.clone(),
+                    board_id,
+                    created_at,
+                });
+            }
+            Err(e) => {
+                error!("Error reading row: {}", e);
+                record_db_operation(&db_counter, "select", "subscriptions", false);
+                return ApiError::database("Error reading row", &e).error_response();
+            }
+        }
+    }
+
+    record_db_operation(&db_counter, "select", "subscriptions", true);
+    HttpResponse::Ok().json(subscriptions)
+}
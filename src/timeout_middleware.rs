@@ -0,0 +1,86 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::ACCEPT;
+use actix_web::{Error, ResponseError};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tracing::warn;
+use crate::config;
+use crate::errors::ApiError;
+
+/// Streaming responses (SSE) are expected to stay open, so they never get a deadline
+fn is_exempt(req: &ServiceRequest) -> bool {
+    let wants_event_stream = req.headers()
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|accept| accept.contains("text/event-stream"))
+        .unwrap_or(false);
+    wants_event_stream || config::path_matches(&config::get().handler_timeout_exempt_paths, req.path())
+}
+
+// Middleware factory answering 504 when a handler runs past `HANDLER_TIMEOUT_MS`
+pub struct RequestTimeout;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTimeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestTimeoutMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTimeoutMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestTimeoutMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let timeout_ms = config::get().handler_timeout_ms;
+        if timeout_ms == 0 || is_exempt(&req) {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        }
+
+        // Keep the request head so a 504 can still be built after the handler future is dropped
+        let http_req = req.request().clone();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            match tokio::time::timeout(Duration::from_millis(timeout_ms), fut).await {
+                Ok(res) => res.map(ServiceResponse::map_into_left_body),
+                Err(_) => {
+                    warn!("Handler timed out after {}ms: {} {}", timeout_ms, http_req.method(), http_req.path());
+                    let response = ApiError::GatewayTimeout(format!("Request did not complete within {}ms", timeout_ms))
+                        .error_response();
+                    Ok(ServiceResponse::new(http_req, response).map_into_right_body())
+                }
+            }
+        })
+    }
+}
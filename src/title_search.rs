@@ -0,0 +1,192 @@
+//! Title-prefix search within a board (`GET /boards/{board_id}/posts/search?prefix=`).
+//!
+//! Works without a search engine: every post is written to `posts_by_title_prefix` once per
+//! prefix of its title (compared like board names, case- and spacing-insensitively), up to
+//! `MAX_PREFIX_CHARS` characters. A lookup is then a single-partition read, newest first;
+//! longer prefixes read the partition of their first `MAX_PREFIX_CHARS` characters and check
+//! the rest against the stored titles. Rows follow the post through edits, soft deletion,
+//! restores and purges.
+
+use actix_web::{get, web, HttpResponse, Responder, ResponseError};
+use actix_web::web::Query;
+use chrono::{TimeZone, Utc};
+use scylla::batch::BatchType;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+use crate::db;
+use crate::db_client;
+use crate::errors::ApiError;
+use crate::models::{PostTitleMatch, TimestampFormatParams, TitleSearchParams};
+use crate::normalize;
+use crate::routes::{get_or_prepare, record_db_operation, respond_json, DbCounter};
+
+/// Longest indexed prefix, in characters; each post has one row per prefix length
+const MAX_PREFIX_CHARS: usize = 16;
+/// Bytes of a row besides the prefix and title, for batch sizing
+const ROW_FIXED_BYTES: usize = 48;
+
+/// Indexed prefixes of a title, shortest first; prefixes ending in a space are left out
+/// since a search never ends in one
+fn prefixes(title: &str) -> Vec<String> {
+    let key = normalize::name_key(title);
+    key.char_indices()
+        .map(|(start, c)| start + c.len_utf8())
+        .take(MAX_PREFIX_CHARS)
+        .map(|end| &key[..end])
+        .filter(|prefix| !prefix.ends_with(' '))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Write a post's rows, one per prefix of its title
+pub(crate) async fn index(
+    session: &Session,
+    board_id: Uuid,
+    post_id: Uuid,
+    title: &str,
+    created_at: i64,
+) -> Result<(), QueryError> {
+    let prepared = get_or_prepare(session, "INSERT INTO posts_by_title_prefix (board_id, prefix, created_at, id, title) VALUES (?, ?, ?, ?, ?)").await?;
+    for prefixes in db::chunk_by_bytes(prefixes(title), |prefix| ROW_FIXED_BYTES + prefix.len() + title.len()) {
+        let mut batch = db::new_batch(BatchType::Logged);
+        let values: Vec<_> = prefixes
+            .iter()
+            .map(|prefix| {
+                batch.append_statement(prepared.clone());
+                (board_id, prefix, created_at, post_id, title)
+            })
+            .collect();
+        db_client::call(session.batch(&batch, values)).await?;
+    }
+    Ok(())
+}
+
+/// Drop a post's rows for the given title
+pub(crate) async fn remove(
+    session: &Session,
+    board_id: Uuid,
+    post_id: Uuid,
+    title: &str,
+    created_at: i64,
+) -> Result<(), QueryError> {
+    let prepared = get_or_prepare(session, "DELETE FROM posts_by_title_prefix WHERE board_id = ? AND prefix = ? AND created_at = ? AND id = ?").await?;
+    for prefixes in db::chunk_by_bytes(prefixes(title), |prefix| ROW_FIXED_BYTES + prefix.len()) {
+        let mut batch = db::new_batch(BatchType::Logged);
+        let values: Vec<_> = prefixes
+            .iter()
+            .map(|prefix| {
+                batch.append_statement(prepared.clone());
+                (board_id, prefix, created_at, post_id)
+            })
+            .collect();
+        db_client::call(session.batch(&batch, values)).await?;
+    }
+    Ok(())
+}
+
+/// Move a post's rows from its previous title to the edited one
+pub(crate) async fn retitle(
+    session: &Session,
+    board_id: Uuid,
+    post_id: Uuid,
+    previous_title: &str,
+    title: &str,
+    created_at: i64,
+) -> Result<(), QueryError> {
+    if normalize::name_key(previous_title) == normalize::name_key(title) {
+        // Same prefixes: only the stored title changes
+        return index(session, board_id, post_id, title, created_at).await;
+    }
+    remove(session, board_id, post_id, previous_title, created_at).await?;
+    index(session, board_id, post_id, title, created_at).await
+}
+
+/// Search a board's posts by title prefix
+///
+/// Returns the board's posts whose title starts with `prefix`, ignoring case and runs of
+/// whitespace, newest first. Deleted posts are not listed. Needs no search engine, unlike
+/// `GET /search`.
+#[utoipa::path(
+    get,
+    path = "/boards/{board_id}/posts/search",
+    params(
+        ("board_id" = uuid::Uuid, Path, description = "Board ID"),
+        ("prefix" = String, Query, description = "Start of the title", example = "rust"),
+        ("limit" = Option<u32>, Query, description = "Maximum number of posts to return (1-100)", example = 10),
+        ("ts" = Option<String>, Query, description = "Timestamp format: rfc3339 (default) or epoch (integer milliseconds)")
+    ),
+    responses(
+        (status = 200, description = "Matching posts, newest first", body = Vec<PostTitleMatch>),
+        (status = 400, description = "Empty prefix"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/boards/{board_id}/posts/search")]
+pub async fn search_posts_by_title(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    params: Query<TitleSearchParams>,
+    db_counter: web::Data<DbCounter>,
+    ts: Query<TimestampFormatParams>,
+) -> impl Responder {
+    let board_id = path.into_inner();
+    let prefix = normalize::name_key(&params.prefix);
+    if prefix.is_empty() {
+        return ApiError::bad_request("prefix must not be empty").error_response();
+    }
+    let limit = params.limit.unwrap_or(10).clamp(1, 100) as usize;
+    let stored_prefix = match prefix.char_indices().nth(MAX_PREFIX_CHARS) {
+        Some((end, _)) => prefix[..end].trim_end().to_string(),
+        None => prefix.clone(),
+    };
+
+    let mut prepared = match get_or_prepare(&session, "SELECT id, title, created_at FROM posts_by_title_prefix WHERE board_id = ? AND prefix = ?").await {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_title_prefix", false);
+            error!("Error preparing title search: {}", e);
+            return ApiError::internal(format!("Error preparing query: {}", e)).error_response();
+        }
+    };
+    prepared.set_page_size(limit as i32);
+    let read = db_client::PagedRead::start();
+    let mut rows = match read.call(session.execute_iter(prepared, (board_id, &stored_prefix))).await {
+        Ok(rows) => rows.into_typed::<(Uuid, Option<String>, Option<i64>)>(),
+        Err(e) => {
+            record_db_operation(&db_counter, "select", "posts_by_title_prefix", false);
+            error!("Error searching titles on board {}: {}", board_id, e);
+            return ApiError::database("Error searching posts", &e).error_response();
+        }
+    };
+
+    let mut matches = Vec::with_capacity(limit);
+    while matches.len() < limit {
+        let Some(row) = read.next(&mut rows).await else { break };
+        let (id, title, created_at) = match row {
+            Ok(row) => row,
+            Err(e) => {
+                record_db_operation(&db_counter, "select", "posts_by_title_prefix", false);
+                error!("Error reading title search results on board {}: {}", board_id, e);
+                return ApiError::database("Error searching posts", &e).error_response();
+            }
+        };
+        let title = title.unwrap_or_default();
+        if stored_prefix != prefix && !normalize::name_key(&title).starts_with(&prefix) {
+            continue;
+        }
+        matches.push(PostTitleMatch {
+            id,
+            board_id,
+            title,
+            created_at: created_at
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+                .unwrap_or_default(),
+        });
+    }
+    record_db_operation(&db_counter, "select", "posts_by_title_prefix", true);
+    info!("Title search {:?} on board {} found {} posts", prefix, board_id, matches.len());
+    respond_json(&mut HttpResponse::Ok(), &matches, &ts)
+}
@@ -1,6 +1,6 @@
 use actix_web::body::MessageBody;
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use actix_web::Error;
+use actix_web::{Error, HttpMessage};
 use actix_web::http::header::{HeaderName, HeaderValue, HeaderMap};
 use std::future::{ready, Ready};
 use std::rc::Rc;
@@ -10,9 +10,11 @@ use futures_util::future::LocalBoxFuture;
 use uuid::Uuid;
 use std::time::Instant;
 use opentelemetry::global;
-use opentelemetry::trace::{TraceContextExt, Status, Tracer, Span};
+use opentelemetry::trace::{FutureExt, TraceContextExt, Status, Tracer, Span};
 use opentelemetry::propagation::Extractor;
 use opentelemetry::{KeyValue};
+use crate::config;
+use crate::in_flight_middleware;
 
 // Custom header extractor for OpenTelemetry context propagation
 struct HeaderExtractor<'a> {
@@ -40,6 +42,22 @@ impl<'a> Extractor for HeaderExtractor<'a> {
     }
 }
 
+/// Decide whether a request's access log lines are written, per `ACCESS_LOG_SAMPLE_RATIO`
+fn access_log_sampled(request_id: &Uuid) -> bool {
+    let ratio = config::get().access_log_sample_ratio;
+    if ratio >= 1.0 {
+        return true;
+    }
+    if ratio <= 0.0 {
+        return false;
+    }
+    // Bytes 10..16 of a v4 UUID are fully random (48 bits)
+    let bits = request_id.as_bytes()[10..16]
+        .iter()
+        .fold(0u64, |acc, &byte| (acc << 8) | byte as u64);
+    (bits as f64 / (1u64 << 48) as f64) < ratio
+}
+
 // Middleware factory for tracing requests
 pub struct TracingLogger;
 
@@ -82,14 +100,38 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let start_time = Instant::now();
+
+        // Generate a request ID up front; its random bits also drive access log sampling
+        let request_id = Uuid::new_v4();
+        let log_request = access_log_sampled(&request_id);
+
+        // Skip span creation entirely for high-frequency probe traffic
+        if config::path_matches(&config::get().trace_exclude_paths, req.path()) {
+            let path = req.path().to_owned();
+            let method = req.method().to_string();
+            let fut = self.service.call(req);
+            return Box::pin(async move {
+                let res = fut.await?;
+                let status = res.status().as_u16();
+                if log_request || status >= 400 {
+                    println!(
+                        "Request completed: {} {} - {} ({}ms)",
+                        method, path, status, start_time.elapsed().as_millis()
+                    );
+                }
+                Ok(res)
+            });
+        }
         
         // Debug: log incoming headers
-        println!("Incoming headers:");
-        for (name, value) in req.headers().iter() {
-            if name.as_str().to_lowercase().contains("trace") || 
-               name.as_str().to_lowercase().contains("baggage") ||
-               name.as_str().to_lowercase().contains("x-") {
-                println!("  {}: {:?}", name, value);
+        if log_request {
+            println!("Incoming headers:");
+            for (name, value) in req.headers().iter() {
+                if name.as_str().to_lowercase().contains("trace") || 
+                   name.as_str().to_lowercase().contains("baggage") ||
+                   name.as_str().to_lowercase().contains("x-") {
+                    println!("  {}: {:?}", name, value);
+                }
             }
         }
         
@@ -104,10 +146,12 @@ where
         let parent_span = parent_cx.span();
         let parent_span_context = parent_span.span_context();
         let has_parent = parent_span_context.is_valid();
-        println!("Parent context extracted: {}", has_parent);
-        if has_parent {
-            println!("Parent trace ID: {}", parent_span_context.trace_id());
-            println!("Parent span ID: {}", parent_span_context.span_id());
+        if log_request {
+            println!("Parent context extracted: {}", has_parent);
+            if has_parent {
+                println!("Parent trace ID: {}", parent_span_context.trace_id());
+                println!("Parent span ID: {}", parent_span_context.span_id());
+            }
         }
 
         // Check for load test indicators
@@ -141,8 +185,16 @@ where
         let span = tracer.build_with_context(span_builder, &parent_cx);
         let span_context = span.span_context().clone();
         let trace_id = span_context.trace_id().to_string();
+        let sampled = span_context.is_sampled();
 
-        println!("Created span with trace ID: {}", trace_id);
+        if log_request {
+            println!("Created span with trace ID: {} (sampled: {})", trace_id, sampled);
+        }
+
+        // Show the trace id next to the request in GET /admin/inflight
+        if let Some(id) = req.extensions().get::<in_flight_middleware::InFlightId>().copied() {
+            in_flight_middleware::set_trace_id(id, &trace_id);
+        }
 
         let service = Rc::clone(&self.service);
 
@@ -151,13 +203,15 @@ where
             let cx = parent_cx.with_span(span);
             
             // Log request info
-            println!(
-                "Request started: {} {} (trace_id: {}, has_parent: {})", 
-                method, path, trace_id, has_parent
-            );
+            if log_request {
+                println!(
+                    "Request started: {} {} (trace_id: {}, has_parent: {})", 
+                    method, path, trace_id, has_parent
+                );
+            }
 
-            // Process the request
-            let res = service.call(req).await?;
+            // Process the request with our span current, so error bodies can carry its trace id
+            let res = service.call(req).with_context(cx.clone()).await?;
 
             // Get response info
             let status = res.status().as_u16();
@@ -180,13 +234,15 @@ where
             // End the span
             current_span.end();
 
-            println!(
-                "Request completed: {} {} - {} ({}ms, trace_id: {})",
-                method, path, status, duration, trace_id
-            );
+            // Failures are always logged; successful requests only when sampled
+            if log_request || status >= 400 {
+                println!(
+                    "Request completed: {} {} - {} ({}ms, trace_id: {})",
+                    method, path, status, duration, trace_id
+                );
+            }
 
-            // Generate a request ID for tracing
-            let request_id = Uuid::new_v4().to_string();
+            let request_id = request_id.to_string();
 
             // Add response headers
             let mut res = res;
@@ -204,6 +260,11 @@ where
                     HeaderName::from_static("x-trace-id"),
                     HeaderValue::from_str(&trace_id).expect("trace_id should be valid header value")
                 );
+                // Lets clients tell whether the trace id can actually be found in Jaeger
+                headers.insert(
+                    HeaderName::from_static("x-trace-sampled"),
+                    HeaderValue::from_static(if sampled { "true" } else { "false" })
+                );
             }
 
             Ok(res)
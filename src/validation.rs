@@ -0,0 +1,164 @@
+//! Field checks on the create and update request bodies, run before any database work.
+//!
+//! Every failing field is reported at once as a 422 `validation_failed` `ApiError` listing
+//! `{field, message}` pairs. Lengths are counted in characters and limited by the
+//! `*_MAX_LENGTH` settings. Checks that need the database (board and post existence, unknown
+//! `user_id`) or that depend on board settings still answer 400 from the handlers. Update bodies
+//! only have their given fields checked.
+
+use crate::config;
+use crate::errors::ApiError;
+use crate::models::{
+    CreateBoardRequest, CreateCommentRequest, CreatePostRequest, FieldError, UpdateBoardRequest, UpdateCommentRequest,
+    UpdatePostRequest,
+};
+use crate::routes;
+
+/// Collects the field errors of one request body
+#[derive(Default)]
+struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    fn add(&mut self, field: &str, message: String) {
+        self.0.push(FieldError { field: field.to_string(), message });
+    }
+
+    /// A text field that must not be blank and is at most `max` characters
+    fn text(&mut self, field: &str, value: &str, max: usize) {
+        if value.trim().is_empty() {
+            self.add(field, "must not be empty".to_string());
+        } else if value.chars().count() > max {
+            self.add(field, format!("must be at most {} characters", max));
+        }
+    }
+
+    /// Board description; may be empty
+    fn description(&mut self, value: &str, max: usize) {
+        if value.chars().count() > max {
+            self.add("description", format!("must be at most {} characters", max));
+        }
+    }
+
+    /// Author name of a post or comment; may be left empty when `user_id` or a bearer token
+    /// identifies the account writing
+    fn author(&mut self, author: &str, has_account: bool) {
+        if author.is_empty() && has_account {
+            return;
+        }
+        if let Err(message) = routes::validate_author(author) {
+            // `validate_author` messages start with the field name
+            let message = message.strip_prefix("author ").map(str::to_string).unwrap_or(message);
+            self.add("author", message);
+        }
+    }
+
+    fn finish(self) -> Result<(), ApiError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(self.0))
+        }
+    }
+}
+
+/// Check a `POST /boards` body
+pub fn create_board(board: &CreateBoardRequest) -> Result<(), ApiError> {
+    let config = config::get();
+    let mut errors = FieldErrors::default();
+    errors.text("name", &board.name, config.board_name_max_length);
+    errors.description(&board.description, config.board_description_max_length);
+    errors.finish()
+}
+
+/// Check a `PATCH /boards/{board_id}` body
+pub fn update_board(update: &UpdateBoardRequest) -> Result<(), ApiError> {
+    let config = config::get();
+    let mut errors = FieldErrors::default();
+    if let Some(name) = &update.name {
+        errors.text("name", name, config.board_name_max_length);
+    }
+    if let Some(description) = &update.description {
+        errors.description(description, config.board_description_max_length);
+    }
+    errors.finish()
+}
+
+/// Check a `POST /posts` body; `authenticated` is whether a bearer token came with it
+pub fn create_post(post: &CreatePostRequest, authenticated: bool) -> Result<(), ApiError> {
+    let config = config::get();
+    let mut errors = FieldErrors::default();
+    errors.text("title", &post.title, config.post_title_max_length);
+    errors.text("content", &post.content, config.post_content_max_length);
+    errors.author(&post.author, authenticated || post.user_id.is_some());
+    errors.finish()
+}
+
+/// Check a `PUT /posts/{post_id}` body
+pub fn update_post(update: &UpdatePostRequest) -> Result<(), ApiError> {
+    let config = config::get();
+    let mut errors = FieldErrors::default();
+    if let Some(title) = &update.title {
+        errors.text("title", title, config.post_title_max_length);
+    }
+    if let Some(content) = &update.content {
+        errors.text("content", content, config.post_content_max_length);
+    }
+    errors.finish()
+}
+
+/// Check a `POST /comments` body; `authenticated` is whether a bearer token came with it
+pub fn create_comment(comment: &CreateCommentRequest, authenticated: bool) -> Result<(), ApiError> {
+    let config = config::get();
+    let mut errors = FieldErrors::default();
+    errors.text("content", &comment.content, config.comment_content_max_length);
+    errors.author(&comment.author, authenticated || comment.user_id.is_some());
+    errors.finish()
+}
+
+/// Check a `PUT /comments/{comment_id}` body
+pub fn update_comment(update: &UpdateCommentRequest) -> Result<(), ApiError> {
+    let mut errors = FieldErrors::default();
+    errors.text("content", &update.content, config::get().comment_content_max_length);
+    errors.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failing_fields(result: Result<(), ApiError>) -> Vec<String> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(ApiError::Validation(errors)) => errors.into_iter().map(|e| e.field).collect(),
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn update_post_checks_only_given_fields() {
+        let update = |title: Option<&str>, content: Option<&str>| UpdatePostRequest {
+            title: title.map(str::to_string),
+            content: content.map(str::to_string),
+            expected_updated_at: None,
+        };
+        assert!(failing_fields(update_post(&update(None, Some("New text")))).is_empty());
+        assert_eq!(failing_fields(update_post(&update(Some("  "), None))), ["title"]);
+        let long_title = "x".repeat(config::get().post_title_max_length + 1);
+        assert_eq!(failing_fields(update_post(&update(Some(&long_title), Some("")))), ["title", "content"]);
+    }
+
+    #[test]
+    fn update_board_and_comment_reject_blank_or_long_text() {
+        let board = UpdateBoardRequest {
+            name: Some(String::new()),
+            description: Some("d".repeat(config::get().board_description_max_length + 1)),
+            max_posts: None,
+            default_page_size: None,
+            default_sort: None,
+            category_id: None,
+        };
+        assert_eq!(failing_fields(update_board(&board)), ["name", "description"]);
+        assert_eq!(failing_fields(update_comment(&UpdateCommentRequest { content: "\n".to_string() })), ["content"]);
+        assert!(failing_fields(update_comment(&UpdateCommentRequest { content: "Edited".to_string() })).is_empty());
+    }
+}
@@ -0,0 +1,459 @@
+//! Up- and downvotes on posts and comments (`POST /posts/{post_id}/vote`,
+//! `POST /comments/{comment_id}/vote`).
+//!
+//! Each user's vote is a row in `votes`, keyed by what was voted on, so a user has at most one
+//! vote per post or comment; changing it is a lightweight transaction on the previous value,
+//! which keeps concurrent requests of the same user from counting twice. Scores are kept in the
+//! `post_score` and `comment_score` counter tables and adjusted by the difference between the
+//! new and the previous vote; the score's ordering row (see `list_order`) follows it.
+//!
+//! Counter updates can't be made conditional, so the difference is first stored on the vote
+//! row as `pending_delta` by the same transaction that changes the vote, and cleared once the
+//! counter has it. If the counter update fails, the next vote of that user on the same post or
+//! comment (a retry, say) adds the pending difference before anything else. A counter update
+//! that timed out but was applied anyway is counted twice; that can't be told apart.
+
+use actix_web::{post, web, HttpResponse, Responder, ResponseError};
+use chrono::Utc;
+use scylla::frame::value::Counter as CqlCounter;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::config;
+use crate::errors::ApiError;
+use crate::jwt_middleware::AuthenticatedUser;
+use crate::list_order::{self, Listing, SortKey};
+use crate::models::{Comment, Post, VoteRequest, VoteResponse};
+use crate::routes::{self, execute_cached, record_db_operation, DbCounter};
+
+/// Attempts at changing a vote before giving up on concurrent changes
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Clone, Copy)]
+enum Target {
+    Post,
+    Comment,
+}
+
+impl Target {
+    fn kind(self) -> &'static str {
+        match self {
+            Target::Post => "post",
+            Target::Comment => "comment",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Target::Post => "Post",
+            Target::Comment => "Comment",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Target::Post => "posts",
+            Target::Comment => "comments",
+        }
+    }
+
+    fn score_table(self) -> &'static str {
+        match self {
+            Target::Post => "post_score",
+            Target::Comment => "comment_score",
+        }
+    }
+
+    /// Deletion flag and board (post) or post (comment)
+    fn select_deleted(self) -> &'static str {
+        match self {
+            Target::Post => "SELECT is_deleted, board_id FROM posts WHERE id = ?",
+            Target::Comment => "SELECT is_deleted, post_id FROM comments WHERE id = ?",
+        }
+    }
+
+    fn listing(self, parent: Uuid) -> Listing {
+        match self {
+            Target::Post => Listing::Posts(parent),
+            Target::Comment => Listing::Comments(parent),
+        }
+    }
+
+    fn add_to_score(self) -> &'static str {
+        match self {
+            Target::Post => "UPDATE post_score SET score = score + ? WHERE post_id = ?",
+            Target::Comment => "UPDATE comment_score SET score = score + ? WHERE comment_id = ?",
+        }
+    }
+
+    fn select_score(self) -> &'static str {
+        match self {
+            Target::Post => "SELECT score FROM post_score WHERE post_id = ?",
+            Target::Comment => "SELECT score FROM comment_score WHERE comment_id = ?",
+        }
+    }
+
+    fn delete_score(self) -> &'static str {
+        match self {
+            Target::Post => "DELETE FROM post_score WHERE post_id = ?",
+            Target::Comment => "DELETE FROM comment_score WHERE comment_id = ?",
+        }
+    }
+}
+
+/// Whether the `[applied]` column of a lightweight transaction says it went through
+fn applied(result: &scylla::QueryResult) -> bool {
+    result
+        .rows
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first())
+        .and_then(|c| c.as_ref())
+        .and_then(|c| c.as_boolean())
+        .unwrap_or(true)
+}
+
+/// `value`, `pending_delta` and `voted_at` of a user's vote
+type VoteRow = (Option<i32>, Option<i32>, Option<i64>);
+
+/// What to do about a user's vote, given their `votes` row
+#[derive(Debug, PartialEq)]
+enum VoteStep {
+    /// The vote is already the requested value and counted in the score
+    Unchanged,
+    /// An earlier change never reached the score: add this difference first
+    ApplyPending(i32),
+    /// An earlier change may still be on its way to the score
+    Busy,
+    /// Replace the vote; `previous` is 0 when there is none
+    Change { previous: i32 },
+}
+
+/// How long a pending difference is left to the request that stored it: its counter update
+/// and the clearing of the difference each run under `DB_QUERY_TIMEOUT_MS`
+fn pending_grace_millis() -> i64 {
+    2 * config::get().db_query_timeout_ms as i64
+}
+
+fn vote_step(row: Option<VoteRow>, value: i32, now_millis: i64) -> VoteStep {
+    match row {
+        Some((_, Some(delta), voted_at)) if delta != 0 => {
+            if now_millis - voted_at.unwrap_or(0) < pending_grace_millis() {
+                VoteStep::Busy
+            } else {
+                VoteStep::ApplyPending(delta)
+            }
+        }
+        Some((previous, _, _)) if previous.unwrap_or(0) == value => VoteStep::Unchanged,
+        Some((previous, _, _)) => VoteStep::Change { previous: previous.unwrap_or(0) },
+        None if value == 0 => VoteStep::Unchanged,
+        None => VoteStep::Change { previous: 0 },
+    }
+}
+
+/// Record the user's vote with its score difference pending, returning the vote it replaced
+/// (0 if none), or `None` when it kept changing concurrently
+async fn replace_vote(session: &Session, target: Target, id: Uuid, user_id: Uuid, value: i32) -> Result<Option<i32>, QueryError> {
+    for _ in 0..MAX_ATTEMPTS {
+        let rows = execute_cached(session, "SELECT value, pending_delta, voted_at FROM votes WHERE target_id = ? AND user_id = ?", (id, user_id)).await?;
+        let row = rows.maybe_first_row_typed::<VoteRow>().ok().flatten();
+        let now = Utc::now().timestamp_millis();
+        let result = match vote_step(row, value, now) {
+            VoteStep::Unchanged => return Ok(Some(value)),
+            VoteStep::Busy => return Ok(None),
+            VoteStep::ApplyPending(delta) => {
+                warn!("Applying the pending score change {} of user {} on {} {}", delta, user_id, target.kind(), id);
+                settle(session, target, id, user_id, delta).await?;
+                continue;
+            }
+            VoteStep::Change { previous } => match row {
+                Some((stored, _, _)) => execute_cached(
+                    session,
+                    "UPDATE votes SET value = ?, voted_at = ?, pending_delta = ? WHERE target_id = ? AND user_id = ? IF value = ? AND pending_delta = null",
+                    (value, now, value - previous, id, user_id, stored),
+                ).await?,
+                None => execute_cached(
+                    session,
+                    "INSERT INTO votes (target_id, user_id, target_type, value, voted_at, pending_delta) VALUES (?, ?, ?, ?, ?, ?) IF NOT EXISTS",
+                    (id, user_id, target.kind(), value, now, value),
+                ).await?,
+            },
+        };
+        if applied(&result) {
+            return Ok(Some(row.and_then(|(previous, _, _)| previous).unwrap_or(0)));
+        }
+    }
+    Ok(None)
+}
+
+/// Add a vote's pending difference to the score, then clear it from the vote row
+async fn settle(session: &Session, target: Target, id: Uuid, user_id: Uuid, delta: i32) -> Result<(), QueryError> {
+    execute_cached(session, target.add_to_score(), (CqlCounter(delta as i64), id)).await?;
+    if matches!(target, Target::Post) {
+        routes::invalidate_post_cache(id).await;
+    }
+    execute_cached(
+        session,
+        "UPDATE votes SET pending_delta = null WHERE target_id = ? AND user_id = ? IF pending_delta = ?",
+        (id, user_id, delta),
+    ).await?;
+    Ok(())
+}
+
+async fn score(session: &Session, target: Target, id: Uuid) -> Result<i64, QueryError> {
+    let rows = execute_cached(session, target.select_score(), (id,)).await?;
+    Ok(rows
+        .maybe_first_row_typed::<(Option<CqlCounter>,)>()
+        .ok()
+        .flatten()
+        .and_then(|(score,)| score)
+        .map_or(0, |CqlCounter(score)| score))
+}
+
+async fn vote(
+    session: &Session,
+    target: Target,
+    id: Uuid,
+    user: &AuthenticatedUser,
+    value: i32,
+    db_counter: &web::Data<DbCounter>,
+) -> HttpResponse {
+    let kind = target.kind();
+    if !(-1..=1).contains(&value) {
+        return ApiError::bad_request("value must be 1, -1 or 0").error_response();
+    }
+
+    let listing = match execute_cached(session, target.select_deleted(), (id,)).await {
+        Ok(rows) => {
+            record_db_operation(db_counter, "select", target.table(), true);
+            match rows.maybe_first_row_typed::<(Option<bool>, Option<Uuid>)>() {
+                Ok(Some((is_deleted, parent))) if is_deleted != Some(true) => parent.map(|parent| target.listing(parent)),
+                _ => return ApiError::not_found(format!("{} with id {} not found", target.label(), id)).error_response(),
+            }
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", target.table(), false);
+            error!("Error fetching {} {}: {}", kind, id, e);
+            return ApiError::internal(format!("Error fetching {}: {}", kind, e)).error_response();
+        }
+    };
+
+    let previous = match replace_vote(session, target, id, user.user_id, value).await {
+        Ok(Some(previous)) => previous,
+        Ok(None) => {
+            record_db_operation(db_counter, "update", "votes", true);
+            info!("Vote of user {} on {} {} kept changing concurrently", user.user_id, kind, id);
+            return ApiError::conflict("The vote was changed concurrently, retry").error_response();
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "update", "votes", false);
+            error!("Error recording vote of user {} on {} {}: {}", user.user_id, kind, id, e);
+            return ApiError::internal(format!("Error recording vote: {}", e)).error_response();
+        }
+    };
+    record_db_operation(db_counter, "update", "votes", true);
+
+    if value != previous {
+        if let Err(e) = settle(session, target, id, user.user_id, value - previous).await {
+            record_db_operation(db_counter, "update", target.score_table(), false);
+            // The difference stays pending on the vote row; the user's next vote adds it
+            error!("Vote of user {} on {} {} recorded but the score was not updated: {}", user.user_id, kind, id, e);
+            return ApiError::internal(format!("Error updating score: {}", e)).error_response();
+        }
+        record_db_operation(db_counter, "update", target.score_table(), true);
+    }
+
+    match score(session, target, id).await {
+        Ok(score) => {
+            // Concurrent votes can leave the ordering row behind; listings skip such rows
+            if let (Some(listing), true) = (listing, value != previous) {
+                match list_order::move_row(session, listing, SortKey::Score, id, score - (value - previous) as i64, score).await {
+                    Ok(()) => record_db_operation(db_counter, "update", listing.table(), true),
+                    Err(e) => {
+                        record_db_operation(db_counter, "update", listing.table(), false);
+                        error!("Vote on {} {} recorded but its ordering row was not moved: {}", kind, id, e);
+                    }
+                }
+            }
+            HttpResponse::Ok().json(VoteResponse { id, vote: value, score })
+        }
+        Err(e) => {
+            record_db_operation(db_counter, "select", target.score_table(), false);
+            error!("Error fetching score of {} {}: {}", kind, id, e);
+            ApiError::internal(format!("Error fetching score: {}", e)).error_response()
+        }
+    }
+}
+
+/// Scores of the given posts or comments; ids without votes are left out
+async fn scores(session: &Session, cql: &'static str, ids: Vec<Uuid>) -> Result<HashMap<Uuid, i64>, QueryError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = execute_cached(session, cql, (ids,)).await?;
+    Ok(rows
+        .rows_typed_or_empty::<(Uuid, Option<CqlCounter>)>()
+        .filter_map(Result::ok)
+        .map(|(id, score)| (id, score.map_or(0, |CqlCounter(score)| score)))
+        .collect())
+}
+
+/// Scores of the given posts; posts without votes are left out
+pub(crate) async fn post_scores(session: &Session, ids: Vec<Uuid>) -> Result<HashMap<Uuid, i64>, QueryError> {
+    scores(session, "SELECT post_id, score FROM post_score WHERE post_id IN ?", ids).await
+}
+
+/// Scores of the given comments; comments without votes are left out
+pub(crate) async fn comment_scores(session: &Session, ids: Vec<Uuid>) -> Result<HashMap<Uuid, i64>, QueryError> {
+    scores(session, "SELECT comment_id, score FROM comment_score WHERE comment_id IN ?", ids).await
+}
+
+/// Fill in `score` of the posts; on failure they keep their current score
+pub(crate) async fn fill_post_scores(session: &Session, posts: &mut [Post]) {
+    let ids = posts.iter().map(|post| post.id).collect();
+    match post_scores(session, ids).await {
+        Ok(scores) => {
+            for post in posts {
+                post.score = scores.get(&post.id).copied().unwrap_or(0);
+            }
+        }
+        Err(e) => warn!("Error fetching post scores: {}", e),
+    }
+}
+
+/// Fill in `score` of the comments; on failure they keep their current score
+pub(crate) async fn fill_comment_scores(session: &Session, comments: &mut [Comment]) {
+    let ids = comments.iter().map(|comment| comment.id).collect();
+    match comment_scores(session, ids).await {
+        Ok(scores) => {
+            for comment in comments {
+                comment.score = scores.get(&comment.id).copied().unwrap_or(0);
+            }
+        }
+        Err(e) => warn!("Error fetching comment scores: {}", e),
+    }
+}
+
+/// Drop the votes and score of a purged post
+pub(crate) async fn remove_post_votes(session: &Session, post_id: Uuid) -> Result<(), QueryError> {
+    remove(session, Target::Post, post_id).await
+}
+
+/// Drop the votes and score of a purged comment
+pub(crate) async fn remove_comment_votes(session: &Session, comment_id: Uuid) -> Result<(), QueryError> {
+    remove(session, Target::Comment, comment_id).await
+}
+
+/// Delete every vote of the user, returning how many there were; scores keep them, so they
+/// stay counted without pointing at the user
+pub(crate) async fn forget_user(session: &Session, user_id: Uuid) -> Result<usize, QueryError> {
+    let rows = execute_cached(session, "SELECT target_id FROM votes WHERE user_id = ?", (user_id,)).await?;
+    let targets: Vec<(Uuid,)> = rows
+        .rows_typed()
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    for (target_id,) in &targets {
+        execute_cached(session, "DELETE FROM votes WHERE target_id = ? AND user_id = ?", (*target_id, user_id)).await?;
+    }
+    Ok(targets.len())
+}
+
+async fn remove(session: &Session, target: Target, id: Uuid) -> Result<(), QueryError> {
+    execute_cached(session, "DELETE FROM votes WHERE target_id = ?", (id,)).await?;
+    execute_cached(session, target.delete_score(), (id,)).await?;
+    Ok(())
+}
+
+/// Vote on a post
+///
+/// Upvotes (1) or downvotes (-1) the post, or takes the caller's vote back (0). Each user has
+/// one vote per post: voting again replaces it. Needs a bearer token.
+#[utoipa::path(
+    post,
+    path = "/posts/{post_id}/vote",
+    request_body = VoteRequest,
+    params(
+        ("post_id" = uuid::Uuid, Path, description = "Post ID")
+    ),
+    responses(
+        (status = 200, description = "Vote recorded", body = VoteResponse),
+        (status = 400, description = "value other than 1, -1 or 0, or unknown field in the body"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Post not found"),
+        (status = 409, description = "The caller's vote kept changing concurrently, or its previous change is still being counted"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/posts/{post_id}/vote")]
+pub async fn vote_on_post(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    request: web::Json<VoteRequest>,
+    user: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    vote(&session, Target::Post, path.into_inner(), &user, request.value, &db_counter).await
+}
+
+/// Vote on a comment
+///
+/// Same as voting on a post: 1, -1, or 0 to take the vote back, one vote per user. Needs a
+/// bearer token.
+#[utoipa::path(
+    post,
+    path = "/comments/{comment_id}/vote",
+    request_body = VoteRequest,
+    params(
+        ("comment_id" = uuid::Uuid, Path, description = "Comment ID")
+    ),
+    responses(
+        (status = 200, description = "Vote recorded", body = VoteResponse),
+        (status = 400, description = "value other than 1, -1 or 0, or unknown field in the body"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Comment not found"),
+        (status = 409, description = "The caller's vote kept changing concurrently, or its previous change is still being counted"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/comments/{comment_id}/vote")]
+pub async fn vote_on_comment(
+    session: web::Data<Arc<Session>>,
+    path: web::Path<Uuid>,
+    request: web::Json<VoteRequest>,
+    user: AuthenticatedUser,
+    db_counter: web::Data<DbCounter>,
+) -> impl Responder {
+    vote(&session, Target::Comment, path.into_inner(), &user, request.value, &db_counter).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn vote_step_changes_or_keeps_settled_votes() {
+        assert_eq!(vote_step(None, 1, NOW), VoteStep::Change { previous: 0 });
+        assert_eq!(vote_step(None, 0, NOW), VoteStep::Unchanged);
+        assert_eq!(vote_step(Some((Some(1), None, Some(NOW))), 1, NOW), VoteStep::Unchanged);
+        assert_eq!(vote_step(Some((Some(1), None, Some(NOW))), -1, NOW), VoteStep::Change { previous: 1 });
+    }
+
+    #[test]
+    fn vote_step_redoes_a_failed_score_update() {
+        // The vote went from 1 to -1 but the counter update failed: a retry with the same value
+        // must add the -2 instead of finding the vote unchanged
+        let failed = Some((Some(-1), Some(-2), Some(NOW)));
+        let later = NOW + pending_grace_millis();
+        assert_eq!(vote_step(failed, -1, later), VoteStep::ApplyPending(-2));
+        assert_eq!(vote_step(failed, 1, later), VoteStep::ApplyPending(-2));
+        // Until then the request that stored it may still be updating the score
+        assert_eq!(vote_step(failed, -1, later - 1), VoteStep::Busy);
+        // Once applied and cleared, the same retry is a no-op
+        assert_eq!(vote_step(Some((Some(-1), None, Some(NOW))), -1, later), VoteStep::Unchanged);
+    }
+}